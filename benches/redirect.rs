@@ -0,0 +1,71 @@
+//! Time to first byte of `GET /:code` through the full router, over an
+//! in-memory database: a known code, a code reached through an alias, and an
+//! unknown code (404). There is no link cache, so there are no cache-hit or
+//! cache-miss cases; every lookup goes to SQLite. Run with
+//! `cargo bench --bench redirect`; the budget these are held to is in
+//! `docs/decisions.md`.
+
+use axum::{body::Body, http::Request, Router};
+use criterion::{criterion_group, criterion_main, Criterion};
+use sqlx::sqlite::SqlitePoolOptions;
+use tower::ServiceExt;
+use url_shortener::{router, AppState};
+
+async fn bench_app() -> Router {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    let app = router(
+        AppState::builder(pool)
+            .base_url("http://localhost:3000")
+            .admin_token("bench")
+            .geo(false)
+            .build(),
+    );
+    let send = |method: &str, uri: &str, body: &str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json")
+            .header("authorization", "Bearer bench")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let shorten = r#"{"url": "https://example.com/landing?ref=bench", "custom_code": "bench"}"#;
+    app.clone().oneshot(send("POST", "/api/shorten", shorten)).await.unwrap();
+    let alias = r#"{"alias": "bench-alias"}"#;
+    app.clone().oneshot(send("POST", "/api/links/bench/aliases", alias)).await.unwrap();
+    app
+}
+
+fn redirect(c: &mut Criterion) {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let app = rt.block_on(bench_app());
+    let get = |uri: &'static str| {
+        let app = app.clone();
+        move || {
+            let app = app.clone();
+            async move {
+                let req = Request::builder()
+                    .uri(uri)
+                    .header("user-agent", "Mozilla/5.0 (X11; Linux x86_64) Firefox/130.0")
+                    .header("x-forwarded-for", "203.0.113.7")
+                    .body(Body::empty())
+                    .unwrap();
+                app.oneshot(req).await.unwrap()
+            }
+        }
+    };
+
+    let mut group = c.benchmark_group("redirect");
+    group.bench_function("known", |b| b.to_async(&rt).iter(get("/bench")));
+    group.bench_function("alias", |b| b.to_async(&rt).iter(get("/bench-alias")));
+    group.bench_function("unknown", |b| b.to_async(&rt).iter(get("/missing")));
+    group.finish();
+}
+
+criterion_group!(benches, redirect);
+criterion_main!(benches);
@@ -0,0 +1,3 @@
+ALTER TABLE urls ADD COLUMN listed INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS idx_urls_listed ON urls(listed, created_at);
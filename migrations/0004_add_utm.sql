@@ -0,0 +1,7 @@
+ALTER TABLE urls ADD COLUMN utm_source TEXT;
+ALTER TABLE urls ADD COLUMN utm_medium TEXT;
+ALTER TABLE urls ADD COLUMN utm_campaign TEXT;
+ALTER TABLE urls ADD COLUMN utm_term TEXT;
+ALTER TABLE urls ADD COLUMN utm_content TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_urls_utm_campaign ON urls(utm_campaign);
@@ -0,0 +1,6 @@
+ALTER TABLE clicks ADD COLUMN visitor_id TEXT;
+
+-- Clicks recorded before fingerprinting counted unique visitors by IP.
+UPDATE clicks SET visitor_id = ip WHERE visitor_id IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_clicks_code_visitor ON clicks(code, visitor_id);
@@ -0,0 +1,2 @@
+-- JSON object of custom fields produced by click enrichers.
+ALTER TABLE clicks ADD COLUMN extra TEXT;
@@ -0,0 +1 @@
+ALTER TABLE urls ADD COLUMN redirect_mode TEXT NOT NULL DEFAULT 'http';
@@ -0,0 +1,7 @@
+-- HyperLogLog sketches of visitor ids, per link per day plus day '*' for all time
+CREATE TABLE IF NOT EXISTS visitor_sketches (
+  code TEXT NOT NULL,
+  day TEXT NOT NULL,
+  registers BLOB NOT NULL,
+  PRIMARY KEY (code, day)
+);
@@ -0,0 +1,5 @@
+-- Runtime settings edited through /api/admin/settings; value is JSON
+CREATE TABLE IF NOT EXISTS settings (
+  key TEXT PRIMARY KEY,
+  value TEXT NOT NULL
+);
@@ -0,0 +1,6 @@
+-- Named link defaults; config is JSON (see src/templates.rs)
+CREATE TABLE IF NOT EXISTS link_templates (
+  id TEXT PRIMARY KEY,
+  config TEXT NOT NULL,
+  created_at TEXT NOT NULL
+);
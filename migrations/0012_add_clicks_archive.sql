@@ -0,0 +1,17 @@
+-- Clicks moved out by POST /api/links/:code/stats/reset
+CREATE TABLE IF NOT EXISTS clicks_archive (
+  id INTEGER PRIMARY KEY,
+  code TEXT NOT NULL,
+  at TEXT NOT NULL,
+  ip TEXT,
+  user_agent TEXT,
+  referer TEXT,
+  country TEXT,
+  city TEXT,
+  visitor_id TEXT,
+  language TEXT,
+  extra TEXT,
+  archived_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_clicks_archive_code ON clicks_archive(code, archived_at);
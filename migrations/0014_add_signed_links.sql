@@ -0,0 +1,8 @@
+-- Links that only redirect through a valid signed URL
+ALTER TABLE urls ADD COLUMN signed_only INTEGER NOT NULL DEFAULT 0;
+
+-- Recipient of the signed URL a click came through
+ALTER TABLE clicks ADD COLUMN recipient TEXT;
+ALTER TABLE clicks_archive ADD COLUMN recipient TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_clicks_code_recipient ON clicks(code, recipient);
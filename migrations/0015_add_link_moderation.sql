@@ -0,0 +1,7 @@
+-- API key that created the link (`admin` for the admin token); NULL for anonymous clients
+ALTER TABLE urls ADD COLUMN created_by TEXT;
+
+-- Anonymous links held for review don't redirect until an admin approves them
+ALTER TABLE urls ADD COLUMN pending_review INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS idx_urls_created_ip ON urls(created_ip, created_at);
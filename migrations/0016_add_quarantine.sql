@@ -0,0 +1,15 @@
+-- Links disabled by an admin (e.g. a quarantined domain) stop redirecting
+ALTER TABLE urls ADD COLUMN disabled_at TEXT;
+ALTER TABLE urls ADD COLUMN disabled_reason TEXT;
+
+-- Append-only record of admin actions
+CREATE TABLE IF NOT EXISTS audit_log (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  at TEXT NOT NULL,
+  actor TEXT NOT NULL,
+  action TEXT NOT NULL,
+  subject TEXT NOT NULL,
+  detail TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_at ON audit_log(at);
@@ -0,0 +1,2 @@
+-- Target as submitted, when it was rewritten at creation (e.g. upgraded to HTTPS)
+ALTER TABLE urls ADD COLUMN original_url TEXT;
@@ -0,0 +1,13 @@
+-- Free-form labels for grouping links, e.g. per marketing campaign.
+CREATE TABLE IF NOT EXISTS tags (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  name TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE IF NOT EXISTS link_tags (
+  code TEXT NOT NULL,
+  tag_id INTEGER NOT NULL REFERENCES tags(id),
+  PRIMARY KEY (code, tag_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_link_tags_tag ON link_tags(tag_id);
@@ -0,0 +1,2 @@
+-- Known URL shortener the submitted target pointed at (bit.ly, t.co, ...)
+ALTER TABLE urls ADD COLUMN chained_via TEXT;
@@ -0,0 +1,3 @@
+-- Human-readable label and free-form notes shown next to the code
+ALTER TABLE urls ADD COLUMN title TEXT;
+ALTER TABLE urls ADD COLUMN notes TEXT;
@@ -0,0 +1,6 @@
+-- Results of the periodic target health check
+ALTER TABLE urls ADD COLUMN target_checked_at TEXT;
+-- Last check that found the target alive
+ALTER TABLE urls ADD COLUMN target_ok_at TEXT;
+-- Set while the target is dead: when it was first found dead
+ALTER TABLE urls ADD COLUMN target_dead_since TEXT;
@@ -0,0 +1,2 @@
+-- Archived links stop redirecting but keep their clicks
+ALTER TABLE urls ADD COLUMN archived_at TEXT;
@@ -0,0 +1,15 @@
+-- Click thresholds that notify a webhook once crossed. Exactly one of code
+-- and campaign (a utm_campaign value) is set.
+CREATE TABLE IF NOT EXISTS click_alerts (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  code TEXT,
+  campaign TEXT,
+  threshold INTEGER NOT NULL,
+  webhook_url TEXT NOT NULL,
+  format TEXT NOT NULL,
+  created_at TEXT NOT NULL,
+  fired_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_click_alerts_code ON click_alerts(code) WHERE fired_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_click_alerts_campaign ON click_alerts(campaign) WHERE fired_at IS NULL;
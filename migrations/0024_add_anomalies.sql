@@ -0,0 +1,14 @@
+-- Traffic anomalies found by the detection job. kind is 'spike' (all clicks
+-- on a link) or 'country' (clicks from one country).
+CREATE TABLE IF NOT EXISTS anomalies (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  code TEXT NOT NULL,
+  kind TEXT NOT NULL,
+  country TEXT,
+  clicks INTEGER NOT NULL,
+  baseline REAL NOT NULL,
+  detected_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_anomalies_detected_at ON anomalies(detected_at);
+CREATE INDEX IF NOT EXISTS idx_clicks_at ON clicks(at);
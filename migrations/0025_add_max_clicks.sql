@@ -0,0 +1,4 @@
+-- Optional click limit, checked against a counter so redirects don't count(*)
+ALTER TABLE urls ADD COLUMN max_clicks INTEGER;
+ALTER TABLE urls ADD COLUMN click_count INTEGER NOT NULL DEFAULT 0;
+UPDATE urls SET click_count = (SELECT count(*) FROM clicks c WHERE c.code = urls.code);
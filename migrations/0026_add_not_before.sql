@@ -0,0 +1,2 @@
+-- Links created ahead of a launch don't redirect before this time
+ALTER TABLE urls ADD COLUMN not_before TEXT;
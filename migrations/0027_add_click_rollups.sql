@@ -0,0 +1,10 @@
+-- Aggregated clicks past click_detail_days. Unknown country or referrer is ''
+-- so the primary key can be upserted.
+CREATE TABLE IF NOT EXISTS click_rollups (
+  code TEXT NOT NULL,
+  day TEXT NOT NULL,
+  country TEXT NOT NULL,
+  referrer TEXT NOT NULL,
+  clicks INTEGER NOT NULL,
+  PRIMARY KEY (code, day, country, referrer)
+);
@@ -0,0 +1,2 @@
+-- Lookup of existing links for reuse_existing
+CREATE INDEX IF NOT EXISTS idx_urls_target_url ON urls(target_url);
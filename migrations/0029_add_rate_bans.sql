@@ -0,0 +1,13 @@
+-- Temporary bans from the rate-limited endpoints, set by admins. Exactly one
+-- of ip and api_key (an api_keys name) is set.
+CREATE TABLE IF NOT EXISTS rate_bans (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  ip TEXT,
+  api_key TEXT,
+  reason TEXT,
+  created_at TEXT NOT NULL,
+  expires_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_rate_bans_ip ON rate_bans(ip);
+CREATE INDEX IF NOT EXISTS idx_rate_bans_api_key ON rate_bans(api_key);
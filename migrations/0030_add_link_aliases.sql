@@ -0,0 +1,9 @@
+-- Extra codes resolving to an existing link. Clicks through an alias are
+-- recorded under the link's own code. Aliases and codes share one namespace.
+CREATE TABLE IF NOT EXISTS link_aliases (
+  alias TEXT PRIMARY KEY,
+  code TEXT NOT NULL,
+  created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_link_aliases_code ON link_aliases(code);
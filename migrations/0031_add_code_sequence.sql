@@ -0,0 +1,6 @@
+-- Counters behind sequential codes, so numbering survives restarts and is
+-- shared by every instance on the database.
+CREATE TABLE IF NOT EXISTS code_sequence (
+  name TEXT PRIMARY KEY,
+  value INTEGER NOT NULL
+);
@@ -0,0 +1,4 @@
+-- Traffic channel of the referrer (search, social, email, direct, internal,
+-- referral), classified when the click is recorded
+ALTER TABLE clicks ADD COLUMN channel TEXT;
+ALTER TABLE clicks_archive ADD COLUMN channel TEXT;
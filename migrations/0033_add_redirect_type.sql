@@ -0,0 +1,2 @@
+-- Status code of HTTP-mode redirects: 301, 302, 307 or 308
+ALTER TABLE urls ADD COLUMN redirect_type INTEGER NOT NULL DEFAULT 307;
@@ -0,0 +1,3 @@
+-- Set once the "link expires soon" notification went out; cleared when the
+-- expiry changes
+ALTER TABLE urls ADD COLUMN expiry_warned_at TEXT;
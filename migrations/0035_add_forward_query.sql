@@ -0,0 +1,2 @@
+-- Append the visitor's query string to the target when redirecting
+ALTER TABLE urls ADD COLUMN forward_query INTEGER NOT NULL DEFAULT 0;
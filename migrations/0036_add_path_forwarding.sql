@@ -0,0 +1,2 @@
+-- Redirect `/<code>/<rest>` to the target with `<rest>` appended to its path
+ALTER TABLE urls ADD COLUMN path_forwarding INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,4 @@
+-- Per-device destinations; NULL falls back to target_url
+ALTER TABLE urls ADD COLUMN target_ios TEXT;
+ALTER TABLE urls ADD COLUMN target_android TEXT;
+ALTER TABLE urls ADD COLUMN target_desktop TEXT;
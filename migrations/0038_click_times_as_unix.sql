@@ -0,0 +1,59 @@
+-- Click times become unix seconds, so time ranges compare integers on the indexes.
+-- SQLite can't change a column type in place: rebuild both click tables.
+
+CREATE TABLE clicks_new (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  code TEXT NOT NULL,
+  at INTEGER NOT NULL,
+  ip TEXT,
+  user_agent TEXT,
+  referer TEXT,
+  country TEXT,
+  city TEXT,
+  visitor_id TEXT,
+  language TEXT,
+  extra TEXT,
+  recipient TEXT,
+  channel TEXT,
+  FOREIGN KEY(code) REFERENCES urls(code)
+);
+INSERT INTO clicks_new (id, code, at, ip, user_agent, referer, country, city, visitor_id, language, extra,
+                        recipient, channel)
+  SELECT id, code, coalesce(CAST(strftime('%s', at) AS INTEGER), 0), ip, user_agent, referer, country, city,
+         visitor_id, language, extra, recipient, channel
+  FROM clicks;
+-- keep the id sequence, so ids of deleted clicks are not handed out again
+DELETE FROM sqlite_sequence WHERE name = 'clicks_new';
+UPDATE sqlite_sequence SET name = 'clicks_new' WHERE name = 'clicks';
+DROP TABLE clicks;
+ALTER TABLE clicks_new RENAME TO clicks;
+CREATE INDEX idx_clicks_code_at ON clicks(code, at);
+CREATE INDEX idx_clicks_code_ip ON clicks(code, ip);
+CREATE INDEX idx_clicks_code_visitor ON clicks(code, visitor_id);
+CREATE INDEX idx_clicks_code_recipient ON clicks(code, recipient);
+CREATE INDEX idx_clicks_at ON clicks(at);
+
+CREATE TABLE clicks_archive_new (
+  id INTEGER PRIMARY KEY,
+  code TEXT NOT NULL,
+  at INTEGER NOT NULL,
+  ip TEXT,
+  user_agent TEXT,
+  referer TEXT,
+  country TEXT,
+  city TEXT,
+  visitor_id TEXT,
+  language TEXT,
+  extra TEXT,
+  archived_at TEXT NOT NULL,
+  recipient TEXT,
+  channel TEXT
+);
+INSERT INTO clicks_archive_new (id, code, at, ip, user_agent, referer, country, city, visitor_id, language, extra,
+                                archived_at, recipient, channel)
+  SELECT id, code, coalesce(CAST(strftime('%s', at) AS INTEGER), 0), ip, user_agent, referer, country, city,
+         visitor_id, language, extra, archived_at, recipient, channel
+  FROM clicks_archive;
+DROP TABLE clicks_archive;
+ALTER TABLE clicks_archive_new RENAME TO clicks_archive;
+CREATE INDEX idx_clicks_archive_code ON clicks_archive(code, archived_at);
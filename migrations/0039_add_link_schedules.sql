@@ -0,0 +1,11 @@
+-- Time windows during which a link redirects to another target.
+-- Times are unix seconds; no window active means target_url.
+CREATE TABLE IF NOT EXISTS link_schedules (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  code TEXT NOT NULL,
+  target_url TEXT NOT NULL,
+  starts_at INTEGER NOT NULL,
+  ends_at INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS idx_link_schedules_code ON link_schedules(code, starts_at);
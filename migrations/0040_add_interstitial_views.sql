@@ -0,0 +1,10 @@
+-- Views of the interstitial shown before links in `interstitial` mode.
+-- Kept apart from clicks: a view only becomes a click when the visitor
+-- continues. Times are unix seconds.
+CREATE TABLE IF NOT EXISTS interstitial_views (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  code TEXT NOT NULL,
+  at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_interstitial_views_code ON interstitial_views(code, at);
@@ -0,0 +1,9 @@
+-- Browser, OS and device type parsed from clicks.user_agent. NULL for
+-- clicks without a User-Agent; older clicks are filled in at startup.
+ALTER TABLE clicks ADD COLUMN browser TEXT;
+ALTER TABLE clicks ADD COLUMN os TEXT;
+ALTER TABLE clicks ADD COLUMN device_type TEXT;
+
+ALTER TABLE clicks_archive ADD COLUMN browser TEXT;
+ALTER TABLE clicks_archive ADD COLUMN os TEXT;
+ALTER TABLE clicks_archive ADD COLUMN device_type TEXT;
@@ -0,0 +1,12 @@
+-- Clicks from crawlers and link unfurlers (device_type 'bot'), left out of
+-- totals and unique visitors unless stats ask for ?include_bots=true.
+ALTER TABLE clicks ADD COLUMN is_bot INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE clicks_archive ADD COLUMN is_bot INTEGER NOT NULL DEFAULT 0;
+-- How many of a rollup's clicks were bots.
+ALTER TABLE click_rollups ADD COLUMN bots INTEGER NOT NULL DEFAULT 0;
+
+UPDATE clicks SET is_bot = 1 WHERE device_type = 'bot';
+UPDATE clicks_archive SET is_bot = 1 WHERE device_type = 'bot';
+
+-- sketches must not count bots; startup rebuilds the dropped ones
+DELETE FROM visitor_sketches WHERE code IN (SELECT code FROM clicks WHERE is_bot = 1);
@@ -0,0 +1,12 @@
+-- QR codes of links with qr_landing open /q/<code>, a landing page that
+-- counts the scan before the visitor continues to the short link
+ALTER TABLE urls ADD COLUMN qr_landing INTEGER NOT NULL DEFAULT 0;
+
+-- Times are unix seconds.
+CREATE TABLE IF NOT EXISTS qr_scans (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  code TEXT NOT NULL,
+  at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_qr_scans_code ON qr_scans(code, at);
@@ -0,0 +1,14 @@
+-- Webhooks that receive every click, on one link (code) or on all of them
+-- (code NULL). Payloads are signed with the webhook's secret.
+CREATE TABLE IF NOT EXISTS click_webhooks (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  code TEXT,
+  url TEXT NOT NULL,
+  secret TEXT NOT NULL,
+  created_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_click_webhooks_code ON click_webhooks(code);
+
+-- Retries of a click webhook are signed again with its current secret.
+ALTER TABLE webhook_deliveries ADD COLUMN webhook_id INTEGER;
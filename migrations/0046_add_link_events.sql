@@ -0,0 +1,8 @@
+-- Set once a link's expiry has been announced to the notifiers. Links that
+-- expired before this migration count as announced.
+ALTER TABLE urls ADD COLUMN expired_notified_at TEXT;
+
+UPDATE urls SET expired_notified_at = expires_at WHERE julianday(expires_at) <= julianday('now');
+
+CREATE INDEX IF NOT EXISTS idx_urls_expiry_unnotified ON urls(expires_at)
+  WHERE expires_at IS NOT NULL AND expired_notified_at IS NULL;
@@ -0,0 +1,178 @@
+//! Click threshold alerts: notify a webhook, and the deployment's notifiers,
+//! once a link, or all links of a UTM campaign together, pass a number of
+//! clicks. Checked after every recorded click; each alert fires once.
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::{
+    internal,
+    notify::{self, Event, WebhookFormat, WebhookNotifier},
+    normalize_url, require_admin, rollup, AppState,
+};
+
+#[derive(Serialize, sqlx::FromRow)]
+pub(crate) struct Alert {
+    id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    campaign: Option<String>,
+    threshold: i64,
+    webhook_url: String,
+    format: WebhookFormat,
+    created_at: String,
+    fired_at: Option<String>,
+}
+
+impl Alert {
+    fn event(&self, clicks: i64) -> Event {
+        Event::ClickThreshold {
+            alert_id: self.id,
+            code: self.code.clone(),
+            campaign: self.campaign.clone(),
+            threshold: self.threshold,
+            clicks,
+        }
+    }
+}
+
+/// Fires the pending alerts that the latest click on `code` pushed over
+/// their threshold. Links without pending alerts cost one indexed lookup.
+pub(crate) async fn evaluate(state: &AppState, code: &str) -> Result<(), sqlx::Error> {
+    let pending: Vec<Alert> = sqlx::query_as(
+        "SELECT a.* FROM click_alerts a JOIN urls u ON u.code = ?1 \
+         WHERE a.fired_at IS NULL AND (a.code = u.code OR a.campaign = u.utm_campaign)",
+    )
+    .bind(code)
+    .fetch_all(&state.pool)
+    .await?;
+
+    for alert in pending {
+        let (clicks,): (i64,) = match &alert.campaign {
+            Some(campaign) => {
+                sqlx::query_as(&format!(
+                    "SELECT coalesce(sum({}), 0) FROM urls u WHERE u.utm_campaign = ?",
+                    rollup::total_clicks_sql("u.code", false)
+                ))
+                .bind(campaign)
+                .fetch_one(&state.pool)
+                .await?
+            }
+            None => {
+                sqlx::query_as(&format!("SELECT {}", rollup::total_clicks_sql("?1", false)))
+                    .bind(code)
+                    .fetch_one(&state.pool)
+                    .await?
+            }
+        };
+        if clicks < alert.threshold {
+            continue;
+        }
+        let now = OffsetDateTime::now_utc()
+            .format(&time::format_description::well_known::Rfc3339)
+            .unwrap();
+        // concurrent clicks may both cross the threshold; only one claims it
+        let claimed = sqlx::query("UPDATE click_alerts SET fired_at = ? WHERE id = ? AND fired_at IS NULL")
+            .bind(&now)
+            .bind(alert.id)
+            .execute(&state.pool)
+            .await?;
+        if claimed.rows_affected() == 1 {
+            let state = state.clone();
+            tokio::spawn(async move {
+                let hook = WebhookNotifier::new(&alert.webhook_url, alert.format);
+                notify::deliver(&state, &alert.event(clicks), Some(&hook)).await;
+            });
+        }
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct CreateAlertReq {
+    code: Option<String>,
+    campaign: Option<String>,
+    threshold: i64,
+    webhook_url: String,
+    #[serde(default)]
+    format: WebhookFormat,
+}
+
+/// Admin only.
+pub(crate) async fn list_alerts(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<Alert>>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let alerts = sqlx::query_as("SELECT * FROM click_alerts ORDER BY id")
+        .fetch_all(&state.pool)
+        .await
+        .map_err(internal)?;
+    Ok(Json(alerts))
+}
+
+/// Registers an alert for one link (`code`) or a campaign. Admin only.
+pub(crate) async fn create_alert(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<CreateAlertReq>,
+) -> Result<(StatusCode, Json<Alert>), (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let bad_request = |msg: &str| (StatusCode::BAD_REQUEST, msg.to_string());
+    let (code, campaign) = match (req.code, req.campaign) {
+        (Some(code), None) => {
+            let Some(link) = crate::fetch_link(&state, &code).await.map_err(internal)? else {
+                return Err((StatusCode::NOT_FOUND, "link not found".to_string()));
+            };
+            (Some(link.code), None)
+        }
+        (None, Some(campaign)) if !campaign.trim().is_empty() => (None, Some(campaign.trim().to_string())),
+        _ => return Err(bad_request("set exactly one of code and campaign")),
+    };
+    if req.threshold < 1 {
+        return Err(bad_request("threshold must be at least 1"));
+    }
+    let webhook_url = normalize_url(&req.webhook_url).map_err(|e| bad_request(&format!("webhook_url {e}")))?;
+
+    let now = OffsetDateTime::now_utc()
+        .format(&time::format_description::well_known::Rfc3339)
+        .unwrap();
+    let alert = sqlx::query_as(
+        "INSERT INTO click_alerts (code, campaign, threshold, webhook_url, format, created_at) \
+         VALUES (?, ?, ?, ?, ?, ?) RETURNING *",
+    )
+    .bind(code)
+    .bind(campaign)
+    .bind(req.threshold)
+    .bind(webhook_url)
+    .bind(req.format)
+    .bind(now)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(internal)?;
+    Ok((StatusCode::CREATED, Json(alert)))
+}
+
+pub(crate) async fn delete_alert(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let done = sqlx::query("DELETE FROM click_alerts WHERE id = ?")
+        .bind(id)
+        .execute(&state.pool)
+        .await
+        .map_err(internal)?;
+    if done.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "not found".to_string()));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,109 @@
+//! Extra codes for an existing link: `/promo` and `/PROMO2024` can both
+//! redirect to the same target. [`fetch_link`] resolves aliases, so redirects,
+//! stats and everything else keyed by code land on the link itself, and clicks
+//! are counted once, under its own code.
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::{api_keys, fetch_link, internal, is_unique_violation, validate_custom_code, AppState};
+
+#[derive(Deserialize)]
+pub(crate) struct AddAliasReq {
+    alias: String,
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+pub(crate) struct Alias {
+    alias: String,
+    code: String,
+    #[sqlx(skip)]
+    short_url: String,
+    created_at: String,
+}
+
+/// `POST /api/links/:code/aliases`. Aliases follow the rules of custom codes
+/// and may not clash with any code or alias. Needs an API key.
+pub(crate) async fn add_alias(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<AddAliasReq>,
+) -> Result<(StatusCode, Json<Alias>), (StatusCode, String)> {
+    let caller = api_keys::require_api_key(&state, &headers).await?;
+    let Some(link) = fetch_link(&state, &code).await.map_err(internal)? else {
+        return Err((StatusCode::NOT_FOUND, "not found".to_string()));
+    };
+    let alias = req.alias.trim();
+    validate_custom_code(alias, &state.settings(), Some(&caller))
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.replace("custom_code", "alias")))?;
+    let alias = state.normalize_code(alias);
+    let taken = || (StatusCode::CONFLICT, "code already exists".to_string());
+    if fetch_link(&state, &alias).await.map_err(internal)?.is_some() {
+        return Err(taken());
+    }
+
+    let now = OffsetDateTime::now_utc()
+        .format(&time::format_description::well_known::Rfc3339)
+        .unwrap();
+    let mut created: Alias = sqlx::query_as(
+        "INSERT INTO link_aliases (alias, code, created_at) SELECT ?1, ?2, ?3 \
+         WHERE NOT EXISTS (SELECT 1 FROM urls WHERE code = ?1) RETURNING *",
+    )
+    .bind(&alias)
+    .bind(&link.code)
+    .bind(now)
+    .fetch_optional(&state.pool)
+    .await
+    .map_err(|e| if is_unique_violation(&e) { taken() } else { internal(e) })?
+    .ok_or_else(taken)?;
+    created.short_url = state.short_url(&created.alias);
+    Ok((StatusCode::CREATED, Json(created)))
+}
+
+/// `GET /api/links/:code/aliases`, oldest first.
+pub(crate) async fn list_aliases(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+) -> Result<Json<Vec<Alias>>, (StatusCode, String)> {
+    let Some(link) = fetch_link(&state, &code).await.map_err(internal)? else {
+        return Err((StatusCode::NOT_FOUND, "not found".to_string()));
+    };
+    let mut aliases: Vec<Alias> =
+        sqlx::query_as("SELECT * FROM link_aliases WHERE code = ? ORDER BY created_at, alias")
+            .bind(&link.code)
+            .fetch_all(&state.pool)
+            .await
+            .map_err(internal)?;
+    for alias in &mut aliases {
+        alias.short_url = state.short_url(&alias.alias);
+    }
+    Ok(Json(aliases))
+}
+
+/// `DELETE /api/links/:code/aliases/:alias`. Needs an API key.
+pub(crate) async fn remove_alias(
+    State(state): State<AppState>,
+    Path((code, alias)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, String)> {
+    api_keys::require_api_key(&state, &headers).await?;
+    let Some(link) = fetch_link(&state, &code).await.map_err(internal)? else {
+        return Err((StatusCode::NOT_FOUND, "not found".to_string()));
+    };
+    let done = sqlx::query("DELETE FROM link_aliases WHERE code = ? AND alias = ?")
+        .bind(&link.code)
+        .bind(state.normalize_code(&alias))
+        .execute(&state.pool)
+        .await
+        .map_err(internal)?;
+    if done.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "not found".to_string()));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,159 @@
+//! Traffic anomaly detection: compares each link's clicks over the last hour
+//! with its hourly average over the week before, overall and per country.
+//! Findings are stored, listed to admins and sent to `anomaly_webhook_url`
+//! and the deployment's notifiers.
+
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::{
+    internal,
+    notify::{self, Event, WebhookFormat, WebhookNotifier},
+    require_admin, AppState,
+};
+
+/// Clicks in the last hour must be at least this multiple of the baseline.
+const SPIKE_FACTOR: f64 = 10.0;
+/// Fewer clicks in an hour are never an anomaly.
+const MIN_CLICKS: i64 = 50;
+/// Links need this much click history for a meaningful baseline.
+const MIN_HISTORY_HOURS: i64 = 24;
+const BASELINE_HOURS: i64 = 7 * 24;
+
+#[derive(Serialize, sqlx::FromRow, Clone)]
+pub struct Anomaly {
+    id: i64,
+    pub(crate) code: String,
+    /// `spike` or `country`.
+    pub(crate) kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) country: Option<String>,
+    /// Clicks in the hour before detection.
+    pub(crate) clicks: i64,
+    /// Average clicks per hour over the preceding week.
+    pub(crate) baseline: f64,
+    pub(crate) detected_at: String,
+}
+
+impl Anomaly {
+    pub(crate) fn text(&self) -> String {
+        let scope = match &self.country {
+            Some(country) => format!(" from {country}"),
+            None => String::new(),
+        };
+        format!(
+            "Link {} got {} clicks{scope} in the last hour, against {:.1} per hour normally",
+            self.code, self.clicks, self.baseline
+        )
+    }
+}
+
+/// Flags links whose last hour of traffic, in total or from one country,
+/// is far above their baseline. A link/country already flagged within the
+/// hour is skipped. Does nothing unless `anomaly_detection` is on, or while
+/// read-only; returns the new anomalies.
+pub async fn detect_anomalies(state: &AppState) -> Result<usize, sqlx::Error> {
+    let settings = state.settings();
+    if !settings.anomaly_detection || state.is_read_only() {
+        return Ok(0);
+    }
+    let rfc3339 = &time::format_description::well_known::Rfc3339;
+    let now = OffsetDateTime::now_utc();
+    let hour_ago = now - time::Duration::HOUR;
+    let since = now - time::Duration::hours(BASELINE_HOURS + 1);
+
+    // (code, country or NULL for all clicks, clicks last hour, clicks before, first click)
+    let groups: Vec<(String, Option<String>, i64, i64, i64)> = sqlx::query_as(
+        "SELECT code, NULL, sum(at >= ?1), sum(at < ?1), min(at) FROM clicks \
+         WHERE at >= ?2 GROUP BY code HAVING sum(at >= ?1) >= ?3 \
+         UNION ALL \
+         SELECT c.code, c.country, sum(c.at >= ?1), sum(c.at < ?1), \
+                (SELECT min(f.at) FROM clicks f WHERE f.code = c.code AND f.at >= ?2) \
+         FROM clicks c WHERE c.at >= ?2 AND c.country IS NOT NULL \
+         GROUP BY c.code, c.country HAVING sum(c.at >= ?1) >= ?3",
+    )
+    .bind(hour_ago.unix_timestamp())
+    .bind(since.unix_timestamp())
+    .bind(MIN_CLICKS)
+    .fetch_all(&state.pool)
+    .await?;
+
+    let mut found = Vec::new();
+    for (code, country, clicks, earlier, first_click) in groups {
+        let Ok(first_click) = OffsetDateTime::from_unix_timestamp(first_click) else {
+            continue;
+        };
+        let history = (hour_ago - first_click).whole_hours();
+        if history < MIN_HISTORY_HOURS {
+            continue;
+        }
+        let baseline = earlier as f64 / history.min(BASELINE_HOURS) as f64;
+        if (clicks as f64) < SPIKE_FACTOR * baseline {
+            continue;
+        }
+        let kind = if country.is_some() { "country" } else { "spike" };
+        let (recent,): (i64,) = sqlx::query_as(
+            "SELECT count(*) FROM anomalies WHERE code = ? AND kind = ? AND country IS ? AND detected_at >= ?",
+        )
+        .bind(&code)
+        .bind(kind)
+        .bind(&country)
+        .bind(hour_ago.format(rfc3339).unwrap())
+        .fetch_one(&state.pool)
+        .await?;
+        if recent > 0 {
+            continue;
+        }
+        let anomaly: Anomaly = sqlx::query_as(
+            "INSERT INTO anomalies (code, kind, country, clicks, baseline, detected_at) \
+             VALUES (?, ?, ?, ?, ?, ?) RETURNING *",
+        )
+        .bind(&code)
+        .bind(kind)
+        .bind(&country)
+        .bind(clicks)
+        .bind(baseline)
+        .bind(now.format(rfc3339).unwrap())
+        .fetch_one(&state.pool)
+        .await?;
+        found.push(anomaly);
+    }
+
+    let count = found.len();
+    let hook = settings
+        .anomaly_webhook_url
+        .map(|url| WebhookNotifier::new(url, WebhookFormat::Json));
+    for anomaly in found {
+        let event = Event::Anomaly { anomaly };
+        notify::deliver(state, &event, hook.as_ref().map(|h| h as &dyn notify::Notifier)).await;
+    }
+    Ok(count)
+}
+
+pub(crate) async fn recent_anomalies(state: &AppState, limit: i64) -> Result<Vec<Anomaly>, sqlx::Error> {
+    sqlx::query_as("SELECT * FROM anomalies ORDER BY id DESC LIMIT ?")
+        .bind(limit)
+        .fetch_all(&state.pool)
+        .await
+}
+
+#[derive(Deserialize)]
+pub(crate) struct AnomalyParams {
+    limit: Option<i64>,
+}
+
+/// Newest first. Admin only.
+pub(crate) async fn list_anomalies(
+    State(state): State<AppState>,
+    Query(params): Query<AnomalyParams>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<Anomaly>>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let limit = params.limit.unwrap_or(100).clamp(1, 1000);
+    recent_anomalies(&state, limit).await.map(Json).map_err(internal)
+}
@@ -0,0 +1,223 @@
+//! Named API keys for machine clients such as edge workers. Admins mint and
+//! revoke them; only a SHA-256 of each key is stored.
+
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    Json,
+};
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use time::{Duration, OffsetDateTime};
+
+use crate::{bans, internal, is_admin, is_unique_violation, require_admin, AppState};
+
+/// Header carrying an API key. `Authorization: Bearer <key>` works too.
+pub(crate) const API_KEY_HEADER: &str = "x-api-key";
+
+fn hash_key(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Hash of the key in [`API_KEY_HEADER`] or a bearer token, if any. Nothing
+/// is checked against the database.
+pub(crate) fn presented_key_hash(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(API_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .or_else(|| {
+            headers
+                .get(header::AUTHORIZATION)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.strip_prefix("Bearer "))
+        })
+        .map(hash_key)
+}
+
+/// Who authenticated a request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Caller {
+    /// The admin token.
+    Admin,
+    /// A named API key.
+    Key(String),
+}
+
+impl Caller {
+    /// How the caller is recorded in `created_by`. No key may be named
+    /// `admin`, so the two can't be confused.
+    pub(crate) fn name(&self) -> &str {
+        match self {
+            Caller::Admin => ADMIN_NAME,
+            Caller::Key(name) => name,
+        }
+    }
+}
+
+/// Name recorded for the admin token, and reserved for it.
+const ADMIN_NAME: &str = "admin";
+
+/// The API key presented with the request. The admin token is accepted
+/// everywhere an API key is.
+pub(crate) async fn require_api_key(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Result<Caller, (StatusCode, String)> {
+    caller(state, headers)
+        .await?
+        .ok_or_else(|| (StatusCode::UNAUTHORIZED, "API key required".to_string()))
+}
+
+/// Like [`require_api_key`] for endpoints open to anonymous clients: `None`
+/// when no key is presented, an error when the presented key is wrong or
+/// banned, or is used from a banned IP.
+pub(crate) async fn caller(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Result<Option<Caller>, (StatusCode, String)> {
+    if is_admin(state, headers) {
+        return Ok(Some(Caller::Admin));
+    }
+    let Some(key_hash) = presented_key_hash(headers) else {
+        return Ok(None);
+    };
+
+    let key: Option<(String, Option<String>)> =
+        sqlx::query_as("SELECT name, last_used_at FROM api_keys WHERE key_hash = ?")
+            .bind(&key_hash)
+            .fetch_optional(&state.pool)
+            .await
+            .map_err(internal)?;
+    let Some((name, last_used_at)) = key else {
+        return Err((StatusCode::UNAUTHORIZED, "invalid API key".to_string()));
+    };
+    touch(state, &key_hash, last_used_at.as_deref()).await;
+    let ip = state.client_ip(headers).unwrap_or_else(|| "local".to_string());
+    if let Some(until) = bans::active_ban(state, &ip, headers).await.map_err(internal)? {
+        return Err((StatusCode::FORBIDDEN, format!("banned until {until}")));
+    }
+    Ok(Some(Caller::Key(name)))
+}
+
+/// Resolution of `last_used_at`: a busy key writes it at most this often.
+const LAST_USED_RESOLUTION: Duration = Duration::minutes(1);
+
+/// Moves `last_used_at` to now when it is older than
+/// [`LAST_USED_RESOLUTION`]. Skipped in read-only mode; a failure only
+/// leaves the timestamp stale.
+async fn touch(state: &AppState, key_hash: &str, last_used_at: Option<&str>) {
+    let now = OffsetDateTime::now_utc();
+    let recent = last_used_at
+        .and_then(|at| OffsetDateTime::parse(at, &time::format_description::well_known::Rfc3339).ok())
+        .is_some_and(|at| now - at < LAST_USED_RESOLUTION);
+    if recent || state.is_read_only() {
+        return;
+    }
+    let now = now.format(&time::format_description::well_known::Rfc3339).unwrap();
+    if let Err(e) = sqlx::query("UPDATE api_keys SET last_used_at = ? WHERE key_hash = ?")
+        .bind(now)
+        .bind(key_hash)
+        .execute(&state.pool)
+        .await
+    {
+        tracing::warn!("recording API key use failed: {e}");
+    }
+}
+
+#[derive(Deserialize)]
+pub(crate) struct CreateKeyReq {
+    name: String,
+}
+
+#[derive(Serialize)]
+pub(crate) struct CreatedKey {
+    name: String,
+    /// Shown once; only its hash is kept.
+    key: String,
+}
+
+#[derive(Serialize)]
+pub(crate) struct KeyInfo {
+    name: String,
+    created_at: String,
+    last_used_at: Option<String>,
+}
+
+pub(crate) async fn create_key(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateKeyReq>,
+) -> Result<Json<CreatedKey>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let name = payload.name.trim().to_string();
+    if name.is_empty() || name.len() > 64 {
+        return Err((StatusCode::BAD_REQUEST, "name must be 1-64 characters".to_string()));
+    }
+    if name.eq_ignore_ascii_case(ADMIN_NAME) {
+        return Err((StatusCode::BAD_REQUEST, format!("{ADMIN_NAME} is reserved for the admin token")));
+    }
+    let key: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(40)
+        .map(char::from)
+        .collect();
+    let now = OffsetDateTime::now_utc()
+        .format(&time::format_description::well_known::Rfc3339)
+        .unwrap();
+    sqlx::query("INSERT INTO api_keys (name, key_hash, created_at) VALUES (?, ?, ?)")
+        .bind(&name)
+        .bind(hash_key(&key))
+        .bind(now)
+        .execute(&state.pool)
+        .await
+        .map_err(|e| {
+            if is_unique_violation(&e) {
+                (StatusCode::CONFLICT, "a key with this name exists".to_string())
+            } else {
+                internal(e)
+            }
+        })?;
+    Ok(Json(CreatedKey { name, key }))
+}
+
+pub(crate) async fn list_keys(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<KeyInfo>>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let rows: Vec<(String, String, Option<String>)> =
+        sqlx::query_as("SELECT name, created_at, last_used_at FROM api_keys ORDER BY name")
+            .fetch_all(&state.pool)
+            .await
+            .map_err(internal)?;
+    Ok(Json(
+        rows.into_iter()
+            .map(|(name, created_at, last_used_at)| KeyInfo {
+                name,
+                created_at,
+                last_used_at,
+            })
+            .collect(),
+    ))
+}
+
+pub(crate) async fn revoke_key(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let done = sqlx::query("DELETE FROM api_keys WHERE name = ?")
+        .bind(&name)
+        .execute(&state.pool)
+        .await
+        .map_err(internal)?;
+    if done.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "not found".to_string()));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,65 @@
+//! Append-only log of admin actions, written in the same transaction as the
+//! change it describes.
+
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::{internal, require_admin, AppState};
+
+/// Adds one entry. `subject` is what was acted on (a code, a domain, ...).
+pub(crate) async fn record(
+    conn: &mut sqlx::SqliteConnection,
+    actor: &str,
+    action: &str,
+    subject: &str,
+    detail: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    let at = OffsetDateTime::now_utc()
+        .format(&time::format_description::well_known::Rfc3339)
+        .unwrap();
+    sqlx::query("INSERT INTO audit_log (at, actor, action, subject, detail) VALUES (?, ?, ?, ?, ?)")
+        .bind(at)
+        .bind(actor)
+        .bind(action)
+        .bind(subject)
+        .bind(detail)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub(crate) struct AuditParams {
+    limit: Option<i64>,
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+pub(crate) struct AuditEntry {
+    at: String,
+    actor: String,
+    action: String,
+    subject: String,
+    detail: Option<String>,
+}
+
+/// `GET /api/admin/audit`: newest entries first.
+pub(crate) async fn list_audit(
+    State(state): State<AppState>,
+    Query(params): Query<AuditParams>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<AuditEntry>>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let entries = sqlx::query_as(
+        "SELECT at, actor, action, subject, detail FROM audit_log ORDER BY id DESC LIMIT ?",
+    )
+    .bind(params.limit.unwrap_or(100).clamp(1, 1000))
+    .fetch_all(&state.pool)
+    .await
+    .map_err(internal)?;
+    Ok(Json(entries))
+}
@@ -0,0 +1,100 @@
+//! Point-in-time snapshots of the SQLite database for single-node
+//! deployments. The server writes one every `BACKUP_INTERVAL_MINS` into
+//! `BACKUP_DIR` with `VACUUM INTO`, which gives a consistent copy without
+//! blocking readers, and keeps the newest `BACKUP_KEEP`. `restore` copies a
+//! snapshot back over the database file while the server is stopped. With an
+//! [`ObjectStore`](crate::ObjectStore) configured, each snapshot is also
+//! uploaded and the bucket keeps the newest `BACKUP_KEEP` as well.
+
+use std::path::{Path, PathBuf};
+
+use sqlx::{Pool, Sqlite};
+use time::OffsetDateTime;
+
+const PREFIX: &str = "snapshot-";
+const SUFFIX: &str = ".db";
+
+/// Writes a snapshot of `pool`'s database into `dir` and returns its path.
+/// Names sort by time, e.g. `snapshot-20261016T120000123Z.db`. In-memory
+/// databases write nothing.
+pub async fn snapshot_database(pool: &Pool<Sqlite>, dir: &Path) -> anyhow::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let now = OffsetDateTime::now_utc();
+    let stamp = format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}{:03}Z",
+        now.year(),
+        u8::from(now.month()),
+        now.day(),
+        now.hour(),
+        now.minute(),
+        now.second(),
+        now.millisecond()
+    );
+    let path = dir.join(format!("{PREFIX}{stamp}{SUFFIX}"));
+    let target = path
+        .to_str()
+        .ok_or_else(|| anyhow::anyhow!("backup path is not valid UTF-8: {}", path.display()))?;
+    sqlx::query("VACUUM INTO ?").bind(target).execute(pool).await?;
+    Ok(path)
+}
+
+/// Snapshots in `dir`, oldest first.
+pub fn list_snapshots(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut snapshots: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(PREFIX) && name.ends_with(SUFFIX))
+        })
+        .collect();
+    snapshots.sort();
+    Ok(snapshots)
+}
+
+/// Deletes all but the newest `keep` snapshots in `dir`; returns how many
+/// went.
+pub fn prune_snapshots(dir: &Path, keep: usize) -> std::io::Result<usize> {
+    let snapshots = list_snapshots(dir)?;
+    let excess = snapshots.len().saturating_sub(keep);
+    for path in &snapshots[..excess] {
+        std::fs::remove_file(path)?;
+    }
+    Ok(excess)
+}
+
+/// Uploads `snapshot` to `store`, then deletes all but the newest `keep`
+/// snapshots there; returns how many went.
+#[cfg(feature = "object-store")]
+pub async fn upload_snapshot(store: &crate::ObjectStore, snapshot: &Path, keep: usize) -> anyhow::Result<usize> {
+    store.upload_file(snapshot).await?;
+    store.prune(PREFIX, keep).await
+}
+
+/// Replaces the database file at `db_path` with `snapshot`. The server must
+/// be stopped: leftover `-wal` and `-shm` files belong to the old database
+/// and are removed.
+pub fn restore_snapshot(snapshot: &Path, db_path: &Path) -> anyhow::Result<()> {
+    if !snapshot.is_file() {
+        anyhow::bail!("no snapshot at {}", snapshot.display());
+    }
+    for suffix in ["-wal", "-shm"] {
+        let mut sidecar = db_path.as_os_str().to_owned();
+        sidecar.push(suffix);
+        match std::fs::remove_file(&sidecar) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+    }
+    std::fs::copy(snapshot, db_path)?;
+    Ok(())
+}
+
+/// File behind a `sqlite://` database URL, or `None` for in-memory ones.
+pub fn database_file(url: &str) -> Option<PathBuf> {
+    let path = url
+        .strip_prefix("sqlite://")
+        .or_else(|| url.strip_prefix("sqlite:"))?;
+    let path = path.split('?').next().unwrap_or(path);
+    (!path.is_empty() && path != ":memory:").then(|| PathBuf::from(path))
+}
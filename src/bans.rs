@@ -0,0 +1,183 @@
+//! Abuse response without a restart: admins see who the rate limiter is
+//! turning away and ban an IP or API key for a while. Bans apply to the
+//! rate-limited endpoints and to every request that presents an API key.
+//! Bans are stored, so they survive restarts.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use time::{Duration, OffsetDateTime};
+
+use crate::{api_keys, audit, internal, is_expired, require_admin, AppState, Offender};
+
+#[derive(Serialize, sqlx::FromRow)]
+pub(crate) struct Ban {
+    id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ip: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    api_key: Option<String>,
+    reason: Option<String>,
+    created_at: String,
+    expires_at: String,
+}
+
+impl Ban {
+    fn subject(&self) -> String {
+        match (&self.ip, &self.api_key) {
+            (Some(ip), _) => format!("ip:{ip}"),
+            (None, key) => format!("key:{}", key.as_deref().unwrap_or_default()),
+        }
+    }
+}
+
+/// End of the longest active ban on `ip` or on the API key presented in
+/// `headers`, if any.
+pub(crate) async fn active_ban(
+    state: &AppState,
+    ip: &str,
+    headers: &HeaderMap,
+) -> Result<Option<String>, sqlx::Error> {
+    let bans: Vec<(String,)> = sqlx::query_as(
+        "SELECT expires_at FROM rate_bans WHERE ip = ? \
+         OR api_key = (SELECT name FROM api_keys WHERE key_hash = ?)",
+    )
+    .bind(ip)
+    .bind(api_keys::presented_key_hash(headers))
+    .fetch_all(&state.pool)
+    .await?;
+    Ok(bans
+        .into_iter()
+        .map(|(until,)| until)
+        .filter(|until| !is_expired(Some(until)))
+        .max())
+}
+
+#[derive(Deserialize)]
+pub(crate) struct OffenderParams {
+    limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct RateLimitReport {
+    limit: usize,
+    window_secs: u64,
+    offenders: Vec<Offender>,
+    bans: Vec<Ban>,
+}
+
+/// `GET /api/admin/ratelimit`: the busiest clients of the current window and
+/// the bans in force. Admin only.
+pub(crate) async fn list_offenders(
+    State(state): State<AppState>,
+    Query(params): Query<OffenderParams>,
+    headers: HeaderMap,
+) -> Result<Json<RateLimitReport>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let (limit, window) = state.rate_limiter.limits();
+    let offenders = state
+        .rate_limiter
+        .offenders(params.limit.unwrap_or(20).clamp(1, 100))
+        .await;
+    let bans: Vec<Ban> = sqlx::query_as("SELECT * FROM rate_bans ORDER BY id")
+        .fetch_all(&state.pool)
+        .await
+        .map_err(internal)?;
+    Ok(Json(RateLimitReport {
+        limit,
+        window_secs: window.as_secs(),
+        offenders,
+        bans: bans
+            .into_iter()
+            .filter(|ban| !is_expired(Some(&ban.expires_at)))
+            .collect(),
+    }))
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct CreateBanReq {
+    ip: Option<String>,
+    /// Name of an API key.
+    api_key: Option<String>,
+    /// Defaults to an hour.
+    minutes: Option<i64>,
+    reason: Option<String>,
+}
+
+/// `POST /api/admin/ratelimit`: bans one IP or API key. Admin only.
+pub(crate) async fn create_ban(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<CreateBanReq>,
+) -> Result<(StatusCode, Json<Ban>), (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let bad_request = |msg: &str| (StatusCode::BAD_REQUEST, msg.to_string());
+    let trimmed = |value: Option<String>| value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    let (ip, api_key) = match (trimmed(req.ip), trimmed(req.api_key)) {
+        (Some(ip), None) => (Some(ip), None),
+        (None, Some(name)) => {
+            let known: Option<(String,)> = sqlx::query_as("SELECT name FROM api_keys WHERE name = ?")
+                .bind(&name)
+                .fetch_optional(&state.pool)
+                .await
+                .map_err(internal)?;
+            if known.is_none() {
+                return Err((StatusCode::NOT_FOUND, "API key not found".to_string()));
+            }
+            (None, Some(name))
+        }
+        _ => return Err(bad_request("set exactly one of ip and api_key")),
+    };
+    let minutes = req.minutes.unwrap_or(60);
+    if !(1..=60 * 24 * 365).contains(&minutes) {
+        return Err(bad_request("minutes must be between 1 and 525600"));
+    }
+
+    let now = OffsetDateTime::now_utc();
+    let format = |at: OffsetDateTime| at.format(&time::format_description::well_known::Rfc3339).unwrap();
+    let mut tx = state.pool.begin().await.map_err(internal)?;
+    let ban: Ban = sqlx::query_as(
+        "INSERT INTO rate_bans (ip, api_key, reason, created_at, expires_at) \
+         VALUES (?, ?, ?, ?, ?) RETURNING *",
+    )
+    .bind(ip)
+    .bind(api_key)
+    .bind(trimmed(req.reason))
+    .bind(format(now))
+    .bind(format(now + Duration::minutes(minutes)))
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(internal)?;
+    audit::record(&mut tx, "admin", "ratelimit.ban", &ban.subject(), Some(&ban.expires_at))
+        .await
+        .map_err(internal)?;
+    tx.commit().await.map_err(internal)?;
+    Ok((StatusCode::CREATED, Json(ban)))
+}
+
+/// `DELETE /api/admin/ratelimit/:id`: lifts a ban early. Admin only.
+pub(crate) async fn delete_ban(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let mut tx = state.pool.begin().await.map_err(internal)?;
+    let ban: Option<Ban> = sqlx::query_as("DELETE FROM rate_bans WHERE id = ? RETURNING *")
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(internal)?;
+    let Some(ban) = ban else {
+        return Err((StatusCode::NOT_FOUND, "not found".to_string()));
+    };
+    audit::record(&mut tx, "admin", "ratelimit.unban", &ban.subject(), None)
+        .await
+        .map_err(internal)?;
+    tx.commit().await.map_err(internal)?;
+    Ok(StatusCode::NO_CONTENT)
+}
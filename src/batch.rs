@@ -0,0 +1,113 @@
+//! `POST /api/shorten/batch`: many shorten requests in one call, for bulk
+//! imports by API clients. The whole batch counts as one request against the
+//! rate limiter, and every link that passes validation is inserted in a single
+//! transaction.
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::Serialize;
+
+use crate::{
+    api_keys::{require_api_key, Caller}, codes_exhausted, insert_url_with, internal, notify, prepare_link, tags, AppState,
+    CodeCandidates, InsertUrlError, PreparedLink, ShortenReq, ShortenResp,
+};
+
+#[derive(Serialize)]
+pub(crate) struct BatchResp {
+    created: usize,
+    failed: usize,
+    /// One entry per request item, in order.
+    results: Vec<BatchResult>,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum BatchResult {
+    Created(ShortenResp),
+    Failed { status: u16, error: String },
+}
+
+pub(crate) async fn shorten_batch(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(items): Json<Vec<ShortenReq>>,
+) -> Result<Json<BatchResp>, (StatusCode, String)> {
+    let caller = require_api_key(&state, &headers).await?;
+    let limit = state.settings().batch_shorten_limit;
+    if items.len() > limit {
+        return Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("at most {limit} links per batch"),
+        ));
+    }
+
+    // validate everything (templates are read here) before taking the write lock
+    let mut prepared = Vec::with_capacity(items.len());
+    for item in items {
+        prepared.push(prepare_link(&state, &headers, Some(&caller), item).await);
+    }
+
+    let mut tx = state.pool.begin().await.map_err(internal)?;
+    let mut results = Vec::with_capacity(prepared.len());
+    let mut events = Vec::new();
+    for link in prepared {
+        let result = match link {
+            Ok(link) => insert(&state, &mut tx, &caller, link, &mut events).await,
+            Err(e) => Err(e),
+        };
+        results.push(match result {
+            Ok(resp) => BatchResult::Created(resp),
+            // database failures abort the whole batch
+            Err(e) if e.0 == StatusCode::INTERNAL_SERVER_ERROR => return Err(e),
+            Err((status, error)) => BatchResult::Failed {
+                status: status.as_u16(),
+                error,
+            },
+        });
+    }
+    tx.commit().await.map_err(internal)?;
+    for event in events {
+        notify::spawn_link_event(&state, event);
+    }
+
+    let created = results
+        .iter()
+        .filter(|r| matches!(r, BatchResult::Created(_)))
+        .count();
+    Ok(Json(BatchResp {
+        created,
+        failed: results.len() - created,
+        results,
+    }))
+}
+
+/// `allocate_code` for a link inside the batch transaction. The link's
+/// created event goes to `events`, to be sent once the batch commits.
+async fn insert(
+    state: &AppState,
+    conn: &mut sqlx::SqliteConnection,
+    caller: &Caller,
+    link: PreparedLink,
+    events: &mut Vec<notify::Event>,
+) -> Result<ShortenResp, (StatusCode, String)> {
+    if let Some(existing) = link.find_existing(&mut *conn).await.map_err(internal)? {
+        return Ok(existing.reused_response(state));
+    }
+    let custom = link.custom_code.as_deref();
+    let mut candidates = CodeCandidates::new(state, Some(caller), custom)?;
+    while let Some(code) = candidates.next(&mut *conn).await? {
+        match insert_url_with(&mut *conn, &code, &link.new_url()).await {
+            Ok(()) => {
+                tags::attach(&mut *conn, &code, &link.tags).await.map_err(internal)?;
+                events.push(link.created_event(state, &code));
+                return Ok(link.response(state, code));
+            }
+            Err(InsertUrlError::CodeTaken) => continue,
+            Err(InsertUrlError::Other(e)) => return Err(internal(e)),
+        }
+    }
+    Err(codes_exhausted(custom))
+}
@@ -0,0 +1,253 @@
+//! Referrer classification: every click gets a traffic channel derived from
+//! its `Referer`, so stats can say "40% social" instead of listing URLs.
+//!
+//! The rules live in [`RULES`], checked top to bottom; the first match wins.
+//! A pattern containing a dot matches that host and its subdomains
+//! (`t.co`, `mail.google.com`); a bare name matches any label of the host
+//! (`google` covers `www.google.co.uk`). Put specific hosts before the
+//! bare names they would otherwise fall under.
+//!
+//! Stats also group referrers by domain ([`domain_breakdown`]), so one site
+//! shows up once however many of its pages link here.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::{target_domain, AppState};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Channel {
+    /// No referrer at all: typed, bookmarked, or from an app that strips it.
+    Direct,
+    /// From a page on this shortener itself.
+    Internal,
+    Search,
+    Social,
+    Email,
+    /// Any other website.
+    Referral,
+}
+
+impl Channel {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Channel::Direct => "direct",
+            Channel::Internal => "internal",
+            Channel::Search => "search",
+            Channel::Social => "social",
+            Channel::Email => "email",
+            Channel::Referral => "referral",
+        }
+    }
+
+    pub(crate) fn parse(s: &str) -> Option<Channel> {
+        [
+            Channel::Direct,
+            Channel::Internal,
+            Channel::Search,
+            Channel::Social,
+            Channel::Email,
+            Channel::Referral,
+        ]
+        .into_iter()
+        .find(|c| c.as_str() == s)
+    }
+}
+
+const RULES: &[(Channel, &[&str])] = &[
+    (
+        Channel::Email,
+        &[
+            "mail.google.com",
+            "com.google.android.gm",
+            "outlook.live.com",
+            "outlook.office.com",
+            "outlook.office365.com",
+            "mail.yahoo.com",
+            "mail.aol.com",
+            "mail.proton.me",
+            "mail.zoho.com",
+            "app.fastmail.com",
+            "webmail",
+        ],
+    ),
+    (
+        Channel::Search,
+        &[
+            "com.google.android.googlequicksearchbox",
+            "search.brave.com",
+            "google",
+            "bing",
+            "duckduckgo",
+            "yahoo",
+            "baidu",
+            "yandex",
+            "ecosia",
+            "startpage",
+            "qwant",
+            "kagi",
+        ],
+    ),
+    (
+        Channel::Social,
+        &[
+            "t.co",
+            "x.com",
+            "lnkd.in",
+            "t.me",
+            "news.ycombinator.com",
+            "mastodon.social",
+            "bsky.app",
+            "threads.net",
+            "facebook",
+            "fb",
+            "instagram",
+            "twitter",
+            "linkedin",
+            "reddit",
+            "pinterest",
+            "tiktok",
+            "youtube",
+            "whatsapp",
+            "telegram",
+            "snapchat",
+        ],
+    ),
+];
+
+/// Channel of a click whose `Referer` is `referer`. `own_hosts` are this
+/// shortener's host names.
+pub(crate) fn classify(referer: Option<&str>, own_hosts: &[&str]) -> Channel {
+    let referer = referer.map(str::trim).unwrap_or_default();
+    if referer.is_empty() {
+        return Channel::Direct;
+    }
+    match target_domain(referer) {
+        Some(host) => classify_host(&host, own_hosts),
+        None => Channel::Referral,
+    }
+}
+
+/// [`classify`] for a bare referrer host, as stored in click rollups. An
+/// empty host means there was no referrer.
+pub(crate) fn classify_host(host: &str, own_hosts: &[&str]) -> Channel {
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    if host.is_empty() {
+        return Channel::Direct;
+    }
+    if own_hosts.iter().any(|own| own.eq_ignore_ascii_case(&host)) {
+        return Channel::Internal;
+    }
+    let matches = |pattern: &str| {
+        if pattern.contains('.') {
+            host.strip_suffix(pattern).is_some_and(|rest| rest.is_empty() || rest.ends_with('.'))
+        } else {
+            host.split('.').any(|label| label == pattern)
+        }
+    };
+    RULES
+        .iter()
+        .find(|(_, patterns)| patterns.iter().any(|p| matches(p)))
+        .map_or(Channel::Referral, |&(channel, _)| channel)
+}
+
+/// Clicks per channel for `code`, busiest first, across both click tiers.
+/// Rollups and clicks recorded before channels existed are classified from
+/// their referrer.
+pub(crate) async fn breakdown(
+    state: &AppState,
+    code: &str,
+    include_bots: bool,
+) -> Result<Vec<(Channel, i64)>, sqlx::Error> {
+    let (human, rolled) = if include_bots { ("", "clicks") } else { (" AND NOT is_bot", "clicks - bots") };
+    let rows: Vec<(Option<String>, Option<String>, i64)> = sqlx::query_as(&format!(
+        "SELECT channel, NULL, count(*) FROM clicks WHERE code = ?1 AND channel IS NOT NULL{human} GROUP BY channel \
+         UNION ALL \
+         SELECT NULL, referer, count(*) FROM clicks WHERE code = ?1 AND channel IS NULL{human} GROUP BY referer \
+         UNION ALL \
+         SELECT NULL, referrer, sum({rolled}) FROM click_rollups WHERE code = ?1 GROUP BY referrer"
+    ))
+    .bind(code)
+    .fetch_all(&state.pool)
+    .await?;
+
+    let own_hosts: Vec<&str> = state.base_host.as_deref().into_iter().collect();
+    let mut counts: Vec<(Channel, i64)> = Vec::new();
+    for (channel, referrer, clicks) in rows {
+        if clicks == 0 {
+            continue;
+        }
+        let channel = match channel {
+            Some(stored) => Channel::parse(&stored).unwrap_or(Channel::Referral),
+            // Rollups keep just the host.
+            None if referrer.as_deref().is_some_and(|r| !r.contains('/')) => {
+                classify_host(referrer.as_deref().unwrap_or_default(), &own_hosts)
+            }
+            None => classify(referrer.as_deref(), &own_hosts),
+        };
+        match counts.iter_mut().find(|(c, _)| *c == channel) {
+            Some((_, total)) => *total += clicks,
+            None => counts.push((channel, clicks)),
+        }
+    }
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.as_str().cmp(b.0.as_str())));
+    Ok(counts)
+}
+
+/// Domain a `Referer` is grouped under; see [`host_domain`]. `None` when it
+/// is empty or unparsable.
+pub(crate) fn referrer_domain(referer: &str) -> Option<String> {
+    host_domain(&target_domain(referer.trim())?)
+}
+
+/// `host` lowercased, without `www.` and a trailing dot. Rollups store
+/// bare hosts, empty for no referrer.
+pub(crate) fn host_domain(host: &str) -> Option<String> {
+    let host = host.trim().trim_end_matches('.').to_ascii_lowercase();
+    let domain = host.strip_prefix("www.").unwrap_or(&host);
+    (!domain.is_empty()).then(|| domain.to_string())
+}
+
+/// Clicks per referrer domain for `code`, busiest first, across both click
+/// tiers; `None` collects clicks without a usable referrer. At most `limit`
+/// domains, plus that bucket.
+pub(crate) async fn domain_breakdown(
+    state: &AppState,
+    code: &str,
+    include_bots: bool,
+    limit: usize,
+) -> Result<Vec<(Option<String>, i64)>, sqlx::Error> {
+    let (human, rolled) = if include_bots { ("", "clicks") } else { (" AND NOT is_bot", "clicks - bots") };
+    let rows: Vec<(bool, Option<String>, i64)> = sqlx::query_as(&format!(
+        "SELECT 0, referer, count(*) FROM clicks WHERE code = ?1{human} GROUP BY referer \
+         UNION ALL \
+         SELECT 1, referrer, sum({rolled}) FROM click_rollups WHERE code = ?1 GROUP BY referrer"
+    ))
+    .bind(code)
+    .fetch_all(&state.pool)
+    .await?;
+
+    let mut unknown = 0;
+    let mut counts: HashMap<String, i64> = HashMap::new();
+    for (rolled_up, referrer, clicks) in rows {
+        let referrer = referrer.unwrap_or_default();
+        let domain = if rolled_up { host_domain(&referrer) } else { referrer_domain(&referrer) };
+        match domain {
+            Some(domain) => *counts.entry(domain).or_default() += clicks,
+            None => unknown += clicks,
+        }
+    }
+    let mut counts: Vec<(Option<String>, i64)> = counts
+        .into_iter()
+        .filter(|(_, clicks)| *clicks > 0)
+        .map(|(domain, clicks)| (Some(domain), clicks))
+        .collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    counts.truncate(limit);
+    if unknown > 0 {
+        counts.push((None, unknown));
+    }
+    Ok(counts)
+}
@@ -0,0 +1,279 @@
+//! Click webhooks: URLs that receive a signed JSON payload for every click,
+//! on one link or on all of them. Managed under `/api/webhooks`. Each
+//! webhook has its own secret; the body's HMAC-SHA256 under it is sent in
+//! `X-Shortener-Signature` so receivers can tell the payload came from here.
+//! Failed sends go to the webhook retry queue; see [`crate::retry_webhooks`].
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, OnceLock};
+use time::OffsetDateTime;
+use tokio::sync::{
+    mpsc::{self, error::TrySendError},
+    Mutex, RwLock,
+};
+
+use crate::{deliveries, fetch_link, internal, normalize_url, require_admin, webhook, AppState};
+
+/// Length of generated secrets.
+const SECRET_LEN: usize = 32;
+
+/// Deliveries waiting for a sender before clicks overflow into the retry
+/// queue.
+const QUEUE_LEN: usize = 1024;
+
+/// Deliveries in flight at once.
+const SENDERS: usize = 4;
+
+/// Body of a click webhook.
+#[derive(Serialize)]
+pub(crate) struct ClickPayload {
+    pub(crate) event: &'static str,
+    /// The click's id, for receivers to drop retried duplicates.
+    pub(crate) id: i64,
+    pub(crate) code: String,
+    pub(crate) at: String,
+    pub(crate) country: Option<String>,
+    /// The full `Referer` header.
+    pub(crate) referer: Option<String>,
+    pub(crate) bot: bool,
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+pub(crate) struct ClickWebhook {
+    id: i64,
+    /// `None` for every link.
+    code: Option<String>,
+    url: String,
+    /// Only returned when the webhook is created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    secret: Option<String>,
+    created_at: String,
+}
+
+/// The secret of webhook `id`, or `None` once it is deleted.
+pub(crate) async fn secret(state: &AppState, id: i64) -> Result<Option<String>, sqlx::Error> {
+    let secret: Option<(String,)> = sqlx::query_as("SELECT secret FROM click_webhooks WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&state.pool)
+        .await?;
+    Ok(secret.map(|(secret,)| secret))
+}
+
+/// A registered webhook, as cached by [`ClickWebhooks`].
+struct Hook {
+    id: i64,
+    code: Option<String>,
+    url: String,
+    secret: String,
+}
+
+/// One payload for one webhook, waiting in the delivery queue.
+struct Delivery {
+    hook_id: i64,
+    event: &'static str,
+    url: String,
+    secret: String,
+    payload: Arc<serde_json::Value>,
+}
+
+/// The registered webhooks and the queue their deliveries go through. The
+/// list is loaded on the first click and again after webhooks change through
+/// this instance's API, so clicks don't query for it. Deliveries are sent by
+/// [`SENDERS`] tasks sharing one HTTP client.
+#[derive(Clone, Default)]
+pub(crate) struct ClickWebhooks {
+    /// `None` until loaded, and again after a change.
+    hooks: Arc<RwLock<Option<Arc<Vec<Hook>>>>>,
+    queue: Arc<OnceLock<mpsc::Sender<Delivery>>>,
+}
+
+impl ClickWebhooks {
+    /// Drops the cached list; the next click reloads it.
+    pub(crate) async fn invalidate(&self) {
+        *self.hooks.write().await = None;
+    }
+
+    async fn hooks(&self, state: &AppState) -> Result<Arc<Vec<Hook>>, sqlx::Error> {
+        if let Some(hooks) = &*self.hooks.read().await {
+            return Ok(hooks.clone());
+        }
+        // loaded under the write lock, so an invalidation can't be overtaken
+        let mut cached = self.hooks.write().await;
+        if let Some(hooks) = &*cached {
+            return Ok(hooks.clone());
+        }
+        let rows: Vec<(i64, Option<String>, String, String)> =
+            sqlx::query_as("SELECT id, code, url, secret FROM click_webhooks ORDER BY id")
+                .fetch_all(&state.pool)
+                .await?;
+        let hooks = Arc::new(
+            rows.into_iter()
+                .map(|(id, code, url, secret)| Hook { id, code, url, secret })
+                .collect(),
+        );
+        *cached = Some(Arc::clone(&hooks));
+        Ok(hooks)
+    }
+
+    /// The queue's sending end, starting the senders on first use.
+    fn queue(&self, state: &AppState) -> &mpsc::Sender<Delivery> {
+        self.queue.get_or_init(|| {
+            let (sender, receiver) = mpsc::channel::<Delivery>(QUEUE_LEN);
+            let receiver = Arc::new(Mutex::new(receiver));
+            let client = webhook::Client::default();
+            for _ in 0..SENDERS {
+                let (state, receiver, client) = (state.clone(), receiver.clone(), client.clone());
+                tokio::spawn(async move {
+                    loop {
+                        let Some(delivery) = receiver.lock().await.recv().await else {
+                            return;
+                        };
+                        let sent = client
+                            .post_signed(&delivery.url, &delivery.payload, Some(&delivery.secret))
+                            .await;
+                        if let Err(e) = sent {
+                            tracing::warn!("click webhook {} failed: {e}", delivery.hook_id);
+                            retry_later(&state, &delivery, &e).await;
+                        }
+                    }
+                });
+            }
+            sender
+        })
+    }
+}
+
+/// Hands `delivery` to the webhook retry queue.
+async fn retry_later(state: &AppState, delivery: &Delivery, error: &str) {
+    let queued = deliveries::enqueue(
+        state,
+        delivery.event,
+        &delivery.url,
+        &delivery.payload,
+        error,
+        Some(delivery.hook_id),
+    )
+    .await;
+    if let Err(e) = queued {
+        tracing::warn!("queueing webhook retry failed: {e}");
+    }
+}
+
+/// Queues `click` for the webhooks of its link and the global ones. When the
+/// delivery queue is full, deliveries go straight to the retry queue.
+pub(crate) async fn dispatch(state: &AppState, click: ClickPayload) -> Result<(), sqlx::Error> {
+    let hooks = state.click_webhooks.hooks(state).await?;
+    let matching: Vec<&Hook> = hooks
+        .iter()
+        .filter(|hook| hook.code.as_deref().is_none_or(|code| code == click.code))
+        .collect();
+    if matching.is_empty() {
+        return Ok(());
+    }
+    let payload = Arc::new(serde_json::to_value(&click).unwrap_or_default());
+    let queue = state.click_webhooks.queue(state);
+    for hook in matching {
+        let delivery = Delivery {
+            hook_id: hook.id,
+            event: click.event,
+            url: hook.url.clone(),
+            secret: hook.secret.clone(),
+            payload: payload.clone(),
+        };
+        if let Err(e) = queue.try_send(delivery) {
+            let delivery = match e {
+                TrySendError::Full(delivery) | TrySendError::Closed(delivery) => delivery,
+            };
+            tracing::warn!("click webhook queue full, queueing webhook {} for retry", delivery.hook_id);
+            retry_later(state, &delivery, "delivery queue full").await;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct CreateWebhookReq {
+    url: String,
+    /// Only clicks on this link; every link when absent.
+    code: Option<String>,
+}
+
+/// `GET /api/webhooks`: every click webhook, without secrets. Admin only.
+pub(crate) async fn list_webhooks(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<ClickWebhook>>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let hooks = sqlx::query_as("SELECT id, code, url, NULL AS secret, created_at FROM click_webhooks ORDER BY id")
+        .fetch_all(&state.pool)
+        .await
+        .map_err(internal)?;
+    Ok(Json(hooks))
+}
+
+/// `POST /api/webhooks`: registers a webhook and returns it with its
+/// secret, which is not shown again. Admin only.
+pub(crate) async fn create_webhook(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<CreateWebhookReq>,
+) -> Result<(StatusCode, Json<ClickWebhook>), (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let url = normalize_url(&req.url).map_err(|e| (StatusCode::BAD_REQUEST, format!("url {e}")))?;
+    let code = match req.code {
+        Some(code) => {
+            let Some(link) = fetch_link(&state, &code).await.map_err(internal)? else {
+                return Err((StatusCode::NOT_FOUND, "link not found".to_string()));
+            };
+            Some(link.code)
+        }
+        None => None,
+    };
+    let secret: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(SECRET_LEN)
+        .map(char::from)
+        .collect();
+    let now = OffsetDateTime::now_utc()
+        .format(&time::format_description::well_known::Rfc3339)
+        .unwrap();
+    let hook = sqlx::query_as(
+        "INSERT INTO click_webhooks (code, url, secret, created_at) VALUES (?, ?, ?, ?) RETURNING *",
+    )
+    .bind(code)
+    .bind(url)
+    .bind(secret)
+    .bind(now)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(internal)?;
+    state.click_webhooks.invalidate().await;
+    Ok((StatusCode::CREATED, Json(hook)))
+}
+
+/// `DELETE /api/webhooks/:id`. Queued retries of it are dead-lettered on
+/// their next attempt. Admin only.
+pub(crate) async fn delete_webhook(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let done = sqlx::query("DELETE FROM click_webhooks WHERE id = ?")
+        .bind(id)
+        .execute(&state.pool)
+        .await
+        .map_err(internal)?;
+    if done.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "not found".to_string()));
+    }
+    state.click_webhooks.invalidate().await;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,127 @@
+//! `GET /api/links/:code/clicks.csv`: the raw click rows of a link as CSV,
+//! for analysis in spreadsheets beyond what the JSON stats aggregate. Rows
+//! are read in batches and streamed, so large histories don't sit in memory.
+//! Clicks already rolled up into daily totals have no rows left to export.
+
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use futures_util::{stream, StreamExt};
+use sqlx::{Pool, Sqlite};
+
+use crate::{fetch_link, internal, require_admin, AppState};
+
+/// Rows fetched per query.
+const BATCH: i64 = 1000;
+
+const HEADER: &str = "at,ip,country,city,user_agent,referer\r\n";
+
+#[derive(sqlx::FromRow)]
+struct ClickRow {
+    id: i64,
+    at: String,
+    ip: Option<String>,
+    country: Option<String>,
+    city: Option<String>,
+    user_agent: Option<String>,
+    referer: Option<String>,
+}
+
+/// Appends `value` as one CSV field. Fields with separators, quotes or line
+/// breaks are quoted; fields a spreadsheet would run as a formula get a
+/// leading `'`, since user agents and referers come from visitors.
+fn push_field(out: &mut String, value: &str) {
+    let formula = value.starts_with(['=', '+', '-', '@', '\t', '\r']);
+    if formula || value.contains([',', '"', '\r', '\n']) {
+        out.push('"');
+        if formula {
+            out.push('\'');
+        }
+        out.push_str(&value.replace('"', "\"\""));
+        out.push('"');
+    } else {
+        out.push_str(value);
+    }
+}
+
+fn push_row(out: &mut String, row: &ClickRow) {
+    let fields = [
+        Some(row.at.as_str()),
+        row.ip.as_deref(),
+        row.country.as_deref(),
+        row.city.as_deref(),
+        row.user_agent.as_deref(),
+        row.referer.as_deref(),
+    ];
+    for (i, field) in fields.into_iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        push_field(out, field.unwrap_or_default());
+    }
+    out.push_str("\r\n");
+}
+
+/// Clicks of `code` with an id above `after`, oldest first.
+async fn batch(pool: &Pool<Sqlite>, code: &str, after: i64) -> Result<Vec<ClickRow>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT id, strftime('%Y-%m-%dT%H:%M:%SZ', at, 'unixepoch') AS at, ip, country, city, user_agent, referer \
+         FROM clicks WHERE code = ? AND id > ? ORDER BY id LIMIT ?",
+    )
+    .bind(code)
+    .bind(after)
+    .bind(BATCH)
+    .fetch_all(pool)
+    .await
+}
+
+/// `GET /api/links/:code/clicks.csv`: every stored click of a link. Admin
+/// only, as rows carry visitor IPs.
+pub(crate) async fn clicks_csv(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let Some(link) = fetch_link(&state, &code).await.map_err(internal)? else {
+        return Err((StatusCode::NOT_FOUND, "not found".to_string()));
+    };
+    let filename = format!("attachment; filename=\"{}-clicks.csv\"", link.code);
+
+    let pool = state.pool.clone();
+    let code = link.code;
+    // `None` once the last batch was sent
+    let rows = stream::unfold(Some(0), move |after| {
+        let pool = pool.clone();
+        let code = code.clone();
+        async move {
+            let rows = match batch(&pool, &code, after?).await {
+                Ok(rows) if rows.is_empty() => return None,
+                Ok(rows) => rows,
+                Err(e) => {
+                    tracing::error!("click export of {code} failed: {e}");
+                    return Some((Err(e), None));
+                }
+            };
+            let next = (rows.len() as i64 == BATCH).then(|| rows[rows.len() - 1].id);
+            let mut chunk = String::new();
+            for row in &rows {
+                push_row(&mut chunk, row);
+            }
+            Some((Ok(chunk), next))
+        }
+    });
+    let body = stream::once(async { Ok(HEADER.to_string()) }).chain(rows);
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, filename),
+        ],
+        Body::from_stream(body),
+    )
+        .into_response())
+}
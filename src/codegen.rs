@@ -0,0 +1,255 @@
+//! How codes for new links are picked when the caller doesn't choose one.
+//! [`RandomCodes`] is the default; [`SequentialCodes`], [`BlockCodes`] and
+//! [`WordlistCodes`] ship as alternatives, and embedders can plug in their own
+//! [`CodeGenerator`] with [`AppStateBuilder::code_generator`].
+//!
+//! [`AppStateBuilder::code_generator`]: crate::AppStateBuilder::code_generator
+
+use std::ops::Range;
+
+use async_trait::async_trait;
+use rand::{seq::SliceRandom, Rng};
+use sqlx::SqliteConnection;
+use tokio::sync::Mutex;
+
+#[async_trait]
+pub trait CodeGenerator: Send + Sync {
+    /// A candidate code for a new link. When it is taken or reserved the
+    /// caller asks again, a few times at most, so generators must not keep
+    /// returning the same code. `conn` may be inside a transaction.
+    async fn generate(&self, conn: &mut SqliteConnection) -> Result<String, sqlx::Error>;
+}
+
+/// Random characters from an alphabet; seven alphanumeric ones by default.
+/// Longer codes and bigger alphabets make collisions rarer, smaller ones
+/// make codes easier to read out and type.
+#[derive(Clone, Debug)]
+pub struct RandomCodes {
+    length: usize,
+    alphabet: Vec<char>,
+}
+
+impl RandomCodes {
+    pub const ALPHANUMERIC: &'static str = "0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+    /// For go-links and anything typed on a phone.
+    pub const LOWERCASE: &'static str = "0123456789abcdefghijklmnopqrstuvwxyz";
+    /// Alphanumeric without the easily confused `0`, `O`, `o`, `1`, `l` and `I`.
+    pub const UNAMBIGUOUS: &'static str = "23456789abcdefghijkmnpqrstuvwxyzABCDEFGHJKLMNPQRSTUVWXYZ";
+
+    /// `length` must be 3-32 and `alphabet` at least two distinct ASCII
+    /// letters or digits, so generated codes pass as custom codes would.
+    pub fn new(length: usize, alphabet: &str) -> Result<Self, String> {
+        if !(3..=32).contains(&length) {
+            return Err("code length must be 3-32".to_string());
+        }
+        let mut chars: Vec<char> = alphabet.chars().collect();
+        if !chars.iter().all(char::is_ascii_alphanumeric) {
+            return Err("code alphabet may only contain ASCII letters and digits".to_string());
+        }
+        chars.sort_unstable();
+        chars.dedup();
+        if chars.len() < 2 {
+            return Err("code alphabet needs at least two characters".to_string());
+        }
+        Ok(Self { length, alphabet: chars })
+    }
+
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    pub fn alphabet(&self) -> String {
+        self.alphabet.iter().collect()
+    }
+}
+
+impl Default for RandomCodes {
+    fn default() -> Self {
+        Self::new(7, Self::ALPHANUMERIC).unwrap()
+    }
+}
+
+#[async_trait]
+impl CodeGenerator for RandomCodes {
+    async fn generate(&self, _conn: &mut SqliteConnection) -> Result<String, sqlx::Error> {
+        let mut rng = rand::thread_rng();
+        Ok((0..self.length)
+            .map(|_| *self.alphabet.choose(&mut rng).unwrap())
+            .collect())
+    }
+}
+
+const BASE62: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+pub(crate) fn base62(mut n: u64) -> String {
+    let mut digits = Vec::new();
+    loop {
+        digits.push(BASE62[(n % 62) as usize]);
+        n /= 62;
+        if n == 0 {
+            break;
+        }
+    }
+    digits.reverse();
+    String::from_utf8(digits).unwrap()
+}
+
+/// Short codes counting up in base62 (`1`, `2`, ... `z`, `A`, ... `10`).
+/// The counter lives in the database, so codes never repeat across restarts
+/// or instances; it starts after the highest link id. Taken codes are
+/// skipped.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SequentialCodes {
+    offset: u64,
+    key: Option<u64>,
+}
+
+impl SequentialCodes {
+    /// Multiplier of the [`SequentialCodes::obfuscated`] permutation; odd and
+    /// not a multiple of 31, so it is invertible modulo every power of 62.
+    const MULTIPLIER: u128 = 2_147_483_647;
+
+    /// Added to every counter value, e.g. `238_328` (62^3) for codes of at
+    /// least four characters.
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Scrambles each code within its length using `key`, so codes don't
+    /// reveal how many links exist or which comes next. Still one code per
+    /// counter value.
+    pub fn obfuscated(mut self, key: u64) -> Self {
+        self.key = Some(key);
+        self
+    }
+
+    fn encode(&self, n: u64) -> String {
+        let Some(key) = self.key else {
+            return base62(n);
+        };
+        let width = base62(n).len();
+        let space = 62u128.pow(width as u32);
+        let mut m = (u128::from(n) * Self::MULTIPLIER + u128::from(key)) % space;
+        let mut digits = vec![BASE62[0]; width];
+        for digit in digits.iter_mut().rev() {
+            *digit = BASE62[(m % 62) as usize];
+            m /= 62;
+        }
+        String::from_utf8(digits).unwrap()
+    }
+}
+
+/// Takes the next `count` values of the shared link counter in one
+/// statement and returns them.
+async fn reserve(conn: &mut SqliteConnection, count: u64) -> Result<Range<u64>, sqlx::Error> {
+    let (last,): (i64,) = sqlx::query_as(
+        "INSERT INTO code_sequence (name, value) \
+         SELECT 'links', coalesce(max(id), 0) + ?1 FROM urls WHERE true \
+         ON CONFLICT(name) DO UPDATE SET value = value + ?1 RETURNING value",
+    )
+    .bind(count as i64)
+    .fetch_one(&mut *conn)
+    .await?;
+    let end = last as u64 + 1;
+    Ok(end - count..end)
+}
+
+#[async_trait]
+impl CodeGenerator for SequentialCodes {
+    async fn generate(&self, conn: &mut SqliteConnection) -> Result<String, sqlx::Error> {
+        let n = reserve(conn, 1).await?.start;
+        Ok(self.encode(n.saturating_add(self.offset)))
+    }
+}
+
+/// [`SequentialCodes`] handed out from blocks of the shared counter, for
+/// several instances on one database: each reserves a block with a single
+/// write and numbers links from it in memory, so instances never race for
+/// the same code and the counter row is written once per block rather than
+/// once per link. Codes stay unique but are not in creation order across
+/// instances, and the unused rest of a block is skipped on restart.
+pub struct BlockCodes {
+    codes: SequentialCodes,
+    block_size: u64,
+    block: Mutex<Range<u64>>,
+}
+
+impl BlockCodes {
+    /// `block_size` values are reserved at a time; at least 1.
+    pub fn new(codes: SequentialCodes, block_size: u64) -> Self {
+        Self {
+            codes,
+            block_size: block_size.max(1),
+            block: Mutex::new(0..0),
+        }
+    }
+}
+
+#[async_trait]
+impl CodeGenerator for BlockCodes {
+    async fn generate(&self, conn: &mut SqliteConnection) -> Result<String, sqlx::Error> {
+        let mut block = self.block.lock().await;
+        let n = match block.next() {
+            Some(n) => n,
+            None => {
+                *block = reserve(conn, self.block_size).await?;
+                block.next().expect("reserved blocks are never empty")
+            }
+        };
+        Ok(self.codes.encode(n.saturating_add(self.codes.offset)))
+    }
+}
+
+const ADJECTIVES: &[&str] = &[
+    "amber", "bold", "brave", "bright", "calm", "clever", "cosmic", "crisp", "dusty", "eager",
+    "fancy", "gentle", "golden", "happy", "jolly", "kind", "lucky", "mellow", "misty", "noble",
+    "proud", "quick", "quiet", "rapid", "royal", "shiny", "silent", "silver", "sunny", "swift",
+    "tidy", "witty", "blue", "green", "red", "purple",
+];
+
+const NOUNS: &[&str] = &[
+    "badger", "bear", "cactus", "canyon", "cloud", "comet", "coral", "falcon", "forest", "fox",
+    "glacier", "harbor", "island", "koala", "lemon", "lion", "maple", "meadow", "otter", "owl",
+    "panda", "pebble", "pepper", "planet", "raven", "river", "rocket", "salmon", "tiger", "tulip",
+    "walrus", "willow", "wolf", "zebra",
+];
+
+/// Memorable `adjective-noun-NN` codes such as `blue-tiger-42`, from a
+/// built-in list or your own.
+pub struct WordlistCodes {
+    adjectives: Vec<String>,
+    nouns: Vec<String>,
+}
+
+impl WordlistCodes {
+    /// Panics when either list is empty.
+    pub fn new(
+        adjectives: impl IntoIterator<Item = impl Into<String>>,
+        nouns: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        let adjectives: Vec<String> = adjectives.into_iter().map(Into::into).collect();
+        let nouns: Vec<String> = nouns.into_iter().map(Into::into).collect();
+        assert!(!adjectives.is_empty() && !nouns.is_empty(), "word lists must not be empty");
+        Self { adjectives, nouns }
+    }
+}
+
+impl Default for WordlistCodes {
+    fn default() -> Self {
+        Self::new(ADJECTIVES.iter().copied(), NOUNS.iter().copied())
+    }
+}
+
+#[async_trait]
+impl CodeGenerator for WordlistCodes {
+    async fn generate(&self, _conn: &mut SqliteConnection) -> Result<String, sqlx::Error> {
+        let mut rng = rand::thread_rng();
+        Ok(format!(
+            "{}-{}-{}",
+            self.adjectives.choose(&mut rng).unwrap(),
+            self.nouns.choose(&mut rng).unwrap(),
+            rng.gen_range(10..100)
+        ))
+    }
+}
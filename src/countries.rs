@@ -0,0 +1,1490 @@
+//! Country names for stats: clicks store ISO 3166-1 alpha-2 codes, and
+//! responses add the country's name in the requested `?locale=` and its flag.
+//!
+//! The names come from the Debian `iso-codes` tables; a locale without its
+//! own translation of a name uses the English one.
+//!
+//! Countries also roll up into continents and the EU/EEA, for regional
+//! totals. Transcontinental countries count once, under the continent they
+//! are usually listed in (Russia in Europe, Turkey in Asia).
+
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+
+/// Supported `?locale=` values, in the column order of [`COUNTRIES`].
+const LOCALES: [&str; 7] = ["en", "de", "es", "fr", "it", "pt", "ro"];
+
+/// A language from [`LOCALES`]; English by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Locale(usize);
+
+impl Locale {
+    /// Matches the primary language of a tag such as `de` or `pt-BR`.
+    pub(crate) fn parse(tag: &str) -> Result<Locale, (StatusCode, String)> {
+        let language = tag.split(['-', '_']).next().unwrap_or_default().trim().to_ascii_lowercase();
+        LOCALES
+            .iter()
+            .position(|l| *l == language)
+            .map(Locale)
+            .ok_or_else(|| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!("unsupported locale {tag:?}; use one of {}", LOCALES.join(", ")),
+                )
+            })
+    }
+}
+
+/// `?locale=` of the stats endpoints.
+#[derive(Deserialize, Default)]
+pub(crate) struct LocaleParams {
+    locale: Option<String>,
+}
+
+impl LocaleParams {
+    pub(crate) fn locale(&self) -> Result<Locale, (StatusCode, String)> {
+        self.locale.as_deref().map_or(Ok(Locale::default()), Locale::parse)
+    }
+}
+
+/// A country code with its display name and flag. Codes outside ISO 3166-1
+/// (such as Cloudflare's `XX` and `T1`) keep only the code.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Country {
+    pub(crate) code: String,
+    pub(crate) name: Option<&'static str>,
+    pub(crate) flag: Option<String>,
+}
+
+impl Country {
+    pub(crate) fn new(code: &str, locale: Locale) -> Country {
+        let code = code.trim().to_ascii_uppercase();
+        let name = COUNTRIES
+            .binary_search_by(|(c, _)| (*c).cmp(code.as_str()))
+            .ok()
+            .map(|i| COUNTRIES[i].1[locale.0]);
+        Country {
+            flag: name.map(|_| flag(&code)),
+            name,
+            code,
+        }
+    }
+
+    /// Flag and name, or the bare code for unknown countries.
+    #[cfg(feature = "dashboard")]
+    pub(crate) fn label(&self) -> String {
+        match (&self.flag, self.name) {
+            (Some(flag), Some(name)) => format!("{flag} {name}"),
+            _ => self.code.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Continent {
+    Africa,
+    Antarctica,
+    Asia,
+    Europe,
+    NorthAmerica,
+    Oceania,
+    SouthAmerica,
+}
+
+impl Continent {
+    /// The continent of an alpha-2 country code; `None` for codes outside
+    /// ISO 3166-1.
+    pub(crate) fn of(code: &str) -> Option<Continent> {
+        let code = code.trim().to_ascii_uppercase();
+        CONTINENTS
+            .iter()
+            .find(|(_, codes)| codes.binary_search(&code.as_str()).is_ok())
+            .map(|(continent, _)| *continent)
+    }
+
+    pub(crate) fn name(self, locale: Locale) -> &'static str {
+        let names = match self {
+            Continent::Africa => ["Africa", "Afrika", "África", "Afrique", "Africa", "África", "Africa"],
+            Continent::Antarctica => [
+                "Antarctica",
+                "Antarktis",
+                "Antártida",
+                "Antarctique",
+                "Antartide",
+                "Antártida",
+                "Antarctica",
+            ],
+            Continent::Asia => ["Asia", "Asien", "Asia", "Asie", "Asia", "Ásia", "Asia"],
+            Continent::Europe => ["Europe", "Europa", "Europa", "Europe", "Europa", "Europa", "Europa"],
+            Continent::NorthAmerica => [
+                "North America",
+                "Nordamerika",
+                "América del Norte",
+                "Amérique du Nord",
+                "America settentrionale",
+                "América do Norte",
+                "America de Nord",
+            ],
+            Continent::Oceania => ["Oceania", "Ozeanien", "Oceanía", "Océanie", "Oceania", "Oceania", "Oceania"],
+            Continent::SouthAmerica => [
+                "South America",
+                "Südamerika",
+                "América del Sur",
+                "Amérique du Sud",
+                "America meridionale",
+                "América do Sul",
+                "America de Sud",
+            ],
+        };
+        names[locale.0]
+    }
+}
+
+/// Member states of the European Union, sorted.
+const EU: &[&str] = &[
+    "AT", "BE", "BG", "CY", "CZ", "DE", "DK", "EE", "ES", "FI", "FR", "GR", "HR", "HU", "IE",
+    "IT", "LT", "LU", "LV", "MT", "NL", "PL", "PT", "RO", "SE", "SI", "SK",
+];
+
+/// Whether an alpha-2 code is an EU member state.
+pub(crate) fn in_eu(code: &str) -> bool {
+    EU.binary_search(&code.trim().to_ascii_uppercase().as_str()).is_ok()
+}
+
+/// Whether an alpha-2 code is in the European Economic Area: the EU plus
+/// Iceland, Liechtenstein and Norway.
+pub(crate) fn in_eea(code: &str) -> bool {
+    in_eu(code) || ["IS", "LI", "NO"].contains(&code.trim().to_ascii_uppercase().as_str())
+}
+
+/// Alpha-2 codes per continent, each list sorted.
+const CONTINENTS: &[(Continent, &[&str])] = &[
+    (
+        Continent::Africa,
+        &[
+            "AO", "BF", "BI", "BJ", "BW", "CD", "CF", "CG", "CI", "CM", "CV", "DJ", "DZ", "EG", "EH",
+            "ER", "ET", "GA", "GH", "GM", "GN", "GQ", "GW", "KE", "KM", "LR", "LS", "LY", "MA", "MG",
+            "ML", "MR", "MU", "MW", "MZ", "NA", "NE", "NG", "RE", "RW", "SC", "SD", "SH", "SL", "SN",
+            "SO", "SS", "ST", "SZ", "TD", "TG", "TN", "TZ", "UG", "YT", "ZA", "ZM", "ZW",
+        ],
+    ),
+    (
+        Continent::Antarctica,
+        &[
+            "AQ", "BV", "GS", "HM", "TF",
+        ],
+    ),
+    (
+        Continent::Asia,
+        &[
+            "AE", "AF", "AM", "AZ", "BD", "BH", "BN", "BT", "CC", "CN", "CX", "GE", "HK", "ID", "IL",
+            "IN", "IO", "IQ", "IR", "JO", "JP", "KG", "KH", "KP", "KR", "KW", "KZ", "LA", "LB", "LK",
+            "MM", "MN", "MO", "MV", "MY", "NP", "OM", "PH", "PK", "PS", "QA", "SA", "SG", "SY", "TH",
+            "TJ", "TL", "TM", "TR", "TW", "UZ", "VN", "YE",
+        ],
+    ),
+    (
+        Continent::Europe,
+        &[
+            "AD", "AL", "AT", "AX", "BA", "BE", "BG", "BY", "CH", "CY", "CZ", "DE", "DK", "EE", "ES",
+            "FI", "FO", "FR", "GB", "GG", "GI", "GR", "HR", "HU", "IE", "IM", "IS", "IT", "JE", "LI",
+            "LT", "LU", "LV", "MC", "MD", "ME", "MK", "MT", "NL", "NO", "PL", "PT", "RO", "RS", "RU",
+            "SE", "SI", "SJ", "SK", "SM", "UA", "VA",
+        ],
+    ),
+    (
+        Continent::NorthAmerica,
+        &[
+            "AG", "AI", "AW", "BB", "BL", "BM", "BQ", "BS", "BZ", "CA", "CR", "CU", "CW", "DM", "DO",
+            "GD", "GL", "GP", "GT", "HN", "HT", "JM", "KN", "KY", "LC", "MF", "MQ", "MS", "MX", "NI",
+            "PA", "PM", "PR", "SV", "SX", "TC", "TT", "US", "VC", "VG", "VI",
+        ],
+    ),
+    (
+        Continent::Oceania,
+        &[
+            "AS", "AU", "CK", "FJ", "FM", "GU", "KI", "MH", "MP", "NC", "NF", "NR", "NU", "NZ", "PF",
+            "PG", "PN", "PW", "SB", "TK", "TO", "TV", "UM", "VU", "WF", "WS",
+        ],
+    ),
+    (
+        Continent::SouthAmerica,
+        &[
+            "AR", "BO", "BR", "CL", "CO", "EC", "FK", "GF", "GY", "PE", "PY", "SR", "UY", "VE",
+        ],
+    ),
+];
+
+/// The flag emoji of a two-letter country code: its letters as regional
+/// indicator symbols.
+fn flag(code: &str) -> String {
+    code.chars()
+        .filter_map(|c| char::from_u32(0x1F1E6 + (c as u32).checked_sub('A' as u32)?))
+        .collect()
+}
+
+/// Alpha-2 code and names in [`LOCALES`] order, sorted by code.
+const COUNTRIES: &[(&str, [&str; 7])] = &[
+    ("AD", ["Andorra", "Andorra", "Andorra", "Andorre", "Andorra", "Andorra", "Andora"]),
+    (
+        "AE",
+        [
+            "United Arab Emirates",
+            "Vereinigte Arabische Emirate",
+            "Emiratos Árabes Unidos",
+            "Émirats arabes unis",
+            "Emirati Arabi Uniti",
+            "Emirados Árabes Unidos",
+            "Emiratele Arabe Unite",
+        ],
+    ),
+    (
+        "AF",
+        [
+            "Afghanistan",
+            "Afghanistan",
+            "Afganistán",
+            "Afghanistan",
+            "Afghanistan",
+            "Afeganistão",
+            "Afganistan",
+        ],
+    ),
+    (
+        "AG",
+        [
+            "Antigua and Barbuda",
+            "Antigua und Barbuda",
+            "Antigua y Barbuda",
+            "Antigua-et-Barbuda",
+            "Antigua e Barbuda",
+            "Antígua e Barbuda",
+            "Antigua și Barbuda",
+        ],
+    ),
+    ("AI", ["Anguilla", "Anguilla", "Anguila", "Anguilla", "Anguilla", "Anguilla", "Anguilla"]),
+    ("AL", ["Albania", "Albanien", "Albania", "Albanie", "Albania", "Albânia", "Albania"]),
+    ("AM", ["Armenia", "Armenien", "Armenia", "Arménie", "Armenia", "Arménia", "Armenia"]),
+    ("AO", ["Angola", "Angola", "Angola", "Angola", "Angola", "Angola", "Angola"]),
+    ("AQ", ["Antarctica", "Antarktis", "Antártida", "Antarctique", "Antartide", "Antártida", "Antarctica"]),
+    ("AR", ["Argentina", "Argentinien", "Argentina", "Argentine", "Argentina", "Argentina", "Argentina"]),
+    (
+        "AS",
+        [
+            "American Samoa",
+            "Amerikanisch-Samoa",
+            "Samoa Estadounidense",
+            "Samoa américaines",
+            "Samoa americane",
+            "Samoa Americana",
+            "Samoa americană",
+        ],
+    ),
+    ("AT", ["Austria", "Österreich", "Austria", "Autriche", "Austria", "Áustria", "Austria"]),
+    ("AU", ["Australia", "Australien", "Australia", "Australie", "Australia", "Austrália", "Australia"]),
+    ("AW", ["Aruba", "Aruba", "Aruba", "Aruba", "Aruba", "Aruba", "Aruba"]),
+    (
+        "AX",
+        [
+            "Åland Islands",
+            "Åland-Inseln",
+            "Islas Äland",
+            "Åland, Îles",
+            "Isole Åland",
+            "Ilhas Alanda",
+            "Insulele Åland",
+        ],
+    ),
+    (
+        "AZ",
+        [
+            "Azerbaijan",
+            "Aserbaidschan",
+            "Azerbaiyán",
+            "Azerbaïdjan",
+            "Azerbaigian",
+            "Azerbaijão",
+            "Azerbaijan",
+        ],
+    ),
+    (
+        "BA",
+        [
+            "Bosnia and Herzegovina",
+            "Bosnien und Herzegowina",
+            "Bosnia y Herzegovina",
+            "Bosnie-Herzégovine",
+            "Bosnia-Erzegovina",
+            "Bósnia e Herzegovina",
+            "Bosnia și Herțegovina",
+        ],
+    ),
+    ("BB", ["Barbados", "Barbados", "Barbados", "Barbade", "Barbados", "Barbados", "Barbados"]),
+    (
+        "BD",
+        [
+            "Bangladesh",
+            "Bangladesch",
+            "Bangladés",
+            "Bangladesh",
+            "Bangladesh",
+            "Bangladeche",
+            "Bangladeș",
+        ],
+    ),
+    ("BE", ["Belgium", "Belgien", "Bélgica", "Belgique", "Belgio", "Bélgica", "Belgia"]),
+    (
+        "BF",
+        [
+            "Burkina Faso",
+            "Burkina Faso",
+            "Burquina Faso",
+            "Burkina Faso",
+            "Burkina Faso",
+            "Burkina Faso",
+            "Burkina Faso",
+        ],
+    ),
+    ("BG", ["Bulgaria", "Bulgarien", "Bulgaria", "Bulgarie", "Bulgaria", "Bulgária", "Bulgaria"]),
+    ("BH", ["Bahrain", "Bahrain", "Baréin", "Bahreïn", "Bahrein", "Barém", "Bahrein"]),
+    ("BI", ["Burundi", "Burundi", "Burundi", "Burundi", "Burundi", "Burundi", "Burundi"]),
+    ("BJ", ["Benin", "Benin", "Benín", "Bénin", "Benin", "Benim", "Benin"]),
+    (
+        "BL",
+        [
+            "Saint Barthélemy",
+            "Saint-Barthélemy",
+            "San Bartolomé",
+            "Saint-Barthélemy",
+            "Saint-Barthélemy",
+            "Saint Barthélemy",
+            "Sfântul Bartolomeu",
+        ],
+    ),
+    ("BM", ["Bermuda", "Bermuda", "Islas Bermudas", "Bermudes", "Bermuda", "Bermudas", "Bermude"]),
+    (
+        "BN",
+        [
+            "Brunei Darussalam",
+            "Brunei Darussalam",
+            "Brunei Darussalam",
+            "Brunéi Darussalam",
+            "Brunei",
+            "Brunei",
+            "Brunei",
+        ],
+    ),
+    (
+        "BO",
+        [
+            "Bolivia",
+            "Bolivien",
+            "Bolivia, Estado plurinacional de",
+            "Bolivie",
+            "Bolivia, Stato Plurinazionale della",
+            "Bolívia",
+            "Bolivia",
+        ],
+    ),
+    (
+        "BQ",
+        [
+            "Bonaire, Sint Eustatius and Saba",
+            "Bonaire, Sint Eustatius und Saba",
+            "Islas BES (Caribe Neerlandés)",
+            "Bonaire, Saint-Eustache et Saba",
+            "Paesi Bassi caraibici",
+            "Bonaire, Santo Eustáquio e Saba",
+            "Bonaire, Sint Eustatius și Saba",
+        ],
+    ),
+    ("BR", ["Brazil", "Brasilien", "Brasil", "Brésil", "Brasile", "Brasil", "Brazilia"]),
+    ("BS", ["Bahamas", "Bahamas", "Bahamas", "Bahamas", "Bahamas", "Bahamas", "Bahamas"]),
+    ("BT", ["Bhutan", "Bhutan", "Bután", "Bhoutan", "Bhutan", "Butão", "Bhutan"]),
+    (
+        "BV",
+        [
+            "Bouvet Island",
+            "Bouvet-Insel",
+            "Isla Bouvet",
+            "île Bouvet",
+            "Isola Bouvet",
+            "Ilha Bouvet",
+            "Insula Bouvet",
+        ],
+    ),
+    ("BW", ["Botswana", "Botsuana", "Botsuana", "Botswana", "Botswana", "Botsuana", "Botswana"]),
+    ("BY", ["Belarus", "Belarus", "Bielorrusia", "Bélarus", "Bielorussia", "Bielorússia", "Bielorusia"]),
+    ("BZ", ["Belize", "Belize", "Belice", "Belize", "Belize", "Belize", "Belize"]),
+    ("CA", ["Canada", "Kanada", "Canadá", "Canada", "Canada", "Canadá", "Canada"]),
+    (
+        "CC",
+        [
+            "Cocos (Keeling) Islands",
+            "Kokos-(Keeling-)Inseln",
+            "Islas Cocos (Keeling)",
+            "Cocos (Keeling), Îles",
+            "Isole Cocos (Keeling)",
+            "Ilhas Cocos",
+            "Insulele Cocos (Keeling)",
+        ],
+    ),
+    (
+        "CD",
+        [
+            "Congo, The Democratic Republic of the",
+            "Demokratische Republik Kongo",
+            "Congo, República Democrática del",
+            "République démocratique du Congo",
+            "Repubblica democratica del Congo",
+            "Congo, República Democrática do",
+            "Congo, Republica democrată",
+        ],
+    ),
+    (
+        "CF",
+        [
+            "Central African Republic",
+            "Zentralafrikanische Republik",
+            "República Centroafricana",
+            "République centrafricaine",
+            "Repubblica Centrafricana",
+            "República Centro-Africana",
+            "Republica Central Africană",
+        ],
+    ),
+    ("CG", ["Congo", "Kongo", "Congo", "République du Congo", "Congo", "Congo", "Congo"]),
+    ("CH", ["Switzerland", "Schweiz", "Suiza", "Suisse", "Svizzera", "Suíça", "Elveția"]),
+    (
+        "CI",
+        [
+            "Côte d'Ivoire",
+            "Côte d'Ivoire",
+            "Costa de Marfíl",
+            "Côte d'Ivoire",
+            "Costa d'Avorio",
+            "Costa do Marfim",
+            "Coasta de Fildeș",
+        ],
+    ),
+    (
+        "CK",
+        [
+            "Cook Islands",
+            "Cookinseln",
+            "Islas Cook",
+            "îles Cook",
+            "Isole Cook",
+            "Ilhas Cook",
+            "Insulele Cook",
+        ],
+    ),
+    ("CL", ["Chile", "Chile", "Chile", "Chili", "Cile", "Chile", "Chile"]),
+    ("CM", ["Cameroon", "Kamerun", "Camerún", "Cameroun", "Camerun", "Camarões", "Camerun"]),
+    ("CN", ["China", "China", "China", "Chine", "Cina", "China", "China"]),
+    ("CO", ["Colombia", "Kolumbien", "Colombia", "Colombie", "Colombia", "Colômbia", "Columbia"]),
+    (
+        "CR",
+        [
+            "Costa Rica",
+            "Costa Rica",
+            "Costa Rica",
+            "Costa Rica",
+            "Costa Rica",
+            "Costa Rica",
+            "Costa Rica",
+        ],
+    ),
+    ("CU", ["Cuba", "Kuba", "Cuba", "Cuba", "Cuba", "Cuba", "Cuba"]),
+    ("CV", ["Cabo Verde", "Kap Verde", "Cabo Verde", "Cap-Vert", "Capo Verde", "Cabo Verde", "Capul Verde"]),
+    ("CW", ["Curaçao", "Curaçao", "Curazao", "Curaçao", "Curaçao", "Curação", "Curaçao"]),
+    (
+        "CX",
+        [
+            "Christmas Island",
+            "Weihnachtsinseln",
+            "Isla de Navidad",
+            "Christmas, Île",
+            "Isola di Natale",
+            "Ilha Natal",
+            "Insula Crăciunului",
+        ],
+    ),
+    ("CY", ["Cyprus", "Zypern", "Chipre", "Chypre", "Cipro", "Chipre", "Cipru"]),
+    ("CZ", ["Czechia", "Tschechien", "Chequia", "Tchéquie", "Cechia", "Chéquia", "Cehia"]),
+    ("DE", ["Germany", "Deutschland", "Alemania", "Allemagne", "Germania", "Alemanha", "Germania"]),
+    ("DJ", ["Djibouti", "Dschibuti", "Yibuti", "Djibouti", "Gibuti", "Djibouti", "Djibouti"]),
+    ("DK", ["Denmark", "Dänemark", "Dinamarca", "Danemark", "Danimarca", "Dinamarca", "Danemarca"]),
+    ("DM", ["Dominica", "Dominica", "Dominica", "Dominique", "Dominica", "Dominica", "Dominica"]),
+    (
+        "DO",
+        [
+            "Dominican Republic",
+            "Dominikanische Republik",
+            "República Dominicana",
+            "République dominicaine",
+            "Repubblica Dominicana",
+            "República Dominicana",
+            "Republica Dominicană",
+        ],
+    ),
+    ("DZ", ["Algeria", "Algerien", "Algeria", "Algérie", "Algeria", "Argélia", "Algeria"]),
+    ("EC", ["Ecuador", "Ecuador", "Ecuador", "Équateur", "Ecuador", "Equador", "Ecuador"]),
+    ("EE", ["Estonia", "Estland", "Estonia", "Estonie", "Estonia", "Estónia", "Estonia"]),
+    ("EG", ["Egypt", "Ägypten", "Egipto", "Égypte", "Egitto", "Egito", "Egipt"]),
+    (
+        "EH",
+        [
+            "Western Sahara",
+            "Westsahara",
+            "Sahara Occidental",
+            "Sahara occidental",
+            "Sahara occidentale",
+            "Saara Ocidental",
+            "Sahara de Vest",
+        ],
+    ),
+    ("ER", ["Eritrea", "Eritrea", "Eritrea", "Érythrée", "Eritrea", "Eritreia", "Eritreea"]),
+    ("ES", ["Spain", "Spanien", "España", "Espagne", "Spagna", "Espanha", "Spania"]),
+    ("ET", ["Ethiopia", "Äthiopien", "Etiopía", "Éthiopie", "Etiopia", "Etiópia", "Etiopia"]),
+    ("FI", ["Finland", "Finnland", "Finlandia", "Finlande", "Finlandia", "Finlândia", "Finlanda"]),
+    ("FJ", ["Fiji", "Fidschi", "Fiyi", "Fidji", "Figi", "Fiji", "Fiji"]),
+    (
+        "FK",
+        [
+            "Falkland Islands (Malvinas)",
+            "Falklandinseln (Malwinen)",
+            "Islas Falkland (Malvinas)",
+            "Malouines, Îles (Falkland)",
+            "Isole Falkland (Malvine)",
+            "Ilhas Falkland (Malvinas)",
+            "Insulele Falkland (Insulele Malvine)",
+        ],
+    ),
+    (
+        "FM",
+        [
+            "Micronesia, Federated States of",
+            "Mikronesien, Föderierte Staaten von",
+            "Micronesia, Estados Federados de",
+            "Micronésie, États fédérés de",
+            "Micronesia",
+            "Micronésia, Estados Federados da",
+            "Micronesia, Statele federale ale",
+        ],
+    ),
+    (
+        "FO",
+        [
+            "Faroe Islands",
+            "Färöer-Inseln",
+            "Islas Feroe",
+            "îles Féroé",
+            "Isole Fær Øer",
+            "Ilhas Faroé",
+            "Insulele Feroe",
+        ],
+    ),
+    ("FR", ["France", "Frankreich", "Francia", "France", "Francia", "França", "Franța"]),
+    ("GA", ["Gabon", "Gabun", "Gabón", "Gabon", "Gabon", "Gabão", "Gabon"]),
+    (
+        "GB",
+        [
+            "United Kingdom",
+            "Vereinigtes Königreich",
+            "Reino Unido",
+            "Royaume-Uni",
+            "Regno Unito",
+            "Reino Unido",
+            "Regatul Unit",
+        ],
+    ),
+    ("GD", ["Grenada", "Grenada", "Granada", "Grenade", "Grenada", "Granada", "Grenada"]),
+    ("GE", ["Georgia", "Georgien", "Georgia", "Géorgie", "Georgia", "Geórgia", "Georgia"]),
+    (
+        "GF",
+        [
+            "French Guiana",
+            "Französisch-Guyana",
+            "Guayana Francesa",
+            "Guyane française",
+            "Guyana francese",
+            "Guiana Francesa",
+            "Guiana Franceză",
+        ],
+    ),
+    ("GG", ["Guernsey", "Guernsey", "Guernsey", "Guernesey", "Guernsey", "Guernsey", "Guernsey"]),
+    ("GH", ["Ghana", "Ghana", "Ghana", "Ghana", "Ghana", "Gana", "Ghana"]),
+    ("GI", ["Gibraltar", "Gibraltar", "Gibraltar", "Gibraltar", "Gibilterra", "Gibraltar", "Gibraltar"]),
+    ("GL", ["Greenland", "Grönland", "Groenlandia", "Groënland", "Groenlandia", "Gronelândia", "Groenlanda"]),
+    ("GM", ["Gambia", "Gambia", "Gambia", "Gambie", "Gambia", "Gâmbia", "Gambia"]),
+    ("GN", ["Guinea", "Guinea", "Guinea", "Guinée", "Guinea", "Guiné", "Guinea"]),
+    ("GP", ["Guadeloupe", "Guadeloupe", "Guadalupe", "Guadeloupe", "Guadalupa", "Guadalupe", "Guadelupa"]),
+    (
+        "GQ",
+        [
+            "Equatorial Guinea",
+            "Äquatorialguinea",
+            "Guinea Ecuatorial",
+            "Guinée Équatoriale",
+            "Guinea equatoriale",
+            "Guiné Equatorial",
+            "Guinea Ecuatorială",
+        ],
+    ),
+    ("GR", ["Greece", "Griechenland", "Grecia", "Grèce", "Grecia", "Grécia", "Grecia"]),
+    (
+        "GS",
+        [
+            "South Georgia and the South Sandwich Islands",
+            "South Georgia und die Südlichen Sandwichinseln",
+            "Islas Georgias del Sur y Sándwich del Sur",
+            "Géorgie du Sud et les îles Sandwich du Sud",
+            "Georgia del Sud e Isole Sandwich Australi",
+            "Ilhas Geórgia do Sul e Sandwich do Sul",
+            "Georgia de Sud și Insulele Sandwich de sud",
+        ],
+    ),
+    ("GT", ["Guatemala", "Guatemala", "Guatemala", "Guatemala", "Guatemala", "Guatemala", "Guatemala"]),
+    ("GU", ["Guam", "Guam", "Guam", "Guam", "Guam", "Guam", "Guam"]),
+    (
+        "GW",
+        [
+            "Guinea-Bissau",
+            "Guinea-Bissau",
+            "Guinea-Bisáu",
+            "Guinée-Bissau",
+            "Guinea-Bissau",
+            "Guiné-Bissáu",
+            "Guinea-Bissau",
+        ],
+    ),
+    ("GY", ["Guyana", "Guyana", "Guyana", "Guyana", "Guyana", "Guiana", "Guyana"]),
+    ("HK", ["Hong Kong", "Hongkong", "Hong Kong", "Hong Kong", "Hong Kong", "Hong Kong", "Hong Kong"]),
+    (
+        "HM",
+        [
+            "Heard Island and McDonald Islands",
+            "Heard und McDonaldinseln",
+            "Islas Heard y McDonald",
+            "îles Heard-et-MacDonald",
+            "Isole Heard e McDonald",
+            "Ilha Heard e Ilhas McDonald",
+            "Insula Heard și Insulele McDonald",
+        ],
+    ),
+    ("HN", ["Honduras", "Honduras", "Honduras", "Honduras", "Honduras", "Honduras", "Honduras"]),
+    ("HR", ["Croatia", "Kroatien", "Croacia", "Croatie", "Croazia", "Croácia", "Croația"]),
+    ("HT", ["Haiti", "Haiti", "Haití", "Haïti", "Haiti", "Haiti", "Haiti"]),
+    ("HU", ["Hungary", "Ungarn", "Hungría", "Hongrie", "Ungheria", "Hungria", "Ungaria"]),
+    ("ID", ["Indonesia", "Indonesien", "Indonesia", "Indonésie", "Indonesia", "Indonésia", "Indonezia"]),
+    ("IE", ["Ireland", "Irland", "Irlanda", "Irlande", "Irlanda", "Irlanda", "Irlanda"]),
+    ("IL", ["Israel", "Israel", "Israel", "Israël", "Israele", "Israel", "Israel"]),
+    (
+        "IM",
+        [
+            "Isle of Man",
+            "Insel Man",
+            "Isla de Man",
+            "Île de Man",
+            "Isola di Man",
+            "Ilha de Man",
+            "Insula Man",
+        ],
+    ),
+    ("IN", ["India", "Indien", "India", "Inde", "India", "Índia", "India"]),
+    (
+        "IO",
+        [
+            "British Indian Ocean Territory",
+            "Britisches Territorium im Indischen Ozean",
+            "Territorio Británico del Océano Índico",
+            "Territoire britannique de l'océan Indien",
+            "Territorio britannico dell'Oceano Indiano",
+            "Território Britânico do Oceano Índico",
+            "Teritoriul britanic din Oceanul Indian",
+        ],
+    ),
+    ("IQ", ["Iraq", "Irak", "Irak", "Irak", "Iraq", "Iraque", "Irak"]),
+    (
+        "IR",
+        [
+            "Iran",
+            "Iran, Islamische Republik",
+            "Irán, República islámica de",
+            "Iran, République islamique d'",
+            "Iran",
+            "Irão, República Islâmica do",
+            "Iran, Republica islamică",
+        ],
+    ),
+    ("IS", ["Iceland", "Island", "Islandia", "Islande", "Islanda", "Islândia", "Islanda"]),
+    ("IT", ["Italy", "Italien", "Italia", "Italie", "Italia", "Itália", "Italia"]),
+    ("JE", ["Jersey", "Jersey", "Jersey", "Jersey", "Jersey", "Jersey", "Jersey"]),
+    ("JM", ["Jamaica", "Jamaika", "Jamaica", "Jamaïque", "Giamaica", "Jamaica", "Jamaica"]),
+    ("JO", ["Jordan", "Jordanien", "Jordania", "Jordanie", "Giordania", "Jordânia", "Iordania"]),
+    ("JP", ["Japan", "Japan", "Japón", "Japon", "Giappone", "Japão", "Japonia"]),
+    ("KE", ["Kenya", "Kenia", "Kenia", "Kenya", "Kenya", "Quénia", "Kenia"]),
+    (
+        "KG",
+        [
+            "Kyrgyzstan",
+            "Kirgisistan",
+            "Kirguistán",
+            "Kirghizistan",
+            "Kirghizistan",
+            "Quirguistão",
+            "Kârgâzstan",
+        ],
+    ),
+    ("KH", ["Cambodia", "Kambodscha", "Camboya", "Cambodge", "Cambogia", "Camboja", "Cambogia"]),
+    ("KI", ["Kiribati", "Kiribati", "Kiribati", "Kiribati", "Kiribati", "Kiribati", "Kiribati"]),
+    ("KM", ["Comoros", "Komoren", "Comores, Islas", "Comores", "Comore", "Comores", "Comoros"]),
+    (
+        "KN",
+        [
+            "Saint Kitts and Nevis",
+            "St. Kitts und Nevis",
+            "San Cristóbal y Nieves",
+            "Saint-Christophe-et-Niévès",
+            "Saint Kitts e Nevis",
+            "São Cristóvão e Nevis",
+            "Saint Kitts și Nevis",
+        ],
+    ),
+    (
+        "KP",
+        [
+            "North Korea",
+            "Nordkorea",
+            "Corea, República Democrática Popular de",
+            "Corée du Nord",
+            "Corea del Nord",
+            "Coreia do Norte",
+            "Republica democrată populară Coreea",
+        ],
+    ),
+    (
+        "KR",
+        [
+            "South Korea",
+            "Südkorea",
+            "Corea, República de",
+            "Corée du Sud",
+            "Corea del Sud",
+            "Coreia do Sul",
+            "Republica Coreea",
+        ],
+    ),
+    ("KW", ["Kuwait", "Kuwait", "Kuwait", "Koweït", "Kuwait", "Kuwait", "Kuweit"]),
+    (
+        "KY",
+        [
+            "Cayman Islands",
+            "Cayman-Inseln",
+            "Islas Caimán",
+            "îles Caïmans",
+            "Isole Cayman",
+            "Ilhas Caimão",
+            "Insulele Caiman",
+        ],
+    ),
+    (
+        "KZ",
+        [
+            "Kazakhstan",
+            "Kasachstan",
+            "Kazajistán",
+            "Kazakhstan",
+            "Kazakistan",
+            "Cazaquistão",
+            "Kazakhstan",
+        ],
+    ),
+    (
+        "LA",
+        [
+            "Laos",
+            "Laos, Demokratische Volksrepublik",
+            "República Democrática Popular de Lao",
+            "Lao, République démocratique populaire",
+            "Laos",
+            "República Democrática Popular do Laos",
+            "Republica populară democrată Lao",
+        ],
+    ),
+    ("LB", ["Lebanon", "Libanon", "Líbano", "Liban", "Libano", "Líbano", "Liban"]),
+    (
+        "LC",
+        [
+            "Saint Lucia",
+            "St. Lucia",
+            "Santa Lucía",
+            "Sainte-Lucie",
+            "Saint Lucia",
+            "Santa Lúcia",
+            "Sfânta Lucia",
+        ],
+    ),
+    (
+        "LI",
+        [
+            "Liechtenstein",
+            "Liechtenstein",
+            "Liechtenstein",
+            "Liechtenstein",
+            "Liechtenstein",
+            "Liechtenstein",
+            "Liechtenstein",
+        ],
+    ),
+    ("LK", ["Sri Lanka", "Sri Lanka", "Sri Lanka", "Sri Lanka", "Sri Lanka", "Sri Lanka", "Sri Lanka"]),
+    ("LR", ["Liberia", "Liberia", "Liberia", "Libéria", "Liberia", "Libéria", "Liberia"]),
+    ("LS", ["Lesotho", "Lesotho", "Lesoto", "Lesotho", "Lesotho", "Lesoto", "Lesotho"]),
+    ("LT", ["Lithuania", "Litauen", "Lituania", "Lituanie", "Lituania", "Lituânia", "Lituania"]),
+    ("LU", ["Luxembourg", "Luxemburg", "Luxemburgo", "Luxembourg", "Lussemburgo", "Luxemburgo", "Luxemburg"]),
+    ("LV", ["Latvia", "Lettland", "Letonia", "Lettonie", "Lettonia", "Letónia", "Letonia"]),
+    ("LY", ["Libya", "Libyen", "Libia", "Libye", "Libia", "Líbia", "Libia"]),
+    ("MA", ["Morocco", "Marokko", "Marruecos", "Maroc", "Marocco", "Marrocos", "Maroc"]),
+    ("MC", ["Monaco", "Monaco", "Mónaco", "Monaco", "Monaco", "Mónaco", "Monaco"]),
+    ("MD", ["Moldova", "Moldau", "Moldavia", "Moldavie", "Moldavia", "Moldávia", "Moldova, Republica"]),
+    (
+        "ME",
+        [
+            "Montenegro",
+            "Montenegro",
+            "Montenegro",
+            "Monténégro",
+            "Montenegro",
+            "Montenegro",
+            "Muntenegru",
+        ],
+    ),
+    (
+        "MF",
+        [
+            "Saint Martin (French part)",
+            "Saint Martin (Französischer Teil)",
+            "San Martín (zona francesa)",
+            "Saint-Martin (partie française)",
+            "Saint-Martin (Francia)",
+            "São Martin (Território Francês)",
+            "Sfântul Martin (partea franceză)",
+        ],
+    ),
+    (
+        "MG",
+        [
+            "Madagascar",
+            "Madagaskar",
+            "Madagascar",
+            "Madagascar",
+            "Madagascar",
+            "Madagáscar",
+            "Madagascar",
+        ],
+    ),
+    (
+        "MH",
+        [
+            "Marshall Islands",
+            "Marshallinseln",
+            "Islas Marshall",
+            "Îles Marshall",
+            "Isole Marshall",
+            "Ilhas Marshall",
+            "Insulele Marshall",
+        ],
+    ),
+    (
+        "MK",
+        [
+            "North Macedonia",
+            "Nordmazedonien",
+            "Macedonia del Norte",
+            "Macédoine du Nord",
+            "Macedonia del Nord",
+            "Macedónia do Norte",
+            "North Macedonia",
+        ],
+    ),
+    ("ML", ["Mali", "Mali", "Malí", "Mali", "Mali", "Mali", "Mali"]),
+    ("MM", ["Myanmar", "Myanmar", "Birmania", "Birmanie", "Birmania", "Birmânia", "Myanmar"]),
+    ("MN", ["Mongolia", "Mongolei", "Mongolia", "Mongolie", "Mongolia", "Mongólia", "Mongolia"]),
+    ("MO", ["Macao", "Macao", "Macao", "Macau", "Macao", "Macau", "Macao"]),
+    (
+        "MP",
+        [
+            "Northern Mariana Islands",
+            "Nördliche Marianen",
+            "Islas Marianas del Norte",
+            "Îles Mariannes du Nord",
+            "Isole Marianne Settentrionali",
+            "Ilhas Marianas do Norte",
+            "Insulele Mariane de Nord",
+        ],
+    ),
+    ("MQ", ["Martinique", "Martinique", "Martinica", "Martinique", "Martinica", "Martinica", "Martinica"]),
+    (
+        "MR",
+        [
+            "Mauritania",
+            "Mauretanien",
+            "Mauritania",
+            "Mauritanie",
+            "Mauritania",
+            "Mauritânia",
+            "Mauritania",
+        ],
+    ),
+    (
+        "MS",
+        [
+            "Montserrat",
+            "Montserrat",
+            "Montserrat",
+            "Montserrat",
+            "Montserrat",
+            "Monserrate",
+            "Montserrat",
+        ],
+    ),
+    ("MT", ["Malta", "Malta", "Malta", "Malte", "Malta", "Malta", "Malta"]),
+    ("MU", ["Mauritius", "Mauritius", "Mauricio", "Maurice", "Maurizio", "Maurícia", "Maurițius"]),
+    ("MV", ["Maldives", "Malediven", "Islas Maldivas", "Maldives", "Maldive", "Maldivas", "Maldive"]),
+    ("MW", ["Malawi", "Malawi", "Malaui", "Malawi", "Malawi", "Malawi", "Malawi"]),
+    ("MX", ["Mexico", "Mexiko", "México", "Mexique", "Messico", "México", "Mexic"]),
+    ("MY", ["Malaysia", "Malaysia", "Malasia", "Malaisie", "Malaysia", "Malásia", "Malaezia"]),
+    ("MZ", ["Mozambique", "Mosambik", "Mozambique", "Mozambique", "Mozambico", "Moçambique", "Mozambic"]),
+    ("NA", ["Namibia", "Namibia", "Namibia", "Namibie", "Namibia", "Namíbia", "Namibia"]),
+    (
+        "NC",
+        [
+            "New Caledonia",
+            "Neukaledonien",
+            "Nueva Caledonia",
+            "Nouvelle-Calédonie",
+            "Nuova Caledonia",
+            "Nova Caledónia",
+            "Noua Caledonie",
+        ],
+    ),
+    ("NE", ["Niger", "Niger", "Niger", "Niger", "Niger", "Níger", "Niger"]),
+    (
+        "NF",
+        [
+            "Norfolk Island",
+            "Norfolkinsel",
+            "Isla Norfolk",
+            "île Norfolk",
+            "Isola Norfolk",
+            "Ilha Norfolk",
+            "Insula Norfolk",
+        ],
+    ),
+    ("NG", ["Nigeria", "Nigeria", "Nigeria", "Nigeria", "Nigeria", "Nigéria", "Nigeria"]),
+    ("NI", ["Nicaragua", "Nicaragua", "Nicaragua", "Nicaragua", "Nicaragua", "Nicarágua", "Nicaragua"]),
+    (
+        "NL",
+        [
+            "Netherlands",
+            "Niederlande",
+            "Países Bajos",
+            "Pays-Bas",
+            "Paesi Bassi",
+            "Países Baixos",
+            "Olanda",
+        ],
+    ),
+    ("NO", ["Norway", "Norwegen", "Noruega", "Norvège", "Norvegia", "Noruega", "Norvegia"]),
+    ("NP", ["Nepal", "Nepal", "Nepal", "Népal", "Nepal", "Nepal", "Nepal"]),
+    ("NR", ["Nauru", "Nauru", "Nauru", "Nauru", "Nauru", "Nauru", "Nauru"]),
+    ("NU", ["Niue", "Niue", "Niue", "Nioue", "Niue", "Niue", "Niue"]),
+    (
+        "NZ",
+        [
+            "New Zealand",
+            "Neuseeland",
+            "Nueva Zelanda",
+            "Nouvelle-Zélande",
+            "Nuova Zelanda",
+            "Nova Zelândia",
+            "Noua Zeelandă",
+        ],
+    ),
+    ("OM", ["Oman", "Oman", "Omán", "Oman", "Oman", "Omã", "Oman"]),
+    ("PA", ["Panama", "Panama", "Panamá", "Panama", "Panama", "Panamá", "Panama"]),
+    ("PE", ["Peru", "Peru", "Perú", "Pérou", "Perù", "Peru", "Peru"]),
+    (
+        "PF",
+        [
+            "French Polynesia",
+            "Französisch-Polynesien",
+            "Polinesia Francesa",
+            "Polynésie française",
+            "Polinesia francese",
+            "Polinésia Francesa",
+            "Polinezia Franceză",
+        ],
+    ),
+    (
+        "PG",
+        [
+            "Papua New Guinea",
+            "Papua-Neuguinea",
+            "Papúa Nueva Guinea",
+            "Papouasie-Nouvelle-Guinée",
+            "Papua Nuova Guinea",
+            "Papua Nova Guiné",
+            "Papua Noua Guinee",
+        ],
+    ),
+    ("PH", ["Philippines", "Philippinen", "Filipinas", "Philippines", "Filippine", "Filipinas", "Filipine"]),
+    ("PK", ["Pakistan", "Pakistan", "Pakistán", "Pakistan", "Pakistan", "Paquistão", "Pakistan"]),
+    ("PL", ["Poland", "Polen", "Polonia", "Pologne", "Polonia", "Polónia", "Polonia"]),
+    (
+        "PM",
+        [
+            "Saint Pierre and Miquelon",
+            "St. Pierre und Miquelon",
+            "San Pedro y Miquelon",
+            "Saint-Pierre-et-Miquelon",
+            "Saint-Pierre e Miquelon",
+            "Saint Pierre e Miquelon",
+            "Saint Pierre și Miquelon",
+        ],
+    ),
+    ("PN", ["Pitcairn", "Pitcairn", "Pitcairn", "Îles Pitcairn", "Pitcairn", "Pitcairn", "Pitcairn"]),
+    (
+        "PR",
+        [
+            "Puerto Rico",
+            "Puerto Rico",
+            "Puerto Rico",
+            "Porto Rico",
+            "Portorico",
+            "Porto Rico",
+            "Puerto Rico",
+        ],
+    ),
+    (
+        "PS",
+        [
+            "Palestine, State of",
+            "Palästina, Staat",
+            "Palestina, Estado de",
+            "Palestine, État de",
+            "Palestina, Stato di",
+            "Palestina, Estado da",
+            "Palestina, Statul",
+        ],
+    ),
+    ("PT", ["Portugal", "Portugal", "Portugal", "Portugal", "Portogallo", "Portugal", "Portugalia"]),
+    ("PW", ["Palau", "Palau", "Palaos", "Palaos", "Palau", "Palau", "Palau"]),
+    ("PY", ["Paraguay", "Paraguay", "Paraguay", "Paraguay", "Paraguay", "Paraguai", "Paraguay"]),
+    ("QA", ["Qatar", "Katar", "Catar", "Qatar", "Qatar", "Catar", "Qatar"]),
+    ("RE", ["Réunion", "Réunion", "Reunión", "Réunion, Île de la", "Riunione", "Ilha Reunião", "Réunion"]),
+    ("RO", ["Romania", "Rumänien", "Rumanía", "Roumanie", "Romania", "Roménia", "România"]),
+    ("RS", ["Serbia", "Serbien", "Serbia", "Serbie", "Serbia", "Sérvia", "Serbia"]),
+    (
+        "RU",
+        [
+            "Russian Federation",
+            "Russische Föderation",
+            "Federación Rusa",
+            "Russie, Fédération de",
+            "Russia",
+            "Federação Russa",
+            "Federația Rusă",
+        ],
+    ),
+    ("RW", ["Rwanda", "Ruanda", "Ruanda", "Rwanda", "Ruanda", "Ruanda", "Rwanda"]),
+    (
+        "SA",
+        [
+            "Saudi Arabia",
+            "Saudi-Arabien",
+            "Arabia Saudí",
+            "Arabie saoudite",
+            "Arabia Saudita",
+            "Arábia Saudita",
+            "Arabia Saudită",
+        ],
+    ),
+    (
+        "SB",
+        [
+            "Solomon Islands",
+            "Salomoninseln",
+            "Islas Salomón",
+            "Salomon, Îles",
+            "Isole Salomone",
+            "Ilhas Salomão",
+            "Insulele Solomon",
+        ],
+    ),
+    (
+        "SC",
+        [
+            "Seychelles",
+            "Seychellen",
+            "Seychelles",
+            "Seychelles",
+            "Seychelles",
+            "Seychelles",
+            "Seychelles",
+        ],
+    ),
+    ("SD", ["Sudan", "Sudan", "Sudán", "Soudan", "Sudan", "Sudão", "Sudan"]),
+    ("SE", ["Sweden", "Schweden", "Suecia", "Suède", "Svezia", "Suécia", "Suedia"]),
+    ("SG", ["Singapore", "Singapur", "Singapur", "Singapour", "Singapore", "Singapura", "Singapore"]),
+    (
+        "SH",
+        [
+            "Saint Helena, Ascension and Tristan da Cunha",
+            "St. Helena, Ascension und Tristan da Cunha",
+            "Santa Elena, Ascensión y Tristán de Acuña",
+            "Sainte-Hélène, Ascension et Tristan da Cunha",
+            "Sant'Elena, Ascensione e Tristan da Cunha",
+            "Santa Helena, Ascensão e Tristão da Cunha",
+            "Sfânta Elena, Ascension și Tristan da Cunha",
+        ],
+    ),
+    ("SI", ["Slovenia", "Slowenien", "Eslovenia", "Slovénie", "Slovenia", "Eslovénia", "Slovenia"]),
+    (
+        "SJ",
+        [
+            "Svalbard and Jan Mayen",
+            "Svalbard und Jan Mayen",
+            "Svalbard y Jan Mayen",
+            "Svalbard et île Jan Mayen",
+            "Svalbard e Jan Mayen",
+            "Svalbard e Jan Mayen",
+            "Svalbard și Jan Mayen",
+        ],
+    ),
+    ("SK", ["Slovakia", "Slowakei", "Eslovaquia", "Slovaquie", "Slovacchia", "Eslováquia", "Slovacia"]),
+    (
+        "SL",
+        [
+            "Sierra Leone",
+            "Sierra Leone",
+            "Sierra Leona",
+            "Sierra Leone",
+            "Sierra Leone",
+            "Serra Leoa",
+            "Sierra Leone",
+        ],
+    ),
+    (
+        "SM",
+        [
+            "San Marino",
+            "San Marino",
+            "San Marino",
+            "Saint-Marin",
+            "San Marino",
+            "San Marino",
+            "San Marino",
+        ],
+    ),
+    ("SN", ["Senegal", "Senegal", "Senegal", "Sénégal", "Senegal", "Senegal", "Senegal"]),
+    ("SO", ["Somalia", "Somalia", "Somalia", "Somalie", "Somalia", "Somália", "Somalia"]),
+    ("SR", ["Suriname", "Suriname", "Surinám", "Surinam", "Suriname", "Suriname", "Surinam"]),
+    (
+        "SS",
+        [
+            "South Sudan",
+            "Südsudan",
+            "Sudán del Sur",
+            "Soudan du Sud",
+            "Sudan del sud",
+            "Sudão do Sul",
+            "Sudanul de Sud",
+        ],
+    ),
+    (
+        "ST",
+        [
+            "Sao Tome and Principe",
+            "São Tomé und Príncipe",
+            "Santo Tomé y Príncipe",
+            "Sao Tomé-et-Principe",
+            "São Tomé e Príncipe",
+            "São Tomé e Príncipe",
+            "Sao Tome și Principe",
+        ],
+    ),
+    (
+        "SV",
+        [
+            "El Salvador",
+            "El Salvador",
+            "El Salvador",
+            "Salvador",
+            "El Salvador",
+            "El Salvador",
+            "El Salvador",
+        ],
+    ),
+    (
+        "SX",
+        [
+            "Sint Maarten (Dutch part)",
+            "Saint-Martin (Niederländischer Teil)",
+            "Isla de San Martín (zona holandsea)",
+            "Saint-Martin (partie néerlandaise)",
+            "Sint Maarten (Olanda)",
+            "São Martinho (Países Baixos)",
+            "Sfântul Martin (partea olandeză)",
+        ],
+    ),
+    (
+        "SY",
+        [
+            "Syria",
+            "Syrien",
+            "República árabe de Siria",
+            "Syrienne, République arabe",
+            "Siria",
+            "República Árabe Síria",
+            "Republica Araba Siria",
+        ],
+    ),
+    ("SZ", ["Eswatini", "Eswatini", "Esuatini", "Eswatini", "Eswatini", "Suazilândia", "Eswatini"]),
+    (
+        "TC",
+        [
+            "Turks and Caicos Islands",
+            "Turks- und Caicosinseln",
+            "Islas Turcas y Caicos",
+            "îles Turques-et-Caïques",
+            "Isole Turks e Caicos",
+            "Ilhas Turcas e Caicos",
+            "Insulele Turks și Caicos",
+        ],
+    ),
+    ("TD", ["Chad", "Tschad", "Chad", "Tchad", "Ciad", "Chade", "Ciad"]),
+    (
+        "TF",
+        [
+            "French Southern Territories",
+            "Französische Süd- und Antarktisgebiete",
+            "Territorios Franceses del Sur",
+            "Terres australes françaises",
+            "Territori francesi meridionali",
+            "Territórios Franceses do Sul",
+            "Teritoriile franceze de sud",
+        ],
+    ),
+    ("TG", ["Togo", "Togo", "Togo", "Togo", "Togo", "Togo", "Togo"]),
+    ("TH", ["Thailand", "Thailand", "Tailandia", "Thaïlande", "Thailandia", "Tailândia", "Tailanda"]),
+    (
+        "TJ",
+        [
+            "Tajikistan",
+            "Tadschikistan",
+            "Tayikistán",
+            "Tadjikistan",
+            "Tagikistan",
+            "Tajiquistão",
+            "Tajikistan",
+        ],
+    ),
+    ("TK", ["Tokelau", "Tokelau", "Tokelau", "Tokelau", "Tokelau", "Tokelau", "Tokelau"]),
+    (
+        "TL",
+        [
+            "Timor-Leste",
+            "Timor-Leste",
+            "Timor Oriental",
+            "Timor oriental",
+            "Timor Est",
+            "Timor-Leste",
+            "Timorul de Est",
+        ],
+    ),
+    (
+        "TM",
+        [
+            "Turkmenistan",
+            "Turkmenistan",
+            "Turkmenistán",
+            "Turkménistan",
+            "Turkmenistan",
+            "Turquemenistão",
+            "Turkmenistan",
+        ],
+    ),
+    ("TN", ["Tunisia", "Tunesien", "Tunez", "Tunisie", "Tunisia", "Tunísia", "Tunisia"]),
+    ("TO", ["Tonga", "Tonga", "Tonga", "Tonga", "Tonga", "Tonga", "Tonga"]),
+    ("TR", ["Türkiye", "Türkei", "Türkiye", "Türkiye", "Türkiye", "Turquia", "Türkiye"]),
+    (
+        "TT",
+        [
+            "Trinidad and Tobago",
+            "Trinidad und Tobago",
+            "Trinidad y Tobago",
+            "Trinité-et-Tobago",
+            "Trinidad e Tobago",
+            "Trindade e Tobago",
+            "Trinidad și Tobago",
+        ],
+    ),
+    ("TV", ["Tuvalu", "Tuvalu", "Tuvalu", "Tuvalu", "Tuvalu", "Tuvalu", "Tuvalu"]),
+    (
+        "TW",
+        [
+            "Taiwan",
+            "Taiwan, Chinesische Provinz",
+            "Taiwán",
+            "Taïwan",
+            "Taiwan, Repubblica di Cina",
+            "Taiwan, Província da China",
+            "Taiwan",
+        ],
+    ),
+    (
+        "TZ",
+        [
+            "Tanzania",
+            "Tansania",
+            "Tanzania, República unida de",
+            "Tanzanie",
+            "Tanzania",
+            "Tanzânia",
+            "Republica Unită Tanzania",
+        ],
+    ),
+    ("UA", ["Ukraine", "Ukraine", "Ucrania", "Ukraine", "Ucraina", "Ucrânia", "Ucraina"]),
+    ("UG", ["Uganda", "Uganda", "Uganda", "Ouganda", "Uganda", "Uganda", "Uganda"]),
+    (
+        "UM",
+        [
+            "United States Minor Outlying Islands",
+            "United States Minor Outlying Islands",
+            "Islas Ultramarinas Menores de Estados Unidos",
+            "Îles mineures éloignées des États-Unis",
+            "Isole minori esterne degli Stati Uniti d'America",
+            "Ilhas Menores Distantes dos Estados Unidos",
+            "Insulele de Coasta ale Statelor Unite",
+        ],
+    ),
+    (
+        "US",
+        [
+            "United States",
+            "Vereinigte Staaten",
+            "Estados Unidos",
+            "États-Unis",
+            "Stati Uniti",
+            "Estados Unidos",
+            "Statele Unite",
+        ],
+    ),
+    ("UY", ["Uruguay", "Uruguay", "Uruguay", "Uruguay", "Uruguay", "Uruguai", "Uruguay"]),
+    (
+        "UZ",
+        [
+            "Uzbekistan",
+            "Usbekistan",
+            "Uzbekistán",
+            "Ouzbékistan",
+            "Uzbekistan",
+            "Uzbequistão",
+            "Uzbekistan",
+        ],
+    ),
+    (
+        "VA",
+        [
+            "Holy See (Vatican City State)",
+            "Heiliger Stuhl (Staat Vatikanstadt)",
+            "Santa Sede (Ciudad Estado del Vaticano)",
+            "Saint-Siège (état de la cité du Vatican)",
+            "Santa Sede (Stato della Città del Vaticano)",
+            "Santa Sé (Estado da Cidade do Vaticano)",
+            "Vatican",
+        ],
+    ),
+    (
+        "VC",
+        [
+            "Saint Vincent and the Grenadines",
+            "St. Vincent und die Grenadinen",
+            "San Vicente y las Granadinas",
+            "Saint-Vincent-et-les-Grenadines",
+            "Saint Vincent e Grenadine",
+            "São Vicente e Granadinas",
+            "Saint Vincent și Grenadinele",
+        ],
+    ),
+    (
+        "VE",
+        [
+            "Venezuela",
+            "Venezuela, Bolivarische Republik",
+            "Venezuela, República Bolivariana de",
+            "Vénézuela",
+            "Venezuela, Repubblica bolivariana del",
+            "Venezuela, República Bolivariana da",
+            "Venezuela, Republica Bolivariană",
+        ],
+    ),
+    (
+        "VG",
+        [
+            "Virgin Islands, British",
+            "Britische Jungferninseln",
+            "Islas Vírgenes, Británicas",
+            "Îles Vierges britanniques",
+            "Isole Vergini, Regno Unito",
+            "Ilhas Virgens, Britânicas",
+            "Insulele virgine (britanice)",
+        ],
+    ),
+    (
+        "VI",
+        [
+            "Virgin Islands, U.S.",
+            "Amerikanische Jungferninseln",
+            "Islas Vírgenes, de EEUU",
+            "Îles Vierges, États-Unis",
+            "Isole Vergini, U.S.A.",
+            "Ilhas Virgens, Estados Unidos",
+            "Insulele virgine (SUA)",
+        ],
+    ),
+    ("VN", ["Vietnam", "Vietnam", "Vietnam", "Viêt Nam", "Vietnam", "Vietname", "Vietnam"]),
+    ("VU", ["Vanuatu", "Vanuatu", "Vanuatu", "Vanuatu", "Vanuatu", "Vanuatu", "Vanuatu"]),
+    (
+        "WF",
+        [
+            "Wallis and Futuna",
+            "Wallis und Futuna",
+            "Wallis y Futuna",
+            "Wallis et Futuna",
+            "Wallis e Futuna",
+            "Wallis e Futuna",
+            "Wallis și Futuna",
+        ],
+    ),
+    ("WS", ["Samoa", "Samoa", "Samoa", "Samoa", "Samoa", "Samoa", "Samoa"]),
+    ("YE", ["Yemen", "Jemen", "Yemen", "Yémen", "Yemen", "Iémen", "Yemen"]),
+    ("YT", ["Mayotte", "Mayotte", "Mayotte", "Mayotte", "Mayotte", "Mayotte", "Mayotte"]),
+    (
+        "ZA",
+        [
+            "South Africa",
+            "Südafrika",
+            "Sudáfrica",
+            "Afrique du Sud",
+            "Sudafrica",
+            "África do Sul",
+            "Africa de sud",
+        ],
+    ),
+    ("ZM", ["Zambia", "Sambia", "Zambia", "Zambie", "Zambia", "Zâmbia", "Zambia"]),
+    ("ZW", ["Zimbabwe", "Simbabwe", "Zimbabue", "Zimbabwe", "Zimbabwe", "Zimbábue", "Zimbabwe"]),
+];
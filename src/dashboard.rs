@@ -0,0 +1,672 @@
+//! Server-rendered HTML dashboard (behind the `dashboard` feature).
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::Html,
+};
+
+use crate::countries::{Country, LocaleParams};
+use crate::page_templates::{render, Page};
+use crate::{anomaly, global_stats, html_escape, schedule, internal, is_admin, query_link_summaries, query_stats, AppState, Granularity, LinkListParams};
+
+pub(crate) async fn dashboard_index(
+    State(state): State<AppState>,
+    Query(params): Query<LinkListParams>,
+    headers: HeaderMap,
+) -> Result<Html<String>, (StatusCode, String)> {
+    let exact = params.exact || state.exact_unique_counts;
+    let (links, total) = query_link_summaries(&state, &params, exact).await.map_err(internal)?;
+
+    let (page, last) = (params.page(), ((total + params.per_page() - 1) / params.per_page()).max(1));
+    let pager_link = |label: &str, page: i64| {
+        format!(
+            "<a href=\"{}/?{}\">{label}</a>",
+            html_escape(state.prefix()),
+            html_escape(&params.query_for(page))
+        )
+    };
+    let mut pager = format!("Page {page} of {last} ({total} links)");
+    if page > 1 {
+        pager.push_str(&format!(" · {}", pager_link("\u{2190} Newer", page - 1)));
+    }
+    if page < last {
+        pager.push_str(&format!(" · {}", pager_link("Older \u{2192}", page + 1)));
+    }
+    if !params.archived {
+        pager.push_str(&format!(
+            " · <a href=\"{}/?archived=true\">Show archived</a>",
+            html_escape(state.prefix())
+        ));
+    }
+
+    if let Some(tag) = &params.tag {
+        pager.push_str(&format!(
+            " · tagged <strong>{}</strong> (<a href=\"{}/\">all links</a>)",
+            html_escape(tag),
+            html_escape(state.prefix())
+        ));
+    }
+
+    let mut rows = String::new();
+    for l in links {
+        let tags = l
+            .tags
+            .iter()
+            .map(|t| {
+                format!(
+                    "<a class=\"tag\" href=\"{}/?tag={}\">{}</a>",
+                    html_escape(state.prefix()),
+                    html_escape(&url::form_urlencoded::byte_serialize(t.as_bytes()).collect::<String>()),
+                    html_escape(t)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        let status = if l.archived {
+            "archived"
+        } else if l.expired {
+            "expired"
+        } else if l.scheduled {
+            "scheduled"
+        } else {
+            "active"
+        };
+        rows.push_str(&format!(
+            "<tr><td><a href=\"{prefix}/links/{code}\">{code}</a></td><td title=\"{notes}\">{title}</td><td class=\"mono\">{target}</td><td>{tags}</td><td>{created}</td><td>{expires}</td><td>{status}</td><td>{clicks}</td><td>{uv}</td></tr>",
+            prefix = html_escape(state.prefix()),
+            code = html_escape(&l.code),
+            title = html_escape(l.title.as_deref().unwrap_or("-")),
+            notes = html_escape(l.notes.as_deref().unwrap_or("")),
+            target = html_escape(&l.target_url),
+            tags = tags,
+            created = html_escape(&l.created_at),
+            expires = html_escape(l.expires_at.as_deref().unwrap_or("-")),
+            status = status,
+            clicks = l.total_clicks,
+            uv = l.unique_visitors,
+        ));
+    }
+
+    let overview = global_stats::query_global_stats(&state).await.map_err(internal)?;
+    let top_links: String = overview
+        .top_links_week
+        .iter()
+        .map(|l| {
+            format!(
+                "<li><a href=\"{}/links/{code}\">{code}</a> {title}: {} clicks</li>",
+                html_escape(state.prefix()),
+                l.clicks,
+                code = html_escape(&l.code),
+                title = html_escape(l.title.as_deref().unwrap_or("")),
+            )
+        })
+        .collect();
+    let top_links = if top_links.is_empty() { "<li>No clicks this week</li>".to_string() } else { top_links };
+
+    let anomalies = if is_admin(&state, &headers) {
+        anomalies_card(&state).await?
+    } else {
+        String::new()
+    };
+
+    let page = layout(
+        &state,
+        "URL Shortener Dashboard",
+        &format!(
+            r#"
+<h1>URL Shortener</h1>
+
+<div class="card">
+  <h2>Create a short link</h2>
+  <form id="shorten-form">
+    <label>Long URL</label>
+    <input name="url" placeholder="https://example.com/very/long" required />
+
+    <label>Title (optional)</label>
+    <input name="title" placeholder="Spring campaign landing page" />
+
+    <label>Tags (optional, comma-separated)</label>
+    <input name="tags" placeholder="campaign-x, newsletter" />
+
+    <label>Custom code (optional)</label>
+    <input name="custom_code" placeholder="my-link" />
+
+    <label>Expires at (optional, RFC3339)</label>
+    <input name="expires_at" placeholder="2026-01-31T00:00:00Z" />
+
+    <label>UTM source / medium / campaign (optional, added when redirecting)</label>
+    <div class="utm">
+      <input name="utm_source" placeholder="newsletter" />
+      <input name="utm_medium" placeholder="email" />
+      <input name="utm_campaign" placeholder="spring" />
+    </div>
+
+    <label>Redirect type</label>
+    <select name="redirect_type">
+      <option value="307">307 Temporary (default)</option>
+      <option value="302">302 Found</option>
+      <option value="301">301 Permanent (cached by browsers)</option>
+      <option value="308">308 Permanent, keeps method</option>
+    </select>
+
+    <button type="submit">Shorten</button>
+  </form>
+  <div id="result" class="result"></div>
+</div>
+
+<div class="card">
+  <h2>Overview</h2>
+  <p class="big">{total_clicks} clicks · {clicks_today} today</p>
+  <p>{links} links: {active_links} active, {expired_links} expired, {scheduled_links} scheduled, {archived_links} archived</p>
+  <h3>Top links this week</h3>
+  <ol>{top_links}</ol>
+</div>
+
+{anomalies}
+<div class="card">
+  <h2>Search</h2>
+  <input id="search" type="search" placeholder="Search codes and targets" autocomplete="off" />
+  <ul id="search-results"></ul>
+</div>
+
+<div class="card">
+  <h2>All links</h2>
+  <table>
+    <thead>
+      <tr><th>Code</th><th>Title</th><th>Target</th><th>Tags</th><th>Created</th><th>Expires</th><th>Status</th><th>Clicks</th><th>Unique</th></tr>
+    </thead>
+    <tbody>
+      {rows}
+    </tbody>
+  </table>
+  <p>{pager}</p>
+</div>
+
+<script>
+  const search = document.getElementById('search');
+  const searchResults = document.getElementById('search-results');
+  let searchTimer;
+  search.addEventListener('input', () => {{
+    clearTimeout(searchTimer);
+    searchTimer = setTimeout(async () => {{
+      searchResults.replaceChildren();
+      if (!search.value.trim()) return;
+      const resp = await fetch('{prefix}/api/search?q=' + encodeURIComponent(search.value));
+      if (!resp.ok) return;
+      for (const hit of await resp.json()) {{
+        const li = document.createElement('li');
+        const a = document.createElement('a');
+        a.href = '{prefix}/links/' + encodeURIComponent(hit.code);
+        a.textContent = hit.code;
+        li.append(a, ' \u2192 ' + hit.target_url);
+        searchResults.append(li);
+      }}
+    }}, 150);
+  }});
+
+  const form = document.getElementById('shorten-form');
+  const result = document.getElementById('result');
+
+  form.addEventListener('submit', async (e) => {{
+    e.preventDefault();
+    result.textContent = 'Working...';
+
+    const data = Object.fromEntries(new FormData(form));
+    if (!data.title) delete data.title;
+    if (!data.custom_code) delete data.custom_code;
+    if (!data.expires_at) delete data.expires_at;
+    data.tags = data.tags.split(',').map(t => t.trim()).filter(Boolean);
+    data.redirect_type = Number(data.redirect_type);
+    const utm = {{}};
+    for (const key of ['source', 'medium', 'campaign']) {{
+      if (data['utm_' + key]) utm[key] = data['utm_' + key];
+      delete data['utm_' + key];
+    }}
+    if (Object.keys(utm).length) data.utm = utm;
+
+    const resp = await fetch('{prefix}/api/shorten', {{
+      method: 'POST',
+      headers: {{ 'Content-Type': 'application/json' }},
+      body: JSON.stringify(data)
+    }});
+
+    const text = await resp.text();
+    if (!resp.ok) {{
+      result.textContent = 'Error: ' + text;
+      return;
+    }}
+    const json = JSON.parse(text);
+    result.innerHTML = `Short URL: <a href="${{json.short_url}}" target="_blank">${{json.short_url}}</a>`
+      + (json.qr_png_url ? `<br/>QR: <a href="${{json.qr_png_url}}" target="_blank">${{json.qr_png_url}}</a>` : '');
+    form.reset();
+  }});
+</script>
+"#,
+            rows = rows,
+            pager = pager,
+            anomalies = anomalies,
+            total_clicks = overview.total_clicks,
+            clicks_today = overview.clicks_today,
+            links = overview.links,
+            active_links = overview.active_links,
+            expired_links = overview.expired_links,
+            scheduled_links = overview.scheduled_links,
+            archived_links = overview.archived_links,
+            top_links = top_links,
+            prefix = html_escape(state.prefix()),
+        ),
+    );
+    Ok(Html(page))
+}
+
+pub(crate) async fn dashboard_link(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+    Query(locale): Query<LocaleParams>,
+) -> Result<Html<String>, (StatusCode, String)> {
+    let locale = locale.locale()?;
+    let stats = query_stats(&state, &code, state.exact_unique_counts, false, locale, Granularity::Day).await?;
+    let windows = schedule::windows(&state, &stats.code).await.map_err(internal)?;
+    let live_target = match windows.iter().find(|w| w.active) {
+        Some(w) => format!(
+            "    <p><strong>Live target</strong> (scheduled)<br/><span class=\"mono\">{}</span></p>\n",
+            html_escape(&w.target_url)
+        ),
+        None => String::new(),
+    };
+
+    let mut countries = String::new();
+    for c in &stats.top_countries {
+        countries.push_str(&format!(
+            "<li>{flag} {name} <span class=\"mono\">{country}</span> — {clicks}</li>",
+            flag = c.flag.as_deref().unwrap_or(""),
+            name = html_escape(c.name.unwrap_or("")),
+            country = html_escape(&c.country),
+            clicks = c.clicks
+        ));
+    }
+    if countries.is_empty() {
+        countries.push_str("<li>-</li>");
+    }
+
+    let mut continents: String = stats
+        .top_continents
+        .iter()
+        .map(|c| format!("<li>{} — {}</li>", html_escape(c.name), c.clicks))
+        .collect();
+    if continents.is_empty() {
+        continents.push_str("<li>-</li>");
+    }
+
+    let mut languages = String::new();
+    for l in &stats.top_languages {
+        languages.push_str(&format!(
+            "<li><span class=\"mono\">{language}</span> — {clicks}</li>",
+            language = html_escape(&l.language),
+            clicks = l.clicks
+        ));
+    }
+    if languages.is_empty() {
+        languages.push_str("<li>-</li>");
+    }
+
+    let breakdown = |rows: Vec<(&str, i64)>| {
+        let mut items: String = rows
+            .into_iter()
+            .map(|(name, clicks)| format!("<li>{} — {clicks}</li>", html_escape(name)))
+            .collect();
+        if items.is_empty() {
+            items.push_str("<li>-</li>");
+        }
+        items
+    };
+    let browsers = breakdown(stats.top_browsers.iter().map(|b| (b.browser.as_str(), b.clicks)).collect());
+    let oses = breakdown(stats.top_os.iter().map(|o| (o.os.as_str(), o.clicks)).collect());
+    let device_types = breakdown(stats.device_types.iter().map(|d| (d.device_type.as_str(), d.clicks)).collect());
+    let referrers = breakdown(
+        stats
+            .top_referrers
+            .iter()
+            .map(|r| (r.domain.as_deref().unwrap_or("Direct / unknown"), r.clicks))
+            .collect(),
+    );
+
+    let mut recent = String::new();
+    for r in &stats.recent_clicks {
+        recent.push_str(&format!(
+            "<tr><td>{at}</td><td class=\"mono\">{ip}</td><td>{country}</td><td class=\"mono\">{ua}</td></tr>",
+            at = html_escape(&r.at),
+            ip = html_escape(r.ip.as_deref().unwrap_or("-")),
+            country = html_escape(&r.country.as_deref().map_or("-".to_string(), |c| Country::new(c, locale).label())),
+            ua = html_escape(r.user_agent.as_deref().unwrap_or("-")),
+        ));
+    }
+    if recent.is_empty() {
+        recent.push_str("<tr><td colspan=\"4\">-</td></tr>");
+    }
+
+    let page = layout(
+        &state,
+        &format!("Stats for {}", html_escape(&code)),
+        &format!(
+            r#"
+<a href="{prefix}/">← Back</a>
+
+<h1>Link <span class="mono">/{code}</span></h1>
+
+<div class="grid">
+  <div class="card">
+    <h2>Link</h2>
+    <p><strong>Title</strong><br/>{title}</p>
+    <p><strong>Notes</strong><br/>{notes}</p>
+    <p><strong>Target</strong><br/><span class="mono">{target}</span></p>
+{live_target}    <p><strong>Short URL</strong><br/><a href="{short_url}" target="_blank">{short_url}</a></p>
+    <p><strong>Created</strong><br/>{created}</p>
+    <p><strong>Expires</strong><br/>{expires}</p>
+    <p><strong>Redirect</strong><br/>{redirect_type}</p>
+  </div>
+
+{qr}
+  <div class="card">
+    <h2>Totals</h2>
+    <p class="big">{clicks} clicks</p>
+    <p class="big">{unique} unique visitors</p>
+    <p>{qr_scans} QR scans</p>
+  </div>
+
+  <div class="card">
+    <h2>Top countries</h2>
+    <ul>{countries}</ul>
+  </div>
+
+  <div class="card">
+    <h2>Continents</h2>
+    <ul>{continents}</ul>
+    <p>EU: {eu_clicks} clicks · EEA: {eea_clicks} clicks</p>
+  </div>
+
+  <div class="card">
+    <h2>Languages</h2>
+    <ul>{languages}</ul>
+  </div>
+
+  <div class="card">
+    <h2>Browsers</h2>
+    <ul>{browsers}</ul>
+  </div>
+
+  <div class="card">
+    <h2>Operating systems</h2>
+    <ul>{oses}</ul>
+    <h2>Devices</h2>
+    <ul>{device_types}</ul>
+  </div>
+
+  <div class="card">
+    <h2>Referrers</h2>
+    <ul>{referrers}</ul>
+  </div>
+{schedule}</div>
+
+<div class="card">
+  <h2>Recent clicks</h2>
+  <table>
+    <thead><tr><th>At</th><th>IP</th><th>Country</th><th>User-Agent</th></tr></thead>
+    <tbody>{recent}</tbody>
+  </table>
+</div>
+
+<div class="card">
+  <h2>Live</h2>
+  <ul id="live"><li id="live-idle">Waiting for clicks…</li></ul>
+</div>
+<script>
+  const live = document.getElementById('live');
+  const feed = new EventSource('{prefix}/api/links/' + encodeURIComponent({code_js}) + '/stream');
+  feed.addEventListener('click', (e) => {{
+    const click = JSON.parse(e.data);
+    document.getElementById('live-idle')?.remove();
+    const li = document.createElement('li');
+    const parts = [click.at.replace('T', ' ').replace('Z', ''), click.country || '??', click.browser, click.os, click.referrer && 'via ' + click.referrer];
+    li.textContent = parts.filter(Boolean).join(' · ') + (click.bot ? ' (bot)' : '');
+    live.prepend(li);
+    while (live.children.length > 20) live.lastChild.remove();
+  }});
+</script>
+"#,
+            code = html_escape(&stats.code),
+            code_js = serde_json::to_string(&stats.code).unwrap_or_default().replace('<', "\\u003c"),
+            title = html_escape(stats.title.as_deref().unwrap_or("-")),
+            notes = html_escape(stats.notes.as_deref().unwrap_or("-")),
+            target = html_escape(&stats.target_url),
+            prefix = html_escape(state.prefix()),
+            qr = qr_card(state.prefix(), &stats.code),
+            short_url = html_escape(&state.short_url(&stats.code)),
+            created = html_escape(&stats.created_at),
+            expires = html_escape(stats.expires_at.as_deref().unwrap_or("-")),
+            redirect_type = u16::from(stats.redirect_type),
+            clicks = stats.total_clicks,
+            unique = stats.unique_visitors,
+            qr_scans = stats.qr_scans,
+            countries = countries,
+            continents = continents,
+            eu_clicks = stats.eu_clicks,
+            eea_clicks = stats.eea_clicks,
+            languages = languages,
+            browsers = browsers,
+            oses = oses,
+            device_types = device_types,
+            referrers = referrers,
+            recent = recent,
+            live_target = live_target,
+            schedule = schedule_card(&windows),
+        ),
+    );
+    Ok(Html(page))
+}
+
+/// The link's scheduled targets, the live one marked. Empty without a
+/// schedule.
+fn schedule_card(windows: &[schedule::Window]) -> String {
+    if windows.is_empty() {
+        return String::new();
+    }
+    let mut rows = String::new();
+    for w in windows {
+        rows.push_str(&format!(
+            "<tr><td>{starts}</td><td>{ends}</td><td class=\"mono\">{target}</td><td>{live}</td></tr>",
+            starts = html_escape(&w.starts_at),
+            ends = html_escape(w.ends_at.as_deref().unwrap_or("-")),
+            target = html_escape(&w.target_url),
+            live = if w.active { "<strong>live</strong>" } else { "" },
+        ));
+    }
+    format!(
+        r#"  <div class="card">
+    <h2>Schedule</h2>
+    <table>
+      <thead><tr><th>From</th><th>Until</th><th>Target</th><th></th></tr></thead>
+      <tbody>{rows}</tbody>
+    </table>
+  </div>
+"#
+    )
+}
+
+/// Latest traffic anomalies, for admins. Empty when there are none.
+async fn anomalies_card(state: &AppState) -> Result<String, (StatusCode, String)> {
+    let anomalies = anomaly::recent_anomalies(state, 10).await.map_err(internal)?;
+    if anomalies.is_empty() {
+        return Ok(String::new());
+    }
+    let mut rows = String::new();
+    for a in anomalies {
+        rows.push_str(&format!(
+            "<tr><td>{at}</td><td><a href=\"{prefix}/links/{code}\">{code}</a></td><td>{kind}</td><td>{country}</td><td>{clicks}</td><td>{baseline:.1}</td></tr>",
+            at = html_escape(&a.detected_at),
+            prefix = html_escape(state.prefix()),
+            code = html_escape(&a.code),
+            kind = html_escape(&a.kind),
+            country = html_escape(a.country.as_deref().unwrap_or("-")),
+            clicks = a.clicks,
+            baseline = a.baseline,
+        ));
+    }
+    Ok(format!(
+        r#"<div class="card">
+  <h2>Traffic anomalies</h2>
+  <table>
+    <thead><tr><th>Detected</th><th>Code</th><th>Kind</th><th>Country</th><th>Clicks (last hour)</th><th>Usual per hour</th></tr></thead>
+    <tbody>{rows}</tbody>
+  </table>
+</div>
+"#
+    ))
+}
+
+fn qr_card(prefix: &str, code: &str) -> String {
+    if !cfg!(feature = "qr") {
+        return String::new();
+    }
+    format!(
+        r#"  <div class="card">
+    <h2>QR</h2>
+    <img class="qr" src="{prefix}/api/links/{code}/qr" alt="QR code" />
+  </div>
+"#,
+        prefix = html_escape(prefix),
+        code = html_escape(code),
+    )
+}
+
+/// Quick-create palette on every dashboard page: Ctrl/Cmd+K opens it, Enter
+/// shortens the URL and copies the short URL; recent links come from
+/// `/api/recent` and are copied on click.
+const PALETTE: &str = r#"<dialog id="palette">
+  <form id="palette-form">
+    <input name="url" placeholder="Paste a long URL and press Enter" autocomplete="off" required />
+  </form>
+  <div id="palette-status" class="result"></div>
+  <ul id="palette-recent"></ul>
+  <p><kbd>Ctrl</kbd>+<kbd>K</kbd> to open, <kbd>Esc</kbd> to close</p>
+</dialog>
+<script>
+  (() => {
+    const prefix = document.body.dataset.prefix;
+    const palette = document.getElementById('palette');
+    const form = document.getElementById('palette-form');
+    const status = document.getElementById('palette-status');
+    const recent = document.getElementById('palette-recent');
+
+    const copy = async (url) => {
+      try {
+        await navigator.clipboard.writeText(url);
+        status.textContent = 'Copied ' + url;
+      } catch {
+        status.textContent = url;
+      }
+    };
+
+    const loadRecent = async () => {
+      recent.replaceChildren();
+      const resp = await fetch(prefix + '/api/recent');
+      if (!resp.ok) return;
+      for (const link of await resp.json()) {
+        const li = document.createElement('li');
+        const a = document.createElement('a');
+        a.href = link.short_url;
+        a.textContent = link.code;
+        a.addEventListener('click', (e) => { e.preventDefault(); copy(link.short_url); });
+        li.append(a, ' \u2192 ' + (link.title || link.target_url));
+        recent.append(li);
+      }
+    };
+
+    document.addEventListener('keydown', (e) => {
+      if ((e.ctrlKey || e.metaKey) && e.key.toLowerCase() === 'k') {
+        e.preventDefault();
+        status.textContent = '';
+        form.reset();
+        palette.showModal();
+        form.url.focus();
+        loadRecent();
+      }
+    });
+
+    form.addEventListener('submit', async (e) => {
+      e.preventDefault();
+      status.textContent = 'Working...';
+      const resp = await fetch(prefix + '/api/shorten', {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify({ url: form.url.value })
+      });
+      const text = await resp.text();
+      if (!resp.ok) {
+        status.textContent = 'Error: ' + text;
+        return;
+      }
+      form.reset();
+      await copy(JSON.parse(text).short_url);
+      loadRecent();
+    });
+  })();
+</script>"#;
+
+/// Built-in frame; see [`Page::Layout`] for the placeholders.
+const DEFAULT_LAYOUT: &str = r#""<!doctype html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8" />
+    <meta name="viewport" content="width=device-width, initial-scale=1" />
+    <title>{{title}}</title>
+    <style>
+      body { font-family: ui-sans-serif, system-ui, -apple-system, Segoe UI, Roboto, Arial; margin: 24px; line-height: 1.35; }
+      h1 { margin: 0 0 12px 0; }
+      h2 { margin: 0 0 12px 0; font-size: 18px; }
+      a { color: #0b62d6; }
+      table { width: 100%; border-collapse: collapse; }
+      th, td { border-bottom: 1px solid #ddd; padding: 8px; vertical-align: top; }
+      th { text-align: left; }
+      .card { border: 1px solid #e5e5e5; border-radius: 12px; padding: 16px; margin: 16px 0; }
+      .grid { display: grid; gap: 16px; grid-template-columns: repeat(auto-fit, minmax(260px, 1fr)); }
+      .mono { font-family: ui-monospace, SFMono-Regular, Menlo, Monaco, Consolas, 'Liberation Mono', 'Courier New', monospace; }
+      .utm { display: flex; gap: 8px; }
+      .tag { display: inline-block; padding: 0 6px; border-radius: 8px; background: #eef; font-size: 12px; }
+      input, select { width: 100%; padding: 10px; border: 1px solid #ccc; border-radius: 10px; margin-bottom: 10px; }
+      button { padding: 10px 14px; border-radius: 10px; border: 1px solid #0b62d6; background: #0b62d6; color: white; cursor: pointer; }
+      .result { margin-top: 10px; }
+      .big { font-size: 22px; margin: 8px 0; }
+      .qr { width: 240px; height: 240px; image-rendering: pixelated; }
+      .banner { background: #fff4ce; border: 1px solid #f0d170; border-radius: 12px; padding: 10px 16px; margin-bottom: 16px; }
+      dialog { width: min(560px, 90vw); border: 1px solid #e5e5e5; border-radius: 12px; padding: 16px; }
+      kbd { border: 1px solid #ccc; border-radius: 4px; padding: 0 4px; font-size: 12px; }
+    </style>
+  </head>
+  <body data-prefix="{{prefix}}">
+    {{banner}}
+    {{body}}
+    {{palette}}
+  </body>
+</html>"#;
+
+fn layout(state: &AppState, title: &str, body: &str) -> String {
+    let mut banner = String::new();
+    if let Some(message) = state.settings().banner {
+        banner.push_str(&format!(r#"<div class="banner" role="status">{}</div>"#, html_escape(&message)));
+    }
+    if state.demo {
+        banner.push_str(r#"<div class="banner">Demo mode: data lives in memory and is lost when the server stops.</div>"#);
+    }
+    render(
+        state.page_templates.template(Page::Layout, DEFAULT_LAYOUT),
+        &[
+            ("title", title),
+            ("banner", &banner),
+            ("body", body),
+            ("prefix", &html_escape(state.prefix())),
+            ("palette", PALETTE),
+        ],
+    )
+}
@@ -0,0 +1,245 @@
+//! Retry queue for webhook notifications. A webhook that fails when an event
+//! is raised is stored in `webhook_deliveries` and retried by
+//! [`retry_webhooks`] with exponential backoff. After `webhook_max_attempts`
+//! failed attempts it is dead-lettered: kept for admins to inspect and
+//! redeliver, but no longer retried. Deliveries of a click webhook keep its
+//! id and are signed again with its secret on every attempt.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize, Serializer};
+use time::OffsetDateTime;
+
+use crate::{click_webhooks, internal, require_admin, webhook, AppState};
+
+/// First retry delay; each further attempt doubles it.
+const BASE_DELAY_SECS: i64 = 30;
+/// Longest delay between attempts.
+const MAX_DELAY_SECS: i64 = 6 * 3600;
+/// How long a claimed delivery is hidden from other retry runs.
+const LEASE_SECS: i64 = 60;
+/// Due deliveries attempted per [`retry_webhooks`] run.
+const BATCH: i64 = 100;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub(crate) enum DeliveryStatus {
+    /// Waiting for its next attempt.
+    Pending,
+    Delivered,
+    /// Gave up after `webhook_max_attempts`; only redelivery sends it again.
+    Dead,
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+pub(crate) struct Delivery {
+    id: i64,
+    url: String,
+    event: String,
+    #[serde(serialize_with = "as_json")]
+    payload: String,
+    status: DeliveryStatus,
+    attempts: i64,
+    last_error: Option<String>,
+    next_attempt_at: Option<i64>,
+    created_at: String,
+    delivered_at: Option<String>,
+    /// The click webhook this delivery belongs to, if any.
+    webhook_id: Option<i64>,
+}
+
+fn as_json<S: Serializer>(payload: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    serde_json::from_str::<serde_json::Value>(payload)
+        .map_err(serde::ser::Error::custom)?
+        .serialize(serializer)
+}
+
+/// What one [`retry_webhooks`] run did.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RetryReport {
+    pub delivered: usize,
+    /// Failed again and scheduled for another attempt.
+    pub retrying: usize,
+    /// Failed for the last time.
+    pub dead: usize,
+}
+
+/// Delay before attempt `attempts + 1`.
+fn backoff(attempts: i64) -> i64 {
+    let doublings = (attempts - 1).clamp(0, 20) as u32;
+    (BASE_DELAY_SECS << doublings).min(MAX_DELAY_SECS)
+}
+
+fn now_rfc3339() -> String {
+    OffsetDateTime::now_utc()
+        .format(&time::format_description::well_known::Rfc3339)
+        .unwrap()
+}
+
+/// Queues `payload` for `url` after its first attempt failed with `error`.
+/// `webhook_id` names the click webhook that signs it.
+pub(crate) async fn enqueue(
+    state: &AppState,
+    event: &str,
+    url: &str,
+    payload: &serde_json::Value,
+    error: &str,
+    webhook_id: Option<i64>,
+) -> Result<(), sqlx::Error> {
+    let max_attempts = i64::from(state.settings().webhook_max_attempts);
+    let (status, next_attempt_at) = if max_attempts <= 1 {
+        (DeliveryStatus::Dead, None)
+    } else {
+        (DeliveryStatus::Pending, Some(OffsetDateTime::now_utc().unix_timestamp() + backoff(1)))
+    };
+    sqlx::query(
+        "INSERT INTO webhook_deliveries \
+         (url, event, payload, status, attempts, last_error, next_attempt_at, created_at, webhook_id) \
+         VALUES (?, ?, ?, ?, 1, ?, ?, ?, ?)",
+    )
+    .bind(url)
+    .bind(event)
+    .bind(payload.to_string())
+    .bind(status)
+    .bind(error)
+    .bind(next_attempt_at)
+    .bind(now_rfc3339())
+    .bind(webhook_id)
+    .execute(&state.pool)
+    .await?;
+    Ok(())
+}
+
+/// Sends `delivery` once more and records the outcome.
+async fn attempt(state: &AppState, delivery: &Delivery) -> Result<DeliveryStatus, sqlx::Error> {
+    let payload: serde_json::Value = serde_json::from_str(&delivery.payload).unwrap_or_default();
+    let attempts = delivery.attempts + 1;
+    let (sent, deleted) = match delivery.webhook_id {
+        None => (webhook::post(&delivery.url, &payload).await, false),
+        Some(id) => match click_webhooks::secret(state, id).await? {
+            Some(secret) => (webhook::post_signed(&delivery.url, &payload, Some(&secret)).await, false),
+            None => (Err("the webhook was deleted".to_string()), true),
+        },
+    };
+    let (status, error, next_attempt_at, delivered_at) = match sent {
+        Ok(()) => (DeliveryStatus::Delivered, delivery.last_error.clone(), None, Some(now_rfc3339())),
+        Err(e) if deleted || attempts >= i64::from(state.settings().webhook_max_attempts) => {
+            (DeliveryStatus::Dead, Some(e), None, None)
+        }
+        Err(e) => {
+            let next = OffsetDateTime::now_utc().unix_timestamp() + backoff(attempts);
+            (DeliveryStatus::Pending, Some(e), Some(next), None)
+        }
+    };
+    sqlx::query(
+        "UPDATE webhook_deliveries \
+         SET status = ?, attempts = ?, last_error = ?, next_attempt_at = ?, delivered_at = ? WHERE id = ?",
+    )
+    .bind(status)
+    .bind(attempts)
+    .bind(error)
+    .bind(next_attempt_at)
+    .bind(delivered_at)
+    .bind(delivery.id)
+    .execute(&state.pool)
+    .await?;
+    Ok(status)
+}
+
+/// Attempts the queued webhooks that are due. Does nothing when the service
+/// is read-only.
+pub async fn retry_webhooks(state: &AppState) -> Result<RetryReport, sqlx::Error> {
+    let mut report = RetryReport::default();
+    if state.is_read_only() {
+        return Ok(report);
+    }
+    let now = OffsetDateTime::now_utc().unix_timestamp();
+    let due: Vec<Delivery> = sqlx::query_as(
+        "SELECT * FROM webhook_deliveries WHERE status = 'pending' AND next_attempt_at <= ? \
+         ORDER BY next_attempt_at LIMIT ?",
+    )
+    .bind(now)
+    .bind(BATCH)
+    .fetch_all(&state.pool)
+    .await?;
+
+    for delivery in due {
+        // another instance may be retrying the same row; only one claims it
+        let claimed = sqlx::query(
+            "UPDATE webhook_deliveries SET next_attempt_at = ? \
+             WHERE id = ? AND status = 'pending' AND next_attempt_at IS ?",
+        )
+        .bind(now + LEASE_SECS)
+        .bind(delivery.id)
+        .bind(delivery.next_attempt_at)
+        .execute(&state.pool)
+        .await?;
+        if claimed.rows_affected() == 0 {
+            continue;
+        }
+        match attempt(state, &delivery).await? {
+            DeliveryStatus::Delivered => report.delivered += 1,
+            DeliveryStatus::Pending => report.retrying += 1,
+            DeliveryStatus::Dead => {
+                tracing::warn!("webhook delivery {} to {} dead-lettered", delivery.id, delivery.url);
+                report.dead += 1;
+            }
+        }
+    }
+    Ok(report)
+}
+
+#[derive(Deserialize)]
+pub(crate) struct DeliveryParams {
+    status: Option<DeliveryStatus>,
+    limit: Option<i64>,
+}
+
+/// `GET /api/admin/webhooks/deliveries[?status=dead]`: queued deliveries,
+/// newest first. Admin only.
+pub(crate) async fn list_deliveries(
+    State(state): State<AppState>,
+    Query(params): Query<DeliveryParams>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<Delivery>>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let deliveries = sqlx::query_as(
+        "SELECT * FROM webhook_deliveries WHERE ?1 IS NULL OR status = ?1 ORDER BY id DESC LIMIT ?2",
+    )
+    .bind(params.status)
+    .bind(params.limit.unwrap_or(100).clamp(1, 1000))
+    .fetch_all(&state.pool)
+    .await
+    .map_err(internal)?;
+    Ok(Json(deliveries))
+}
+
+/// `POST /api/admin/webhooks/deliveries/:id/redeliver`: sends a delivery
+/// again now, whatever its status, with a fresh attempt budget. Admin only.
+pub(crate) async fn redeliver(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+) -> Result<Json<Delivery>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let Some(mut delivery) = sqlx::query_as::<_, Delivery>("SELECT * FROM webhook_deliveries WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&state.pool)
+        .await
+        .map_err(internal)?
+    else {
+        return Err((StatusCode::NOT_FOUND, "delivery not found".to_string()));
+    };
+    delivery.attempts = 0;
+    attempt(&state, &delivery).await.map_err(internal)?;
+    let delivery = sqlx::query_as("SELECT * FROM webhook_deliveries WHERE id = ?")
+        .bind(id)
+        .fetch_one(&state.pool)
+        .await
+        .map_err(internal)?;
+    Ok(Json(delivery))
+}
@@ -0,0 +1,79 @@
+//! Device-specific redirect targets: a link can send iOS, Android and
+//! desktop visitors to different destinations (typically the App Store,
+//! Google Play and the website), falling back to its `target_url`.
+//!
+//! Detection is a plain `User-Agent` substring check, good enough to pick
+//! a store; it is not a general-purpose UA parser.
+
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::{validate_target, AppState};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Device {
+    Ios,
+    Android,
+    Desktop,
+    /// Other phones and tablets, and clients without a `User-Agent`.
+    Other,
+}
+
+impl Device {
+    pub(crate) fn detect(user_agent: Option<&str>) -> Device {
+        let Some(ua) = user_agent.filter(|ua| !ua.trim().is_empty()) else {
+            return Device::Other;
+        };
+        if ["iPhone", "iPad", "iPod"].iter().any(|m| ua.contains(m)) {
+            Device::Ios
+        } else if ua.contains("Android") {
+            Device::Android
+        } else if ["Mobile", "Tablet", "Windows Phone", "BlackBerry"].iter().any(|m| ua.contains(m)) {
+            Device::Other
+        } else {
+            Device::Desktop
+        }
+    }
+}
+
+/// Per-device destinations of a link; unset devices get `target_url`.
+#[derive(Deserialize, Serialize, Default, Clone, PartialEq)]
+pub(crate) struct DeviceTargets {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) ios: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) android: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) desktop: Option<String>,
+}
+
+impl DeviceTargets {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.ios.is_none() && self.android.is_none() && self.desktop.is_none()
+    }
+
+    /// The target for `device`, if this link sets one.
+    pub(crate) fn for_device(&self, device: Device) -> Option<&str> {
+        match device {
+            Device::Ios => self.ios.as_deref(),
+            Device::Android => self.android.as_deref(),
+            Device::Desktop => self.desktop.as_deref(),
+            Device::Other => None,
+        }
+    }
+
+    /// Normalizes every target with the same checks as `url`.
+    pub(crate) fn validated(self, state: &AppState) -> Result<DeviceTargets, (StatusCode, String)> {
+        let check = |device: &str, url: Option<String>| {
+            url.map(|url| {
+                validate_target(state, &url).map_err(|(status, e)| (status, format!("device_targets.{device}: {e}")))
+            })
+            .transpose()
+        };
+        Ok(DeviceTargets {
+            ios: check("ios", self.ios)?,
+            android: check("android", self.android)?,
+            desktop: check("desktop", self.desktop)?,
+        })
+    }
+}
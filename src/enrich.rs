@@ -0,0 +1,121 @@
+//! Click enrichment: a chain of [`ClickEnricher`]s runs for every recorded
+//! click and fills in derived fields (geo, language, ...). Embedders can add
+//! their own enrichers through `AppState::enrichers` without touching `redirect`.
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use async_trait::async_trait;
+use axum::http::{header, HeaderMap};
+
+use crate::{country_from_headers_or_ip, primary_language};
+
+/// What an enricher gets to look at for a single click.
+pub struct ClickContext<'a> {
+    pub code: &'a str,
+    pub headers: &'a HeaderMap,
+    pub ip: Option<&'a str>,
+    /// Fields produced by the enrichers that ran earlier in the chain.
+    pub fields: &'a ClickFields,
+}
+
+/// Fields an enricher can set. `Some` values and `extra` entries from later
+/// enrichers override earlier ones.
+#[derive(Clone, Debug, Default)]
+pub struct ClickFields {
+    pub country: Option<String>,
+    pub city: Option<String>,
+    pub language: Option<String>,
+    /// Free-form fields, stored as a JSON object in `clicks.extra`.
+    pub extra: BTreeMap<String, String>,
+}
+
+impl ClickFields {
+    fn merge(&mut self, other: ClickFields) {
+        if other.country.is_some() {
+            self.country = other.country;
+        }
+        if other.city.is_some() {
+            self.city = other.city;
+        }
+        if other.language.is_some() {
+            self.language = other.language;
+        }
+        self.extra.extend(other.extra);
+    }
+}
+
+#[async_trait]
+pub trait ClickEnricher: Send + Sync {
+    async fn enrich(&self, ctx: &ClickContext<'_>) -> ClickFields;
+}
+
+/// Country from CDN/proxy headers, falling back to an IP lookup when
+/// `ip_lookup` is set, plus city headers.
+pub struct GeoEnricher {
+    pub ip_lookup: bool,
+}
+
+#[async_trait]
+impl ClickEnricher for GeoEnricher {
+    async fn enrich(&self, ctx: &ClickContext<'_>) -> ClickFields {
+        let city = ctx
+            .headers
+            .get("x-geo-city")
+            .or_else(|| ctx.headers.get("cf-ipcity"))
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string());
+
+        ClickFields {
+            country: country_from_headers_or_ip(ctx.headers, ctx.ip, self.ip_lookup).await,
+            city,
+            ..ClickFields::default()
+        }
+    }
+}
+
+/// Primary language from Accept-Language.
+pub struct LanguageEnricher;
+
+#[async_trait]
+impl ClickEnricher for LanguageEnricher {
+    async fn enrich(&self, ctx: &ClickContext<'_>) -> ClickFields {
+        ClickFields {
+            language: ctx
+                .headers
+                .get(header::ACCEPT_LANGUAGE)
+                .and_then(|v| v.to_str().ok())
+                .and_then(primary_language),
+            ..ClickFields::default()
+        }
+    }
+}
+
+pub fn default_enrichers() -> Vec<Arc<dyn ClickEnricher>> {
+    enrichers_with_geo(true)
+}
+
+pub(crate) fn enrichers_with_geo(ip_lookup: bool) -> Vec<Arc<dyn ClickEnricher>> {
+    vec![Arc::new(GeoEnricher { ip_lookup }), Arc::new(LanguageEnricher)]
+}
+
+pub(crate) async fn run_chain(
+    enrichers: &[Arc<dyn ClickEnricher>],
+    code: &str,
+    headers: &HeaderMap,
+    ip: Option<&str>,
+) -> ClickFields {
+    let mut fields = ClickFields::default();
+    for enricher in enrichers {
+        let produced = {
+            let ctx = ClickContext {
+                code,
+                headers,
+                ip,
+                fields: &fields,
+            };
+            enricher.enrich(&ctx).await
+        };
+        fields.merge(produced);
+    }
+    fields
+}
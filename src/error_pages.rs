@@ -0,0 +1,120 @@
+//! What visitors see when a short link doesn't redirect. Browsers get an HTML
+//! page: an operator template from [`Settings`], else one from the template
+//! directory, else the built-in one; other clients keep the plain-text
+//! message. With `fallback_url` set, unknown,
+//! gone and disabled links redirect there instead.
+//!
+//! [`Settings`]: crate::Settings
+
+use axum::{
+    http::{header, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Redirect, Response},
+};
+
+use crate::{
+    html_escape,
+    page_templates::{render, Page},
+    AppState,
+};
+
+/// Which page a failed redirect gets.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum LinkError {
+    /// Unknown code, or a link not active yet: 404.
+    NotFound,
+    /// Expired, archived or used up: 410.
+    Gone,
+    /// Disabled by an admin: 410.
+    Disabled,
+    /// Awaiting review or needing a signature: 403. Never sent to the
+    /// fallback, since the link itself is fine.
+    Forbidden,
+}
+
+impl LinkError {
+    fn page(self) -> Page {
+        match self {
+            LinkError::NotFound => Page::NotFound,
+            LinkError::Gone => Page::Gone,
+            LinkError::Disabled => Page::Disabled,
+            LinkError::Forbidden => Page::Forbidden,
+        }
+    }
+
+    fn status(self) -> StatusCode {
+        match self {
+            LinkError::NotFound => StatusCode::NOT_FOUND,
+            LinkError::Gone | LinkError::Disabled => StatusCode::GONE,
+            LinkError::Forbidden => StatusCode::FORBIDDEN,
+        }
+    }
+}
+
+/// Built-in page; the placeholders are the same as in operator templates.
+const DEFAULT_PAGE: &str = r#"<!doctype html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8" />
+    <meta name="viewport" content="width=device-width, initial-scale=1" />
+    <meta name="robots" content="noindex" />
+    <title>{{message}}</title>
+    <style>
+      body { font-family: ui-sans-serif, system-ui, -apple-system, Segoe UI, Roboto, Arial; margin: 0; line-height: 1.35; background: #f6f7f9; }
+      main { max-width: 480px; margin: 15vh auto 0; padding: 24px; background: white; border: 1px solid #e5e5e5; border-radius: 12px; text-align: center; }
+      h1 { margin: 0 0 12px 0; font-size: 22px; }
+      .status { color: #888; font-size: 14px; }
+      .mono { font-family: ui-monospace, SFMono-Regular, Menlo, Monaco, Consolas, monospace; }
+    </style>
+  </head>
+  <body>
+    <main>
+      <p class="status">{{status}}</p>
+      <h1>{{message}}</h1>
+      <p>There is nothing to see at <span class="mono">/{{code}}</span>.</p>
+    </main>
+  </body>
+</html>"#;
+
+/// Response for a visit to `code` that can't redirect. `message` is the
+/// plain-text body, also shown on the HTML page.
+pub(crate) fn link_error(
+    state: &AppState,
+    headers: &HeaderMap,
+    kind: LinkError,
+    code: &str,
+    message: &str,
+) -> Response {
+    let settings = state.settings();
+    if kind != LinkError::Forbidden {
+        if let Some(fallback) = &settings.fallback_url {
+            return Redirect::temporary(fallback).into_response();
+        }
+    }
+    let status = kind.status();
+    if !wants_html(headers) {
+        return (status, message.to_string()).into_response();
+    }
+    let template = match kind {
+        LinkError::NotFound => settings.not_found_page.as_deref(),
+        LinkError::Gone => settings.gone_page.as_deref(),
+        LinkError::Disabled => settings.disabled_page.as_deref(),
+        LinkError::Forbidden => None,
+    };
+    let template = template.unwrap_or_else(|| state.page_templates.template(kind.page(), DEFAULT_PAGE));
+    let page = render(
+        template,
+        &[
+            ("code", &html_escape(code)),
+            ("message", &html_escape(message)),
+            ("status", status.as_str()),
+        ],
+    );
+    (status, [(header::CACHE_CONTROL, "no-store")], Html(page)).into_response()
+}
+
+fn wants_html(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/html"))
+}
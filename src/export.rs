@@ -0,0 +1,378 @@
+//! Full data export and import, for backups and moving to another instance.
+//! `GET /api/export` streams every row of `urls`, `clicks` and
+//! `click_rollups` with all their columns, as one JSON document or as
+//! NDJSON; `POST /api/import` loads such a dump back. Unlike the link
+//! manifest, every column is kept, so a dump carries creation metadata,
+//! counters and click history, detailed and rolled up, as they were.
+//! Aliases, tags and schedules are not part of it.
+//!
+//! Row ids are not exported: links are matched by code, and clicks get new
+//! ids on import.
+
+use std::collections::BTreeSet;
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures_util::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::{sqlite::SqliteRow, Column, Pool, Row, Sqlite, TypeInfo, ValueRef};
+
+use crate::{hll, internal, notify, require_admin, AppState};
+
+/// Largest import body accepted.
+pub(crate) const MAX_IMPORT_BYTES: usize = 512 * 1024 * 1024;
+
+/// Rows fetched per query while exporting.
+const BATCH: i64 = 1000;
+
+/// Exported tables, in the order an import needs them.
+const TABLES: [&str; 3] = ["urls", "clicks", "click_rollups"];
+
+const NDJSON: &str = "application/x-ndjson";
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Format {
+    /// `{"urls": [...], "clicks": [...]}`
+    #[default]
+    Json,
+    /// One `{"table": ..., "row": {...}}` per line.
+    Ndjson,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct ExportParams {
+    #[serde(default)]
+    format: Format,
+}
+
+/// A table row by column name.
+type Record = Map<String, Value>;
+
+/// One NDJSON line.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Line {
+    table: String,
+    row: Record,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct Dump {
+    #[serde(default)]
+    urls: Vec<Record>,
+    #[serde(default)]
+    clicks: Vec<Record>,
+    #[serde(default)]
+    click_rollups: Vec<Record>,
+}
+
+/// `row` as a record without its `id`, plus its rowid, which comes first.
+fn record(row: &SqliteRow) -> Result<(i64, Record), sqlx::Error> {
+    let rowid = row.try_get(0)?;
+    let mut record = Map::new();
+    for (i, column) in row.columns().iter().enumerate().skip(1) {
+        if column.name() == "id" {
+            continue;
+        }
+        let raw = row.try_get_raw(i)?;
+        let value = if raw.is_null() {
+            Value::Null
+        } else {
+            match raw.type_info().name() {
+                "INTEGER" => Value::from(row.try_get::<i64, _>(i)?),
+                "REAL" => Value::from(row.try_get::<f64, _>(i)?),
+                _ => Value::from(row.try_get::<String, _>(i)?),
+            }
+        };
+        record.insert(column.name().to_string(), value);
+    }
+    Ok((rowid, record))
+}
+
+/// Rows of `table` with a rowid above `after`, in rowid order. Rollups have
+/// no `id` column, so the cursor uses rowids throughout.
+async fn batch(pool: &Pool<Sqlite>, table: &str, after: i64) -> Result<Vec<(i64, Record)>, sqlx::Error> {
+    sqlx::query(&format!("SELECT rowid, * FROM {table} WHERE rowid > ? ORDER BY rowid LIMIT ?"))
+        .bind(after)
+        .bind(BATCH)
+        .fetch_all(pool)
+        .await?
+        .iter()
+        .map(record)
+        .collect()
+}
+
+/// Where the export stream is: the table and the last id sent from it.
+struct Cursor {
+    table: usize,
+    after: i64,
+    first: bool,
+}
+
+/// Text that goes before the rows of `TABLES[table]`, or after the last.
+fn framing(format: Format, table: usize) -> &'static str {
+    match (format, table) {
+        (Format::Ndjson, _) => "",
+        (Format::Json, 0) => "{\"urls\":[",
+        (Format::Json, 1) => "],\"clicks\":[",
+        (Format::Json, 2) => "],\"click_rollups\":[",
+        (Format::Json, _) => "]}\n",
+    }
+}
+
+/// `GET /api/export[?format=ndjson]`: every link, click and rollup. Admin
+/// only.
+pub(crate) async fn export(
+    State(state): State<AppState>,
+    Query(params): Query<ExportParams>,
+    headers: HeaderMap,
+) -> Result<Response, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let format = params.format;
+    let pool = state.pool.clone();
+    let start = Cursor {
+        table: 0,
+        after: 0,
+        first: true,
+    };
+    let rows = stream::unfold(Some(start), move |cursor| {
+        let pool = pool.clone();
+        async move {
+            let mut cursor = cursor?;
+            let table = TABLES.get(cursor.table)?;
+            let rows = match batch(&pool, table, cursor.after).await {
+                Ok(rows) => rows,
+                Err(e) => {
+                    tracing::error!("export of {table} failed: {e}");
+                    return Some((Err(e), None));
+                }
+            };
+            if rows.is_empty() {
+                let next = Cursor {
+                    table: cursor.table + 1,
+                    after: 0,
+                    first: true,
+                };
+                let chunk = framing(format, next.table);
+                return Some((Ok(chunk.to_string()), (next.table < TABLES.len()).then_some(next)));
+            }
+            let mut chunk = String::new();
+            for (id, row) in rows {
+                match format {
+                    Format::Json => {
+                        if !cursor.first {
+                            chunk.push(',');
+                        }
+                        chunk.push_str(&Value::Object(row).to_string());
+                    }
+                    Format::Ndjson => {
+                        let line = Line {
+                            table: table.to_string(),
+                            row,
+                        };
+                        chunk.push_str(&serde_json::to_string(&line).unwrap_or_default());
+                        chunk.push('\n');
+                    }
+                }
+                cursor.first = false;
+                cursor.after = id;
+            }
+            Some((Ok(chunk), Some(cursor)))
+        }
+    });
+    let body = stream::once(async move { Ok(framing(format, 0).to_string()) }).chain(rows);
+    let content_type = match format {
+        Format::Json => "application/json",
+        Format::Ndjson => NDJSON,
+    };
+    Ok(([(header::CONTENT_TYPE, content_type)], Body::from_stream(body)).into_response())
+}
+
+#[derive(Serialize, Default)]
+pub(crate) struct ImportReport {
+    created: usize,
+    updated: usize,
+    clicks: usize,
+    click_rollups: usize,
+}
+
+/// Parses a dump in either export format, by its content type.
+fn parse(headers: &HeaderMap, body: &[u8]) -> Result<Dump, String> {
+    let ndjson = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with(NDJSON));
+    if !ndjson {
+        return serde_json::from_slice(body).map_err(|e| e.to_string());
+    }
+    let mut dump = Dump::default();
+    let text = std::str::from_utf8(body).map_err(|e| e.to_string())?;
+    for (n, line) in text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+        let line: Line = serde_json::from_str(line).map_err(|e| format!("line {}: {e}", n + 1))?;
+        match line.table.as_str() {
+            "urls" => dump.urls.push(line.row),
+            "clicks" => dump.clicks.push(line.row),
+            "click_rollups" => dump.click_rollups.push(line.row),
+            other => return Err(format!("line {}: unknown table {other}", n + 1)),
+        }
+    }
+    Ok(dump)
+}
+
+/// Column names of `table` in this database, without `id`.
+async fn columns(conn: &mut sqlx::SqliteConnection, table: &str) -> Result<BTreeSet<String>, sqlx::Error> {
+    let names: Vec<(String,)> = sqlx::query_as("SELECT name FROM pragma_table_info(?) WHERE name != 'id'")
+        .bind(table)
+        .fetch_all(conn)
+        .await?;
+    Ok(names.into_iter().map(|(name,)| name).collect())
+}
+
+/// Checks that `record` only names columns of `table` and has a string `code`.
+fn check<'r>(table: &str, index: usize, record: &'r Record, known: &BTreeSet<String>) -> Result<&'r str, String> {
+    if let Some(column) = record.keys().find(|c| !known.contains(*c)) {
+        return Err(format!("{table}[{index}]: unknown column {column}"));
+    }
+    record
+        .get("code")
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("{table}[{index}]: code is required"))
+}
+
+/// Inserts `record` into `table`; `upsert` names the conflict target whose
+/// row is overwritten instead.
+async fn insert(
+    conn: &mut sqlx::SqliteConnection,
+    table: &str,
+    record: &Record,
+    upsert: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    let names: Vec<&str> = record.keys().map(String::as_str).collect();
+    let quoted: Vec<String> = names.iter().map(|c| format!("\"{c}\"")).collect();
+    let mut sql = format!(
+        "INSERT INTO {table} ({}) VALUES ({})",
+        quoted.join(", "),
+        vec!["?"; names.len()].join(", ")
+    );
+    if let Some(key) = upsert {
+        let set: Vec<String> = quoted.iter().map(|c| format!("{c} = excluded.{c}")).collect();
+        sql.push_str(&format!(" ON CONFLICT({key}) DO UPDATE SET {}", set.join(", ")));
+    }
+    let mut query = sqlx::query(&sql);
+    for value in record.values() {
+        query = match value {
+            Value::Null => query.bind(None::<String>),
+            Value::Bool(b) => query.bind(*b),
+            Value::Number(n) => match n.as_i64() {
+                Some(i) => query.bind(i),
+                None => query.bind(n.as_f64()),
+            },
+            Value::String(s) => query.bind(s.as_str()),
+            other => query.bind(other.to_string()),
+        };
+    }
+    query.execute(conn).await?;
+    Ok(())
+}
+
+/// `POST /api/import`: loads a dump from [`export`], JSON or NDJSON (sent as
+/// `application/x-ndjson`). Links are upserted by code, and the clicks and
+/// rollups of every imported link are replaced by the dump's. A code that is
+/// an alias here gets 409. Everything runs in one transaction. Admin only.
+pub(crate) async fn import(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<ImportReport>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let bad = |msg: String| (StatusCode::BAD_REQUEST, msg);
+    let dump = parse(&headers, &body).map_err(bad)?;
+
+    let mut tx = state.pool.begin().await.map_err(internal)?;
+    let url_columns = columns(&mut tx, "urls").await.map_err(internal)?;
+    let click_columns = columns(&mut tx, "clicks").await.map_err(internal)?;
+    let rollup_columns = columns(&mut tx, "click_rollups").await.map_err(internal)?;
+    let mut codes = BTreeSet::new();
+    for (i, url) in dump.urls.iter().enumerate() {
+        let code = check("urls", i, url, &url_columns).map_err(bad)?;
+        if !codes.insert(code) {
+            return Err(bad(format!("urls[{i}]: duplicate code {code}")));
+        }
+    }
+    for (i, click) in dump.clicks.iter().enumerate() {
+        let code = check("clicks", i, click, &click_columns).map_err(bad)?;
+        if !codes.contains(code) {
+            return Err(bad(format!("clicks[{i}]: code {code} is not among the urls")));
+        }
+    }
+    for (i, rollup) in dump.click_rollups.iter().enumerate() {
+        let code = check("click_rollups", i, rollup, &rollup_columns).map_err(bad)?;
+        if !codes.contains(code) {
+            return Err(bad(format!("click_rollups[{i}]: code {code} is not among the urls")));
+        }
+    }
+    // an imported link would shadow the alias, as `insert_url_with` prevents
+    let aliases: Vec<(String,)> = sqlx::query_as("SELECT alias FROM link_aliases ORDER BY alias")
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(internal)?;
+    if let Some((alias,)) = aliases.iter().find(|(alias,)| codes.contains(alias.as_str())) {
+        return Err((StatusCode::CONFLICT, format!("code {alias} is an alias of another link")));
+    }
+
+    let existing: BTreeSet<String> = sqlx::query_as::<_, (String,)>("SELECT code FROM urls")
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(internal)?
+        .into_iter()
+        .map(|(code,)| code)
+        .collect();
+    let mut report = ImportReport::default();
+    let mut events = Vec::new();
+    for url in &dump.urls {
+        insert(&mut tx, "urls", url, Some("code")).await.map_err(|e| bad(e.to_string()))?;
+        let code = url["code"].as_str().unwrap_or_default();
+        if existing.contains(code) {
+            report.updated += 1;
+        } else {
+            report.created += 1;
+            let target_url = url["target_url"].as_str().unwrap_or_default();
+            events.push(notify::Event::link_created(&state, code, target_url));
+        }
+    }
+    for code in &codes {
+        for table in ["clicks", "click_rollups"] {
+            sqlx::query(&format!("DELETE FROM {table} WHERE code = ?"))
+                .bind(code)
+                .execute(&mut *tx)
+                .await
+                .map_err(internal)?;
+        }
+    }
+    for click in &dump.clicks {
+        insert(&mut tx, "clicks", click, None).await.map_err(|e| bad(e.to_string()))?;
+        report.clicks += 1;
+    }
+    for rollup in &dump.click_rollups {
+        insert(&mut tx, "click_rollups", rollup, None).await.map_err(|e| bad(e.to_string()))?;
+        report.click_rollups += 1;
+    }
+    tx.commit().await.map_err(internal)?;
+    for event in events {
+        notify::spawn_link_event(&state, event);
+    }
+
+    // the clicks bypassed the click writer
+    for code in &codes {
+        hll::rebuild(&state, code).await.map_err(internal)?;
+    }
+    Ok(Json(report))
+}
@@ -0,0 +1,107 @@
+//! `GET /api/stats`: instance-wide totals in a few aggregate queries, for the
+//! dashboard overview and external reports. Click counts leave out bots and
+//! include rolled-up days, like per-link stats.
+
+use axum::{extract::State, http::StatusCode, Json};
+use serde::Serialize;
+use time::OffsetDateTime;
+
+use crate::{internal, AppState};
+
+/// Links in the weekly top list.
+const TOP_LINKS: i64 = 10;
+
+#[derive(Serialize)]
+pub(crate) struct TopLink {
+    pub(crate) code: String,
+    pub(crate) title: Option<String>,
+    pub(crate) short_url: String,
+    pub(crate) clicks: i64,
+}
+
+#[derive(Serialize)]
+pub(crate) struct GlobalStats {
+    /// Every link; the four counts below add up to it.
+    pub(crate) links: i64,
+    pub(crate) active_links: i64,
+    /// Past `expires_at` and not archived.
+    pub(crate) expired_links: i64,
+    /// Not active yet (`not_before` in the future).
+    pub(crate) scheduled_links: i64,
+    pub(crate) archived_links: i64,
+    pub(crate) total_clicks: i64,
+    /// Since midnight UTC.
+    pub(crate) clicks_today: i64,
+    /// Busiest links over the last seven days.
+    pub(crate) top_links_week: Vec<TopLink>,
+}
+
+pub(crate) async fn query_global_stats(state: &AppState) -> Result<GlobalStats, sqlx::Error> {
+    let (links, active_links, expired_links, scheduled_links, archived_links): (i64, i64, i64, i64, i64) =
+        sqlx::query_as(
+            "SELECT count(*), coalesce(sum(status = 'active'), 0), coalesce(sum(status = 'expired'), 0), \
+                    coalesce(sum(status = 'scheduled'), 0), coalesce(sum(status = 'archived'), 0) \
+             FROM (SELECT CASE \
+                            WHEN archived_at IS NOT NULL THEN 'archived' \
+                            WHEN julianday(expires_at) <= julianday('now') THEN 'expired' \
+                            WHEN julianday(not_before) > julianday('now') THEN 'scheduled' \
+                            ELSE 'active' END AS status \
+                   FROM urls)",
+        )
+        .fetch_one(&state.pool)
+        .await?;
+
+    let (total_clicks,): (i64,) = sqlx::query_as(
+        "SELECT (SELECT count(*) FROM clicks WHERE NOT is_bot) + \
+                (SELECT coalesce(sum(clicks - bots), 0) FROM click_rollups)",
+    )
+    .fetch_one(&state.pool)
+    .await?;
+
+    let now = OffsetDateTime::now_utc();
+    let midnight = now.replace_time(time::Time::MIDNIGHT).unix_timestamp();
+    let (clicks_today,): (i64,) = sqlx::query_as("SELECT count(*) FROM clicks WHERE at >= ? AND NOT is_bot")
+        .bind(midnight)
+        .fetch_one(&state.pool)
+        .await?;
+
+    // rolled-up days count whole, so the week starts at a day boundary there
+    let week_ago = now - time::Duration::days(7);
+    let rows: Vec<(String, Option<String>, i64)> = sqlx::query_as(
+        "SELECT t.code, u.title, sum(t.clicks) AS clicks \
+         FROM (SELECT code, count(*) AS clicks FROM clicks WHERE at >= ?1 AND NOT is_bot GROUP BY code \
+               UNION ALL \
+               SELECT code, sum(clicks - bots) FROM click_rollups WHERE day > date(?1, 'unixepoch') GROUP BY code) t \
+         JOIN urls u ON u.code = t.code \
+         GROUP BY t.code HAVING sum(t.clicks) > 0 ORDER BY clicks DESC, t.code LIMIT ?2",
+    )
+    .bind(week_ago.unix_timestamp())
+    .bind(TOP_LINKS)
+    .fetch_all(&state.pool)
+    .await?;
+    let top_links_week = rows
+        .into_iter()
+        .map(|(code, title, clicks)| TopLink {
+            short_url: state.short_url(&code),
+            code,
+            title,
+            clicks,
+        })
+        .collect();
+
+    Ok(GlobalStats {
+        links,
+        active_links,
+        expired_links,
+        scheduled_links,
+        archived_links,
+        total_clicks,
+        clicks_today,
+        top_links_week,
+    })
+}
+
+/// `GET /api/stats`
+pub(crate) async fn global_stats(State(state): State<AppState>) -> Result<Json<GlobalStats>, (StatusCode, String)> {
+    Ok(Json(query_global_stats(&state).await.map_err(internal)?))
+}
@@ -0,0 +1,127 @@
+//! Periodic target health checks and the "destination unavailable" page that
+//! `redirect` can serve for dead targets instead of bouncing visitors to a
+//! 404 elsewhere.
+
+use axum::{
+    http::{header, StatusCode},
+    response::{Html, IntoResponse},
+};
+use time::OffsetDateTime;
+
+use crate::{
+    html_escape,
+    page_templates::{render, Page},
+    AppState, LinkRow,
+};
+
+/// Links checked per [`check_targets`] call, oldest check first.
+const BATCH: i64 = 200;
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct HealthReport {
+    pub checked: usize,
+    pub dead: usize,
+}
+
+/// Checks targets not checked within `health_check_interval_hours`. A target
+/// is marked dead only when a second attempt fails as well. Does nothing when
+/// checks are disabled, there is no probe, or the service is read-only.
+pub async fn check_targets(state: &AppState) -> Result<HealthReport, sqlx::Error> {
+    let mut report = HealthReport::default();
+    let (Some(hours), Some(probe)) = (
+        state.settings().health_check_interval_hours,
+        state.target_probe.as_ref(),
+    ) else {
+        return Ok(report);
+    };
+    if state.is_read_only() {
+        return Ok(report);
+    }
+    let rfc3339 = &time::format_description::well_known::Rfc3339;
+    let now = OffsetDateTime::now_utc();
+    let due_before = (now - time::Duration::hours(hours.into())).format(rfc3339).unwrap();
+    let now = now.format(rfc3339).unwrap();
+
+    let due: Vec<(String, String)> = sqlx::query_as(
+        "SELECT code, target_url FROM urls \
+         WHERE disabled_at IS NULL AND archived_at IS NULL AND (target_checked_at IS NULL OR target_checked_at < ?) \
+         ORDER BY target_checked_at IS NOT NULL, target_checked_at LIMIT ?",
+    )
+    .bind(&due_before)
+    .bind(BATCH)
+    .fetch_all(&state.pool)
+    .await?;
+
+    for (code, target) in due {
+        let alive = probe.alive(&target).await || probe.alive(&target).await;
+        let sql = if alive {
+            "UPDATE urls SET target_checked_at = ?1, target_ok_at = ?1, target_dead_since = NULL WHERE code = ?2"
+        } else {
+            report.dead += 1;
+            "UPDATE urls SET target_checked_at = ?1, target_dead_since = coalesce(target_dead_since, ?1) \
+             WHERE code = ?2"
+        };
+        sqlx::query(sql).bind(&now).bind(&code).execute(&state.pool).await?;
+        report.checked += 1;
+    }
+    Ok(report)
+}
+
+/// Wayback Machine snapshot closest to when the target was last seen alive.
+fn archive_url(link: &LinkRow) -> Option<String> {
+    let ok_at = OffsetDateTime::parse(
+        link.target_ok_at.as_deref()?,
+        &time::format_description::well_known::Rfc3339,
+    )
+    .ok()?;
+    let stamp = format!(
+        "{:04}{:02}{:02}{:02}{:02}{:02}",
+        ok_at.year(),
+        u8::from(ok_at.month()),
+        ok_at.day(),
+        ok_at.hour(),
+        ok_at.minute(),
+        ok_at.second()
+    );
+    Some(format!("https://web.archive.org/web/{stamp}/{}", link.target_url))
+}
+
+/// Built-in page; see [`Page::Unavailable`] for the placeholders.
+const DEFAULT_PAGE: &str = r#"<!doctype html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8" />
+    <meta name="robots" content="noindex" />
+    <title>Destination unavailable</title>
+  </head>
+  <body>
+    <h1>Destination unavailable</h1>
+    <p>This link points to <strong>{{name}}</strong>, which was unavailable when last checked.</p>
+    {{archive}}
+    <p><a href="{{target}}" rel="nofollow">Try the original address anyway</a></p>
+  </body>
+</html>"#;
+
+pub(crate) fn unavailable_page(state: &AppState, link: &LinkRow) -> impl IntoResponse {
+    let name = link.title.as_deref().unwrap_or(&link.target_url);
+    let archive = match archive_url(link) {
+        Some(url) => format!(
+            "<p><a href=\"{}\">View an archived copy</a> from when it last worked.</p>",
+            html_escape(&url)
+        ),
+        None => String::new(),
+    };
+    let page = render(
+        state.page_templates.template(Page::Unavailable, DEFAULT_PAGE),
+        &[
+            ("name", &html_escape(name)),
+            ("archive", &archive),
+            ("target", &html_escape(&link.target_url)),
+        ],
+    );
+    (
+        StatusCode::OK,
+        [(header::CACHE_CONTROL, "no-store")],
+        Html(page),
+    )
+}
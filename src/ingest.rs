@@ -0,0 +1,138 @@
+//! `POST /api/clicks`: click events pushed by edge workers or CDNs that serve
+//! redirects themselves. Each event goes through the same enricher chain,
+//! click limit and click writer as a local redirect.
+
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use time::OffsetDateTime;
+
+use crate::{api_keys::require_api_key, claim_click_with, fetch_link, internal, AppState, NewClick};
+
+const MAX_BATCH: usize = 1000;
+
+#[derive(Deserialize)]
+pub(crate) struct ClickBatch {
+    clicks: Vec<ClickEvent>,
+}
+
+/// One redirect served elsewhere, described by the visitor's request.
+#[derive(Deserialize)]
+struct ClickEvent {
+    code: String,
+    /// RFC3339; defaults to the time of ingestion.
+    at: Option<String>,
+    ip: Option<String>,
+    user_agent: Option<String>,
+    referer: Option<String>,
+    accept_language: Option<String>,
+    /// ISO country code, if the edge already knows it.
+    country: Option<String>,
+    /// Recipient of a signed URL the edge verified.
+    recipient: Option<String>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct IngestReport {
+    accepted: usize,
+    rejected: Vec<Rejected>,
+}
+
+#[derive(Serialize)]
+struct Rejected {
+    index: usize,
+    error: String,
+}
+
+impl ClickEvent {
+    /// Rebuilds the request headers the enrichers and fingerprint read.
+    fn headers(&self) -> Result<HeaderMap, String> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in [
+            (HeaderName::from_static("x-forwarded-for"), &self.ip),
+            (header::USER_AGENT, &self.user_agent),
+            (header::REFERER, &self.referer),
+            (header::ACCEPT_LANGUAGE, &self.accept_language),
+            (HeaderName::from_static("x-geo-country"), &self.country),
+        ] {
+            if let Some(value) = value {
+                let value = HeaderValue::from_str(value)
+                    .map_err(|_| format!("invalid characters in {name}"))?;
+                headers.insert(name, value);
+            }
+        }
+        Ok(headers)
+    }
+}
+
+pub(crate) async fn ingest_clicks(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(batch): Json<ClickBatch>,
+) -> Result<Json<IngestReport>, (StatusCode, String)> {
+    require_api_key(&state, &headers).await?;
+    if batch.clicks.len() > MAX_BATCH {
+        return Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("at most {MAX_BATCH} clicks per batch"),
+        ));
+    }
+
+    // code as sent -> stored code, or None when unknown
+    let mut codes: HashMap<String, Option<String>> = HashMap::new();
+    let mut report = IngestReport {
+        accepted: 0,
+        rejected: Vec::new(),
+    };
+    for (index, event) in batch.clicks.iter().enumerate() {
+        let result = async {
+            let code = match codes.get(&event.code) {
+                Some(code) => code.clone(),
+                None => {
+                    let link = fetch_link(&state, &event.code).await.map_err(internal)?;
+                    let code = link.map(|l| l.code);
+                    codes.insert(event.code.clone(), code.clone());
+                    code
+                }
+            }
+            .ok_or_else(|| (StatusCode::NOT_FOUND, "unknown code".to_string()))?;
+            let at = match &event.at {
+                Some(at) => OffsetDateTime::parse(at, &time::format_description::well_known::Rfc3339)
+                    .map_err(|_| (StatusCode::BAD_REQUEST, "at must be RFC3339".to_string()))?,
+                None => OffsetDateTime::now_utc(),
+            };
+            let headers = event.headers().map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+            let click = NewClick::enrich(&state, &code, &headers, event.recipient.as_deref(), at).await;
+
+            // the edge served the redirect, but it still counts toward
+            // max_clicks; the count and the click are stored together
+            let mut tx = state.pool.begin().await.map_err(internal)?;
+            let claimed = if click.is_bot {
+                None
+            } else {
+                let claimed = claim_click_with(&mut tx, &code).await.map_err(internal)?;
+                Some(claimed.ok_or_else(|| (StatusCode::GONE, "click limit reached".to_string()))?)
+            };
+            let id = click.insert(&mut tx).await.map_err(internal)?;
+            tx.commit().await.map_err(internal)?;
+
+            if let Some(claimed) = claimed {
+                claimed.notify(&state);
+            }
+            if let Err(e) = click.publish(&state, id).await {
+                tracing::warn!("click {id} for {code} was stored, but publishing it failed: {e}");
+            }
+            Ok(())
+        }
+        .await;
+        match result {
+            Ok(()) => report.accepted += 1,
+            Err((_, error)) => report.rejected.push(Rejected { index, error }),
+        }
+    }
+    Ok(Json(report))
+}
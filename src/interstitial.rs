@@ -0,0 +1,176 @@
+//! Branded interstitial for links with `redirect_mode: "interstitial"`: a
+//! page with the deployment's HTML snippet (ad, logo, notice), a countdown
+//! and a continue button, shown before the redirect. Views of the page are
+//! counted in `interstitial_views`; the click is only recorded once the
+//! visitor continues.
+//!
+//! The continue link carries `?continue=<issued>.<sig>`, an HMAC over code
+//! and issue time, so visitors can't skip the countdown by editing the URL.
+
+use std::time::Duration;
+
+use axum::{
+    http::header,
+    response::{Html, IntoResponse},
+};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use time::OffsetDateTime;
+
+use crate::{
+    html_escape,
+    page_templates::{render, Page},
+    signing, target_domain, AppState, LinkRow,
+};
+
+/// Query parameter carrying the continue token.
+const PARAM: &str = "continue";
+
+/// How long a continue link stays valid after the page was shown.
+const TOKEN_TTL_SECS: i64 = 3600;
+
+/// Deployment-wide look of the interstitial.
+#[derive(Clone, Debug)]
+pub struct Interstitial {
+    /// Raw HTML placed above the countdown. Trusted: it comes from the
+    /// operator, not from link creators.
+    pub html: String,
+    /// Wait before the continue button works and the page moves on.
+    pub countdown: Duration,
+}
+
+impl Default for Interstitial {
+    fn default() -> Self {
+        Self {
+            html: String::new(),
+            countdown: Duration::from_secs(5),
+        }
+    }
+}
+
+fn mac(state: &AppState, code: &str, issued: i64) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(state.signing_secret.as_bytes()).expect("any key length");
+    mac.update(format!("interstitial\n{code}\n{issued}").as_bytes());
+    mac
+}
+
+fn token(state: &AppState, code: &str, issued: i64) -> String {
+    let sig: String = mac(state, code, issued).finalize().into_bytes()[..16]
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    format!("{issued}.{sig}")
+}
+
+/// Whether `query` has a continue token for `code` whose countdown is over
+/// and which has not expired.
+pub(crate) fn passed(state: &AppState, code: &str, query: Option<&str>) -> bool {
+    let Some(given) = query.and_then(|q| {
+        url::form_urlencoded::parse(q.as_bytes()).find_map(|(key, value)| (key == PARAM).then_some(value))
+    }) else {
+        return false;
+    };
+    let Some((issued, sig)) = given.split_once('.') else {
+        return false;
+    };
+    let (Ok(issued), Some(sig)) = (issued.parse::<i64>(), signing::decode_hex(sig).filter(|s| s.len() == 16)) else {
+        return false;
+    };
+    if mac(state, code, issued).verify_truncated_left(&sig).is_err() {
+        return false;
+    }
+    let waited = OffsetDateTime::now_utc().unix_timestamp() - issued;
+    waited >= state.interstitial.countdown.as_secs() as i64 && waited <= TOKEN_TTL_SECS
+}
+
+/// `query` without the continue token, for the target and signature checks.
+pub(crate) fn strip(query: Option<&str>) -> Option<String> {
+    let rest: Vec<_> = url::form_urlencoded::parse(query?.as_bytes())
+        .filter(|(key, _)| key != PARAM)
+        .collect();
+    if rest.is_empty() {
+        return None;
+    }
+    Some(url::form_urlencoded::Serializer::new(String::new()).extend_pairs(rest).finish())
+}
+
+/// Counts one view of the interstitial for `code`. Failures are swallowed
+/// like click recording.
+pub(crate) async fn record_view(state: &AppState, code: &str) {
+    let _ = sqlx::query("INSERT INTO interstitial_views (code, at) VALUES (?, ?)")
+        .bind(code)
+        .bind(OffsetDateTime::now_utc().unix_timestamp())
+        .execute(&state.pool)
+        .await;
+}
+
+/// Built-in page; see [`Page::Interstitial`] for the placeholders.
+const DEFAULT_PAGE: &str = r#"<!doctype html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8" />
+    <meta name="robots" content="noindex" />
+    <meta name="viewport" content="width=device-width, initial-scale=1" />
+    <meta http-equiv="refresh" content="{{refresh}}; url={{continue_url}}" />
+    <title>Continue to {{domain}}</title>
+    <style>
+      body { font-family: system-ui, sans-serif; max-width: 640px; margin: 40px auto; padding: 0 16px; text-align: center; }
+      .continue { display: inline-block; margin-top: 16px; padding: 10px 14px; border-radius: 10px; background: #0b62d6; color: white; text-decoration: none; }
+      .continue[aria-disabled="true"] { background: #9bb7e0; pointer-events: none; }
+    </style>
+  </head>
+  <body>
+    <div class="brand">{{snippet}}</div>
+    <p>You are going to <strong>{{domain}}</strong><span id="wait"> in <span id="seconds">{{seconds}}</span> s</span>.</p>
+    <a class="continue" id="continue" href="{{continue_url}}" rel="nofollow" aria-disabled="{{disabled}}">Continue to {{domain}}</a>
+    <script>
+      let left = {{seconds}};
+      const button = document.getElementById("continue");
+      const tick = () => {
+        if (left <= 0) {
+          button.setAttribute("aria-disabled", "false");
+          document.getElementById("wait").textContent = "";
+          return;
+        }
+        document.getElementById("seconds").textContent = left;
+        left -= 1;
+        setTimeout(tick, 1000);
+      };
+      tick();
+      setTimeout(() => window.location.replace({{continue_url_js}}), {{seconds}} * 1000 + 250);
+    </script>
+  </body>
+</html>"#;
+
+/// The interstitial for `link`, continuing to `path` (the short URL plus any
+/// forwarded path) with `query` kept so signed URLs still verify.
+pub(crate) fn page(state: &AppState, link: &LinkRow, path: &str, query: Option<&str>, target: &str) -> impl IntoResponse {
+    let issued = OffsetDateTime::now_utc().unix_timestamp();
+    let mut continue_url = format!("{path}?");
+    if let Some(query) = query {
+        continue_url.push_str(query);
+        continue_url.push('&');
+    }
+    continue_url.push_str(PARAM);
+    continue_url.push('=');
+    continue_url.push_str(&token(state, &link.code, issued));
+    let seconds = state.interstitial.countdown.as_secs();
+    let domain = target_domain(target).unwrap_or_default();
+    let js_url = serde_json::to_string(&continue_url)
+        .unwrap_or_else(|_| "\"\"".to_string())
+        .replace('<', "\\u003c");
+    let page = render(
+        state.page_templates.template(Page::Interstitial, DEFAULT_PAGE),
+        &[
+            // a second of slack so the token is never refused as too early
+            ("refresh", &(seconds + 1).to_string()),
+            ("continue_url", &html_escape(&continue_url)),
+            ("continue_url_js", &js_url),
+            ("domain", &html_escape(&domain)),
+            ("snippet", &state.interstitial.html),
+            ("seconds", &seconds.to_string()),
+            ("disabled", if seconds > 0 { "true" } else { "false" }),
+        ],
+    );
+    ([(header::CACHE_CONTROL, "no-store")], Html(page))
+}
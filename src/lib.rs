@@ -1,307 +1,1079 @@
 use axum::{
-    extract::{Path, State},
-    http::{header, HeaderMap, StatusCode},
-    body::Bytes,
-    response::{Html, IntoResponse, Redirect},
-    routing::{get, post},
+    extract::{Path, Query, RawQuery, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method, Request, StatusCode},
+    body::Body,
+    response::{IntoResponse, Redirect},
+    routing::{get, post, Route},
     Json, Router,
 };
 use rand::{distributions::Alphanumeric, Rng};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use sqlx::{Pool, Sqlite};
-use std::{collections::HashMap, sync::Arc, time::Duration};
-use std::io::Cursor;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::Duration,
+};
+use std::convert::Infallible;
+use axum::error_handling::HandleErrorLayer;
+use tower::{
+    limit::GlobalConcurrencyLimitLayer, timeout::TimeoutLayer, BoxError, Layer, Service,
+    ServiceBuilder,
+};
 use tokio::sync::Mutex;
 use time::OffsetDateTime;
 
+use api_keys::Caller;
+use error_pages::{link_error, LinkError};
+
+#[cfg(feature = "dashboard")]
+mod dashboard;
+pub mod enrich;
+mod export;
+mod error_pages;
+mod alerts;
+mod aliases;
+mod anomaly;
+mod backup;
+mod api_keys;
+mod audit;
+mod bans;
+mod channels;
+mod click_webhooks;
+mod clicks_csv;
+mod batch;
+mod codegen;
+mod countries;
+mod deliveries;
+mod devices;
+mod global_stats;
+mod health;
+mod interstitial;
+mod live;
+mod hll;
+mod ingest;
+mod schedule;
+mod search;
+mod seed;
+mod manifest;
+mod metrics;
+mod migrations;
+mod preview;
+mod qr_landing;
+mod probe;
+mod moderation;
+mod notify;
+mod page_templates;
+#[cfg(feature = "object-store")]
+mod object_store;
+mod quarantine;
+mod rollup;
+mod selfcheck;
+mod settings;
+mod signing;
+mod stats_batch;
+mod status;
+mod tags;
+mod templates;
+mod user_agents;
+mod validate;
+mod webhook;
+
+pub use anomaly::{detect_anomalies, Anomaly};
+pub use async_trait::async_trait;
+pub use backup::{database_file, list_snapshots, prune_snapshots, restore_snapshot, snapshot_database};
+pub use codegen::{BlockCodes, CodeGenerator, RandomCodes, SequentialCodes, WordlistCodes};
+pub use deliveries::{retry_webhooks, RetryReport};
+pub use enrich::{default_enrichers, ClickContext, ClickEnricher, ClickFields};
+pub use health::{check_targets, HealthReport};
+pub use notify::{notify_expired_links, warn_expiring_links, Event, Notifier, WebhookFormat, WebhookNotifier};
+pub use page_templates::PageTemplates;
+#[cfg(feature = "object-store")]
+pub use object_store::ObjectStore;
+#[cfg(feature = "object-store")]
+pub use backup::upload_snapshot;
+pub use hll::backfill_sketches;
+pub use interstitial::Interstitial;
+pub use migrations::{migration_status, run_migrations, MigrationStatus};
+#[cfg(feature = "probe")]
+pub use probe::HttpProbe;
+pub use probe::TargetProbe;
+pub use rollup::compact_clicks;
+pub use seed::{seed_demo, seed_synthetic, SeedOptions, SeedReport};
+pub use selfcheck::{self_check, Check, CheckStatus, SelfCheckReport};
+pub use settings::{load_settings, purge_old_clicks, Settings};
+pub use user_agents::backfill_user_agents;
+
+/// Shared application state. Construct it with [`AppState::builder`]; new
+/// fields get builder defaults instead of breaking existing callers.
 #[derive(Clone)]
+#[non_exhaustive]
 pub struct AppState {
     pub pool: Pool<Sqlite>,
     pub base_url: String,
+    /// Path the app is mounted under inside a larger router, e.g. `/shortener`.
+    /// Empty when served at the root.
+    pub path_prefix: String,
     pub rate_limiter: RateLimiter,
+    pub fingerprint: FingerprintConfig,
+    /// Runs in order for every click; see [`enrich::default_enrichers`].
+    pub enrichers: Vec<Arc<dyn ClickEnricher>>,
+    /// In-memory demo instance: the dashboard warns that data is not persisted.
+    pub demo: bool,
+    pub security_headers: SecurityHeaders,
+    pub limits: RequestLimits,
+    /// Count unique visitors with `count(DISTINCT ...)` instead of the
+    /// HyperLogLog estimates. Per request, `?exact=true` does the same.
+    pub exact_unique_counts: bool,
+    /// Serializes sketch updates per link; see [`hll::record`].
+    pub(crate) sketch_locks: hll::SketchLocks,
+    /// Bearer token for `/api/admin/*`. Admin endpoints are disabled without one.
+    pub admin_token: Option<String>,
+    /// Runtime policy; read with [`AppState::settings`].
+    pub(crate) settings: Arc<std::sync::RwLock<Settings>>,
+    /// Intranet "go/" mode: codes are case-insensitive keywords and unknown
+    /// codes show the closest existing ones.
+    pub go_links: bool,
+    /// HMAC key for signed per-recipient URLs. Random per process unless set;
+    /// a random one breaks every signed URL on restart.
+    pub signing_secret: String,
+    /// `signing_secret` was not set and is random.
+    pub(crate) signing_secret_generated: bool,
+    /// Checks targets for the `https_upgrade` setting; `None` disables it.
+    pub target_probe: Option<Arc<dyn TargetProbe>>,
+    /// Single-IP headers set by a trusted proxy (e.g. `cf-connecting-ip`),
+    /// tried in order before `X-Forwarded-For`; see [`AppState::client_ip`].
+    pub client_ip_headers: Vec<HeaderName>,
+    /// Picks codes for links created without a custom code.
+    pub code_generator: Arc<dyn CodeGenerator>,
+    /// Receive every alert, anomaly, moderation and expiry [`Event`].
+    pub notifiers: Vec<Arc<dyn Notifier>>,
+    /// Read-only for the life of the process, whatever the `read_only`
+    /// setting says; see [`AppState::is_read_only`].
+    pub read_only: bool,
+    /// Host of `base_url`, parsed once for the click writer.
+    pub(crate) base_host: Option<String>,
+    /// Page shown before redirecting links in `interstitial` mode.
+    pub interstitial: Interstitial,
+    /// Operator versions of the HTML pages; see [`PageTemplates`].
+    pub page_templates: Arc<PageTemplates>,
+    /// Counters served at `/metrics`.
+    pub(crate) metrics: Arc<metrics::Metrics>,
+    /// Clicks without a CDN country header are looked up by IP; see
+    /// [`AppStateBuilder::geo`].
+    pub geo_lookup: bool,
+    /// Feeds the click streams; see [`live`].
+    pub(crate) live: live::LiveClicks,
+    /// Cached click webhooks and their delivery queue.
+    pub(crate) click_webhooks: click_webhooks::ClickWebhooks,
+}
+
+/// Guards against slow dependencies (geo lookup, SQLite contention) piling up
+/// unbounded in-flight requests. `None` disables a limit.
+#[derive(Clone, Debug)]
+pub struct RequestLimits {
+    /// Requests running longer than this get 408.
+    pub timeout: Option<Duration>,
+    /// Requests beyond this many in flight are shed immediately with 503.
+    pub max_in_flight: Option<usize>,
+}
+
+impl Default for RequestLimits {
+    fn default() -> Self {
+        Self {
+            timeout: Some(Duration::from_secs(30)),
+            max_in_flight: Some(512),
+        }
+    }
+}
+
+/// Response headers added by the security middleware. `None` disables a
+/// header. The CSP is only sent with HTML responses (dashboard and redirect
+/// pages), which use inline script and style.
+#[derive(Clone, Debug)]
+pub struct SecurityHeaders {
+    pub hsts: Option<String>,
+    pub content_type_options: bool,
+    pub referrer_policy: Option<String>,
+    /// `frame-ancestors` directive for HTML responses, e.g. `'none'`.
+    pub frame_ancestors: Option<String>,
+    pub html_csp: Option<String>,
+}
+
+impl Default for SecurityHeaders {
+    fn default() -> Self {
+        Self {
+            hsts: None,
+            content_type_options: true,
+            referrer_policy: Some("strict-origin-when-cross-origin".to_string()),
+            frame_ancestors: Some("'none'".to_string()),
+            html_csp: Some(
+                "default-src 'self'; script-src 'self' 'unsafe-inline'; \
+                 style-src 'self' 'unsafe-inline'; img-src 'self' data:; connect-src 'self'; \
+                 base-uri 'none'; form-action 'self'"
+                    .to_string(),
+            ),
+        }
+    }
+}
+
+impl SecurityHeaders {
+    /// Defaults plus a one-year HSTS policy, for deployments served over HTTPS.
+    pub fn with_hsts() -> Self {
+        Self {
+            hsts: Some("max-age=31536000; includeSubDomains".to_string()),
+            ..Self::default()
+        }
+    }
+
+    /// Sends none of the headers, e.g. when a proxy in front already sets them.
+    pub fn disabled() -> Self {
+        Self {
+            hsts: None,
+            content_type_options: false,
+            referrer_policy: None,
+            frame_ancestors: None,
+            html_csp: None,
+        }
+    }
+}
+
+impl AppState {
+    pub fn builder(pool: Pool<Sqlite>) -> AppStateBuilder {
+        AppStateBuilder {
+            pool,
+            base_url: "http://localhost:3000".to_string(),
+            path_prefix: String::new(),
+            rate_limiter: RateLimiter::new(10, Duration::from_secs(60)),
+            fingerprint: FingerprintConfig::default(),
+            geo_lookup: true,
+            enrichers: None,
+            demo: false,
+            security_headers: None,
+            limits: RequestLimits::default(),
+            exact_unique_counts: false,
+            admin_token: None,
+            go_links: false,
+            signing_secret: None,
+            target_probe: probe::default_probe(),
+            client_ip_headers: Vec::new(),
+            code_generator: Arc::new(RandomCodes::default()),
+            notifiers: Vec::new(),
+            read_only: false,
+            interstitial: Interstitial::default(),
+            page_templates: PageTemplates::default(),
+        }
+    }
+
+    /// Visitor IP: the first configured [`AppState::client_ip_headers`]
+    /// present, else the first `X-Forwarded-For` entry.
+    fn client_ip(&self, headers: &HeaderMap) -> Option<String> {
+        self.client_ip_headers
+            .iter()
+            .chain([&HeaderName::from_static("x-forwarded-for")])
+            .find_map(|name| {
+                let value = headers.get(name)?.to_str().ok()?;
+                let first = value.split(',').next()?.trim();
+                (!first.is_empty()).then(|| first.to_string())
+            })
+    }
+
+    /// Snapshot of the current runtime settings.
+    pub fn settings(&self) -> Settings {
+        self.settings.read().unwrap().clone()
+    }
+
+    /// Whether writes are refused, by the builder flag or the `read_only`
+    /// setting. Redirects and stats keep working; clicks are not recorded.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only || self.settings.read().unwrap().read_only
+    }
+
+    /// Spelling a new custom code is stored under: lowercase in go-links mode.
+    fn normalize_code(&self, code: &str) -> String {
+        if self.go_links {
+            code.to_lowercase()
+        } else {
+            code.to_string()
+        }
+    }
+
+    fn prefix(&self) -> &str {
+        self.path_prefix.trim_end_matches('/')
+    }
+
+    /// Public URL of a short code, including the mount prefix.
+    pub fn short_url(&self, code: &str) -> String {
+        self.public_url(&format!("/{}", code))
+    }
+
+    /// Public URL of an app path such as `/api/links/abc/qr`.
+    pub fn public_url(&self, path: &str) -> String {
+        format!("{}{}{}", self.base_url.trim_end_matches('/'), self.prefix(), path)
+    }
+}
+
+pub struct AppStateBuilder {
+    pool: Pool<Sqlite>,
+    base_url: String,
+    path_prefix: String,
+    rate_limiter: RateLimiter,
+    fingerprint: FingerprintConfig,
+    geo_lookup: bool,
+    enrichers: Option<Vec<Arc<dyn ClickEnricher>>>,
+    demo: bool,
+    security_headers: Option<SecurityHeaders>,
+    limits: RequestLimits,
+    exact_unique_counts: bool,
+    admin_token: Option<String>,
+    go_links: bool,
+    signing_secret: Option<String>,
+    target_probe: Option<Arc<dyn TargetProbe>>,
+    client_ip_headers: Vec<HeaderName>,
+    code_generator: Arc<dyn CodeGenerator>,
+    notifiers: Vec<Arc<dyn Notifier>>,
+    read_only: bool,
+    interstitial: Interstitial,
+    page_templates: PageTemplates,
+}
+
+impl AppStateBuilder {
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    pub fn path_prefix(mut self, path_prefix: impl Into<String>) -> Self {
+        self.path_prefix = path_prefix.into();
+        self
+    }
+
+    /// Requests per `window` allowed per client IP on write endpoints.
+    pub fn rate_limit(mut self, limit: usize, window: Duration) -> Self {
+        self.rate_limiter = RateLimiter::new(limit, window);
+        self
+    }
+
+    pub fn fingerprint(mut self, fingerprint: FingerprintConfig) -> Self {
+        self.fingerprint = fingerprint;
+        self
+    }
+
+    /// Whether the default geo enricher may look up countries by IP when no
+    /// country header is present. Ignored when `enrichers` is set explicitly.
+    pub fn geo(mut self, ip_lookup: bool) -> Self {
+        self.geo_lookup = ip_lookup;
+        self
+    }
+
+    /// Replaces the default click enricher chain.
+    pub fn enrichers(mut self, enrichers: Vec<Arc<dyn ClickEnricher>>) -> Self {
+        self.enrichers = Some(enrichers);
+        self
+    }
+
+    /// Appends an enricher to the chain (after the defaults, unless replaced).
+    pub fn enricher(mut self, enricher: Arc<dyn ClickEnricher>) -> Self {
+        let geo_lookup = self.geo_lookup;
+        self.enrichers
+            .get_or_insert_with(|| enrich::enrichers_with_geo(geo_lookup))
+            .push(enricher);
+        self
+    }
+
+    pub fn demo(mut self, demo: bool) -> Self {
+        self.demo = demo;
+        self
+    }
+
+    /// Overrides the security headers. By default HSTS is only sent when
+    /// `base_url` is `https://`.
+    pub fn security_headers(mut self, headers: SecurityHeaders) -> Self {
+        self.security_headers = Some(headers);
+        self
+    }
+
+    pub fn limits(mut self, limits: RequestLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Starts in read-only mode, which the `read_only` setting cannot lift.
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Uses exact unique-visitor counts everywhere. Slower on large links.
+    pub fn exact_unique_counts(mut self, exact: bool) -> Self {
+        self.exact_unique_counts = exact;
+        self
+    }
+
+    pub fn admin_token(mut self, token: impl Into<String>) -> Self {
+        self.admin_token = Some(token.into()).filter(|t| !t.is_empty());
+        self
+    }
+
+    pub fn go_links(mut self, go_links: bool) -> Self {
+        self.go_links = go_links;
+        self
+    }
+
+    pub fn signing_secret(mut self, secret: impl Into<String>) -> Self {
+        self.signing_secret = Some(secret.into()).filter(|s| !s.is_empty());
+        self
+    }
+
+    /// Replaces the probe used for HTTPS upgrades; `None` turns them off.
+    pub fn target_probe(mut self, probe: Option<Arc<dyn TargetProbe>>) -> Self {
+        self.target_probe = probe;
+        self
+    }
+
+    /// Headers carrying the visitor IP, tried in order before
+    /// `X-Forwarded-For`. Only list headers your proxy sets and strips from
+    /// incoming requests, such as `cf-connecting-ip`, `true-client-ip` or
+    /// `fly-client-ip`; otherwise clients can spoof their IP.
+    pub fn client_ip_headers(mut self, headers: impl IntoIterator<Item = HeaderName>) -> Self {
+        self.client_ip_headers = headers.into_iter().collect();
+        self
+    }
+
+    /// Replaces the default [`RandomCodes`].
+    pub fn code_generator(mut self, generator: Arc<dyn CodeGenerator>) -> Self {
+        self.code_generator = generator;
+        self
+    }
+
+    /// Adds a deployment-wide notification channel.
+    pub fn notifier(mut self, notifier: Arc<dyn Notifier>) -> Self {
+        self.notifiers.push(notifier);
+        self
+    }
+
+    /// Snippet and countdown of the interstitial page.
+    pub fn interstitial(mut self, interstitial: Interstitial) -> Self {
+        self.interstitial = interstitial;
+        self
+    }
+
+    /// Replaces built-in HTML pages; see [`PageTemplates::load`].
+    pub fn page_templates(mut self, templates: PageTemplates) -> Self {
+        self.page_templates = templates;
+        self
+    }
+
+    pub fn build(self) -> AppState {
+        let security_headers = self.security_headers.unwrap_or_else(|| {
+            if self.base_url.starts_with("https://") {
+                SecurityHeaders::with_hsts()
+            } else {
+                SecurityHeaders::default()
+            }
+        });
+        let (limit, window) = self.rate_limiter.limits();
+        let settings = Settings {
+            rate_limit_requests: limit,
+            rate_limit_window_secs: window.as_secs().max(1),
+            ..Settings::default()
+        };
+        AppState {
+            pool: self.pool,
+            base_host: target_domain(&self.base_url),
+            base_url: self.base_url,
+            path_prefix: self.path_prefix,
+            rate_limiter: self.rate_limiter,
+            fingerprint: self.fingerprint,
+            enrichers: self
+                .enrichers
+                .unwrap_or_else(|| enrich::enrichers_with_geo(self.geo_lookup)),
+            demo: self.demo,
+            security_headers,
+            limits: self.limits,
+            exact_unique_counts: self.exact_unique_counts,
+            sketch_locks: hll::SketchLocks::new(),
+            admin_token: self.admin_token,
+            settings: Arc::new(std::sync::RwLock::new(settings)),
+            go_links: self.go_links,
+            signing_secret_generated: self.signing_secret.is_none(),
+            signing_secret: self.signing_secret.unwrap_or_else(|| {
+                rand::thread_rng()
+                    .sample_iter(&Alphanumeric)
+                    .take(32)
+                    .map(char::from)
+                    .collect()
+            }),
+            target_probe: self.target_probe,
+            client_ip_headers: self.client_ip_headers,
+            code_generator: self.code_generator,
+            notifiers: self.notifiers,
+            read_only: self.read_only,
+            interstitial: self.interstitial,
+            page_templates: Arc::new(self.page_templates),
+            metrics: Arc::default(),
+            geo_lookup: self.geo_lookup,
+            live: live::LiveClicks::default(),
+            click_webhooks: click_webhooks::ClickWebhooks::default(),
+        }
+    }
+}
+
+/// Which request signals make up the visitor hash used for unique-visitor
+/// counts. More signals means finer-grained (and less private) uniqueness.
+#[derive(Clone)]
+pub struct FingerprintConfig {
+    pub use_ip: bool,
+    pub use_user_agent: bool,
+    pub use_accept_language: bool,
+    /// Mixed into every hash so visitor ids can't be reversed by brute-forcing
+    /// IPs. The default is random per process, so ids (and unique counts) only
+    /// stay stable across restarts when a fixed secret is set.
+    pub secret: String,
+    /// When set, the salt changes every period, so the same visitor gets a new id
+    /// (and counts as unique again) once per period.
+    pub salt_rotation: Option<Duration>,
+    /// The random secret [`Default`] picked.
+    generated_secret: String,
+}
+
+impl Default for FingerprintConfig {
+    fn default() -> Self {
+        let secret: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(32)
+            .map(char::from)
+            .collect();
+        Self {
+            use_ip: true,
+            use_user_agent: false,
+            use_accept_language: false,
+            secret: secret.clone(),
+            salt_rotation: None,
+            generated_secret: secret,
+        }
+    }
+}
+
+impl FingerprintConfig {
+    /// Whether `secret` is still the random per-process default.
+    pub fn has_generated_secret(&self) -> bool {
+        self.secret == self.generated_secret
+    }
+
+    /// Uses exactly the signals of a comma-separated list such as `ip,ua,lang`
+    /// (case-insensitive). Fails on an unknown name or an empty list, so a typo
+    /// can't silently turn every visitor into the same one.
+    pub fn with_signals(mut self, signals: &str) -> Result<Self, String> {
+        self.use_ip = false;
+        self.use_user_agent = false;
+        self.use_accept_language = false;
+        for signal in signals.split(',').map(str::trim) {
+            match signal.to_ascii_lowercase().as_str() {
+                "ip" => self.use_ip = true,
+                "ua" => self.use_user_agent = true,
+                "lang" => self.use_accept_language = true,
+                "" => return Err("empty visitor signal; use ip, ua or lang".to_string()),
+                other => return Err(format!("unknown visitor signal {other:?}; use ip, ua or lang")),
+            }
+        }
+        Ok(self)
+    }
+
+    fn visitor_id(
+        &self,
+        ip: &str,
+        user_agent: Option<&str>,
+        accept_language: Option<&str>,
+        now: OffsetDateTime,
+    ) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.secret.as_bytes());
+        if let Some(period) = self.salt_rotation {
+            let bucket = now.unix_timestamp() / period.as_secs().max(1) as i64;
+            hasher.update(bucket.to_be_bytes());
+        }
+        let signals = [
+            (self.use_ip, "ip", Some(ip)),
+            (self.use_user_agent, "ua", user_agent),
+            (self.use_accept_language, "lang", accept_language),
+        ];
+        for (enabled, name, value) in signals {
+            if enabled {
+                hasher.update(name.as_bytes());
+                hasher.update([0]);
+                hasher.update(value.unwrap_or("").as_bytes());
+                hasher.update([0]);
+            }
+        }
+        hasher.finalize()[..16]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+}
+
+/// Requests allowed and turned away for one client within the window.
+#[derive(Default)]
+struct Bucket {
+    hits: Vec<std::time::Instant>,
+    rejected: Vec<std::time::Instant>,
+}
+
+/// A client of the rate-limited endpoints, as reported by
+/// [`RateLimiter::offenders`].
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Offender {
+    pub ip: String,
+    /// Requests let through in the current window.
+    pub requests: usize,
+    /// Requests refused with 429 in the current window.
+    pub rejected: usize,
 }
 
 #[derive(Clone)]
 pub struct RateLimiter {
-    inner: Arc<Mutex<HashMap<String, Vec<std::time::Instant>>>>,
-    limit: usize,
-    window: Duration,
+    inner: Arc<Mutex<HashMap<String, Bucket>>>,
+    /// `(limit, window)`, shared so settings changes apply to every clone.
+    config: Arc<std::sync::RwLock<(usize, Duration)>>,
 }
 
 impl RateLimiter {
     pub fn new(limit: usize, window: Duration) -> Self {
         Self {
             inner: Arc::new(Mutex::new(HashMap::new())),
-            limit,
-            window,
+            config: Arc::new(std::sync::RwLock::new((limit, window))),
         }
     }
 
+    pub fn limits(&self) -> (usize, Duration) {
+        *self.config.read().unwrap()
+    }
+
+    /// Changes the limit in place; existing windows are kept.
+    pub fn reconfigure(&self, limit: usize, window: Duration) {
+        *self.config.write().unwrap() = (limit, window);
+    }
+
     pub async fn allow(&self, key: &str) -> bool {
+        let (limit, window) = self.limits();
         let mut map = self.inner.lock().await;
         let now = std::time::Instant::now();
         let entry = map.entry(key.to_string()).or_default();
-        entry.retain(|t| now.duration_since(*t) < self.window);
-        if entry.len() >= self.limit {
+        entry.hits.retain(|t| now.duration_since(*t) < window);
+        entry.rejected.retain(|t| now.duration_since(*t) < window);
+        if entry.hits.len() >= limit {
+            entry.rejected.push(now);
             return false;
         }
-        entry.push(now);
+        entry.hits.push(now);
         true
     }
+
+    /// The busiest clients of the current window, most refused requests
+    /// first, then most requests.
+    pub async fn offenders(&self, count: usize) -> Vec<Offender> {
+        let (_, window) = self.limits();
+        let now = std::time::Instant::now();
+        let live = |times: &[std::time::Instant]| times.iter().filter(|t| now.duration_since(**t) < window).count();
+        let map = self.inner.lock().await;
+        let mut offenders: Vec<Offender> = map
+            .iter()
+            .map(|(ip, bucket)| Offender {
+                ip: ip.clone(),
+                requests: live(&bucket.hits),
+                rejected: live(&bucket.rejected),
+            })
+            .filter(|o| o.requests + o.rejected > 0)
+            .collect();
+        offenders.sort_by(|a, b| {
+            (b.rejected, b.requests)
+                .cmp(&(a.rejected, a.requests))
+                .then_with(|| a.ip.cmp(&b.ip))
+        });
+        offenders.truncate(count);
+        offenders
+    }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone, Default)]
 struct ShortenReq {
     url: String,
     custom_code: Option<String>,
     expires_at: Option<String>,
+    listed: Option<bool>,
+    utm: Option<UtmParams>,
+    redirect_mode: Option<RedirectMode>,
+    /// 301, 302, 307 (default) or 308.
+    redirect_type: Option<RedirectType>,
+    /// Append the visitor's query string to the target.
+    forward_query: Option<bool>,
+    /// Redirect `/<code>/<rest>` to the target with `<rest>` appended.
+    path_forwarding: Option<bool>,
+    /// QR codes open the `/q/<code>` landing page instead of redirecting.
+    qr_landing: Option<bool>,
+    /// Separate targets for iOS, Android and desktop visitors.
+    device_targets: Option<devices::DeviceTargets>,
+    /// Only redirect through signed per-recipient URLs.
+    signed_only: Option<bool>,
+    /// Id of a link template whose defaults fill the unset fields.
+    template: Option<String>,
+    /// Short label shown instead of the bare code.
+    title: Option<String>,
+    notes: Option<String>,
+    /// Redirects allowed before the link answers 410.
+    max_clicks: Option<i64>,
+    /// Shorthand for `max_clicks: 1`: the first redirect uses the link up.
+    single_use: Option<bool>,
+    /// RFC3339; the link answers 404 until then.
+    not_before: Option<String>,
+    /// Return the caller's existing link to the same target instead of
+    /// creating one. Defaults to the `reuse_existing_links` setting.
+    reuse_existing: Option<bool>,
+    /// Labels for grouping links, e.g. `["campaign-x"]`.
+    tags: Option<Vec<String>>,
+}
+
+/// How `redirect` sends visitors on. `Html` serves a tiny page with a
+/// meta-refresh and a JS redirect, for webviews and mail scanners that mangle
+/// `Location` headers. Clicks are recorded the same way in both modes.
+/// `Interstitial` shows the deployment's branded page first; see
+/// [`interstitial`].
+#[derive(Deserialize, Serialize, sqlx::Type, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+enum RedirectMode {
+    #[default]
+    Http,
+    Html,
+    Interstitial,
+}
+
+/// Status code of an `Http` mode redirect, written as the bare number in
+/// JSON. 301 suits SEO-sensitive links but browsers cache it, so repeat
+/// visits may never reach us again; 308 and 307 keep POST bodies intact.
+#[derive(Deserialize, Serialize, sqlx::Type, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(try_from = "u16", into = "u16")]
+#[repr(i32)]
+enum RedirectType {
+    Permanent = 301,
+    Found = 302,
+    #[default]
+    Temporary = 307,
+    PermanentRedirect = 308,
+}
+
+impl RedirectType {
+    fn status(self) -> StatusCode {
+        match self {
+            RedirectType::Permanent => StatusCode::MOVED_PERMANENTLY,
+            RedirectType::Found => StatusCode::FOUND,
+            RedirectType::Temporary => StatusCode::TEMPORARY_REDIRECT,
+            RedirectType::PermanentRedirect => StatusCode::PERMANENT_REDIRECT,
+        }
+    }
+}
+
+impl TryFrom<u16> for RedirectType {
+    type Error = String;
+
+    fn try_from(code: u16) -> Result<Self, String> {
+        match code {
+            301 => Ok(RedirectType::Permanent),
+            302 => Ok(RedirectType::Found),
+            307 => Ok(RedirectType::Temporary),
+            308 => Ok(RedirectType::PermanentRedirect),
+            _ => Err(format!("redirect_type must be 301, 302, 307 or 308, not {code}")),
+        }
+    }
+}
+
+impl From<RedirectType> for u16 {
+    fn from(kind: RedirectType) -> u16 {
+        kind.status().as_u16()
+    }
+}
+
+#[derive(Deserialize, Serialize, Default, Clone, PartialEq)]
+struct UtmParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    medium: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    campaign: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    term: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+}
+
+impl UtmParams {
+    fn is_empty(&self) -> bool {
+        self.pairs().is_empty()
+    }
+
+    fn pairs(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("utm_source", &self.source),
+            ("utm_medium", &self.medium),
+            ("utm_campaign", &self.campaign),
+            ("utm_term", &self.term),
+            ("utm_content", &self.content),
+        ]
+        .into_iter()
+        .filter_map(|(k, v)| v.as_deref().map(|v| (k, v)))
+        .collect()
+    }
 }
 
 #[derive(Serialize)]
 struct ShortenResp {
     code: String,
     short_url: String,
-    qr_png_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    qr_png_url: Option<String>,
     expires_at: Option<String>,
+    /// Held for moderation: redirects once an admin approves it.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pending_review: bool,
+    /// An existing link was returned instead of creating one.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    reused: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
 }
 
-fn gen_code() -> String {
-    rand::thread_rng()
-        .sample_iter(&Alphanumeric)
-        .map(char::from)
-        .take(7)
-        .collect()
+impl ShortenResp {
+    fn new(state: &AppState, code: String, expires_at: Option<String>, pending_review: bool) -> Self {
+        Self {
+            qr_png_url: cfg!(feature = "qr")
+                .then(|| state.public_url(&format!("/api/links/{}/qr", code))),
+            short_url: state.short_url(&code),
+            code,
+            expires_at,
+            pending_review,
+            reused: false,
+            tags: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
 }
 
 pub fn router(state: AppState) -> Router {
+    RouterBuilder::new(state).build()
+}
+
+type RouterTransform = Box<dyn FnOnce(Router) -> Router + Send>;
+
+/// Builds the application router with optional embedder extensions:
+///
+/// ```ignore
+/// let app = RouterBuilder::new(state)
+///     .with_routes(Router::new().route("/internal/ping", get(ping)))
+///     .with_layer(my_auth_layer)
+///     .build();
+/// ```
+///
+/// Extra routes share `AppState` and must not overlap the built-in ones.
+/// Layers wrap the whole app; the first one added is the innermost.
+pub struct RouterBuilder {
+    state: AppState,
+    routes: Router<AppState>,
+    layers: Vec<RouterTransform>,
+}
+
+impl RouterBuilder {
+    pub fn new(state: AppState) -> Self {
+        Self {
+            state,
+            routes: Router::new(),
+            layers: Vec::new(),
+        }
+    }
+
+    pub fn with_routes(mut self, routes: Router<AppState>) -> Self {
+        self.routes = self.routes.merge(routes);
+        self
+    }
+
+    pub fn with_layer<L>(mut self, layer: L) -> Self
+    where
+        L: Layer<Route> + Clone + Send + 'static,
+        L::Service: Service<Request<Body>> + Clone + Send + 'static,
+        <L::Service as Service<Request<Body>>>::Response: IntoResponse + 'static,
+        <L::Service as Service<Request<Body>>>::Error: Into<Infallible> + 'static,
+        <L::Service as Service<Request<Body>>>::Future: Send + 'static,
+    {
+        self.layers.push(Box::new(move |app: Router| app.layer(layer)));
+        self
+    }
+
+    /// Builds the router. When `AppState::path_prefix` is set, every route is
+    /// nested under it, so the result can be merged into a larger app as is.
+    pub fn build(self) -> Router {
+        let prefix = self.state.prefix().to_string();
+        let limits = self.state.limits.clone();
+        let mut app = app_routes(&self.state)
+            .merge(self.routes)
+            .layer(axum::middleware::from_fn_with_state(
+                self.state.clone(),
+                read_only_middleware,
+            ))
+            .layer(axum::middleware::from_fn_with_state(
+                self.state.clone(),
+                security_headers_middleware,
+            ))
+            .with_state(self.state);
+        // later layers wrap earlier ones: shed load before starting the timeout clock
+        if let Some(timeout) = limits.timeout {
+            app = app.layer(
+                ServiceBuilder::new()
+                    .layer(HandleErrorLayer::new(handle_limit_error))
+                    .layer(TimeoutLayer::new(timeout)),
+            );
+        }
+        if let Some(max) = limits.max_in_flight {
+            app = app.layer(
+                ServiceBuilder::new()
+                    .layer(HandleErrorLayer::new(handle_limit_error))
+                    .load_shed()
+                    .layer(GlobalConcurrencyLimitLayer::new(max)),
+            );
+        }
+        if !prefix.is_empty() {
+            app = Router::new().nest(&prefix, app);
+        }
+        for apply in self.layers {
+            app = apply(app);
+        }
+        app
+    }
+}
+
+async fn handle_limit_error(err: BoxError) -> (StatusCode, String) {
+    if err.is::<tower::timeout::error::Elapsed>() {
+        (StatusCode::REQUEST_TIMEOUT, "request timed out".to_string())
+    } else if err.is::<tower::load_shed::error::Overloaded>() {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "server is overloaded, try again later".to_string(),
+        )
+    } else {
+        internal(err)
+    }
+}
+
+fn app_routes(state: &AppState) -> Router<AppState> {
+    let router = Router::new();
+    #[cfg(feature = "dashboard")]
+    let router = router
+        .route("/", get(dashboard::dashboard_index))
+        .route("/links/:code", get(dashboard::dashboard_link));
+    #[cfg(feature = "qr")]
+    let router = router.route("/api/links/:code/qr", get(qr_png));
+
     let rate_limited_shorten = post(shorten)
+        .get(shorten_get)
+        .route_layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            rate_limit_middleware,
+        ));
+    let rate_limited_utm = post(utm_builder)
+        .route_layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            rate_limit_middleware,
+        ));
+    let rate_limited_batch = post(batch::shorten_batch)
+        .route_layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            rate_limit_middleware,
+        ));
+    let rate_limited_clone = post(clone_link)
         .route_layer(axum::middleware::from_fn_with_state(
             state.clone(),
             rate_limit_middleware,
         ));
 
-    Router::new()
-        .route("/", get(dashboard_index))
-        .route("/links/:code", get(dashboard_link))
+    router
         .route("/health", get(|| async { "ok" }))
+        .route("/metrics", get(metrics::metrics))
         .route("/api/shorten", rate_limited_shorten)
+        .route("/api/shorten/batch", rate_limited_batch)
+        .route("/api/shorten/validate", post(validate::validate_shorten))
+        .route("/api/utm", rate_limited_utm)
         .route("/api/links", get(list_links))
+        .route("/api/directory", get(directory))
         .route("/:code", get(redirect))
-        .route("/api/links/:code/qr", get(qr_png))
+        .route("/:code/*rest", get(redirect))
+        .route("/q/:code", get(qr_scan))
+        .route(
+            "/api/links/:code",
+            axum::routing::patch(patch_link).delete(delete_link),
+        )
+        .route("/api/links/:code/archive", post(archive_link))
+        .route("/api/links/:code/aliases", get(aliases::list_aliases).post(aliases::add_alias))
+        .route("/api/links/:code/aliases/:alias", axum::routing::delete(aliases::remove_alias))
+        .route(
+            "/api/links/:code/schedule",
+            get(schedule::get_schedule).put(schedule::put_schedule),
+        )
+        .route("/api/links/:code/unarchive", post(unarchive_link))
         .route("/api/links/:code/stats", get(stats))
-        .with_state(state)
-}
-
-async fn dashboard_index(State(state): State<AppState>) -> Result<Html<String>, (StatusCode, String)> {
-    let links = query_link_summaries(&state).await.map_err(internal)?;
-
-    let mut rows = String::new();
-    for l in links {
-        let status = if l.expired { "expired" } else { "active" };
-        rows.push_str(&format!(
-            "<tr><td><a href=\"/links/{code}\">{code}</a></td><td class=\"mono\">{target}</td><td>{created}</td><td>{expires}</td><td>{status}</td><td>{clicks}</td><td>{uv}</td></tr>",
-            code = html_escape(&l.code),
-            target = html_escape(&l.target_url),
-            created = html_escape(&l.created_at),
-            expires = html_escape(l.expires_at.as_deref().unwrap_or("-")),
-            status = status,
-            clicks = l.total_clicks,
-            uv = l.unique_visitors,
-        ));
-    }
-
-    let page = layout(
-        "URL Shortener Dashboard",
-        &format!(
-            r#"
-<h1>URL Shortener</h1>
-
-<div class="card">
-  <h2>Create a short link</h2>
-  <form id="shorten-form">
-    <label>Long URL</label>
-    <input name="url" placeholder="https://example.com/very/long" required />
-
-    <label>Custom code (optional)</label>
-    <input name="custom_code" placeholder="my-link" />
-
-    <label>Expires at (optional, RFC3339)</label>
-    <input name="expires_at" placeholder="2026-01-31T00:00:00Z" />
-
-    <button type="submit">Shorten</button>
-  </form>
-  <div id="result" class="result"></div>
-</div>
-
-<div class="card">
-  <h2>All links</h2>
-  <table>
-    <thead>
-      <tr><th>Code</th><th>Target</th><th>Created</th><th>Expires</th><th>Status</th><th>Clicks</th><th>Unique</th></tr>
-    </thead>
-    <tbody>
-      {rows}
-    </tbody>
-  </table>
-</div>
-
-<script>
-  const form = document.getElementById('shorten-form');
-  const result = document.getElementById('result');
-
-  form.addEventListener('submit', async (e) => {{
-    e.preventDefault();
-    result.textContent = 'Working...';
-
-    const data = Object.fromEntries(new FormData(form));
-    if (!data.custom_code) delete data.custom_code;
-    if (!data.expires_at) delete data.expires_at;
-
-    const resp = await fetch('/api/shorten', {{
-      method: 'POST',
-      headers: {{ 'Content-Type': 'application/json' }},
-      body: JSON.stringify(data)
-    }});
-
-    const text = await resp.text();
-    if (!resp.ok) {{
-      result.textContent = 'Error: ' + text;
-      return;
-    }}
-    const json = JSON.parse(text);
-    result.innerHTML = `Short URL: <a href="${{json.short_url}}" target="_blank">${{json.short_url}}</a>
-      <br/>QR: <a href="${{json.qr_png_url}}" target="_blank">${{json.qr_png_url}}</a>`;
-    form.reset();
-  }});
-</script>
-"#,
-            rows = rows
-        ),
-    );
-    Ok(Html(page))
-}
-
-async fn dashboard_link(
-    State(state): State<AppState>,
-    Path(code): Path<String>,
-) -> Result<Html<String>, (StatusCode, String)> {
-    let stats = query_stats(&state, &code).await?;
-
-    let mut countries = String::new();
-    for c in &stats.top_countries {
-        countries.push_str(&format!(
-            "<li><span class=\"mono\">{country}</span> — {clicks}</li>",
-            country = html_escape(&c.country),
-            clicks = c.clicks
-        ));
-    }
-    if countries.is_empty() {
-        countries.push_str("<li>-</li>");
-    }
-
-    let mut recent = String::new();
-    for r in &stats.recent_clicks {
-        recent.push_str(&format!(
-            "<tr><td>{at}</td><td class=\"mono\">{ip}</td><td>{country}</td><td class=\"mono\">{ua}</td></tr>",
-            at = html_escape(&r.at),
-            ip = html_escape(r.ip.as_deref().unwrap_or("-")),
-            country = html_escape(r.country.as_deref().unwrap_or("-")),
-            ua = html_escape(r.user_agent.as_deref().unwrap_or("-")),
-        ));
-    }
-    if recent.is_empty() {
-        recent.push_str("<tr><td colspan=\"4\">-</td></tr>");
-    }
-
-    let page = layout(
-        &format!("Stats for {}", html_escape(&code)),
-        &format!(
-            r#"
-<a href="/">← Back</a>
-
-<h1>Link <span class="mono">/{code}</span></h1>
-
-<div class="grid">
-  <div class="card">
-    <h2>Link</h2>
-    <p><strong>Target</strong><br/><span class="mono">{target}</span></p>
-    <p><strong>Short URL</strong><br/><a href="{short_url}" target="_blank">{short_url}</a></p>
-    <p><strong>Created</strong><br/>{created}</p>
-    <p><strong>Expires</strong><br/>{expires}</p>
-  </div>
-
-  <div class="card">
-    <h2>QR</h2>
-    <img class="qr" src="/api/links/{code}/qr" alt="QR code" />
-  </div>
-
-  <div class="card">
-    <h2>Totals</h2>
-    <p class="big">{clicks} clicks</p>
-    <p class="big">{unique} unique visitors</p>
-  </div>
-
-  <div class="card">
-    <h2>Top countries</h2>
-    <ul>{countries}</ul>
-  </div>
-</div>
-
-<div class="card">
-  <h2>Recent clicks</h2>
-  <table>
-    <thead><tr><th>At</th><th>IP</th><th>Country</th><th>User-Agent</th></tr></thead>
-    <tbody>{recent}</tbody>
-  </table>
-</div>
-"#,
-            code = html_escape(&stats.code),
-            target = html_escape(&stats.target_url),
-            short_url = html_escape(&format!("{}/{}", state.base_url, stats.code)),
-            created = html_escape(&stats.created_at),
-            expires = html_escape(stats.expires_at.as_deref().unwrap_or("-")),
-            clicks = stats.total_clicks,
-            unique = stats.unique_visitors,
-            countries = countries,
-            recent = recent,
-        ),
-    );
-    Ok(Html(page))
-}
-
-fn layout(title: &str, body: &str) -> String {
-    format!(
-        r#"<!doctype html>
-<html lang="en">
-  <head>
-    <meta charset="utf-8" />
-    <meta name="viewport" content="width=device-width, initial-scale=1" />
-    <title>{title}</title>
-    <style>
-      body {{ font-family: ui-sans-serif, system-ui, -apple-system, Segoe UI, Roboto, Arial; margin: 24px; line-height: 1.35; }}
-      h1 {{ margin: 0 0 12px 0; }}
-      h2 {{ margin: 0 0 12px 0; font-size: 18px; }}
-      a {{ color: #0b62d6; }}
-      table {{ width: 100%; border-collapse: collapse; }}
-      th, td {{ border-bottom: 1px solid #ddd; padding: 8px; vertical-align: top; }}
-      th {{ text-align: left; }}
-      .card {{ border: 1px solid #e5e5e5; border-radius: 12px; padding: 16px; margin: 16px 0; }}
-      .grid {{ display: grid; gap: 16px; grid-template-columns: repeat(auto-fit, minmax(260px, 1fr)); }}
-      .mono {{ font-family: ui-monospace, SFMono-Regular, Menlo, Monaco, Consolas, 'Liberation Mono', 'Courier New', monospace; }}
-      input {{ width: 100%; padding: 10px; border: 1px solid #ccc; border-radius: 10px; margin-bottom: 10px; }}
-      button {{ padding: 10px 14px; border-radius: 10px; border: 1px solid #0b62d6; background: #0b62d6; color: white; cursor: pointer; }}
-      .result {{ margin-top: 10px; }}
-      .big {{ font-size: 22px; margin: 8px 0; }}
-      .qr {{ width: 240px; height: 240px; image-rendering: pixelated; }}
-    </style>
-  </head>
-  <body>
-    {body}
-  </body>
-</html>"#,
-        title = title,
-        body = body
-    )
+        .route("/api/links/:code/clicks.csv", get(clicks_csv::clicks_csv))
+        .route("/api/links/:code/status", get(status::link_status))
+        .route("/api/stats", get(global_stats::global_stats))
+        .route("/api/stream", get(live::global_stream))
+        .route("/api/links/:code/stream", get(live::link_stream))
+        .route("/api/stats/batch", post(stats_batch::batch_stats))
+        .route("/api/links/:code/clone", rate_limited_clone)
+        .route("/api/links/:code/sign", post(signing::sign_link))
+        .route("/api/links/:code/stats/reset", post(reset_stats))
+        .route("/api/admin/links/export", get(manifest::export_links))
+        .route("/api/admin/links/apply", post(manifest::apply_links))
+        .route("/api/export", get(export::export))
+        .route(
+            "/api/import",
+            post(export::import).layer(axum::extract::DefaultBodyLimit::max(export::MAX_IMPORT_BYTES)),
+        )
+        .route("/api/search", get(search::search))
+        .route("/api/tags", get(tags::list_tags))
+        .route("/api/recent", get(recent_links))
+        .route("/api/resolve/:code", get(resolve))
+        .route("/api/clicks", post(ingest::ingest_clicks))
+        .route(
+            "/api/admin/api-keys",
+            get(api_keys::list_keys).post(api_keys::create_key),
+        )
+        .route("/api/admin/api-keys/:name", axum::routing::delete(api_keys::revoke_key))
+        .route("/api/templates", get(templates::list_templates))
+        .route(
+            "/api/templates/:id",
+            axum::routing::put(templates::put_template).delete(templates::delete_template),
+        )
+        .route("/admin/login", get(admin_login_form).post(admin_login))
+        .route("/api/admin/quarantine", post(quarantine::quarantine))
+        .route("/api/admin/audit", get(audit::list_audit))
+        .route("/api/admin/alerts", get(alerts::list_alerts).post(alerts::create_alert))
+        .route("/api/admin/anomalies", get(anomaly::list_anomalies))
+        .route("/api/webhooks", get(click_webhooks::list_webhooks).post(click_webhooks::create_webhook))
+        .route("/api/webhooks/:id", axum::routing::delete(click_webhooks::delete_webhook))
+        .route("/api/admin/webhooks/deliveries", get(deliveries::list_deliveries))
+        .route("/api/admin/webhooks/deliveries/:id/redeliver", post(deliveries::redeliver))
+        .route("/api/admin/ratelimit", get(bans::list_offenders).post(bans::create_ban))
+        .route("/api/admin/ratelimit/:id", axum::routing::delete(bans::delete_ban))
+        .route("/api/admin/alerts/:id", axum::routing::delete(alerts::delete_alert))
+        .route("/api/admin/migrations", get(migrations::list_migrations))
+        .route("/api/admin/selfcheck", get(selfcheck::selfcheck))
+        .route("/api/admin/moderation", get(moderation::list_pending))
+        .route("/api/admin/moderation/:code/approve", post(moderation::approve))
+        .route(
+            "/api/admin/settings",
+            get(settings::get_settings).put(settings::put_settings),
+        )
 }
 
 fn html_escape(input: &str) -> String {
@@ -317,59 +1089,450 @@ fn html_escape(input: &str) -> String {
 struct LinkSummary {
     code: String,
     target_url: String,
+    title: Option<String>,
+    notes: Option<String>,
     created_at: String,
     expires_at: Option<String>,
     expired: bool,
+    /// `not_before` is still ahead.
+    scheduled: bool,
+    archived: bool,
+    tags: Vec<String>,
     total_clicks: i64,
     unique_visitors: i64,
 }
 
-async fn query_link_summaries(state: &AppState) -> Result<Vec<LinkSummary>, sqlx::Error> {
-    let rows: Vec<(String, String, String, Option<String>, i64, i64)> = sqlx::query_as(
-        "SELECT u.code, u.target_url, u.created_at, u.expires_at, \
-                count(c.id) as total_clicks, count(DISTINCT c.ip) as unique_visitors \
-         FROM urls u LEFT JOIN clicks c ON c.code = u.code \
-         GROUP BY u.code ORDER BY u.created_at DESC",
-    )
-    .fetch_all(&state.pool)
-    .await?;
+type SummaryRow = (
+    String,
+    String,
+    Option<String>,
+    Option<String>,
+    String,
+    Option<String>,
+    Option<String>,
+    bool,
+    i64,
+    Option<i64>,
+    Option<Vec<u8>>,
+);
 
-    Ok(rows
-        .into_iter()
-        .map(|(code, target_url, created_at, expires_at, total_clicks, unique_visitors)| {
-            let expired = is_expired(expires_at.as_deref());
-            LinkSummary {
-                code,
-                target_url,
-                created_at,
-                expires_at,
-                expired,
-                total_clicks,
-                unique_visitors,
-            }
-        })
-        .collect())
+/// Order of `GET /api/links`; both newest or busiest first.
+#[derive(Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum LinkSort {
+    #[default]
+    CreatedAt,
+    Clicks,
 }
 
-async fn list_links(
-    State(state): State<AppState>,
-) -> Result<Json<Vec<LinkSummary>>, (StatusCode, String)> {
-    let out = query_link_summaries(&state).await.map_err(internal)?;
-    Ok(Json(out))
+impl LinkSort {
+    fn as_str(self) -> &'static str {
+        match self {
+            LinkSort::CreatedAt => "created_at",
+            LinkSort::Clicks => "clicks",
+        }
+    }
 }
 
-async fn rate_limit_middleware(
-    State(state): State<AppState>,
-    req: axum::http::Request<axum::body::Body>,
-    next: axum::middleware::Next,
-) -> impl IntoResponse {
-    let headers = req.headers();
-    let ip = client_ip_from_headers(headers).unwrap_or_else(|| "local".to_string());
+#[derive(Deserialize, Default)]
+struct LinkListParams {
+    #[serde(default)]
+    exact: bool,
+    page: Option<i64>,
+    per_page: Option<i64>,
+    #[serde(default)]
+    sort: LinkSort,
+    /// Case-insensitive substring of the code or target.
+    q: Option<String>,
+    /// Include archived links.
+    #[serde(default)]
+    archived: bool,
+    /// Only links with this tag.
+    tag: Option<String>,
+}
+
+impl LinkListParams {
+    fn page(&self) -> i64 {
+        self.page.unwrap_or(1).max(1)
+    }
+
+    fn per_page(&self) -> i64 {
+        self.per_page.unwrap_or(50).clamp(1, 100)
+    }
+
+    fn tag(&self) -> Option<String> {
+        self.tag
+            .as_deref()
+            .map(|t| t.trim().to_lowercase())
+            .filter(|t| !t.is_empty())
+    }
+
+    fn filter(&self) -> Option<String> {
+        self.q
+            .as_deref()
+            .map(str::trim)
+            .filter(|q| !q.is_empty())
+            .map(search::like_pattern)
+    }
+
+    /// Query string for `page`, keeping the other parameters.
+    fn query_for(&self, page: i64) -> String {
+        let mut query = url::form_urlencoded::Serializer::new(String::new());
+        query
+            .append_pair("page", &page.to_string())
+            .append_pair("per_page", &self.per_page().to_string())
+            .append_pair("sort", self.sort.as_str());
+        if let Some(q) = &self.q {
+            query.append_pair("q", q);
+        }
+        if self.exact {
+            query.append_pair("exact", "true");
+        }
+        if self.archived {
+            query.append_pair("archived", "true");
+        }
+        if let Some(tag) = &self.tag {
+            query.append_pair("tag", tag);
+        }
+        query.finish()
+    }
+}
+
+/// One page of links plus the number matching the filter. Unique visitors
+/// come from the all-time HyperLogLog sketch unless `exact`; click counts are
+/// only computed for the page, except when sorting by them.
+async fn query_link_summaries(
+    state: &AppState,
+    params: &LinkListParams,
+    exact: bool,
+) -> Result<(Vec<LinkSummary>, i64), sqlx::Error> {
+    const FILTER: &str = "(?1 IS NULL OR lower(code) LIKE ?1 ESCAPE '\\' OR lower(target_url) LIKE ?1 ESCAPE '\\' \
+                           OR lower(title) LIKE ?1 ESCAPE '\\') AND (?4 OR archived_at IS NULL) AND ";
+    let (inner_order, outer_order) = match params.sort {
+        LinkSort::CreatedAt => ("created_at DESC, code".to_string(), "u.created_at DESC, u.code"),
+        LinkSort::Clicks => (
+            format!("{} DESC, created_at DESC, code", rollup::total_clicks_sql("urls.code", false)),
+            "total_clicks DESC, u.created_at DESC, u.code",
+        ),
+    };
+    let total_clicks = rollup::total_clicks_sql("u.code", false);
+    let (unique, registers) = if exact {
+        ("(SELECT count(DISTINCT c.visitor_id) FROM clicks c WHERE c.code = u.code AND NOT c.is_bot)", "NULL")
+    } else {
+        ("NULL", "s.registers")
+    };
+    let filter_sql = format!("{FILTER}{}", tags::FILTER_SQL);
+    let sql = format!(
+        "SELECT u.code, u.target_url, u.title, u.notes, u.created_at, u.expires_at, u.not_before, u.archived_at IS NOT NULL, \
+                {total_clicks} as total_clicks, \
+                {unique} as unique_visitors, {registers} as registers \
+         FROM (SELECT code, target_url, title, notes, created_at, expires_at, not_before, archived_at FROM urls WHERE {filter_sql} \
+               ORDER BY {inner_order} LIMIT ?2 OFFSET ?3) u \
+         LEFT JOIN visitor_sketches s ON s.code = u.code AND s.day = '*' \
+         ORDER BY {outer_order}"
+    );
+    let filter = params.filter();
+    let tag = params.tag();
+    let per_page = params.per_page();
+    let rows: Vec<SummaryRow> = sqlx::query_as(&sql)
+        .bind(&filter)
+        .bind(per_page)
+        .bind((params.page() - 1) * per_page)
+        .bind(params.archived)
+        .bind(&tag)
+        .fetch_all(&state.pool)
+        .await?;
+    let (total,): (i64,) = sqlx::query_as(&format!("SELECT count(*) FROM urls WHERE {filter_sql}"))
+        .bind(&filter)
+        .bind(None::<i64>)
+        .bind(None::<i64>)
+        .bind(params.archived)
+        .bind(&tag)
+        .fetch_one(&state.pool)
+        .await?;
+    let codes: Vec<String> = rows.iter().map(|row| row.0.clone()).collect();
+    let mut link_tags = tags::for_codes(&state.pool, &codes).await?;
+
+    let links = rows
+        .into_iter()
+        .map(|(code, target_url, title, notes, created_at, expires_at, not_before, archived, total_clicks, exact_unique, registers)| {
+            let expired = is_expired(expires_at.as_deref());
+            let scheduled = is_scheduled(not_before.as_deref());
+            let unique_visitors =
+                exact_unique.unwrap_or_else(|| hll::estimate_blob(registers.as_deref()));
+            let tags = link_tags.remove(&code).unwrap_or_default();
+            LinkSummary {
+                code,
+                target_url,
+                title,
+                notes,
+                created_at,
+                expires_at,
+                expired,
+                scheduled,
+                archived,
+                tags,
+                total_clicks,
+                unique_visitors,
+            }
+        })
+        .collect();
+    Ok((links, total))
+}
+
+#[derive(Deserialize, Default)]
+struct ExactParams {
+    #[serde(default)]
+    exact: bool,
+}
+
+/// The page goes in the body; the match count in `X-Total-Count` and
+/// first/prev/next/last URLs in an RFC 8288 `Link` header.
+async fn list_links(
+    State(state): State<AppState>,
+    Query(params): Query<LinkListParams>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let exact = params.exact || state.exact_unique_counts;
+    let (links, total) = query_link_summaries(&state, &params, exact).await.map_err(internal)?;
+
+    let page = params.page();
+    let last = ((total + params.per_page() - 1) / params.per_page()).max(1);
+    let base = state.public_url("/api/links");
+    let mut rels = vec![("first", 1), ("last", last)];
+    if page > 1 {
+        rels.push(("prev", (page - 1).min(last)));
+    }
+    if page < last {
+        rels.push(("next", page + 1));
+    }
+    let link = rels
+        .into_iter()
+        .map(|(rel, page)| format!("<{base}?{}>; rel=\"{rel}\"", params.query_for(page)))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut headers = HeaderMap::new();
+    headers.insert("x-total-count", HeaderValue::from(total));
+    if let Ok(link) = HeaderValue::from_str(&link) {
+        headers.insert(header::LINK, link);
+    }
+    Ok((headers, Json(links)))
+}
+
+#[derive(Deserialize)]
+struct RecentParams {
+    limit: Option<i64>,
+}
+
+#[derive(Serialize, sqlx::FromRow)]
+struct RecentLink {
+    code: String,
+    #[sqlx(skip)]
+    short_url: String,
+    target_url: String,
+    title: Option<String>,
+    created_at: String,
+}
+
+/// Newest links, unarchived, for the dashboard's quick-create palette.
+/// `?limit=` defaults to 10, at most 50.
+async fn recent_links(
+    State(state): State<AppState>,
+    Query(params): Query<RecentParams>,
+) -> Result<Json<Vec<RecentLink>>, (StatusCode, String)> {
+    let mut links: Vec<RecentLink> = sqlx::query_as(
+        "SELECT code, target_url, title, created_at FROM urls WHERE archived_at IS NULL \
+         ORDER BY created_at DESC, code LIMIT ?",
+    )
+    .bind(params.limit.unwrap_or(10).clamp(1, 50))
+    .fetch_all(&state.pool)
+    .await
+    .map_err(internal)?;
+    for link in &mut links {
+        link.short_url = state.short_url(&link.code);
+    }
+    Ok(Json(links))
+}
+
+#[derive(Deserialize)]
+struct PageParams {
+    page: Option<i64>,
+    per_page: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct DirectoryEntry {
+    code: String,
+    short_url: String,
+    title: Option<String>,
+    target_domain: Option<String>,
+    total_clicks: i64,
+}
 
+#[derive(Serialize)]
+struct DirectoryResp {
+    page: i64,
+    per_page: i64,
+    total: i64,
+    links: Vec<DirectoryEntry>,
+}
+
+/// Public, read-only listing of links created with `listed: true`.
+/// Only exposes the title, target domain and click count, never visitor data.
+async fn directory(
+    State(state): State<AppState>,
+    Query(params): Query<PageParams>,
+) -> Result<Json<DirectoryResp>, (StatusCode, String)> {
+    const FILTER: &str = "listed = 1 AND pending_review = 0 AND archived_at IS NULL \
+                          AND (expires_at IS NULL OR julianday(expires_at) > julianday('now')) \
+                          AND (not_before IS NULL OR julianday(not_before) <= julianday('now'))";
+    let page = params.page.unwrap_or(1).max(1);
+    let per_page = params.per_page.unwrap_or(50).clamp(1, 100);
+
+    let rows: Vec<(String, Option<String>, String, i64)> = sqlx::query_as(&format!(
+        "SELECT u.code, u.title, u.target_url, {} as total_clicks \
+         FROM (SELECT code, title, target_url, created_at FROM urls WHERE {FILTER} \
+               ORDER BY created_at DESC, code LIMIT ? OFFSET ?) u \
+         ORDER BY u.created_at DESC, u.code",
+        rollup::total_clicks_sql("u.code", false)
+    ))
+    .bind(per_page)
+    .bind((page - 1) * per_page)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(internal)?;
+    let (total,): (i64,) = sqlx::query_as(&format!("SELECT count(*) FROM urls WHERE {FILTER}"))
+        .fetch_one(&state.pool)
+        .await
+        .map_err(internal)?;
+
+    let links = rows
+        .into_iter()
+        .map(|(code, title, target_url, total_clicks)| DirectoryEntry {
+            short_url: state.short_url(&code),
+            title,
+            target_domain: target_domain(&target_url),
+            code,
+            total_clicks,
+        })
+        .collect();
+
+    Ok(Json(DirectoryResp {
+        page,
+        per_page,
+        total,
+        links,
+    }))
+}
+
+fn target_domain(target_url: &str) -> Option<String> {
+    url::Url::parse(target_url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.to_string()))
+}
+
+const READ_ONLY_MESSAGE: &str = "the service is in read-only mode for maintenance; try again later";
+
+/// Requests that only read despite their method, and the settings update
+/// that lets an admin leave read-only mode.
+const READ_ONLY_ALLOWED: &[(Method, &str)] = &[
+    (Method::PUT, "/api/admin/settings"),
+    (Method::POST, "/admin/login"),
+    (Method::POST, "/api/shorten/validate"),
+    (Method::POST, "/api/stats/batch"),
+];
+
+/// GET endpoints that write.
+const READ_ONLY_WRITING_GETS: &[&str] = &["/api/shorten"];
+
+/// Answers 503 to every write while [`AppState::is_read_only`].
+async fn read_only_middleware(
+    State(state): State<AppState>,
+    req: axum::http::Request<axum::body::Body>,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let method = req.method();
+    let reads = (matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+        && !READ_ONLY_WRITING_GETS.contains(&req.uri().path()))
+        || READ_ONLY_ALLOWED
+            .iter()
+            .any(|(m, path)| m == method && req.uri().path() == *path);
+    if !reads && state.is_read_only() {
+        return (StatusCode::SERVICE_UNAVAILABLE, READ_ONLY_MESSAGE).into_response();
+    }
+    next.run(req).await
+}
+
+async fn security_headers_middleware(
+    State(state): State<AppState>,
+    req: axum::http::Request<axum::body::Body>,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let mut resp = next.run(req).await;
+    let config = &state.security_headers;
+    let is_html = resp
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.starts_with("text/html"));
+
+    let mut csp: Vec<&str> = Vec::new();
+    if is_html {
+        csp.extend(config.html_csp.as_deref());
+    }
+    let frame_ancestors = config
+        .frame_ancestors
+        .as_deref()
+        .filter(|_| is_html)
+        .map(|fa| format!("frame-ancestors {}", fa));
+    csp.extend(frame_ancestors.as_deref());
+
+    let mut to_set: Vec<(header::HeaderName, String)> = Vec::new();
+    if let Some(hsts) = &config.hsts {
+        to_set.push((header::STRICT_TRANSPORT_SECURITY, hsts.clone()));
+    }
+    if config.content_type_options {
+        to_set.push((header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()));
+    }
+    if let Some(policy) = &config.referrer_policy {
+        to_set.push((header::REFERRER_POLICY, policy.clone()));
+    }
+    if !csp.is_empty() {
+        to_set.push((header::CONTENT_SECURITY_POLICY, csp.join("; ")));
+    }
+    if is_html && config.frame_ancestors.as_deref() == Some("'none'") {
+        to_set.push((header::X_FRAME_OPTIONS, "DENY".to_string()));
+    }
+
+    let headers = resp.headers_mut();
+    for (name, value) in to_set {
+        if let Ok(value) = value.parse() {
+            headers.entry(name).or_insert(value);
+        }
+    }
+    resp
+}
+
+async fn rate_limit_middleware(
+    State(state): State<AppState>,
+    req: axum::http::Request<axum::body::Body>,
+    next: axum::middleware::Next,
+) -> impl IntoResponse {
+    let headers = req.headers();
+    let ip = state.client_ip(headers).unwrap_or_else(|| "local".to_string());
+
+    match bans::active_ban(&state, &ip, headers).await {
+        Ok(Some(until)) => {
+            return (StatusCode::FORBIDDEN, format!("banned until {until}")).into_response();
+        }
+        Ok(None) => {}
+        Err(e) => return internal(e).into_response(),
+    }
     if !state.rate_limiter.allow(&ip).await {
+        let (limit, window) = state.rate_limiter.limits();
         return (
             StatusCode::TOO_MANY_REQUESTS,
-            "rate limit exceeded (10 requests/minute)".to_string(),
+            format!("rate limit exceeded ({} requests per {}s)", limit, window.as_secs()),
         )
             .into_response();
     }
@@ -382,100 +1545,795 @@ async fn shorten(
     headers: HeaderMap,
     Json(payload): Json<ShortenReq>,
 ) -> Result<Json<ShortenResp>, (StatusCode, String)> {
-    let target = normalize_url(&payload.url).ok_or_else(|| {
+    create_link(&state, &headers, payload).await.map(Json)
+}
+
+#[derive(Deserialize)]
+struct ShortenQuery {
+    url: String,
+    code: Option<String>,
+    /// API key, for clients that can't set headers.
+    key: Option<String>,
+}
+
+/// `GET /api/shorten?url=...&code=...` for tools that can only fire a GET.
+/// Needs an API key, as a header or `key=`. Answers the short URL as plain
+/// text, or the usual JSON when the client accepts `application/json`.
+async fn shorten_get(
+    State(state): State<AppState>,
+    mut headers: HeaderMap,
+    Query(query): Query<ShortenQuery>,
+) -> Result<axum::response::Response, (StatusCode, String)> {
+    if let Some(key) = query.key.as_deref().and_then(|k| HeaderValue::from_str(k).ok()) {
+        headers.insert(api_keys::API_KEY_HEADER, key);
+    }
+    api_keys::require_api_key(&state, &headers).await?;
+    let payload = ShortenReq {
+        url: query.url,
+        custom_code: query.code,
+        ..ShortenReq::default()
+    };
+    let created = create_link(&state, &headers, payload).await?;
+    let wants_json = headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|accept| accept.contains("application/json"));
+    Ok(if wants_json {
+        Json(created).into_response()
+    } else {
+        created.short_url.into_response()
+    })
+}
+
+async fn create_link(
+    state: &AppState,
+    headers: &HeaderMap,
+    payload: ShortenReq,
+) -> Result<ShortenResp, (StatusCode, String)> {
+    let caller = api_keys::caller(state, headers).await?;
+    let link = prepare_link(state, headers, caller.as_ref(), payload).await?;
+    if let Some(existing) = link.find_existing(&state.pool).await.map_err(internal)? {
+        return Ok(existing.reused_response(state));
+    }
+    let new_url = link.new_url();
+    let code = allocate_code(state, caller.as_ref(), link.custom_code.as_deref(), |code| {
+        let new_url = &new_url;
+        async move { insert_url(state, &code, new_url).await }
+    })
+    .await?;
+    let mut conn = state.pool.acquire().await.map_err(internal)?;
+    tags::attach(&mut conn, &code, &link.tags).await.map_err(internal)?;
+    if link.pending_review {
+        moderation::notify_held(state, &code, &link.target);
+    }
+    notify::spawn_link_event(state, link.created_event(state, &code));
+    Ok(link.response(state, code))
+}
+
+/// A shorten request that passed validation, ready to insert.
+struct PreparedLink {
+    target: String,
+    expires_at: Option<String>,
+    custom_code: Option<String>,
+    created_ip: Option<String>,
+    created_user_agent: Option<String>,
+    listed: bool,
+    utm: Option<UtmParams>,
+    redirect_mode: RedirectMode,
+    redirect_type: RedirectType,
+    forward_query: bool,
+    path_forwarding: bool,
+    qr_landing: bool,
+    device_targets: devices::DeviceTargets,
+    signed_only: bool,
+    created_by: Option<String>,
+    pending_review: bool,
+    original_url: Option<String>,
+    chained_via: Option<String>,
+    title: Option<String>,
+    notes: Option<String>,
+    max_clicks: Option<i64>,
+    not_before: Option<String>,
+    /// Plain link (no custom code or per-link limits) whose caller asked to
+    /// reuse an existing one.
+    reuse_existing: bool,
+    tags: Vec<String>,
+    /// Accepted, but worth telling the caller about.
+    warnings: Vec<String>,
+}
+
+impl PreparedLink {
+    fn new_url(&self) -> NewUrl<'_> {
+        NewUrl {
+            target_url: &self.target,
+            expires_at: self.expires_at.as_deref(),
+            created_ip: self.created_ip.as_deref(),
+            created_user_agent: self.created_user_agent.as_deref(),
+            listed: self.listed,
+            utm: self.utm.as_ref(),
+            redirect_mode: self.redirect_mode,
+            redirect_type: self.redirect_type,
+            forward_query: self.forward_query,
+            path_forwarding: self.path_forwarding,
+            qr_landing: self.qr_landing,
+            device_targets: Some(&self.device_targets),
+            signed_only: self.signed_only,
+            created_by: self.created_by.as_deref(),
+            pending_review: self.pending_review,
+            original_url: self.original_url.as_deref(),
+            chained_via: self.chained_via.as_deref(),
+            title: self.title.as_deref(),
+            notes: self.notes.as_deref(),
+            max_clicks: self.max_clicks,
+            not_before: self.not_before.as_deref(),
+        }
+    }
+
+    /// With `reuse_existing`, the caller's live link with the same target and
+    /// redirect behavior, if any. The target is compared as submitted and in
+    /// canonical form (lowercase host, no default port).
+    async fn find_existing(
+        &self,
+        executor: impl sqlx::SqliteExecutor<'_>,
+    ) -> Result<Option<LinkRow>, sqlx::Error> {
+        if !self.reuse_existing {
+            return Ok(None);
+        }
+        let canonical = url::Url::parse(&self.target)
+            .map(String::from)
+            .unwrap_or_else(|_| self.target.clone());
+        let utm = self.utm.clone().unwrap_or_default();
+        let candidates: Vec<LinkRow> = sqlx::query_as(
+            "SELECT * FROM urls WHERE target_url IN (?, ?) AND created_by IS ? \
+             AND utm_source IS ? AND utm_medium IS ? AND utm_campaign IS ? AND utm_term IS ? AND utm_content IS ? \
+             AND redirect_mode = ? AND redirect_type = ? AND forward_query = ? AND path_forwarding = ? AND qr_landing = ? \
+             AND target_ios IS NULL AND target_android IS NULL AND target_desktop IS NULL AND signed_only = 0 AND max_clicks IS NULL AND not_before IS NULL \
+             AND pending_review = 0 AND disabled_at IS NULL AND archived_at IS NULL \
+             ORDER BY created_at DESC",
+        )
+        .bind(&self.target)
+        .bind(&canonical)
+        .bind(&self.created_by)
+        .bind(&utm.source)
+        .bind(&utm.medium)
+        .bind(&utm.campaign)
+        .bind(&utm.term)
+        .bind(&utm.content)
+        .bind(self.redirect_mode)
+        .bind(self.redirect_type)
+        .bind(self.forward_query)
+        .bind(self.path_forwarding)
+        .bind(self.qr_landing)
+        .fetch_all(executor)
+        .await?;
+        Ok(candidates
+            .into_iter()
+            .find(|link| !is_expired(link.expires_at.as_deref())))
+    }
+
+    fn created_event(&self, state: &AppState, code: &str) -> notify::Event {
+        notify::Event::link_created(state, code, &self.target)
+    }
+
+    fn response(self, state: &AppState, code: String) -> ShortenResp {
+        ShortenResp {
+            tags: self.tags,
+            warnings: self.warnings,
+            ..ShortenResp::new(state, code, self.expires_at, self.pending_review)
+        }
+    }
+}
+
+/// A submitted target after [`check_target`].
+struct CheckedTarget {
+    target: String,
+    /// What was submitted, when `target` differs from it.
+    original_url: Option<String>,
+    /// Known shortener the submitted target pointed at.
+    chained_via: Option<String>,
+    warnings: Vec<String>,
+}
+
+/// Validates a submitted target and checks that its host is public. Links
+/// into known shorteners are followed when `resolve_shortener_chains` is on
+/// and flagged otherwise; http targets are upgraded when `https_upgrade` is.
+async fn check_target(state: &AppState, url: &str) -> Result<CheckedTarget, (StatusCode, String)> {
+    let mut target = validate_target(state, url)?;
+    probe::require_public_host(state, &target).await?;
+
+    let mut original_url = None;
+    let mut warnings = Vec::new();
+    let settings = state.settings();
+    let chained_via = target_domain(&target)
+        .and_then(|host| settings.shortener(&host).map(str::to_string));
+    if let Some(shortener) = &chained_via {
+        let resolved = if settings.resolve_shortener_chains {
+            probe::unshorten(state, &target).await
+        } else {
+            None
+        };
+        match resolved {
+            Some(destination) => {
+                // the real destination must pass the same checks
+                let destination = validate_target(state, &destination)?;
+                probe::require_public_host(state, &destination).await?;
+                original_url = Some(std::mem::replace(&mut target, destination));
+            }
+            None => warnings.push(format!(
+                "target is a {shortener} short link; chained shorteners hide the destination and add a redirect"
+            )),
+        }
+    }
+    if settings.https_upgrade {
+        if let Some(upgraded) = probe::https_upgrade(state, &target).await {
+            let submitted = std::mem::replace(&mut target, upgraded);
+            original_url.get_or_insert(submitted);
+        }
+    }
+    Ok(CheckedTarget {
+        target,
+        original_url,
+        chained_via,
+        warnings,
+    })
+}
+
+/// Applies the template, defaults and anonymous policy, and validates
+/// everything but the code. `caller` is `None` for anonymous clients.
+async fn prepare_link(
+    state: &AppState,
+    headers: &HeaderMap,
+    caller: Option<&Caller>,
+    mut payload: ShortenReq,
+) -> Result<PreparedLink, (StatusCode, String)> {
+    let ip = state.client_ip(headers);
+    if let Some(id) = &payload.template {
+        let template = templates::load(state, id)
+            .await
+            .map_err(internal)?
+            .ok_or_else(|| (StatusCode::BAD_REQUEST, format!("unknown template: {id}")))?;
+        template.apply(&mut payload);
+    }
+
+    let CheckedTarget {
+        target,
+        original_url,
+        chained_via,
+        warnings,
+    } = check_target(state, &payload.url).await?;
+    let device_targets = payload.device_targets.unwrap_or_default().validated(state)?;
+    for url in [&device_targets.ios, &device_targets.android, &device_targets.desktop].into_iter().flatten() {
+        probe::require_public_host(state, url).await?;
+    }
+    let title = validate_label("title", payload.title, MAX_TITLE_LEN)?;
+    let notes = validate_label("notes", payload.notes, MAX_NOTES_LEN)?;
+    let tags = tags::validate(payload.tags)?;
+    if let Some(exp) = &payload.expires_at {
+        validate_expires_at(exp)?;
+    }
+    if let Some(start) = &payload.not_before {
+        validate_not_before(start, payload.expires_at.as_deref())?;
+    }
+    if payload.max_clicks.is_some_and(|max| max < 1) {
+        return Err((StatusCode::BAD_REQUEST, "max_clicks must be at least 1".to_string()));
+    }
+    let reuse_existing = payload.reuse_existing.unwrap_or(state.settings().reuse_existing_links)
+        && payload.custom_code.is_none()
+        && payload.expires_at.is_none()
+        && payload.max_clicks.is_none()
+        && payload.single_use != Some(true)
+        && payload.not_before.is_none()
+        && payload.signed_only != Some(true)
+        && device_targets.is_empty()
+        && tags.is_empty();
+    let max_clicks = match (payload.single_use, payload.max_clicks) {
+        (Some(true), Some(max)) if max != 1 => {
+            return Err((StatusCode::BAD_REQUEST, "single_use conflicts with max_clicks".to_string()));
+        }
+        (Some(true), _) => Some(1),
+        (_, max) => max,
+    };
+    let expires_at = payload
+        .expires_at
+        .or_else(|| state.settings().default_expires_at());
+    let (expires_at, pending_review) =
+        creation_policy(state, caller, ip.as_deref(), expires_at).await?;
+
+    Ok(PreparedLink {
+        target,
+        expires_at,
+        custom_code: payload.custom_code.as_deref().map(|c| state.normalize_code(c)),
+        created_ip: ip,
+        created_user_agent: headers
+            .get(header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string()),
+        listed: payload.listed.unwrap_or(false),
+        utm: payload.utm,
+        redirect_mode: payload.redirect_mode.unwrap_or_default(),
+        redirect_type: payload.redirect_type.unwrap_or_default(),
+        forward_query: payload.forward_query.unwrap_or(false),
+        path_forwarding: payload.path_forwarding.unwrap_or(false),
+        qr_landing: payload.qr_landing.unwrap_or(false),
+        device_targets,
+        signed_only: payload.signed_only.unwrap_or(false),
+        created_by: caller.map(|c| c.name().to_string()),
+        pending_review,
+        original_url,
+        chained_via,
+        title,
+        notes,
+        max_clicks,
+        not_before: payload.not_before,
+        reuse_existing,
+        tags,
+        warnings,
+    })
+}
+
+/// Expiry and review flag for a link created by `caller`. Anonymous callers
+/// must pass [`moderation::check_anonymous`] and get the stricter settings.
+async fn creation_policy(
+    state: &AppState,
+    caller: Option<&Caller>,
+    ip: Option<&str>,
+    expires_at: Option<String>,
+) -> Result<(Option<String>, bool), (StatusCode, String)> {
+    if caller.is_some() {
+        return Ok((expires_at, false));
+    }
+    moderation::check_anonymous(state, ip).await?;
+    let settings = state.settings();
+    Ok((
+        settings.anonymous_expires_at(expires_at),
+        settings.anonymous_moderation,
+    ))
+}
+
+const MAX_TITLE_LEN: usize = 200;
+const MAX_NOTES_LEN: usize = 2000;
+
+/// Trims a free-text field; blank means unset.
+fn validate_label(
+    field: &str,
+    value: Option<String>,
+    max_len: usize,
+) -> Result<Option<String>, (StatusCode, String)> {
+    let Some(value) = value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty()) else {
+        return Ok(None);
+    };
+    if value.chars().count() > max_len {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("{field} must be at most {max_len} characters"),
+        ));
+    }
+    Ok(Some(value))
+}
+
+/// Normalizes a target URL and checks it against the domain blocklist.
+fn validate_target(state: &AppState, url: &str) -> Result<String, (StatusCode, String)> {
+    let target = normalize_url(url).map_err(|e| (StatusCode::BAD_REQUEST, format!("url {e}")))?;
+    if target_domain(&target).is_some_and(|host| state.settings().is_blocked(&host)) {
+        return Err((StatusCode::BAD_REQUEST, "target domain is blocked".to_string()));
+    }
+    Ok(target)
+}
+
+/// `not_before` must be RFC3339 and come before `expires_at`, if any.
+fn validate_not_before(not_before: &str, expires_at: Option<&str>) -> Result<(), (StatusCode, String)> {
+    let rfc3339 = &time::format_description::well_known::Rfc3339;
+    let start = OffsetDateTime::parse(not_before, rfc3339).map_err(|_| {
         (
             StatusCode::BAD_REQUEST,
-            "url must start with http:// or https://".to_string(),
+            "not_before must be RFC3339 (e.g. 2026-01-31T00:00:00Z)".to_string(),
         )
     })?;
+    if expires_at
+        .and_then(|exp| OffsetDateTime::parse(exp, rfc3339).ok())
+        .is_some_and(|exp| exp <= start)
+    {
+        return Err((StatusCode::BAD_REQUEST, "not_before must be before expires_at".to_string()));
+    }
+    Ok(())
+}
 
-    if let Some(exp) = &payload.expires_at {
-        time::OffsetDateTime::parse(exp, &time::format_description::well_known::Rfc3339)
-            .map_err(|_| {
-                (
-                    StatusCode::BAD_REQUEST,
-                    "expires_at must be RFC3339 (e.g. 2026-01-31T00:00:00Z)".to_string(),
-                )
-            })?;
-    }
-
-    let ip = client_ip_from_headers(&headers);
-    let ua = headers
-        .get(header::USER_AGENT)
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.to_string());
+fn validate_expires_at(expires_at: &str) -> Result<(), (StatusCode, String)> {
+    OffsetDateTime::parse(expires_at, &time::format_description::well_known::Rfc3339)
+        .map(|_| ())
+        .map_err(|_| {
+            (
+                StatusCode::BAD_REQUEST,
+                "expires_at must be RFC3339 (e.g. 2026-01-31T00:00:00Z)".to_string(),
+            )
+        })
+}
+
+/// Distinguishes an absent field (`None`) from an explicit `null`
+/// (`Some(None)`) in partial updates.
+fn double_option<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PatchReq {
+    target_url: Option<String>,
+    /// `null` removes the expiry.
+    #[serde(default, deserialize_with = "double_option")]
+    expires_at: Option<Option<String>>,
+    /// `null` or blank clears the title.
+    #[serde(default, deserialize_with = "double_option")]
+    title: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option")]
+    notes: Option<Option<String>>,
+}
+
+/// Edits a link in place; omitted fields are left alone. Redirects read the
+/// row on every request, so changes apply immediately.
+async fn patch_link(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<PatchReq>,
+) -> Result<Json<ResolveResp>, (StatusCode, String)> {
+    api_keys::require_api_key(&state, &headers).await?;
+    let Some(link) = fetch_link(&state, &code).await.map_err(internal)? else {
+        return Err((StatusCode::NOT_FOUND, "not found".to_string()));
+    };
+
+    // a new target passes the same checks as at creation
+    let (target_url, original_url, chained_via) = match &payload.target_url {
+        Some(url) => {
+            let checked = check_target(&state, url).await?;
+            (checked.target, checked.original_url, checked.chained_via)
+        }
+        None => (link.target_url.clone(), link.original_url.clone(), link.chained_via.clone()),
+    };
+    let expires_at = match payload.expires_at {
+        Some(Some(exp)) => {
+            validate_expires_at(&exp)?;
+            Some(exp)
+        }
+        Some(None) => None,
+        None => link.expires_at.clone(),
+    };
+    if let Some(start) = &link.not_before {
+        validate_not_before(start, expires_at.as_deref())?;
+    }
+    let title = match payload.title {
+        Some(title) => validate_label("title", title, MAX_TITLE_LEN)?,
+        None => link.title.clone(),
+    };
+    let notes = match payload.notes {
+        Some(notes) => validate_label("notes", notes, MAX_NOTES_LEN)?,
+        None => link.notes.clone(),
+    };
+
+    // a new expiry gets its own warning and expiry notification
+    sqlx::query(
+        "UPDATE urls SET target_url = ?, expires_at = ?, title = ?, notes = ?, \
+                expiry_warned_at = CASE WHEN expires_at IS ?5 THEN expiry_warned_at END, \
+                expired_notified_at = CASE WHEN expires_at IS ?5 THEN expired_notified_at END, \
+                original_url = ?6, chained_via = ?7 \
+         WHERE code = ?8",
+    )
+    .bind(&target_url)
+    .bind(&expires_at)
+    .bind(&title)
+    .bind(&notes)
+    .bind(&expires_at)
+    .bind(&original_url)
+    .bind(&chained_via)
+    .bind(&link.code)
+    .execute(&state.pool)
+    .await
+    .map_err(internal)?;
 
-    let code = if let Some(custom) = payload.custom_code.as_deref() {
-        validate_custom_code(custom).map_err(|msg| (StatusCode::BAD_REQUEST, msg))?;
-        insert_url(
+    let changed: Vec<&'static str> = [
+        ("target_url", link.target_url != target_url),
+        ("expires_at", link.expires_at != expires_at),
+        ("title", link.title != title),
+        ("notes", link.notes != notes),
+    ]
+    .into_iter()
+    .filter_map(|(field, changed)| changed.then_some(field))
+    .collect();
+    if !changed.is_empty() {
+        notify::spawn_link_event(
             &state,
-            custom,
-            &target,
-            payload.expires_at.as_deref(),
-            ip.as_deref(),
-            ua.as_deref(),
-        )
+            notify::Event::LinkEdited {
+                code: link.code.clone(),
+                target_url: target_url.clone(),
+                changed,
+            },
+        );
+    }
+
+    let updated = fetch_link(&state, &link.code)
         .await
-        .map_err(|e| match e {
-            InsertUrlError::CodeTaken => (StatusCode::CONFLICT, "code already exists".to_string()),
-            InsertUrlError::Other(e) => internal(e),
-        })?;
-        custom.to_string()
-    } else {
-        const MAX_ATTEMPTS: usize = 8;
-        let mut last_err: Option<anyhow::Error> = None;
-        let mut code: Option<String> = None;
-        for _ in 0..MAX_ATTEMPTS {
-            let candidate = gen_code();
-            match insert_url(
-                &state,
-                &candidate,
-                &target,
-                payload.expires_at.as_deref(),
-                ip.as_deref(),
-                ua.as_deref(),
-            )
-            .await
-            {
-                Ok(()) => {
-                    code = Some(candidate);
-                    break;
-                }
-                Err(InsertUrlError::CodeTaken) => continue,
-                Err(InsertUrlError::Other(e)) => {
-                    last_err = Some(e);
-                    break;
-                }
+        .map_err(internal)?
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "not found".to_string()))?;
+    Ok(Json(ResolveResp::new(&state, updated)))
+}
+
+/// Inserts under `custom` (validated) or under fresh random codes until one
+/// is free. `insert` gets the candidate code.
+async fn allocate_code<F, Fut>(
+    state: &AppState,
+    caller: Option<&Caller>,
+    custom: Option<&str>,
+    mut insert: F,
+) -> Result<String, (StatusCode, String)>
+where
+    F: FnMut(String) -> Fut,
+    Fut: std::future::Future<Output = Result<(), InsertUrlError>>,
+{
+    let mut candidates = CodeCandidates::new(state, caller, custom)?;
+    loop {
+        // the connection goes back to the pool before `insert` needs one
+        let candidate = {
+            let mut conn = state.pool.acquire().await.map_err(internal)?;
+            candidates.next(&mut conn).await?
+        };
+        let Some(candidate) = candidate else {
+            return Err(codes_exhausted(custom));
+        };
+        match insert(candidate.clone()).await {
+            Ok(()) => return Ok(candidate),
+            Err(InsertUrlError::CodeTaken) => continue,
+            Err(InsertUrlError::Other(e)) => return Err(internal(e)),
+        }
+    }
+}
+
+/// Codes to try, in order: the custom code alone, or a few from
+/// [`AppState::code_generator`] that are neither reserved codes nor under a
+/// reserved prefix, generated one at a time.
+struct CodeCandidates<'a> {
+    state: &'a AppState,
+    custom: Option<String>,
+    attempts: usize,
+}
+
+impl<'a> CodeCandidates<'a> {
+    const MAX_ATTEMPTS: usize = 8;
+
+    fn new(state: &'a AppState, caller: Option<&Caller>, custom: Option<&str>) -> Result<Self, (StatusCode, String)> {
+        if let Some(custom) = custom {
+            validate_custom_code(custom, &state.settings(), caller)
+                .map_err(|msg| (StatusCode::BAD_REQUEST, msg))?;
+        }
+        Ok(Self {
+            state,
+            custom: custom.map(str::to_string),
+            attempts: 0,
+        })
+    }
+
+    /// The next code to try, `None` once they are used up.
+    async fn next(&mut self, conn: &mut sqlx::SqliteConnection) -> Result<Option<String>, (StatusCode, String)> {
+        if let Some(custom) = self.custom.take() {
+            self.attempts = Self::MAX_ATTEMPTS;
+            return Ok(Some(custom));
+        }
+        let settings = self.state.settings();
+        while self.attempts < Self::MAX_ATTEMPTS {
+            self.attempts += 1;
+            let code = self.state.code_generator.generate(conn).await.map_err(internal)?;
+            if settings.prefix_owner(&code).is_none() && !settings.is_reserved_code(&code) {
+                return Ok(Some(code));
             }
         }
-        code.ok_or_else(|| {
-            internal(last_err.unwrap_or_else(|| anyhow::anyhow!("failed to generate code")))
-        })?
+        Ok(None)
+    }
+}
+
+/// Error once every candidate from [`CodeCandidates`] was taken.
+fn codes_exhausted(custom: Option<&str>) -> (StatusCode, String) {
+    match custom {
+        Some(_) => (StatusCode::CONFLICT, "code already exists".to_string()),
+        None => internal("failed to generate code"),
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct CloneReq {
+    custom_code: Option<String>,
+    expires_at: Option<String>,
+}
+
+/// Creates a new code with the same configuration, schedules and tags as
+/// `code`.
+/// The expiry is not copied: the clone gets `expires_at` from the request, or
+/// the default.
+async fn clone_link(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+    headers: HeaderMap,
+    payload: Option<Json<CloneReq>>,
+) -> Result<Json<ShortenResp>, (StatusCode, String)> {
+    let payload = payload.map(|Json(p)| p).unwrap_or_default();
+    let Some(source) = fetch_link(&state, &code).await.map_err(internal)? else {
+        return Err((StatusCode::NOT_FOUND, "not found".to_string()));
+    };
+
+    validate_target(&state, &source.target_url)?;
+    if let Some(exp) = &payload.expires_at {
+        validate_expires_at(exp)?;
+    }
+    let expires_at = payload
+        .expires_at
+        .or_else(|| state.settings().default_expires_at());
+
+    let ip = state.client_ip(&headers);
+    let caller = api_keys::caller(&state, &headers).await?;
+    let (expires_at, pending_review) =
+        creation_policy(&state, caller.as_ref(), ip.as_deref(), expires_at).await?;
+    if let Some(start) = &source.not_before {
+        validate_not_before(start, expires_at.as_deref())?;
+    }
+    let origin = LinkOrigin {
+        ip: ip.as_deref(),
+        user_agent: headers.get(header::USER_AGENT).and_then(|v| v.to_str().ok()),
+        created_by: caller.as_ref().map(Caller::name),
+        pending_review,
+    };
+    let custom_code = payload.custom_code.as_deref().map(|c| state.normalize_code(c));
+    let new_code = allocate_code(&state, caller.as_ref(), custom_code.as_deref(), |new_code| {
+        let (expires_at, origin) = (expires_at.as_deref(), &origin);
+        let source = source.code.as_str();
+        let state = &state;
+        async move { copy_url(state, source, &new_code, expires_at, origin).await }
+    })
+    .await?;
+    if pending_review {
+        moderation::notify_held(&state, &new_code, &source.target_url);
+    }
+    notify::spawn_link_event(&state, notify::Event::link_created(&state, &new_code, &source.target_url));
+
+    Ok(Json(ShortenResp::new(&state, new_code, expires_at, pending_review)))
+}
+
+#[derive(Deserialize)]
+struct UtmReq {
+    url: String,
+    #[serde(flatten)]
+    utm: UtmParams,
+    #[serde(default)]
+    shorten: bool,
+    custom_code: Option<String>,
+    expires_at: Option<String>,
+    #[serde(default)]
+    listed: bool,
+}
+
+#[derive(Serialize)]
+struct UtmResp {
+    url: String,
+    link: Option<ShortenResp>,
+}
+
+/// Builds a UTM-tagged URL and, when `shorten` is set, creates a link for it
+/// carrying the campaign fields as metadata.
+async fn utm_builder(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<UtmReq>,
+) -> Result<Json<UtmResp>, (StatusCode, String)> {
+    for (name, value) in [
+        ("source", &payload.utm.source),
+        ("medium", &payload.utm.medium),
+        ("campaign", &payload.utm.campaign),
+    ] {
+        if value.as_deref().map(str::trim).unwrap_or("").is_empty() {
+            return Err((StatusCode::BAD_REQUEST, format!("{name} is required")));
+        }
+    }
+
+    let base = normalize_url(&payload.url).map_err(|e| (StatusCode::BAD_REQUEST, format!("url {e}")))?;
+    let tagged = apply_utm(&base, &payload.utm, true)
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, "url is not valid".to_string()))?;
+
+    let link = if payload.shorten {
+        let req = ShortenReq {
+            url: tagged.clone(),
+            custom_code: payload.custom_code,
+            expires_at: payload.expires_at,
+            listed: Some(payload.listed),
+            utm: Some(payload.utm),
+            ..ShortenReq::default()
+        };
+        Some(create_link(&state, &headers, req).await?)
+    } else {
+        None
     };
 
-    let short_url = format!("{}/{}", state.base_url, code);
-    Ok(Json(ShortenResp {
-        qr_png_url: format!("{}/api/links/{}/qr", state.base_url, code),
-        code: code.clone(),
-        short_url,
-        expires_at: payload.expires_at,
-    }))
+    Ok(Json(UtmResp { url: tagged, link }))
 }
 
-async fn qr_png(State(state): State<AppState>, Path(code): Path<String>) -> impl IntoResponse {
-    let exists: Option<(i64,)> = sqlx::query_as("SELECT 1 FROM urls WHERE code = ?")
-        .bind(&code)
-        .fetch_optional(&state.pool)
-        .await
-        .unwrap();
+/// Appends `rest` to the path of `target`, keeping its query and fragment.
+/// `None` when `target` does not parse.
+fn forward_path(target: &str, rest: &str) -> Option<String> {
+    let mut url = url::Url::parse(target).ok()?;
+    let path = format!("{}/{}", url.path().trim_end_matches('/'), rest.trim_start_matches('/'));
+    url.set_path(&path);
+    Some(url.to_string())
+}
+
+/// Appends the parameters of `query` to `target`, dropping the target's
+/// parameters of the same name. Signed-link parameters are not forwarded.
+/// `None` when there is nothing to forward or `target` does not parse.
+fn forward_query(target: &str, query: &str) -> Option<String> {
+    let incoming: Vec<(String, String)> = url::form_urlencoded::parse(query.as_bytes())
+        .filter(|(k, _)| !signing::PARAM_NAMES.contains(&k.as_ref()))
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect();
+    if incoming.is_empty() {
+        return None;
+    }
+    let mut url = url::Url::parse(target).ok()?;
+    let kept: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(k, _)| !incoming.iter().any(|(key, _)| k == key))
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect();
+    url.query_pairs_mut().clear().extend_pairs(kept).extend_pairs(incoming);
+    Some(url.to_string())
+}
+
+/// Sets the `utm_*` query parameters on `target`. Existing parameters are
+/// replaced when `overwrite` is true and left untouched otherwise.
+fn apply_utm(target: &str, utm: &UtmParams, overwrite: bool) -> Option<String> {
+    let mut url = url::Url::parse(target).ok()?;
+    let wanted: Vec<(&str, &str)> = utm
+        .pairs()
+        .into_iter()
+        .filter(|(key, _)| overwrite || !url.query_pairs().any(|(k, _)| k == *key))
+        .collect();
+    if wanted.is_empty() {
+        return Some(url.to_string());
+    }
 
-    if exists.is_none() {
-        return (StatusCode::NOT_FOUND, "not found").into_response();
+    let kept: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(k, _)| !wanted.iter().any(|(key, _)| k == key))
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect();
+
+    {
+        let mut query = url.query_pairs_mut();
+        query.clear();
+        for (k, v) in &kept {
+            query.append_pair(k, v);
+        }
+        for (k, v) in wanted {
+            query.append_pair(k, v);
+        }
     }
+    Some(url.to_string())
+}
+
+#[cfg(feature = "qr")]
+async fn qr_png(State(state): State<AppState>, Path(code): Path<String>) -> impl IntoResponse {
+    let link = match fetch_link(&state, &code).await {
+        Ok(Some(link)) => link,
+        Ok(None) => return (StatusCode::NOT_FOUND, "not found").into_response(),
+        Err(e) => return internal(e).into_response(),
+    };
 
-    let short_url = format!("{}/{}", state.base_url, code);
+    // encode the code as requested, so an alias's QR code shows the alias
+    let url = if link.qr_landing {
+        state.public_url(&format!("/q/{code}"))
+    } else {
+        state.short_url(&code)
+    };
 
-    let qr = match qrcode::QrCode::new(short_url.as_bytes()) {
+    let qr = match qrcode::QrCode::new(url.as_bytes()) {
         Ok(qr) => qr,
         Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "qr error").into_response(),
     };
@@ -483,7 +2341,7 @@ async fn qr_png(State(state): State<AppState>, Path(code): Path<String>) -> impl
     let img = qr.render::<image::Luma<u8>>().min_dimensions(256, 256).build();
     let mut png_bytes = Vec::new();
     if image::DynamicImage::ImageLuma8(img)
-        .write_to(&mut Cursor::new(&mut png_bytes), image::ImageFormat::Png)
+        .write_to(&mut std::io::Cursor::new(&mut png_bytes), image::ImageFormat::Png)
         .is_err()
     {
         return (StatusCode::INTERNAL_SERVER_ERROR, "qr encode error").into_response();
@@ -491,49 +2349,384 @@ async fn qr_png(State(state): State<AppState>, Path(code): Path<String>) -> impl
 
     (
         [(header::CONTENT_TYPE, "image/png")],
-        Bytes::from(png_bytes),
+        axum::body::Bytes::from(png_bytes),
     )
         .into_response()
 }
 
+/// A full row of `urls`, for handlers that need more than a column or two.
+#[derive(sqlx::FromRow)]
+struct LinkRow {
+    code: String,
+    target_url: String,
+    created_at: String,
+    expires_at: Option<String>,
+    listed: bool,
+    utm_source: Option<String>,
+    utm_medium: Option<String>,
+    utm_campaign: Option<String>,
+    utm_term: Option<String>,
+    utm_content: Option<String>,
+    redirect_mode: RedirectMode,
+    redirect_type: RedirectType,
+    forward_query: bool,
+    path_forwarding: bool,
+    qr_landing: bool,
+    target_ios: Option<String>,
+    target_android: Option<String>,
+    target_desktop: Option<String>,
+    signed_only: bool,
+    pending_review: bool,
+    /// Set when an admin disabled the link, e.g. by quarantining its domain.
+    disabled_reason: Option<String>,
+    disabled_at: Option<String>,
+    original_url: Option<String>,
+    chained_via: Option<String>,
+    title: Option<String>,
+    notes: Option<String>,
+    target_ok_at: Option<String>,
+    target_dead_since: Option<String>,
+    archived_at: Option<String>,
+    max_clicks: Option<i64>,
+    /// Redirects so far, counted against `max_clicks`.
+    click_count: i64,
+    not_before: Option<String>,
+    /// Target of the schedule window active now; only [`fetch_link`] sets it.
+    #[sqlx(default)]
+    scheduled_target: Option<String>,
+}
+
+impl LinkRow {
+    /// Shorten response handing out this existing link again.
+    fn reused_response(self, state: &AppState) -> ShortenResp {
+        ShortenResp {
+            reused: true,
+            ..ShortenResp::new(state, self.code, self.expires_at, self.pending_review)
+        }
+    }
+
+    fn utm(&self) -> UtmParams {
+        UtmParams {
+            source: self.utm_source.clone(),
+            medium: self.utm_medium.clone(),
+            campaign: self.utm_campaign.clone(),
+            term: self.utm_term.clone(),
+            content: self.utm_content.clone(),
+        }
+    }
+
+    fn device_targets(&self) -> devices::DeviceTargets {
+        devices::DeviceTargets {
+            ios: self.target_ios.clone(),
+            android: self.target_android.clone(),
+            desktop: self.target_desktop.clone(),
+        }
+    }
+
+    /// Where `redirect` sends visitors: the target for their `device` (or
+    /// `target_url`), with the `path` suffix of the short URL appended, plus
+    /// the visitor's `query` with `forward_query` (replacing parameters of
+    /// the same name), plus the link's UTM parameters where the URL has none
+    /// yet.
+    fn redirect_target(&self, device: devices::Device, path: Option<&str>, query: Option<&str>) -> String {
+        let devices = self.device_targets();
+        let mut target = devices.for_device(device).unwrap_or(&self.target_url).to_string();
+        if let Some(path) = path {
+            target = forward_path(&target, path).unwrap_or(target);
+        }
+        if let Some(query) = query.filter(|_| self.forward_query) {
+            target = forward_query(&target, query).unwrap_or(target);
+        }
+        let utm = self.utm();
+        if utm.is_empty() {
+            return target;
+        }
+        apply_utm(&target, &utm, false).unwrap_or(target)
+    }
+}
+
+/// Looks a link up by code or alias, ignoring case in go-links mode. Callers
+/// should use `LinkRow::code` afterwards, which has the stored spelling of the
+/// link's own code. The active schedule window comes along in the same query,
+/// so a redirect costs one round trip before the click is recorded.
+async fn fetch_link(state: &AppState, code: &str) -> Result<Option<LinkRow>, sqlx::Error> {
+    let sql = if state.go_links {
+        "WITH l AS (SELECT * FROM urls WHERE code = ?1 COLLATE NOCASE \
+                    UNION ALL SELECT u.* FROM link_aliases a JOIN urls u ON u.code = a.code \
+                    WHERE a.alias = ?1 COLLATE NOCASE LIMIT 1) \
+         SELECT l.*, (SELECT s.target_url FROM link_schedules s \
+                      WHERE s.code = l.code AND s.starts_at <= ?2 AND (s.ends_at IS NULL OR s.ends_at > ?2) \
+                      ORDER BY s.starts_at DESC, s.id DESC LIMIT 1) AS scheduled_target FROM l"
+    } else {
+        "WITH l AS (SELECT * FROM urls WHERE code = ?1 \
+                    UNION ALL SELECT u.* FROM link_aliases a JOIN urls u ON u.code = a.code \
+                    WHERE a.alias = ?1 LIMIT 1) \
+         SELECT l.*, (SELECT s.target_url FROM link_schedules s \
+                      WHERE s.code = l.code AND s.starts_at <= ?2 AND (s.ends_at IS NULL OR s.ends_at > ?2) \
+                      ORDER BY s.starts_at DESC, s.id DESC LIMIT 1) AS scheduled_target FROM l"
+    };
+    sqlx::query_as(sql)
+        .bind(code)
+        .bind(OffsetDateTime::now_utc().unix_timestamp())
+        .fetch_optional(&state.pool)
+        .await
+}
+
 #[derive(Debug)]
 enum InsertUrlError {
     CodeTaken,
     Other(anyhow::Error),
 }
 
-async fn insert_url(
-    state: &AppState,
+struct NewUrl<'a> {
+    target_url: &'a str,
+    expires_at: Option<&'a str>,
+    created_ip: Option<&'a str>,
+    created_user_agent: Option<&'a str>,
+    listed: bool,
+    utm: Option<&'a UtmParams>,
+    redirect_mode: RedirectMode,
+    redirect_type: RedirectType,
+    forward_query: bool,
+    path_forwarding: bool,
+    qr_landing: bool,
+    device_targets: Option<&'a devices::DeviceTargets>,
+    signed_only: bool,
+    /// API key name, `admin`, or `None` for anonymous clients.
+    created_by: Option<&'a str>,
+    /// Held for moderation; see [`moderation`].
+    pending_review: bool,
+    /// Submitted target when `target_url` is a rewrite of it.
+    original_url: Option<&'a str>,
+    /// Known shortener the submitted target pointed at.
+    chained_via: Option<&'a str>,
+    title: Option<&'a str>,
+    notes: Option<&'a str>,
+    max_clicks: Option<i64>,
+    not_before: Option<&'a str>,
+}
+
+async fn insert_url(state: &AppState, code: &str, new: &NewUrl<'_>) -> Result<(), InsertUrlError> {
+    insert_url_with(&state.pool, code, new).await
+}
+
+/// [`insert_url`] on any executor, e.g. inside a transaction.
+async fn insert_url_with(
+    executor: impl sqlx::SqliteExecutor<'_>,
     code: &str,
-    target_url: &str,
-    expires_at: Option<&str>,
-    created_ip: Option<&str>,
-    created_user_agent: Option<&str>,
+    new: &NewUrl<'_>,
 ) -> Result<(), InsertUrlError> {
     let created_at = OffsetDateTime::now_utc()
         .format(&time::format_description::well_known::Rfc3339)
         .unwrap();
 
     let res = sqlx::query(
-        "INSERT INTO urls (code, target_url, created_at, expires_at, created_ip, created_user_agent) \
-         VALUES (?, ?, ?, ?, ?, ?)",
+        "INSERT INTO urls (code, target_url, created_at, expires_at, created_ip, created_user_agent, listed, \
+                           utm_source, utm_medium, utm_campaign, utm_term, utm_content, redirect_mode, \
+                           redirect_type, forward_query, path_forwarding, qr_landing, target_ios, target_android, \
+                           target_desktop, signed_only, created_by, pending_review, original_url, chained_via, title, \
+                           notes, max_clicks, not_before) \
+         SELECT ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ? \
+         WHERE NOT EXISTS (SELECT 1 FROM link_aliases WHERE alias = ?)",
     )
     .bind(code)
-    .bind(target_url)
+    .bind(new.target_url)
     .bind(created_at)
-    .bind(expires_at)
-    .bind(created_ip)
-    .bind(created_user_agent)
-    .execute(&state.pool)
+    .bind(new.expires_at)
+    .bind(new.created_ip)
+    .bind(new.created_user_agent)
+    .bind(new.listed)
+    .bind(new.utm.and_then(|u| u.source.as_deref()))
+    .bind(new.utm.and_then(|u| u.medium.as_deref()))
+    .bind(new.utm.and_then(|u| u.campaign.as_deref()))
+    .bind(new.utm.and_then(|u| u.term.as_deref()))
+    .bind(new.utm.and_then(|u| u.content.as_deref()))
+    .bind(new.redirect_mode)
+    .bind(new.redirect_type)
+    .bind(new.forward_query)
+    .bind(new.path_forwarding)
+    .bind(new.qr_landing)
+    .bind(new.device_targets.and_then(|d| d.ios.as_deref()))
+    .bind(new.device_targets.and_then(|d| d.android.as_deref()))
+    .bind(new.device_targets.and_then(|d| d.desktop.as_deref()))
+    .bind(new.signed_only)
+    .bind(new.created_by)
+    .bind(new.pending_review)
+    .bind(new.original_url)
+    .bind(new.chained_via)
+    .bind(new.title)
+    .bind(new.notes)
+    .bind(new.max_clicks)
+    .bind(new.not_before)
+    .bind(code)
+    .execute(executor)
     .await;
 
     match res {
+        Ok(done) if done.rows_affected() == 0 => Err(InsertUrlError::CodeTaken),
         Ok(_) => Ok(()),
         Err(e) if is_unique_violation(&e) => Err(InsertUrlError::CodeTaken),
         Err(e) => Err(InsertUrlError::Other(anyhow::Error::new(e))),
     }
 }
 
+/// Per-link configuration copied by [`copy_url`]. Columns describing how a
+/// link behaves belong here; creation metadata and expiry do not.
+const LINK_CONFIG_COLUMNS: &str = "target_url, listed, \
+    utm_source, utm_medium, utm_campaign, utm_term, utm_content, redirect_mode, redirect_type, \
+    forward_query, path_forwarding, qr_landing, target_ios, target_android, target_desktop, signed_only, title, notes, \
+    max_clicks, not_before";
+
+/// Who is creating a link, recorded alongside it by [`copy_url`].
+struct LinkOrigin<'a> {
+    ip: Option<&'a str>,
+    user_agent: Option<&'a str>,
+    created_by: Option<&'a str>,
+    pending_review: bool,
+}
+
+/// Inserts `code` as a copy of `source`, along with its schedules and tags,
+/// in one transaction.
+async fn copy_url(
+    state: &AppState,
+    source: &str,
+    code: &str,
+    expires_at: Option<&str>,
+    origin: &LinkOrigin<'_>,
+) -> Result<(), InsertUrlError> {
+    let other = |e: sqlx::Error| InsertUrlError::Other(anyhow::Error::new(e));
+    let created_at = OffsetDateTime::now_utc()
+        .format(&time::format_description::well_known::Rfc3339)
+        .unwrap();
+
+    let mut tx = state.pool.begin().await.map_err(other)?;
+    let res = sqlx::query(&format!(
+        "INSERT INTO urls (code, created_at, expires_at, created_ip, created_user_agent, created_by, \
+                           pending_review, {LINK_CONFIG_COLUMNS}) \
+         SELECT ?, ?, ?, ?, ?, ?, ?, {LINK_CONFIG_COLUMNS} FROM urls WHERE code = ? \
+         AND NOT EXISTS (SELECT 1 FROM link_aliases WHERE alias = ?)"
+    ))
+    .bind(code)
+    .bind(created_at)
+    .bind(expires_at)
+    .bind(origin.ip)
+    .bind(origin.user_agent)
+    .bind(origin.created_by)
+    .bind(origin.pending_review)
+    .bind(source)
+    .bind(code)
+    .execute(&mut *tx)
+    .await;
+
+    match res {
+        Ok(done) if done.rows_affected() == 0 => {
+            let alias: Option<(String,)> = sqlx::query_as("SELECT alias FROM link_aliases WHERE alias = ?")
+                .bind(code)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(other)?;
+            return match alias {
+                Some(_) => Err(InsertUrlError::CodeTaken),
+                None => Err(InsertUrlError::Other(anyhow::anyhow!("source link disappeared"))),
+            };
+        }
+        Ok(_) => {}
+        Err(e) if is_unique_violation(&e) => return Err(InsertUrlError::CodeTaken),
+        Err(e) => return Err(other(e)),
+    }
+
+    sqlx::query(
+        "INSERT INTO link_schedules (code, target_url, starts_at, ends_at) \
+         SELECT ?, target_url, starts_at, ends_at FROM link_schedules WHERE code = ? ORDER BY id",
+    )
+    .bind(code)
+    .bind(source)
+    .execute(&mut *tx)
+    .await
+    .map_err(other)?;
+    sqlx::query("INSERT INTO link_tags (code, tag_id) SELECT ?, tag_id FROM link_tags WHERE code = ?")
+        .bind(code)
+        .bind(source)
+        .execute(&mut *tx)
+        .await
+        .map_err(other)?;
+
+    tx.commit().await.map_err(other)
+}
+
+/// Tables with per-link rows, cleared before the `urls` row itself.
+const LINK_CHILD_TABLES: &[&str] = &["clicks", "click_rollups", "visitor_sketches", "interstitial_views", "qr_scans"];
+
+/// Copies the clicks of `code` into `clicks_archive`. Returns how many.
+async fn archive_clicks(conn: &mut sqlx::SqliteConnection, code: &str) -> Result<u64, sqlx::Error> {
+    let now = OffsetDateTime::now_utc()
+        .format(&time::format_description::well_known::Rfc3339)
+        .unwrap();
+    let done = sqlx::query(
+        "INSERT INTO clicks_archive (id, code, at, ip, user_agent, referer, country, city, \
+                                     visitor_id, language, extra, recipient, channel, browser, os, \
+                                     device_type, is_bot, archived_at) \
+         SELECT id, code, at, ip, user_agent, referer, country, city, visitor_id, language, extra, \
+                recipient, channel, browser, os, device_type, is_bot, ? \
+         FROM clicks WHERE code = ?",
+    )
+    .bind(now)
+    .bind(code)
+    .execute(conn)
+    .await?;
+    Ok(done.rows_affected())
+}
+
+/// Deletes the clicks and sketches of `code`, keeping the link.
+async fn clear_link_children(conn: &mut sqlx::SqliteConnection, code: &str) -> Result<(), sqlx::Error> {
+    for table in LINK_CHILD_TABLES {
+        sqlx::query(&format!("DELETE FROM {table} WHERE code = ?"))
+            .bind(code)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Deletes a link with its clicks, sketches, aliases, schedules, tags, click
+/// webhooks, click alerts and anomalies. Returns whether it existed. Callers drop the cached
+/// webhook list once this commits.
+async fn delete_link_rows(
+    conn: &mut sqlx::SqliteConnection,
+    code: &str,
+) -> Result<bool, sqlx::Error> {
+    clear_link_children(conn, code).await?;
+    sqlx::query("DELETE FROM link_aliases WHERE code = ?")
+        .bind(code)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("DELETE FROM link_schedules WHERE code = ?")
+        .bind(code)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("DELETE FROM link_tags WHERE code = ?")
+        .bind(code)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("DELETE FROM click_webhooks WHERE code = ?")
+        .bind(code)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("DELETE FROM click_alerts WHERE code = ?")
+        .bind(code)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("DELETE FROM anomalies WHERE code = ?")
+        .bind(code)
+        .execute(&mut *conn)
+        .await?;
+    let done = sqlx::query("DELETE FROM urls WHERE code = ?")
+        .bind(code)
+        .execute(&mut *conn)
+        .await?;
+    Ok(done.rows_affected() > 0)
+}
+
 fn is_unique_violation(e: &sqlx::Error) -> bool {
     match e {
         sqlx::Error::Database(db) => db.is_unique_violation(),
@@ -541,39 +2734,69 @@ fn is_unique_violation(e: &sqlx::Error) -> bool {
     }
 }
 
-fn validate_custom_code(code: &str) -> Result<(), String> {
-    if !(6..=8).contains(&code.len()) {
-        return Err("custom_code must be 6-8 characters".to_string());
+/// Checks the code's format, that it isn't a reserved code, and that `caller`
+/// (an API key name, `admin`, or `None` for anonymous clients) may use its
+/// reserved prefix, if any.
+fn validate_custom_code(code: &str, settings: &Settings, caller: Option<&Caller>) -> Result<(), String> {
+    if !(3..=32).contains(&code.len()) {
+        return Err("custom_code must be 3-32 characters".to_string());
+    }
+    if !code.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        || code.starts_with('-')
+        || code.ends_with('-')
+    {
+        return Err("custom_code must be alphanumeric, with optional inner hyphens".to_string());
+    }
+    if settings.is_reserved_code(code) {
+        return Err(format!("custom_code {code:?} is reserved"));
     }
-    if !code.chars().all(|c| c.is_ascii_alphanumeric()) {
-        return Err("custom_code must be alphanumeric".to_string());
+    if let Some((prefix, owner)) = settings.prefix_owner(code) {
+        let allowed = match caller {
+            Some(Caller::Admin) => true,
+            Some(Caller::Key(name)) => name == owner,
+            None => false,
+        };
+        if !allowed {
+            return Err(format!("codes starting with {prefix} are reserved for {owner}"));
+        }
     }
     Ok(())
 }
 
-fn normalize_url(input: &str) -> Option<String> {
+/// Checks a target URL and returns it as submitted, minus surrounding
+/// whitespace and an empty trailing `#`. Only http(s) URLs with a valid host
+/// and no credentials pass; the error completes "url ...".
+fn normalize_url(input: &str) -> Result<String, String> {
     let trimmed = input.trim();
-    if trimmed.starts_with("http://") || trimmed.starts_with("https://") {
-        Some(trimmed.to_string())
-    } else {
-        None
+    let trimmed = trimmed.strip_suffix('#').unwrap_or(trimmed);
+    let url = url::Url::parse(trimmed).map_err(|e| format!("is not a valid URL ({e})"))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("must use http:// or https://, not {}:", url.scheme()));
     }
-}
-
-fn client_ip_from_headers(headers: &HeaderMap) -> Option<String> {
-    if let Some(v) = headers
-        .get("x-forwarded-for")
-        .and_then(|v| v.to_str().ok())
-    {
-        let first = v.split(',').next().map(|s| s.trim()).filter(|s| !s.is_empty());
-        if let Some(ip) = first {
-            return Some(ip.to_string());
+    if !url.username().is_empty() || url.password().is_some() {
+        return Err("must not contain credentials (user:password@)".to_string());
+    }
+    match url.host() {
+        Some(url::Host::Domain(domain)) => {
+            let domain = domain.strip_suffix('.').unwrap_or(domain);
+            let valid_label = |label: &str| {
+                !label.is_empty()
+                    && label.len() <= 63
+                    && !label.starts_with('-')
+                    && !label.ends_with('-')
+                    && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            };
+            if domain.len() > 253 || !domain.split('.').all(valid_label) {
+                return Err(format!("has an invalid host: {domain}"));
+            }
         }
+        Some(_) => {}
+        None => return Err("has no host".to_string()),
     }
-    None
+    Ok(trimmed.to_string())
 }
 
-#[cfg(not(test))]
+#[cfg(all(feature = "geo", not(test)))]
 fn is_private_or_local_ip(ip: &str) -> bool {
     ip == "127.0.0.1"
         || ip == "::1"
@@ -588,7 +2811,7 @@ fn is_private_or_local_ip(ip: &str) -> bool {
         || ip.starts_with("172.31.")
 }
 
-#[cfg(not(test))]
+#[cfg(all(feature = "geo", not(test)))]
 async fn geo_country_lookup(ip: &str) -> Option<String> {
     if is_private_or_local_ip(ip) {
         return None;
@@ -618,38 +2841,480 @@ async fn geo_country_lookup(ip: &str) -> Option<String> {
     }
 }
 
-#[cfg(test)]
+#[cfg(any(test, not(feature = "geo")))]
 async fn geo_country_lookup(_ip: &str) -> Option<String> {
     None
 }
 
-async fn country_from_headers_or_ip(headers: &HeaderMap) -> Option<String> {
+async fn country_from_headers_or_ip(headers: &HeaderMap, ip: Option<&str>, ip_lookup: bool) -> Option<String> {
     if let Some(c) = country_from_headers(headers) {
         return Some(c);
     }
+    if !ip_lookup {
+        return None;
+    }
+
+    geo_country_lookup(ip?).await
+}
+
+/// `/:code`, or `/:code/*rest` for links with `path_forwarding`.
+#[derive(Deserialize)]
+struct RedirectPath {
+    code: String,
+    rest: Option<String>,
+}
+
+/// How a visitor reached a link.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Entry {
+    /// The short URL itself.
+    Link,
+    /// A QR code encoding `/q/<code>`.
+    QrScan,
+}
+
+async fn redirect(
+    State(state): State<AppState>,
+    Path(path): Path<RedirectPath>,
+    Query(signed): Query<signing::SignedParams>,
+    RawQuery(query): RawQuery,
+    headers: HeaderMap,
+) -> axum::response::Response {
+    let mut labels = None;
+    let response = follow(state.clone(), path, signed, query, headers, Entry::Link, &mut labels).await;
+    state.metrics.record_redirect(labels, response.status());
+    response
+}
+
+/// `GET /q/:code`: the QR landing page of a link, counted as a scan.
+async fn qr_scan(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+    Query(signed): Query<signing::SignedParams>,
+    RawQuery(query): RawQuery,
+    headers: HeaderMap,
+) -> axum::response::Response {
+    let path = RedirectPath { code, rest: None };
+    let mut labels = None;
+    let response = follow(state.clone(), path, signed, query, headers, Entry::QrScan, &mut labels).await;
+    state.metrics.record_redirect(labels, response.status());
+    response
+}
+
+/// Sets `labels` as soon as the link is known, for [`metrics::Metrics`].
+async fn follow(
+    state: AppState,
+    path: RedirectPath,
+    signed: signing::SignedParams,
+    mut query: Option<String>,
+    headers: HeaderMap,
+    entry: Entry,
+    labels: &mut Option<metrics::RedirectLabels>,
+) -> axum::response::Response {
+    // `/<code>+` and `/<code>?preview` show where the link goes instead
+    let (code, preview) = match path.code.strip_suffix('+') {
+        Some(code) if !code.is_empty() && path.rest.is_none() && entry == Entry::Link => (code, true),
+        _ => (path.code.as_str(), preview::requested(query.as_deref())),
+    };
+    let row = match fetch_link(&state, code).await {
+        Ok(row) => row,
+        Err(e) => return internal(e).into_response(),
+    };
+    let Some(mut link) = row else {
+        return if entry == Entry::QrScan {
+            link_error(&state, &headers, LinkError::NotFound, code, "Not found")
+        } else if path.rest.is_none() && is_admin(&state, &headers)
+            && validate_custom_code(code, &state.settings(), Some(&Caller::Admin)).is_ok()
+        {
+            (StatusCode::NOT_FOUND, claim_page(&state, code)).into_response()
+        } else if state.go_links {
+            let suggestions = search::closest_codes(&state, code).await.unwrap_or_default();
+            (StatusCode::NOT_FOUND, search::suggestions_page(&state, code, &suggestions)).into_response()
+        } else {
+            link_error(&state, &headers, LinkError::NotFound, code, "Not found")
+        };
+    };
+    *labels = Some(metrics::RedirectLabels::of(&link));
+    if path.rest.is_some() && !link.path_forwarding {
+        return link_error(&state, &headers, LinkError::NotFound, code, "Not found");
+    }
+    let fail = |kind, message| link_error(&state, &headers, kind, &link.code, message);
+
+    if is_expired(link.expires_at.as_deref()) {
+        return fail(LinkError::Gone, "This link has expired");
+    }
+    if is_scheduled(link.not_before.as_deref()) {
+        return fail(LinkError::NotFound, "This link is not active yet");
+    }
+    if link.disabled_reason.is_some() {
+        return fail(LinkError::Disabled, "This link has been disabled");
+    }
+    if link.archived_at.is_some() {
+        return fail(LinkError::Gone, "This link has been archived");
+    }
+    if link.pending_review {
+        return fail(LinkError::Forbidden, "This link is awaiting review");
+    }
+
+    let recipient = match signing::check(&state, &link.code, &signed) {
+        signing::SignatureCheck::Valid { recipient } => Some(recipient),
+        signing::SignatureCheck::Unsigned if !link.signed_only => signed.rid().map(str::to_string),
+        signing::SignatureCheck::Unsigned => {
+            return fail(LinkError::Forbidden, "This link requires a signed URL");
+        }
+        signing::SignatureCheck::Invalid => return fail(LinkError::Forbidden, "Invalid link signature"),
+        signing::SignatureCheck::Expired => return fail(LinkError::Gone, "This link has expired"),
+    };
+
+    if let Some(scheduled) = link.scheduled_target.take() {
+        link.target_url = scheduled;
+    }
+    let user_agent = headers.get(header::USER_AGENT).and_then(|v| v.to_str().ok());
+    let device = devices::Device::detect(user_agent);
+    if entry == Entry::QrScan {
+        if !state.is_read_only() {
+            qr_landing::record_scan(&state, &link.code).await;
+        }
+        let target = link.redirect_target(device, None, query.as_deref());
+        return qr_landing::page(&state, &link, &target, query.as_deref()).into_response();
+    }
+    if preview {
+        let query = preview::strip(query.as_deref());
+        let target = link.redirect_target(device, None, query.as_deref());
+        return preview::page(&state, &link, &target, query.as_deref()).into_response();
+    }
+
+    if link.redirect_mode == RedirectMode::Interstitial {
+        if !interstitial::passed(&state, &link.code, query.as_deref()) {
+            if !state.is_read_only() {
+                interstitial::record_view(&state, &link.code).await;
+            }
+            let mut continue_path = state.short_url(&link.code);
+            if let Some(rest) = &path.rest {
+                continue_path.push('/');
+                continue_path.push_str(rest);
+            }
+            let query = interstitial::strip(query.as_deref());
+            let target = link.redirect_target(device, path.rest.as_deref(), query.as_deref());
+            return interstitial::page(&state, &link, &continue_path, query.as_deref(), &target).into_response();
+        }
+        query = interstitial::strip(query.as_deref());
+    }
+
+    // a visitor who never reaches the target hasn't used up a click
+    if link.target_dead_since.is_some() && state.settings().dead_target_page {
+        return health::unavailable_page(&state, &link).into_response();
+    }
+    // link unfurlers (chat and social previews) must not use up limited
+    // links, so they get the preview page instead
+    let is_bot = user_agent.map(user_agents::parse).is_some_and(|ua| ua.is_bot());
+    if is_bot && link.max_clicks.is_some() {
+        let target = link.redirect_target(device, path.rest.as_deref(), query.as_deref());
+        return preview::page(&state, &link, &target, query.as_deref()).into_response();
+    }
+
+    if state.is_read_only() {
+        // redirects go on uncounted, except where the count is the point
+        if link.max_clicks.is_some() {
+            return (StatusCode::SERVICE_UNAVAILABLE, READ_ONLY_MESSAGE).into_response();
+        }
+    } else {
+        if !is_bot {
+            match claim_click(&state, &link.code).await {
+                Ok(true) => {}
+                Ok(false) if link.max_clicks == Some(1) => {
+                    return fail(LinkError::Gone, "This link has already been used");
+                }
+                Ok(false) => return fail(LinkError::Gone, "This link has reached its click limit"),
+                Err(e) => return internal(e).into_response(),
+            }
+        }
+        record_click(&state, &link.code, &headers, recipient.as_deref()).await;
+    }
+
+    let target = link.redirect_target(device, path.rest.as_deref(), query.as_deref());
+    let mut response = match link.redirect_mode {
+        RedirectMode::Http | RedirectMode::Interstitial => {
+            (link.redirect_type.status(), [(header::LOCATION, target)]).into_response()
+        }
+        RedirectMode::Html => html_redirect(&target).into_response(),
+    };
+    if !link.device_targets().is_empty() {
+        // caches must not hand one device's destination to another
+        response.headers_mut().insert(header::VARY, HeaderValue::from_static("user-agent"));
+    }
+    response
+}
+
+#[derive(Serialize)]
+struct ResolveResp {
+    code: String,
+    target_url: String,
+    short_url: String,
+    created_at: String,
+    expires_at: Option<String>,
+    expired: bool,
+    listed: bool,
+    redirect_mode: RedirectMode,
+    redirect_type: RedirectType,
+    /// Where visitors are sent, when the link's UTM parameters make it
+    /// differ from `target_url`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    redirect_url: Option<String>,
+    forward_query: bool,
+    path_forwarding: bool,
+    qr_landing: bool,
+    #[serde(skip_serializing_if = "devices::DeviceTargets::is_empty")]
+    device_targets: devices::DeviceTargets,
+    signed_only: bool,
+    pending_review: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    disabled_reason: Option<String>,
+    /// Target as submitted, when it was upgraded to HTTPS.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    original_url: Option<String>,
+    /// Known shortener the submitted target pointed at.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    chained_via: Option<String>,
+    title: Option<String>,
+    notes: Option<String>,
+    /// When the health check first found the target dead, while it still is.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target_dead_since: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    archived_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_clicks: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    not_before: Option<String>,
+    #[serde(skip_serializing_if = "UtmParams::is_empty")]
+    utm: UtmParams,
+}
+
+impl ResolveResp {
+    fn new(state: &AppState, link: LinkRow) -> Self {
+        let redirect_url = Some(link.redirect_target(devices::Device::Other, None, None)).filter(|url| *url != link.target_url);
+        Self {
+            short_url: state.short_url(&link.code),
+            expired: is_expired(link.expires_at.as_deref()),
+            redirect_url,
+            utm: link.utm(),
+            code: link.code,
+            target_url: link.target_url,
+            created_at: link.created_at,
+            expires_at: link.expires_at,
+            listed: link.listed,
+            redirect_mode: link.redirect_mode,
+            redirect_type: link.redirect_type,
+            forward_query: link.forward_query,
+            path_forwarding: link.path_forwarding,
+            qr_landing: link.qr_landing,
+            device_targets: devices::DeviceTargets {
+                ios: link.target_ios,
+                android: link.target_android,
+                desktop: link.target_desktop,
+            },
+            signed_only: link.signed_only,
+            pending_review: link.pending_review,
+            disabled_reason: link.disabled_reason,
+            original_url: link.original_url,
+            chained_via: link.chained_via,
+            title: link.title,
+            notes: link.notes,
+            target_dead_since: link.target_dead_since,
+            archived_at: link.archived_at,
+            max_clicks: link.max_clicks,
+            not_before: link.not_before,
+        }
+    }
+}
+
+/// What `redirect` would do for `code`, as JSON, without redirecting or
+/// recording a click. For backend services resolving codes programmatically.
+async fn resolve(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+) -> Result<Json<ResolveResp>, (StatusCode, String)> {
+    let Some(link) = fetch_link(&state, &code).await.map_err(internal)? else {
+        return Err((StatusCode::NOT_FOUND, "not found".to_string()));
+    };
+    Ok(Json(ResolveResp::new(&state, link)))
+}
+
+/// 404 page for admins: offers to create a link under the unknown code.
+fn claim_page(state: &AppState, code: &str) -> impl IntoResponse {
+    let page = format!(
+        r#"<!doctype html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8" />
+    <meta name="robots" content="noindex" />
+    <title>Claim /{code}</title>
+  </head>
+  <body>
+    <h1>/{code} is free</h1>
+    <form id="claim">
+      <input type="hidden" name="custom_code" value="{code}" />
+      <label>Target URL <input type="url" name="url" required placeholder="https://" /></label>
+      <button type="submit">Claim /{code}</button>
+    </form>
+    <p id="result"></p>
+    <script>
+      document.getElementById("claim").addEventListener("submit", async (e) => {{
+        e.preventDefault();
+        const form = new FormData(e.target);
+        const resp = await fetch("{shorten_url}", {{
+          method: "POST",
+          headers: {{ "Content-Type": "application/json" }},
+          body: JSON.stringify({{ url: form.get("url"), custom_code: form.get("custom_code") }}),
+        }});
+        const result = document.getElementById("result");
+        if (resp.ok) {{
+          const link = await resp.json();
+          result.textContent = "Created " + link.short_url;
+        }} else {{
+          result.textContent = await resp.text();
+        }}
+      }});
+    </script>
+  </body>
+</html>"#,
+        code = html_escape(code),
+        shorten_url = html_escape(&state.public_url("/api/shorten")),
+    );
+    (
+        [(header::CACHE_CONTROL, "no-store")],
+        axum::response::Html(page),
+    )
+}
+
+fn html_redirect(target: &str) -> impl IntoResponse {
+    // JSON string literal for the script; `<` is escaped so the target can't close the tag.
+    let js_target = serde_json::to_string(target)
+        .unwrap_or_else(|_| "\"\"".to_string())
+        .replace('<', "\\u003c");
+    let page = format!(
+        r#"<!doctype html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8" />
+    <meta http-equiv="refresh" content="0; url={href}" />
+    <meta name="robots" content="noindex" />
+    <title>Redirecting…</title>
+  </head>
+  <body>
+    <p>Redirecting to <a href="{href}">{href}</a>…</p>
+    <script>window.location.replace({js_target});</script>
+  </body>
+</html>"#,
+        href = html_escape(target),
+        js_target = js_target,
+    );
+    (
+        [(header::CACHE_CONTROL, "no-store")],
+        axum::response::Html(page),
+    )
+}
+
+/// Counts a redirect against the link's `max_clicks`, atomically, so
+/// concurrent visitors can't overshoot the limit. Returns whether the
+/// redirect may go ahead. The redirect that uses the last click raises
+/// [`notify::Event::LinkClickLimitReached`].
+async fn claim_click(state: &AppState, code: &str) -> Result<bool, sqlx::Error> {
+    let mut conn = state.pool.acquire().await?;
+    let Some(claimed) = claim_click_with(&mut conn, code).await? else {
+        return Ok(false);
+    };
+    claimed.notify(state);
+    Ok(true)
+}
+
+/// A click counted by [`claim_click_with`].
+struct ClaimedClick {
+    /// Set when this click used the last one allowed.
+    limit_reached: Option<notify::Event>,
+}
+
+impl ClaimedClick {
+    /// Raises the limit event, once the claim has committed.
+    fn notify(self, state: &AppState) {
+        if let Some(event) = self.limit_reached {
+            notify::spawn_link_event(state, event);
+        }
+    }
+}
+
+/// [`claim_click`] on any connection, e.g. inside a transaction. `None`
+/// when the link has no clicks left.
+async fn claim_click_with(conn: &mut sqlx::SqliteConnection, code: &str) -> Result<Option<ClaimedClick>, sqlx::Error> {
+    let claimed: Option<(String, Option<i64>, i64)> = sqlx::query_as(
+        "UPDATE urls SET click_count = click_count + 1 \
+         WHERE code = ? AND (max_clicks IS NULL OR click_count < max_clicks) \
+         RETURNING target_url, max_clicks, click_count",
+    )
+    .bind(code)
+    .fetch_optional(conn)
+    .await?;
+    Ok(claimed.map(|(target_url, max_clicks, click_count)| ClaimedClick {
+        limit_reached: max_clicks
+            .filter(|max| *max == click_count)
+            .map(|max_clicks| notify::Event::LinkClickLimitReached {
+                code: code.to_string(),
+                target_url,
+                max_clicks,
+            }),
+    }))
+}
 
-    let ip = client_ip_from_headers(headers)?;
-    geo_country_lookup(&ip).await
+/// Runs the enricher chain for a click and stores it. Failures are swallowed:
+/// analytics must never break a redirect.
+async fn record_click(state: &AppState, code: &str, headers: &HeaderMap, recipient: Option<&str>) {
+    let _ = record_click_at(state, code, headers, recipient, OffsetDateTime::now_utc()).await;
 }
 
-async fn redirect(
-    State(state): State<AppState>,
-    Path(code): Path<String>,
-    headers: HeaderMap,
-) -> impl IntoResponse {
-    let row: Option<(String, Option<String>)> =
-        sqlx::query_as("SELECT target_url, expires_at FROM urls WHERE code = ?")
-        .bind(&code)
-        .fetch_optional(&state.pool)
-        .await
-        .unwrap();
+/// [`record_click`] for a click that happened at `at`, reporting failures.
+/// The headers are the visitor's request headers; `recipient` comes from a
+/// verified signed URL.
+async fn record_click_at(
+    state: &AppState,
+    code: &str,
+    headers: &HeaderMap,
+    recipient: Option<&str>,
+    at: OffsetDateTime,
+) -> Result<(), sqlx::Error> {
+    let click = NewClick::enrich(state, code, headers, recipient, at).await;
+    let mut conn = state.pool.acquire().await?;
+    let id = click.insert(&mut conn).await?;
+    drop(conn);
+    click.publish(state, id).await
+}
 
-    if let Some((target, expires_at)) = row {
-        if is_expired(expires_at.as_deref()) {
-            return (StatusCode::GONE, "This link has expired").into_response();
-        }
+/// A click after the enricher chain, ready to store.
+struct NewClick {
+    code: String,
+    at: OffsetDateTime,
+    ip: String,
+    user_agent: Option<String>,
+    referer: Option<String>,
+    fields: enrich::ClickFields,
+    visitor_id: String,
+    recipient: Option<String>,
+    channel: channels::Channel,
+    parsed: Option<user_agents::ParsedUa>,
+    is_bot: bool,
+}
 
-        let ip_opt = client_ip_from_headers(&headers);
+impl NewClick {
+    /// Derives everything stored with a click from the visitor's request
+    /// headers. Touches no tables, so it can run before a transaction starts.
+    async fn enrich(
+        state: &AppState,
+        code: &str,
+        headers: &HeaderMap,
+        recipient: Option<&str>,
+        at: OffsetDateTime,
+    ) -> NewClick {
+        let ip_opt = state.client_ip(headers);
         let ip = ip_opt.clone().unwrap_or_else(|| "local".to_string());
 
         let ua = headers
@@ -660,35 +3325,132 @@ async fn redirect(
             .get(header::REFERER)
             .and_then(|v| v.to_str().ok())
             .map(|s| s.to_string());
+        let accept_language = headers
+            .get(header::ACCEPT_LANGUAGE)
+            .and_then(|v| v.to_str().ok());
 
-        let country = country_from_headers_or_ip(&headers).await;
-
-        let city = headers
-            .get("x-geo-city")
-            .or_else(|| headers.get("cf-ipcity"))
+        let fields = enrich::run_chain(&state.enrichers, code, headers, ip_opt.as_deref()).await;
+        let visitor_id = state
+            .fingerprint
+            .visitor_id(&ip, ua.as_deref(), accept_language, at);
+        let host = headers
+            .get(header::HOST)
             .and_then(|v| v.to_str().ok())
-            .map(|s| s.to_string());
+            .map(|h| h.split(':').next().unwrap_or(h));
+        let own_hosts: Vec<&str> = host.into_iter().chain(state.base_host.as_deref()).collect();
+        let channel = channels::classify(referer.as_deref(), &own_hosts);
+        let parsed = ua.as_deref().map(user_agents::parse);
+        NewClick {
+            code: code.to_string(),
+            at,
+            ip,
+            user_agent: ua,
+            referer,
+            fields,
+            visitor_id,
+            recipient: recipient.map(str::to_string),
+            channel,
+            parsed,
+            is_bot: parsed.is_some_and(|p| p.is_bot()),
+        }
+    }
 
-        let now = OffsetDateTime::now_utc()
-            .format(&time::format_description::well_known::Rfc3339)
-            .unwrap();
-        let _ = sqlx::query(
-            "INSERT INTO clicks (code, at, ip, user_agent, referer, country, city) \
-             VALUES (?, ?, ?, ?, ?, ?, ?)",
+    /// Inserts the `clicks` row and returns its id.
+    async fn insert(&self, conn: &mut sqlx::SqliteConnection) -> Result<i64, sqlx::Error> {
+        let extra = if self.fields.extra.is_empty() {
+            None
+        } else {
+            serde_json::to_string(&self.fields.extra).ok()
+        };
+        let inserted = sqlx::query(
+            "INSERT INTO clicks (code, at, ip, user_agent, referer, country, city, visitor_id, language, extra, \
+                                 recipient, channel, browser, os, device_type, is_bot) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
         )
-        .bind(&code)
-        .bind(now)
-        .bind(ip)
-        .bind(ua)
-        .bind(referer)
-        .bind(country)
-        .bind(city)
-        .execute(&state.pool)
-        .await;
-
-        Redirect::temporary(&target).into_response()
+        .bind(&self.code)
+        .bind(self.at.unix_timestamp())
+        .bind(&self.ip)
+        .bind(&self.user_agent)
+        .bind(&self.referer)
+        .bind(&self.fields.country)
+        .bind(&self.fields.city)
+        .bind(&self.visitor_id)
+        .bind(&self.fields.language)
+        .bind(extra)
+        .bind(&self.recipient)
+        .bind(self.channel.as_str())
+        .bind(self.parsed.map(|p| p.browser))
+        .bind(self.parsed.map(|p| p.os))
+        .bind(self.parsed.map(|p| p.device_type))
+        .bind(self.is_bot)
+        .execute(conn)
+        .await?;
+        Ok(inserted.last_insert_rowid())
+    }
+
+    /// Everything that follows a stored click: click webhooks, the live
+    /// feed, the unique-visitor sketch and threshold alerts.
+    async fn publish(self, state: &AppState, id: i64) -> Result<(), sqlx::Error> {
+        let code = self.code;
+        let day = self.at.to_offset(time::UtcOffset::UTC).date().to_string();
+        let referrer = self.referer.as_deref().and_then(channels::referrer_domain);
+        let at = unix_to_rfc3339(self.at.unix_timestamp());
+        let click = click_webhooks::ClickPayload {
+            event: "click",
+            id,
+            code: code.clone(),
+            at: at.clone(),
+            country: self.fields.country.clone(),
+            referer: self.referer,
+            bot: self.is_bot,
+        };
+        if let Err(e) = click_webhooks::dispatch(state, click).await {
+            tracing::warn!("click webhooks for {code} failed: {e}");
+        }
+        state.live.publish(live::ClickEvent {
+            id,
+            code: code.clone(),
+            at,
+            country: self.fields.country,
+            city: self.fields.city,
+            referrer,
+            channel: self.channel.as_str(),
+            browser: self.parsed.map(|p| p.browser),
+            os: self.parsed.map(|p| p.os),
+            device_type: self.parsed.map(|p| p.device_type),
+            bot: self.is_bot,
+        });
+        if !self.is_bot {
+            hll::record(state, &code, &day, &self.visitor_id).await?;
+        }
+        alerts::evaluate(state, &code).await
+    }
+}
+
+/// Picks the highest-weighted language from an Accept-Language header and
+/// reduces it to its primary subtag, e.g. `ro-RO,en;q=0.8` -> `ro`.
+fn primary_language(accept_language: &str) -> Option<String> {
+    let mut best: Option<(&str, f32)> = None;
+    for part in accept_language.split(',') {
+        let mut pieces = part.split(';');
+        let tag = pieces.next().unwrap_or("").trim();
+        let q = pieces
+            .find_map(|p| p.trim().strip_prefix("q="))
+            .and_then(|q| q.parse::<f32>().ok())
+            .unwrap_or(1.0);
+        if tag.is_empty() || tag == "*" || q <= 0.0 {
+            continue;
+        }
+        if best.is_none_or(|(_, best_q)| q > best_q) {
+            best = Some((tag, q));
+        }
+    }
+
+    let primary = best?.0.split('-').next()?.to_ascii_lowercase();
+    if (2..=3).contains(&primary.len()) && primary.chars().all(|c| c.is_ascii_alphabetic()) {
+        Some(primary)
     } else {
-        (StatusCode::NOT_FOUND, "Not found").into_response()
+        None
     }
 }
 
@@ -700,13 +3462,28 @@ fn is_expired(expires_at: Option<&str>) -> bool {
     OffsetDateTime::now_utc() >= exp
 }
 
+/// RFC3339 form of a unix timestamp, as click times are stored.
+pub(crate) fn unix_to_rfc3339(secs: i64) -> String {
+    OffsetDateTime::from_unix_timestamp(secs)
+        .ok()
+        .and_then(|at| at.format(&time::format_description::well_known::Rfc3339).ok())
+        .unwrap_or_default()
+}
+
+/// Whether a link's `not_before` is still in the future.
+fn is_scheduled(not_before: Option<&str>) -> bool {
+    not_before
+        .and_then(|start| OffsetDateTime::parse(start, &time::format_description::well_known::Rfc3339).ok())
+        .is_some_and(|start| OffsetDateTime::now_utc() < start)
+}
+
 fn country_from_headers(headers: &HeaderMap) -> Option<String> {
     let candidates = ["cf-ipcountry", "x-geo-country", "x-country"];
     for key in candidates {
         if let Some(v) = headers.get(key).and_then(|v| v.to_str().ok()) {
             let trimmed = v.trim();
             if !trimmed.is_empty() {
-                return Some(trimmed.to_string());
+                return Some(trimmed.to_ascii_uppercase());
             }
         }
     }
@@ -719,11 +3496,50 @@ struct StatsResp {
     target_url: String,
     created_at: String,
     expires_at: Option<String>,
+    utm: UtmParams,
+    redirect_type: RedirectType,
+    title: Option<String>,
+    notes: Option<String>,
 
+    /// Bots are left out of this and every breakdown unless
+    /// `?include_bots=true`.
     total_clicks: i64,
+    /// Clicks from crawlers and link unfurlers, counted or not.
+    bot_clicks: i64,
     unique_visitors: i64,
+    /// Times the interstitial was shown; visitors who continued are in
+    /// `total_clicks`.
+    interstitial_views: i64,
+    /// Scans of the QR landing page; visitors who continued are in
+    /// `total_clicks`.
+    qr_scans: i64,
     clicks_by_day: Vec<DailyStats>,
+    /// Only with `?granularity=hour`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    clicks_by_hour: Option<Vec<HourlyStats>>,
     top_countries: Vec<CountryStat>,
+    /// Clicks per continent, from every country counted, not just the top
+    /// ten.
+    top_continents: Vec<ContinentStat>,
+    /// Clicks from the European Union, and from the EEA (the EU plus
+    /// Iceland, Liechtenstein and Norway).
+    eu_clicks: i64,
+    eea_clicks: i64,
+    top_languages: Vec<LanguageStat>,
+    /// Clicks per browser and OS parsed from the User-Agent; see
+    /// [`user_agents`].
+    top_browsers: Vec<BrowserStat>,
+    top_os: Vec<OsStat>,
+    /// Clicks per `desktop`, `mobile`, `tablet` and `bot`.
+    device_types: Vec<DeviceTypeStat>,
+    /// Clicks per traffic channel (search, social, email, ...).
+    top_channels: Vec<ChannelStat>,
+    /// Clicks per referring domain, `www.` stripped; `domain: null` counts
+    /// clicks with no or an unparsable `Referer`.
+    top_referrers: Vec<ReferrerStat>,
+    /// Clicks per recipient: verified ones from signed URLs, opaque ones
+    /// from `?rid=`.
+    top_recipients: Vec<RecipientStat>,
     recent_clicks: Vec<RecentClick>,
 }
 
@@ -734,9 +3550,74 @@ struct DailyStats {
     unique_visitors: i64,
 }
 
+#[derive(Serialize)]
+struct HourlyStats {
+    /// Start of the hour, UTC.
+    hour: String,
+    clicks: i64,
+    /// Exact: the hour's clicks are still detailed.
+    unique_visitors: i64,
+}
+
 #[derive(Serialize)]
 struct CountryStat {
+    /// ISO 3166-1 alpha-2 code, as stored.
     country: String,
+    /// In the requested `?locale=`; absent for codes outside ISO 3166-1.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    flag: Option<String>,
+    clicks: i64,
+}
+
+#[derive(Serialize)]
+struct ContinentStat {
+    continent: countries::Continent,
+    /// In the requested `?locale=`.
+    name: &'static str,
+    clicks: i64,
+}
+
+#[derive(Serialize)]
+struct LanguageStat {
+    language: String,
+    clicks: i64,
+}
+
+#[derive(Serialize)]
+struct BrowserStat {
+    browser: String,
+    clicks: i64,
+}
+
+#[derive(Serialize)]
+struct OsStat {
+    os: String,
+    clicks: i64,
+}
+
+#[derive(Serialize)]
+struct DeviceTypeStat {
+    device_type: String,
+    clicks: i64,
+}
+
+#[derive(Serialize)]
+struct ChannelStat {
+    channel: channels::Channel,
+    clicks: i64,
+}
+
+#[derive(Serialize)]
+struct ReferrerStat {
+    domain: Option<String>,
+    clicks: i64,
+}
+
+#[derive(Serialize)]
+struct RecipientStat {
+    recipient: String,
     clicks: i64,
 }
 
@@ -747,47 +3628,243 @@ struct RecentClick {
     country: Option<String>,
     user_agent: Option<String>,
     referer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    extra: Option<BTreeMap<String, String>>,
+}
+
+/// Day, clicks, and either the exact unique count or that day's sketch.
+type DailyRow = (String, i64, Option<i64>, Option<Vec<u8>>);
+
+type RecentClickRow = (
+    i64,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+);
+
+/// Finest time bucket the stats should include.
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum Granularity {
+    #[default]
+    Day,
+    /// Adds `clicks_by_hour` for the last [`HOURLY_STATS_HOURS`].
+    Hour,
+}
+
+/// How far back `clicks_by_hour` goes.
+const HOURLY_STATS_HOURS: i64 = 72;
+
+#[derive(Deserialize, Default)]
+struct GranularityParams {
+    #[serde(default)]
+    granularity: Granularity,
+}
+
+#[derive(Deserialize, Default)]
+struct BotParams {
+    /// Count crawler and unfurler clicks too.
+    #[serde(default)]
+    include_bots: bool,
 }
 
 async fn stats(
     State(state): State<AppState>,
     Path(code): Path<String>,
+    Query(params): Query<ExactParams>,
+    Query(bots): Query<BotParams>,
+    Query(locale): Query<countries::LocaleParams>,
+    Query(granularity): Query<GranularityParams>,
 ) -> Result<Json<StatsResp>, (StatusCode, String)> {
-    let stats = query_stats(&state, &code).await?;
+    let exact = params.exact || state.exact_unique_counts;
+    let stats = query_stats(&state, &code, exact, bots.include_bots, locale.locale()?, granularity.granularity).await?;
     Ok(Json(stats))
 }
 
-async fn query_stats(state: &AppState, code: &str) -> Result<StatsResp, (StatusCode, String)> {
-    let url_row: Option<(String, String, Option<String>)> = sqlx::query_as(
-        "SELECT target_url, created_at, expires_at FROM urls WHERE code = ?",
+#[derive(Serialize)]
+struct ResetResp {
+    code: String,
+    archived_clicks: u64,
+}
+
+/// Moves the click history of `code` into `clicks_archive` and drops its
+/// visitor sketches, so stats start from zero. Admin only.
+async fn reset_stats(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<ResetResp>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let Some(link) = fetch_link(&state, &code).await.map_err(internal)? else {
+        return Err((StatusCode::NOT_FOUND, "not found".to_string()));
+    };
+    let code = link.code;
+
+    let _guard = state.sketch_locks.lock(&code).await;
+    let mut tx = state.pool.begin().await.map_err(internal)?;
+    let archived = archive_clicks(&mut tx, &code).await.map_err(internal)?;
+    clear_link_children(&mut tx, &code).await.map_err(internal)?;
+    sqlx::query("UPDATE urls SET click_count = 0 WHERE code = ?")
+        .bind(&code)
+        .execute(&mut *tx)
+        .await
+        .map_err(internal)?;
+    tx.commit().await.map_err(internal)?;
+
+    Ok(Json(ResetResp {
+        code,
+        archived_clicks: archived,
+    }))
+}
+
+#[derive(Deserialize)]
+struct DeleteParams {
+    /// Drop the link's clicks instead of archiving them.
+    #[serde(default)]
+    purge_clicks: bool,
+}
+
+/// Removes a link. Its clicks move to `clicks_archive` unless
+/// `?purge_clicks=true`. Needs the admin token or an API key.
+async fn delete_link(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+    Query(params): Query<DeleteParams>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, String)> {
+    api_keys::require_api_key(&state, &headers).await?;
+    let Some(link) = fetch_link(&state, &code).await.map_err(internal)? else {
+        return Err((StatusCode::NOT_FOUND, "not found".to_string()));
+    };
+
+    let _guard = state.sketch_locks.lock(&link.code).await;
+    let mut tx = state.pool.begin().await.map_err(internal)?;
+    if !params.purge_clicks {
+        archive_clicks(&mut tx, &link.code).await.map_err(internal)?;
+    }
+    delete_link_rows(&mut tx, &link.code).await.map_err(internal)?;
+    tx.commit().await.map_err(internal)?;
+    state.click_webhooks.invalidate().await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Archives a link: it stops redirecting (410) and leaves the default
+/// listings, but keeps its clicks. Needs the admin token or an API key.
+async fn archive_link(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, String)> {
+    api_keys::require_api_key(&state, &headers).await?;
+    let now = OffsetDateTime::now_utc()
+        .format(&time::format_description::well_known::Rfc3339)
+        .unwrap();
+    set_archived_at(&state, &code, Some(now)).await
+}
+
+/// Restores an archived link.
+async fn unarchive_link(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, String)> {
+    api_keys::require_api_key(&state, &headers).await?;
+    set_archived_at(&state, &code, None).await
+}
+
+/// Archiving an already archived link keeps the original timestamp.
+async fn set_archived_at(
+    state: &AppState,
+    code: &str,
+    archived_at: Option<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let Some(link) = fetch_link(state, code).await.map_err(internal)? else {
+        return Err((StatusCode::NOT_FOUND, "not found".to_string()));
+    };
+    sqlx::query(
+        "UPDATE urls SET archived_at = CASE WHEN ?1 IS NULL THEN NULL ELSE coalesce(archived_at, ?1) END \
+         WHERE code = ?2",
     )
-    .bind(code)
-    .fetch_optional(&state.pool)
+    .bind(archived_at)
+    .bind(&link.code)
+    .execute(&state.pool)
     .await
     .map_err(internal)?;
+    Ok(StatusCode::NO_CONTENT)
+}
 
-    let Some((target_url, created_at, expires_at)) = url_row else {
+async fn query_stats(
+    state: &AppState,
+    code: &str,
+    exact: bool,
+    include_bots: bool,
+    locale: countries::Locale,
+    granularity: Granularity,
+) -> Result<StatsResp, (StatusCode, String)> {
+    let Some(link) = fetch_link(state, code).await.map_err(internal)? else {
         return Err((StatusCode::NOT_FOUND, "not found".to_string()));
     };
+    let code = link.code.as_str();
+    // sketches never see bots, so counting them needs exact uniques
+    let exact = exact || include_bots;
+    let (human, rolled) = if include_bots { ("", "clicks") } else { (" AND NOT is_bot", "clicks - bots") };
 
-    let total_clicks: (i64,) = sqlx::query_as("SELECT count(*) FROM clicks WHERE code = ?")
+    let total_clicks: (i64,) = sqlx::query_as(&format!("SELECT {}", rollup::total_clicks_sql("?1", include_bots)))
         .bind(code)
         .fetch_one(&state.pool)
         .await
         .map_err(internal)?;
-
-    let unique_visitors: (i64,) = sqlx::query_as(
-        "SELECT count(DISTINCT ip) FROM clicks WHERE code = ? AND ip IS NOT NULL",
+    let (bot_clicks,): (i64,) = sqlx::query_as(
+        "SELECT (SELECT count(*) FROM clicks WHERE code = ?1 AND is_bot) + \
+                (SELECT coalesce(sum(bots), 0) FROM click_rollups WHERE code = ?1)",
     )
     .bind(code)
     .fetch_one(&state.pool)
     .await
     .map_err(internal)?;
 
-    let daily_rows: Vec<(String, i64, i64)> = sqlx::query_as(
-        "SELECT substr(at, 1, 10) as day, count(*) as clicks, count(DISTINCT ip) as unique_visitors \
-         FROM clicks WHERE code = ? GROUP BY day ORDER BY day DESC LIMIT 30",
-    )
+    let unique_sql = format!(
+        "SELECT count(DISTINCT visitor_id), NULL FROM clicks WHERE code = ? AND visitor_id IS NOT NULL{human}"
+    );
+    let unique_visitors: (Option<i64>, Option<Vec<u8>>) = if exact {
+        sqlx::query_as(&unique_sql)
+    } else {
+        sqlx::query_as(
+            "SELECT NULL, (SELECT registers FROM visitor_sketches WHERE code = ? AND day = '*')",
+        )
+    }
+    .bind(code)
+    .fetch_one(&state.pool)
+    .await
+    .map_err(internal)?;
+    let unique_visitors = unique_visitors
+        .0
+        .unwrap_or_else(|| hll::estimate_blob(unique_visitors.1.as_deref()));
+
+    let (interstitial_views,): (i64,) = sqlx::query_as("SELECT count(*) FROM interstitial_views WHERE code = ?")
+        .bind(code)
+        .fetch_one(&state.pool)
+        .await
+        .map_err(internal)?;
+    let (qr_scans,): (i64,) = sqlx::query_as("SELECT count(*) FROM qr_scans WHERE code = ?")
+        .bind(code)
+        .fetch_one(&state.pool)
+        .await
+        .map_err(internal)?;
+
+    // rolled-up days have no visitor ids, so they always use the day sketch
+    let exact_unique = if exact { "count(DISTINCT visitor_id)" } else { "NULL" };
+    let daily_rows: Vec<DailyRow> = sqlx::query_as(&format!(
+        "SELECT d.day, sum(d.clicks), max(d.unique_visitors), \
+                (SELECT s.registers FROM visitor_sketches s WHERE s.code = ?1 AND s.day = d.day) FROM \
+           (SELECT date(at, 'unixepoch') as day, count(*) as clicks, {exact_unique} as unique_visitors \
+            FROM clicks WHERE code = ?1{human} GROUP BY day \
+            UNION ALL \
+            SELECT day, sum({rolled}), NULL FROM click_rollups WHERE code = ?1 GROUP BY day) d \
+         GROUP BY d.day HAVING sum(d.clicks) > 0 ORDER BY d.day DESC LIMIT 30"
+    ))
     .bind(code)
     .fetch_all(&state.pool)
     .await
@@ -795,33 +3872,160 @@ async fn query_stats(state: &AppState, code: &str) -> Result<StatsResp, (StatusC
 
     let clicks_by_day = daily_rows
         .into_iter()
-        .map(|(day, clicks, unique_visitors)| DailyStats {
+        .map(|(day, clicks, exact_unique, registers)| DailyStats {
             day,
             clicks,
-            unique_visitors,
+            unique_visitors: exact_unique
+                .unwrap_or_else(|| hll::estimate_blob(registers.as_deref())),
         })
         .collect();
 
-    let country_rows: Vec<(String, i64)> = sqlx::query_as(
-        "SELECT country, count(*) as clicks FROM clicks \
-         WHERE code = ? AND country IS NOT NULL \
-         GROUP BY country ORDER BY clicks DESC LIMIT 10",
-    )
+    // rollups are per day, so hours come from detailed clicks only
+    let clicks_by_hour = if granularity == Granularity::Hour {
+        let since = OffsetDateTime::now_utc().unix_timestamp() - HOURLY_STATS_HOURS * 3600;
+        let rows: Vec<(String, i64, i64)> = sqlx::query_as(&format!(
+            "SELECT strftime('%Y-%m-%dT%H:00:00Z', at, 'unixepoch') as hour, count(*), count(DISTINCT visitor_id) \
+             FROM clicks WHERE code = ? AND at >= ?{human} GROUP BY hour ORDER BY hour DESC"
+        ))
+        .bind(code)
+        .bind(since)
+        .fetch_all(&state.pool)
+        .await
+        .map_err(internal)?;
+        Some(
+            rows.into_iter()
+                .map(|(hour, clicks, unique_visitors)| HourlyStats { hour, clicks, unique_visitors })
+                .collect(),
+        )
+    } else {
+        None
+    };
+
+    let country_rows: Vec<(String, i64)> = sqlx::query_as(&format!(
+        "SELECT country, sum(clicks) as clicks FROM \
+           (SELECT country, count(*) as clicks FROM clicks \
+            WHERE code = ?1 AND country IS NOT NULL{human} GROUP BY country \
+            UNION ALL \
+            SELECT country, sum({rolled}) FROM click_rollups \
+            WHERE code = ?1 AND country != '' GROUP BY country) \
+         GROUP BY country HAVING sum(clicks) > 0 ORDER BY clicks DESC, country"
+    ))
     .bind(code)
     .fetch_all(&state.pool)
     .await
     .map_err(internal)?;
 
+    let mut top_continents: Vec<ContinentStat> = Vec::new();
+    let (mut eu_clicks, mut eea_clicks) = (0, 0);
+    for (country, clicks) in &country_rows {
+        if countries::in_eu(country) {
+            eu_clicks += clicks;
+        }
+        if countries::in_eea(country) {
+            eea_clicks += clicks;
+        }
+        let Some(continent) = countries::Continent::of(country) else {
+            continue;
+        };
+        match top_continents.iter_mut().find(|c| c.continent == continent) {
+            Some(stat) => stat.clicks += clicks,
+            None => top_continents.push(ContinentStat {
+                continent,
+                name: continent.name(locale),
+                clicks: *clicks,
+            }),
+        }
+    }
+    top_continents.sort_by(|a, b| b.clicks.cmp(&a.clicks).then(a.name.cmp(b.name)));
+
     let top_countries = country_rows
         .into_iter()
-        .map(|(country, clicks)| CountryStat { country, clicks })
+        .take(10)
+        .map(|(country, clicks)| {
+            let countries::Country { code, name, flag } = countries::Country::new(&country, locale);
+            CountryStat { country: code, name, flag, clicks }
+        })
         .collect();
 
-    let recent_rows: Vec<(String, Option<String>, Option<String>, Option<String>, Option<String>)> =
-        sqlx::query_as(
-            "SELECT at, ip, country, user_agent, referer \
-             FROM clicks WHERE code = ? ORDER BY at DESC LIMIT 25",
-        )
+    let language_rows: Vec<(String, i64)> = sqlx::query_as(&format!(
+        "SELECT language, count(*) as clicks FROM clicks \
+         WHERE code = ? AND language IS NOT NULL{human} \
+         GROUP BY language ORDER BY clicks DESC LIMIT 10"
+    ))
+    .bind(code)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(internal)?;
+
+    let top_languages = language_rows
+        .into_iter()
+        .map(|(language, clicks)| LanguageStat { language, clicks })
+        .collect();
+
+    // browser, os and device_type are fixed column names, never user input
+    let breakdown = |column: &'static str| async move {
+        sqlx::query_as::<_, (String, i64)>(&format!(
+            "SELECT {column}, count(*) as clicks FROM clicks \
+             WHERE code = ? AND {column} IS NOT NULL{human} \
+             GROUP BY {column} ORDER BY clicks DESC, {column} LIMIT 10"
+        ))
+        .bind(code)
+        .fetch_all(&state.pool)
+        .await
+    };
+    let top_browsers = breakdown("browser")
+        .await
+        .map_err(internal)?
+        .into_iter()
+        .map(|(browser, clicks)| BrowserStat { browser, clicks })
+        .collect();
+    let top_os = breakdown("os")
+        .await
+        .map_err(internal)?
+        .into_iter()
+        .map(|(os, clicks)| OsStat { os, clicks })
+        .collect();
+    let device_types = breakdown("device_type")
+        .await
+        .map_err(internal)?
+        .into_iter()
+        .map(|(device_type, clicks)| DeviceTypeStat { device_type, clicks })
+        .collect();
+
+    let top_channels = channels::breakdown(state, code, include_bots)
+        .await
+        .map_err(internal)?
+        .into_iter()
+        .map(|(channel, clicks)| ChannelStat { channel, clicks })
+        .collect();
+
+    let top_referrers = channels::domain_breakdown(state, code, include_bots, 10)
+        .await
+        .map_err(internal)?
+        .into_iter()
+        .map(|(domain, clicks)| ReferrerStat { domain, clicks })
+        .collect();
+
+    let recipient_rows: Vec<(String, i64)> = sqlx::query_as(&format!(
+        "SELECT recipient, count(*) as clicks FROM clicks \
+         WHERE code = ? AND recipient IS NOT NULL{human} \
+         GROUP BY recipient ORDER BY clicks DESC LIMIT 50"
+    ))
+    .bind(code)
+    .fetch_all(&state.pool)
+    .await
+    .map_err(internal)?;
+
+    let top_recipients = recipient_rows
+        .into_iter()
+        .map(|(recipient, clicks)| RecipientStat { recipient, clicks })
+        .collect();
+
+    let recent_rows: Vec<RecentClickRow> =
+        sqlx::query_as(&format!(
+            "SELECT at, ip, country, user_agent, referer, extra \
+             FROM clicks WHERE code = ?{human} ORDER BY at DESC, id DESC LIMIT 25"
+        ))
         .bind(code)
         .fetch_all(&state.pool)
         .await
@@ -829,28 +4033,154 @@ async fn query_stats(state: &AppState, code: &str) -> Result<StatsResp, (StatusC
 
     let recent_clicks = recent_rows
         .into_iter()
-        .map(|(at, ip, country, user_agent, referer)| RecentClick {
-            at,
+        .map(|(at, ip, country, user_agent, referer, extra)| RecentClick {
+            at: unix_to_rfc3339(at),
             ip,
             country,
             user_agent,
             referer,
+            extra: extra.and_then(|e| serde_json::from_str(&e).ok()),
         })
         .collect();
 
     Ok(StatsResp {
-        code: code.to_string(),
-        target_url,
-        created_at,
-        expires_at,
+        utm: link.utm(),
+        code: link.code,
+        target_url: link.target_url,
+        created_at: link.created_at,
+        expires_at: link.expires_at,
+        redirect_type: link.redirect_type,
+        title: link.title,
+        notes: link.notes,
         total_clicks: total_clicks.0,
-        unique_visitors: unique_visitors.0,
+        bot_clicks,
+        unique_visitors,
+        interstitial_views,
+        qr_scans,
         clicks_by_day,
+        clicks_by_hour,
         top_countries,
+        top_continents,
+        eu_clicks,
+        eea_clicks,
+        top_languages,
+        top_browsers,
+        top_os,
+        device_types,
+        top_channels,
+        top_referrers,
+        top_recipients,
         recent_clicks,
     })
 }
 
+/// Cookie set by `/admin/login` so browsers can use admin-only pages.
+const ADMIN_COOKIE: &str = "admin_token";
+
+/// Admin endpoints take `Authorization: Bearer <admin token>` (or the login
+/// cookie) and answer 404 when no token is configured.
+fn require_admin(state: &AppState, headers: &HeaderMap) -> Result<(), (StatusCode, String)> {
+    if state.admin_token.is_none() {
+        return Err((StatusCode::NOT_FOUND, "admin API disabled".to_string()));
+    }
+    if is_admin(state, headers) {
+        Ok(())
+    } else {
+        Err((StatusCode::UNAUTHORIZED, "admin token required".to_string()))
+    }
+}
+
+fn is_admin(state: &AppState, headers: &HeaderMap) -> bool {
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    let cookie = headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .find_map(|pair| pair.trim().strip_prefix(ADMIN_COOKIE)?.strip_prefix('='));
+    bearer
+        .or(cookie)
+        .is_some_and(|given| token_matches(state, given))
+}
+
+fn token_matches(state: &AppState, given: &str) -> bool {
+    let Some(expected) = state.admin_token.as_deref() else {
+        return false;
+    };
+    // compare digests so response timing says nothing about the token
+    Sha256::digest(given.as_bytes()) == Sha256::digest(expected.as_bytes())
+}
+
+#[derive(Deserialize)]
+struct LoginForm {
+    token: String,
+}
+
+/// Built-in admin login page; see [`page_templates::Page::Login`] for the
+/// placeholders.
+const LOGIN_PAGE: &str = r#"<!doctype html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8" />
+    <meta name="robots" content="noindex" />
+    <title>Admin login</title>
+  </head>
+  <body>
+    <h1>Admin login</h1>
+    {{error}}
+    <form method="post" action="{{action}}">
+      <label>Admin token <input type="password" name="token" required /></label>
+      <button type="submit">Log in</button>
+    </form>
+  </body>
+</html>"#;
+
+fn login_page(state: &AppState, error: Option<&str>) -> axum::response::Html<String> {
+    let error = error
+        .map(|e| format!("<p>{}</p>", html_escape(e)))
+        .unwrap_or_default();
+    axum::response::Html(page_templates::render(
+        state.page_templates.template(page_templates::Page::Login, LOGIN_PAGE),
+        &[("error", &error), ("action", &html_escape(&state.public_url("/admin/login")))],
+    ))
+}
+
+async fn admin_login_form(
+    State(state): State<AppState>,
+) -> Result<axum::response::Html<String>, (StatusCode, String)> {
+    if state.admin_token.is_none() {
+        return Err((StatusCode::NOT_FOUND, "admin API disabled".to_string()));
+    }
+    Ok(login_page(&state, None))
+}
+
+/// Exchanges the admin token for an HttpOnly cookie scoped to the app.
+async fn admin_login(
+    State(state): State<AppState>,
+    axum::Form(form): axum::Form<LoginForm>,
+) -> axum::response::Response {
+    if state.admin_token.is_none() {
+        return (StatusCode::NOT_FOUND, "admin API disabled").into_response();
+    }
+    if !token_matches(&state, &form.token) {
+        return (StatusCode::UNAUTHORIZED, login_page(&state, Some("Wrong token"))).into_response();
+    }
+    let path = if state.prefix().is_empty() { "/" } else { state.prefix() };
+    let secure = if state.base_url.starts_with("https://") { "; Secure" } else { "" };
+    let cookie = format!(
+        "{ADMIN_COOKIE}={}; Path={path}; HttpOnly; SameSite=Strict{secure}",
+        form.token
+    );
+    (
+        [(header::SET_COOKIE, cookie)],
+        Redirect::to(&state.public_url("/")),
+    )
+        .into_response()
+}
+
 fn internal<E: std::fmt::Display>(e: E) -> (StatusCode, String) {
     (
         StatusCode::INTERNAL_SERVER_ERROR,
@@ -0,0 +1,103 @@
+//! Live click feed over Server-Sent Events: `GET /api/links/:code/stream`
+//! for one link and `GET /api/stream` for all of them. Every recorded click
+//! is published to an in-process broadcast channel; subscribers that fall
+//! behind get a `lagged` event with the number of clicks they missed. Only
+//! this instance's clicks are seen, and nothing is replayed on connect.
+//!
+//! Events carry what the public stats show: no IP, visitor id or full
+//! referrer URL.
+
+use std::{convert::Infallible, sync::Arc};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures_util::{stream, Stream};
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::{fetch_link, internal, AppState};
+
+/// Clicks buffered per subscriber before it lags.
+const CAPACITY: usize = 1024;
+
+#[derive(Serialize, Debug)]
+pub(crate) struct ClickEvent {
+    pub(crate) id: i64,
+    pub(crate) code: String,
+    pub(crate) at: String,
+    pub(crate) country: Option<String>,
+    pub(crate) city: Option<String>,
+    /// Referrer domain; see `channels::host_domain`.
+    pub(crate) referrer: Option<String>,
+    pub(crate) channel: &'static str,
+    pub(crate) browser: Option<&'static str>,
+    pub(crate) os: Option<&'static str>,
+    pub(crate) device_type: Option<&'static str>,
+    pub(crate) bot: bool,
+}
+
+/// Publishes clicks to the open streams.
+#[derive(Clone)]
+pub(crate) struct LiveClicks {
+    sender: broadcast::Sender<Arc<ClickEvent>>,
+}
+
+impl Default for LiveClicks {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::channel(CAPACITY).0,
+        }
+    }
+}
+
+impl LiveClicks {
+    /// Sends `event` to every subscriber; a no-op without any.
+    pub(crate) fn publish(&self, event: ClickEvent) {
+        let _ = self.sender.send(Arc::new(event));
+    }
+}
+
+/// The SSE stream of clicks on `code`, or on every link when `None`.
+fn events(state: &AppState, code: Option<String>) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let receiver = state.live.sender.subscribe();
+    let events = stream::unfold(receiver, move |mut receiver| {
+        let code = code.clone();
+        async move {
+            loop {
+                let event = match receiver.recv().await {
+                    Ok(click) if code.as_ref().is_some_and(|c| *c != click.code) => continue,
+                    Ok(click) => Event::default()
+                        .event("click")
+                        .id(click.id.to_string())
+                        .json_data(&*click)
+                        .unwrap_or_default(),
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        Event::default().event("lagged").data(missed.to_string())
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                };
+                return Some((Ok(event), receiver));
+            }
+        }
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// `GET /api/links/:code/stream`: clicks on one link (aliases resolve to it).
+pub(crate) async fn link_stream(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, (StatusCode, String)> {
+    let Some(link) = fetch_link(&state, &code).await.map_err(internal)? else {
+        return Err((StatusCode::NOT_FOUND, "not found".to_string()));
+    };
+    Ok(events(&state, Some(link.code)))
+}
+
+/// `GET /api/stream`: clicks on every link.
+pub(crate) async fn global_stream(State(state): State<AppState>) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    events(&state, None)
+}
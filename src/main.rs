@@ -1,9 +1,17 @@
+use axum::http::HeaderName;
 use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite};
-use std::{net::SocketAddr, time::Duration};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use url_shortener::{router, AppState, RateLimiter};
+use url_shortener::{
+    backfill_sketches, backfill_user_agents, check_targets, compact_clicks, database_file, detect_anomalies, list_snapshots, load_settings, migration_status, notify_expired_links, prune_snapshots,
+    purge_old_clicks, restore_snapshot, retry_webhooks, router, run_migrations, self_check, snapshot_database, seed_demo, seed_synthetic, AppState, BlockCodes, CheckStatus, CodeGenerator, FingerprintConfig, Interstitial, PageTemplates,
+    RandomCodes, RequestLimits, SecurityHeaders, SeedOptions, SequentialCodes, WebhookFormat, WebhookNotifier, WordlistCodes,
+    warn_expiring_links,
+};
+#[cfg(feature = "object-store")]
+use url_shortener::{upload_snapshot, ObjectStore};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -14,25 +22,419 @@ async fn main() -> anyhow::Result<()> {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
-    let db_url = std::env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite://dev.db".to_string());
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = args.first().map(String::as_str).filter(|a| !a.starts_with("--"));
+
+    // --demo: throwaway in-memory database with sample data
+    let demo = args.iter().any(|a| a == "--demo");
+
+    let db_url = if demo {
+        "sqlite::memory:".to_string()
+    } else {
+        std::env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite://dev.db".to_string())
+    };
     let base_url = std::env::var("BASE_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
+    let path_prefix = std::env::var("PATH_PREFIX").unwrap_or_default();
     let listen = std::env::var("LISTEN_ADDR").unwrap_or_else(|_| "127.0.0.1:3000".to_string());
-    let pool: Pool<Sqlite> = SqlitePoolOptions::new()
+    // BACKUP_DIR=backups: snapshot the database every BACKUP_INTERVAL_MINS
+    // (default 60), keeping the newest BACKUP_KEEP (default 24)
+    let backup_dir = std::env::var("BACKUP_DIR").ok().filter(|d| !d.is_empty()).map(PathBuf::from);
+
+    // `restore <snapshot>|latest`: copy a snapshot over the database file,
+    // with the server stopped, and exit
+    if command == Some("restore") {
+        let db_path = database_file(&db_url)
+            .ok_or_else(|| anyhow::anyhow!("DATABASE_URL is not a database file: {db_url}"))?;
+        let snapshot = match args.get(1).map(String::as_str) {
+            Some("latest") => {
+                let dir = backup_dir
+                    .as_deref()
+                    .ok_or_else(|| anyhow::anyhow!("restore latest needs BACKUP_DIR"))?;
+                list_snapshots(dir)?
+                    .pop()
+                    .ok_or_else(|| anyhow::anyhow!("no snapshots in {}", dir.display()))?
+            }
+            Some(path) => PathBuf::from(path),
+            None => anyhow::bail!("usage: restore <snapshot file>|latest"),
+        };
+        restore_snapshot(&snapshot, &db_path)?;
+        tracing::info!("restored {} to {}", snapshot.display(), db_path.display());
+        return Ok(());
+    }
+    // every connection to sqlite::memory: is a separate database, so demo
+    // mode keeps exactly one connection alive for the whole run
+    let pool_options = if demo {
+        SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+    } else {
+        SqlitePoolOptions::new().max_connections(5)
+    };
+    let pool: Pool<Sqlite> = pool_options
         .acquire_timeout(Duration::from_secs(5))
-        .max_connections(5)
         .connect(&db_url)
         .await?;
 
-    // run migrations
-    sqlx::migrate!("./migrations").run(&pool).await?;
+    // BACKUP_S3_BUCKET: also upload every snapshot to S3-compatible storage
+    #[cfg(feature = "object-store")]
+    let object_store = object_store_from_env()?.map(Arc::new);
+
+    // `backup`: write one snapshot into BACKUP_DIR (and the bucket) and exit
+    if command == Some("backup") {
+        let dir = backup_dir.as_deref().ok_or_else(|| anyhow::anyhow!("backup needs BACKUP_DIR"))?;
+        let path = snapshot_database(&pool, dir).await?;
+        tracing::info!("wrote {}", path.display());
+        #[cfg(feature = "object-store")]
+        if let Some(store) = &object_store {
+            let keep = env_parse::<usize>("BACKUP_KEEP")?.unwrap_or(24).max(1);
+            upload_snapshot(store, &path, keep).await?;
+            tracing::info!("uploaded {}", path.display());
+        }
+        return Ok(());
+    }
+
+    // `migrate up` applies pending migrations, `migrate status` lists them;
+    // both exit without starting the server
+    if command == Some("migrate") {
+        return migrate(&pool, args.get(1).map(String::as_str)).await;
+    }
+    // AUTO_MIGRATE=false when the schema is managed with `migrate up`: the
+    // server then refuses to start on pending migrations
+    if demo || !std::env::var("AUTO_MIGRATE").is_ok_and(|v| v == "false") {
+        let applied = run_migrations(&pool).await?;
+        if applied > 0 {
+            tracing::info!("applied {applied} migrations");
+        }
+    } else {
+        let pending: Vec<_> = migration_status(&pool)
+            .await?
+            .into_iter()
+            .filter(|m| !m.is_applied())
+            .collect();
+        if let Some(first) = pending.first() {
+            anyhow::bail!(
+                "{} pending migrations, starting with {} ({}); run `url-shortener migrate up`",
+                pending.len(),
+                first.version,
+                first.description
+            );
+        }
+    }
+
+    // visitor fingerprint: VISITOR_SIGNALS=ip,ua,lang, VISITOR_SALT_ROTATION_HOURS=24
+    let mut fingerprint = FingerprintConfig::default();
+    match std::env::var("VISITOR_SECRET") {
+        Ok(secret) if !secret.is_empty() => fingerprint.secret = secret,
+        _ if demo => {}
+        _ => tracing::warn!("VISITOR_SECRET is not set: visitor ids change on every restart"),
+    }
+    let signing_secret = std::env::var("SIGNING_SECRET").unwrap_or_default();
+    if signing_secret.is_empty() && !demo {
+        tracing::warn!("SIGNING_SECRET is not set: signed links stop working on every restart");
+    }
+    if let Ok(signals) = std::env::var("VISITOR_SIGNALS") {
+        fingerprint = fingerprint.with_signals(&signals).map_err(anyhow::Error::msg)?;
+    }
+    if let Ok(hours) = std::env::var("VISITOR_SALT_ROTATION_HOURS") {
+        fingerprint.salt_rotation = Some(Duration::from_secs(hours.parse::<u64>()? * 3600));
+    }
 
     // shared state
-    let state = AppState {
-        pool,
-        base_url,
-        rate_limiter: RateLimiter::new(10, Duration::from_secs(60)),
+    let mut builder = AppState::builder(pool);
+    // SECURITY_HEADERS=off when a reverse proxy already sets them
+    if std::env::var("SECURITY_HEADERS").is_ok_and(|v| v == "off") {
+        builder = builder.security_headers(SecurityHeaders::disabled());
+    }
+    // REQUEST_TIMEOUT_SECS / MAX_IN_FLIGHT, 0 disables the limit
+    let mut limits = RequestLimits::default();
+    if let Some(secs) = env_parse::<u64>("REQUEST_TIMEOUT_SECS")? {
+        limits.timeout = (secs > 0).then(|| Duration::from_secs(secs));
+    }
+    if let Some(max) = env_parse::<usize>("MAX_IN_FLIGHT")? {
+        limits.max_in_flight = (max > 0).then_some(max);
+    }
+
+    // CLIENT_IP_HEADERS=cf-connecting-ip: visitor IP headers set by a trusted
+    // proxy, tried before X-Forwarded-For
+    if let Ok(names) = std::env::var("CLIENT_IP_HEADERS") {
+        let names = names
+            .split(',')
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .map(|n| {
+                HeaderName::from_bytes(n.to_ascii_lowercase().as_bytes())
+                    .map_err(|_| anyhow::anyhow!("invalid CLIENT_IP_HEADERS entry: {n}"))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        builder = builder.client_ip_headers(names);
+    }
+
+    // INTERSTITIAL_HTML_FILE: snippet shown on the interstitial of links in
+    // interstitial mode, for INTERSTITIAL_SECONDS (default 5)
+    let mut interstitial = Interstitial::default();
+    if let Ok(path) = std::env::var("INTERSTITIAL_HTML_FILE") {
+        interstitial.html = std::fs::read_to_string(&path)
+            .map_err(|e| anyhow::anyhow!("reading INTERSTITIAL_HTML_FILE {path}: {e}"))?;
+    }
+    if let Some(secs) = env_parse::<u64>("INTERSTITIAL_SECONDS")? {
+        interstitial.countdown = Duration::from_secs(secs);
+    }
+    builder = builder.interstitial(interstitial);
+
+    // TEMPLATE_DIR: operator versions of the HTML pages (not_found.html,
+    // layout.html, ...); missing files keep the built-in pages
+    if let Some(dir) = std::env::var("TEMPLATE_DIR").ok().filter(|d| !d.is_empty()) {
+        let templates = PageTemplates::load(std::path::Path::new(&dir))
+            .map_err(|e| anyhow::anyhow!("loading TEMPLATE_DIR {dir}: {e}"))?;
+        tracing::info!(dir = %dir, pages = ?templates.overridden(), "loaded page templates");
+        builder = builder.page_templates(templates);
+    }
+
+    // CODE_GENERATOR=random (default), sequential or words. Random codes take
+    // CODE_LENGTH (default 7) and CODE_ALPHABET: alphanumeric (default),
+    // lowercase, unambiguous, or the characters to use
+    let code_generator: Arc<dyn CodeGenerator> = match std::env::var("CODE_GENERATOR").as_deref() {
+        Err(_) | Ok("random") => {
+            let alphabet = match std::env::var("CODE_ALPHABET").as_deref() {
+                Err(_) | Ok("alphanumeric") => RandomCodes::ALPHANUMERIC.to_string(),
+                Ok("lowercase") => RandomCodes::LOWERCASE.to_string(),
+                Ok("unambiguous") => RandomCodes::UNAMBIGUOUS.to_string(),
+                Ok(chars) => chars.to_string(),
+            };
+            let length = env_parse::<usize>("CODE_LENGTH")?.unwrap_or(7);
+            Arc::new(RandomCodes::new(length, &alphabet).map_err(anyhow::Error::msg)?)
+        }
+        // CODE_SEQUENCE_OFFSET=238328 starts at four characters;
+        // CODE_SEQUENCE_KEY scrambles the order; CODE_BLOCK_SIZE=100 has each
+        // instance reserve that many codes at a time
+        Ok("sequential") => {
+            let mut codes = SequentialCodes::default()
+                .offset(env_parse::<u64>("CODE_SEQUENCE_OFFSET")?.unwrap_or(0));
+            if let Some(key) = env_parse::<u64>("CODE_SEQUENCE_KEY")? {
+                codes = codes.obfuscated(key);
+            }
+            match env_parse::<u64>("CODE_BLOCK_SIZE")? {
+                Some(size) if size > 1 => Arc::new(BlockCodes::new(codes, size)),
+                _ => Arc::new(codes),
+            }
+        }
+        Ok("words") => Arc::new(WordlistCodes::default()),
+        Ok(other) => anyhow::bail!("unknown CODE_GENERATOR: {other}"),
     };
 
+    // alerts, anomalies, held links, expiry warnings and (with the
+    // link_events setting) link lifecycle events also go to
+    // NOTIFY_WEBHOOK_URL (JSON) and NOTIFY_SLACK_URL (Slack incoming webhook)
+    for (var, format) in [
+        ("NOTIFY_WEBHOOK_URL", WebhookFormat::Json),
+        ("NOTIFY_SLACK_URL", WebhookFormat::Slack),
+    ] {
+        if let Ok(url) = std::env::var(var) {
+            builder = builder.notifier(Arc::new(WebhookNotifier::new(url, format)));
+        }
+    }
+
+    let state = builder
+        .code_generator(code_generator)
+        .limits(limits)
+        .base_url(base_url)
+        .path_prefix(path_prefix)
+        .rate_limit(10, Duration::from_secs(60))
+        .fingerprint(fingerprint)
+        .geo(std::env::var("GEO_LOOKUP").map(|v| v != "false").unwrap_or(true))
+        .demo(demo)
+        .signing_secret(signing_secret)
+        .go_links(std::env::var("GO_LINKS").is_ok_and(|v| v == "true"))
+        .admin_token(std::env::var("ADMIN_TOKEN").unwrap_or_default())
+        .exact_unique_counts(std::env::var("EXACT_UNIQUE_COUNTS").is_ok_and(|v| v == "true"))
+        .read_only(std::env::var("READ_ONLY").is_ok_and(|v| v == "true"))
+        .build();
+
+    // policy changed through /api/admin/settings overrides the defaults above
+    load_settings(&state).await?;
+
+    // SELF_CHECK=strict refuses to start when a configuration check fails,
+    // off skips the checks; by default failures are only logged
+    let self_check_mode = std::env::var("SELF_CHECK").unwrap_or_default();
+    let startup_check = if self_check_mode == "off" {
+        None
+    } else {
+        let report = self_check(&state, false).await;
+        report.checks.iter().for_each(|c| c.log());
+        if !report.ok && self_check_mode == "strict" {
+            anyhow::bail!("self-check failed; see the errors above");
+        }
+        Some(report)
+    };
+
+    // clicks recorded before visitor sketches existed
+    if state.is_read_only() {
+        tracing::warn!("read-only mode: writes are refused until it is turned off");
+    } else {
+        let parsed = backfill_user_agents(&state.pool).await?;
+        if parsed > 0 {
+            tracing::info!("parsed the user agents of {parsed} older clicks");
+        }
+        let backfilled = backfill_sketches(&state).await?;
+        if backfilled > 0 {
+            tracing::info!("built visitor sketches for {backfilled} links");
+        }
+    }
+
+    // `seed [--links N] [--days N] [--max-clicks N]`: fill the database with
+    // synthetic links and clicks, then exit
+    if command == Some("seed") {
+        let defaults = SeedOptions::default();
+        let options = SeedOptions {
+            links: flag_value(&args, "--links")?.unwrap_or(defaults.links),
+            days: flag_value(&args, "--days")?.unwrap_or(defaults.days),
+            max_clicks_per_link: flag_value(&args, "--max-clicks")?
+                .unwrap_or(defaults.max_clicks_per_link),
+        };
+        let report = seed_synthetic(&state, &options).await?;
+        tracing::info!("seeded {} links with {} clicks", report.links, report.clicks);
+        return Ok(());
+    }
+    if let Some(other) = command {
+        anyhow::bail!("unknown command: {other}");
+    }
+
+    if demo {
+        seed_demo(&state).await?;
+        tracing::info!("demo mode: seeded sample links into an in-memory database");
+    }
+
+    // hourly click compaction, retention purge and expiry warnings; periods
+    // come from live settings, and compaction runs first so old clicks are
+    // rolled up
+    let purge_state = state.clone();
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(Duration::from_secs(3600));
+        loop {
+            tick.tick().await;
+            match compact_clicks(&purge_state).await {
+                Ok(0) => {}
+                Ok(n) => tracing::info!("rolled up {n} clicks past the detail period"),
+                Err(e) => tracing::warn!("click compaction failed: {e}"),
+            }
+            match purge_old_clicks(&purge_state).await {
+                Ok(0) => {}
+                Ok(n) => tracing::info!("purged {n} clicks past retention"),
+                Err(e) => tracing::warn!("click retention purge failed: {e}"),
+            }
+            if let Err(e) = warn_expiring_links(&purge_state).await {
+                tracing::warn!("expiry warnings failed: {e}");
+            }
+        }
+    });
+
+    // target health checks; interval and on/off come from live settings
+    let health_state = state.clone();
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(Duration::from_secs(300));
+        loop {
+            tick.tick().await;
+            match check_targets(&health_state).await {
+                Ok(report) if report.dead > 0 => {
+                    tracing::info!("health check: {} of {} targets dead", report.dead, report.checked)
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("target health check failed: {e}"),
+            }
+        }
+    });
+
+    // click anomaly detection; on/off comes from live settings
+    let anomaly_state = state.clone();
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(Duration::from_secs(600));
+        loop {
+            tick.tick().await;
+            match detect_anomalies(&anomaly_state).await {
+                Ok(0) => {}
+                Ok(n) => tracing::warn!("flagged {n} traffic anomalies"),
+                Err(e) => tracing::warn!("anomaly detection failed: {e}"),
+            }
+        }
+    });
+
+    // link expiry notifications; on/off comes from live settings
+    let expiry_state = state.clone();
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(Duration::from_secs(60));
+        loop {
+            tick.tick().await;
+            if let Err(e) = notify_expired_links(&expiry_state).await {
+                tracing::warn!("expiry notifications failed: {e}");
+            }
+        }
+    });
+
+    // retries of failed webhook notifications, with backoff per delivery
+    let webhook_state = state.clone();
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(Duration::from_secs(30));
+        loop {
+            tick.tick().await;
+            match retry_webhooks(&webhook_state).await {
+                Ok(report) if report.delivered + report.dead > 0 => tracing::info!(
+                    "webhook retries: {} delivered, {} dead-lettered",
+                    report.delivered,
+                    report.dead
+                ),
+                Ok(_) => {}
+                Err(e) => tracing::warn!("webhook retries failed: {e}"),
+            }
+        }
+    });
+
+    if let (Some(dir), false) = (backup_dir, demo) {
+        let interval = env_parse::<u64>("BACKUP_INTERVAL_MINS")?.unwrap_or(60).max(1);
+        let keep = env_parse::<usize>("BACKUP_KEEP")?.unwrap_or(24).max(1);
+        let backup_pool = state.pool.clone();
+        #[cfg(feature = "object-store")]
+        let backup_store = object_store.clone();
+        tokio::spawn(async move {
+            let mut tick = tokio::time::interval(Duration::from_secs(interval * 60));
+            loop {
+                tick.tick().await;
+                let path = match snapshot_database(&backup_pool, &dir).await {
+                    Ok(path) => path,
+                    Err(e) => {
+                        tracing::warn!("database snapshot failed: {e}");
+                        continue;
+                    }
+                };
+                tracing::info!("wrote {}", path.display());
+                #[cfg(feature = "object-store")]
+                if let Some(store) = &backup_store {
+                    if let Err(e) = upload_snapshot(store, &path, keep).await {
+                        tracing::warn!("uploading {} failed: {e}", path.display());
+                    }
+                }
+                if let Err(e) = prune_snapshots(&dir, keep) {
+                    tracing::warn!("pruning snapshots failed: {e}");
+                }
+            }
+        });
+    }
+
+    // network checks need the server listening: run them once it is, and
+    // log the ones skipped at startup
+    if let Some(startup) = startup_check {
+        let check_state = state.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            let report = self_check(&check_state, true).await;
+            for (before, now) in startup.checks.iter().zip(&report.checks) {
+                if before.status == CheckStatus::Skipped {
+                    now.log();
+                }
+            }
+        });
+    }
+
     let app = router(state).layer(TraceLayer::new_for_http());
 
     let addr: SocketAddr = listen.parse()?;
@@ -44,3 +446,71 @@ async fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+async fn migrate(pool: &Pool<Sqlite>, action: Option<&str>) -> anyhow::Result<()> {
+    match action {
+        Some("up") => {
+            let applied = run_migrations(pool).await?;
+            tracing::info!("applied {applied} migrations");
+        }
+        Some("status") | None => {
+            for m in migration_status(pool).await? {
+                let state = match &m.applied_at {
+                    _ if m.checksum_mismatch => "changed since applied",
+                    Some(at) => at.as_str(),
+                    None => "pending",
+                };
+                println!("{:>16}  {:<32}  {state}", m.version, m.description);
+            }
+        }
+        Some(other) => anyhow::bail!("unknown migrate action: {other} (use up or status)"),
+    }
+    Ok(())
+}
+
+fn flag_value<T: std::str::FromStr>(args: &[String], flag: &str) -> anyhow::Result<Option<T>>
+where
+    T::Err: std::fmt::Display,
+{
+    let Some(pos) = args.iter().position(|a| a == flag) else {
+        return Ok(None);
+    };
+    let raw = args
+        .get(pos + 1)
+        .ok_or_else(|| anyhow::anyhow!("{flag} needs a value"))?;
+    raw.parse()
+        .map(Some)
+        .map_err(|e| anyhow::anyhow!("invalid value for {flag}: {e}"))
+}
+
+/// The bucket from `BACKUP_S3_*`, or `None` without `BACKUP_S3_BUCKET`.
+/// The endpoint defaults to AWS in `BACKUP_S3_REGION` (default us-east-1).
+#[cfg(feature = "object-store")]
+fn object_store_from_env() -> anyhow::Result<Option<ObjectStore>> {
+    let Some(bucket) = std::env::var("BACKUP_S3_BUCKET").ok().filter(|b| !b.is_empty()) else {
+        return Ok(None);
+    };
+    let region = std::env::var("BACKUP_S3_REGION").unwrap_or_else(|_| "us-east-1".to_string());
+    let endpoint = std::env::var("BACKUP_S3_ENDPOINT")
+        .unwrap_or_else(|_| format!("https://s3.{region}.amazonaws.com"));
+    let access_key = std::env::var("BACKUP_S3_ACCESS_KEY")
+        .map_err(|_| anyhow::anyhow!("BACKUP_S3_BUCKET needs BACKUP_S3_ACCESS_KEY"))?;
+    let secret_key = std::env::var("BACKUP_S3_SECRET_KEY")
+        .map_err(|_| anyhow::anyhow!("BACKUP_S3_BUCKET needs BACKUP_S3_SECRET_KEY"))?;
+    let store = ObjectStore::new(&endpoint, &bucket, &region, &access_key, &secret_key)?
+        .prefix(&std::env::var("BACKUP_S3_PREFIX").unwrap_or_default());
+    Ok(Some(store))
+}
+
+fn env_parse<T: std::str::FromStr>(name: &str) -> anyhow::Result<Option<T>>
+where
+    T::Err: std::fmt::Display,
+{
+    match std::env::var(name) {
+        Ok(raw) => raw
+            .parse()
+            .map(Some)
+            .map_err(|e| anyhow::anyhow!("invalid {name}: {e}")),
+        Err(_) => Ok(None),
+    }
+}
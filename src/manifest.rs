@@ -0,0 +1,484 @@
+//! Declarative link management: export every link as one document and apply
+//! such a document back, creating, updating and (with `prune`) deleting links
+//! until the database matches. Applying the same document twice is a no-op.
+
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use time::OffsetDateTime;
+
+use crate::api_keys::Caller;
+use crate::devices::DeviceTargets;
+use crate::{
+    archive_clicks, delete_link_rows, insert_url_with, internal, is_unique_violation, notify, probe, require_admin,
+    schedule, tags, unix_to_rfc3339, validate_custom_code, validate_expires_at, validate_label, validate_not_before,
+    validate_target, AppState, InsertUrlError, LinkRow, NewUrl, RedirectMode, RedirectType, UtmParams,
+    MAX_NOTES_LEN, MAX_TITLE_LEN,
+};
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct LinkManifest {
+    links: Vec<LinkSpec>,
+}
+
+/// Everything that defines a link's behaviour. Creation metadata and clicks
+/// are not part of the document.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct LinkSpec {
+    code: String,
+    target_url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_at: Option<String>,
+    #[serde(default)]
+    listed: bool,
+    #[serde(default, skip_serializing_if = "UtmParams::is_empty")]
+    utm: UtmParams,
+    #[serde(default)]
+    redirect_mode: RedirectMode,
+    #[serde(default)]
+    redirect_type: RedirectType,
+    #[serde(default)]
+    forward_query: bool,
+    #[serde(default)]
+    path_forwarding: bool,
+    #[serde(default)]
+    qr_landing: bool,
+    #[serde(default, skip_serializing_if = "DeviceTargets::is_empty")]
+    device_targets: DeviceTargets,
+    #[serde(default)]
+    signed_only: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    notes: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_clicks: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    not_before: Option<String>,
+    /// Alphabetical.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    /// Earliest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    schedules: Vec<WindowSpec>,
+    /// Alphabetical.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    aliases: Vec<String>,
+}
+
+/// One schedule window, as `PUT /api/links/:code/schedule` takes it.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct WindowSpec {
+    target_url: String,
+    starts_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ends_at: Option<String>,
+}
+
+impl From<LinkRow> for LinkSpec {
+    fn from(row: LinkRow) -> Self {
+        Self {
+            utm: row.utm(),
+            code: row.code,
+            target_url: row.target_url,
+            expires_at: row.expires_at,
+            listed: row.listed,
+            redirect_mode: row.redirect_mode,
+            redirect_type: row.redirect_type,
+            forward_query: row.forward_query,
+            path_forwarding: row.path_forwarding,
+            qr_landing: row.qr_landing,
+            device_targets: DeviceTargets {
+                ios: row.target_ios,
+                android: row.target_android,
+                desktop: row.target_desktop,
+            },
+            signed_only: row.signed_only,
+            title: row.title,
+            notes: row.notes,
+            max_clicks: row.max_clicks,
+            not_before: row.not_before,
+            tags: Vec::new(),
+            schedules: Vec::new(),
+            aliases: Vec::new(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub(crate) struct ApplyParams {
+    /// Report the changes without making them.
+    #[serde(default)]
+    dry_run: bool,
+    /// Delete links that are missing from the document.
+    #[serde(default)]
+    prune: bool,
+}
+
+#[derive(Serialize, Default)]
+pub(crate) struct ApplyReport {
+    dry_run: bool,
+    created: Vec<String>,
+    updated: Vec<String>,
+    deleted: Vec<String>,
+    unchanged: usize,
+}
+
+/// Every link with its tags, schedule and aliases, by code.
+async fn load(conn: &mut sqlx::SqliteConnection) -> Result<BTreeMap<String, LinkSpec>, sqlx::Error> {
+    let mut links: BTreeMap<String, LinkSpec> = sqlx::query_as::<_, LinkRow>("SELECT * FROM urls")
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .map(|row| (row.code.clone(), LinkSpec::from(row)))
+        .collect();
+
+    let codes: Vec<String> = links.keys().cloned().collect();
+    for (code, tags) in tags::for_codes(&mut *conn, &codes).await? {
+        if let Some(link) = links.get_mut(&code) {
+            link.tags = tags;
+        }
+    }
+    let windows: Vec<(String, String, i64, Option<i64>)> = sqlx::query_as(
+        "SELECT code, target_url, starts_at, ends_at FROM link_schedules ORDER BY code, starts_at, id",
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    for (code, target_url, starts_at, ends_at) in windows {
+        if let Some(link) = links.get_mut(&code) {
+            link.schedules.push(WindowSpec {
+                target_url,
+                starts_at: unix_to_rfc3339(starts_at),
+                ends_at: ends_at.map(unix_to_rfc3339),
+            });
+        }
+    }
+    let aliases: Vec<(String, String)> = sqlx::query_as("SELECT alias, code FROM link_aliases ORDER BY alias")
+        .fetch_all(&mut *conn)
+        .await?;
+    for (alias, code) in aliases {
+        if let Some(link) = links.get_mut(&code) {
+            link.aliases.push(alias);
+        }
+    }
+    Ok(links)
+}
+
+/// `GET /api/admin/links/export`
+pub(crate) async fn export_links(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<LinkManifest>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let mut conn = state.pool.acquire().await.map_err(internal)?;
+    let links = load(&mut conn).await.map_err(internal)?;
+    Ok(Json(LinkManifest {
+        links: links.into_values().collect(),
+    }))
+}
+
+/// `POST /api/admin/links/apply`: all changes run in one transaction.
+pub(crate) async fn apply_links(
+    State(state): State<AppState>,
+    Query(params): Query<ApplyParams>,
+    headers: HeaderMap,
+    Json(manifest): Json<LinkManifest>,
+) -> Result<Json<ApplyReport>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let wanted = validate(&state, manifest).await?;
+
+    let mut tx = state.pool.begin().await.map_err(internal)?;
+    let existing = load(&mut tx).await.map_err(internal)?;
+    if !params.prune {
+        check_kept_links(&wanted, &existing)?;
+    }
+
+    let mut report = ApplyReport {
+        dry_run: params.dry_run,
+        ..ApplyReport::default()
+    };
+    let mut changed = Vec::new();
+    for spec in wanted.values() {
+        match existing.get(&spec.code) {
+            Some(current) if current == spec => report.unchanged += 1,
+            Some(_) => {
+                report.updated.push(spec.code.clone());
+                changed.push(spec);
+            }
+            None => {
+                report.created.push(spec.code.clone());
+                changed.push(spec);
+            }
+        }
+    }
+    if params.prune {
+        report.deleted = existing.keys().filter(|code| !wanted.contains_key(*code)).cloned().collect();
+    }
+
+    if !params.dry_run {
+        for code in &report.deleted {
+            archive_clicks(&mut tx, code).await.map_err(internal)?;
+            delete_link_rows(&mut tx, code).await.map_err(internal)?;
+        }
+        // aliases may move between links, so clear them all before writing any
+        for spec in &changed {
+            clear_extras(&mut tx, &spec.code).await.map_err(internal)?;
+        }
+        for spec in &changed {
+            if existing.contains_key(&spec.code) {
+                update(&mut tx, spec).await.map_err(internal)?;
+            } else {
+                insert(&mut tx, spec).await?;
+            }
+            write_extras(&mut tx, spec).await.map_err(|e| {
+                if is_unique_violation(&e) {
+                    (StatusCode::CONFLICT, format!("{}: alias already exists", spec.code))
+                } else {
+                    internal(e)
+                }
+            })?;
+        }
+    }
+    tx.commit().await.map_err(internal)?;
+    if !report.deleted.is_empty() && !params.dry_run {
+        state.click_webhooks.invalidate().await;
+    }
+    if !params.dry_run {
+        for code in &report.created {
+            notify::spawn_link_event(&state, notify::Event::link_created(&state, code, &wanted[code].target_url));
+        }
+    }
+
+    Ok(Json(report))
+}
+
+/// Checks every entry up front so a bad document changes nothing. Targets
+/// pass the same checks as new links, minus the rewrites that would make the
+/// stored target differ from the document.
+async fn validate(
+    state: &AppState,
+    manifest: LinkManifest,
+) -> Result<BTreeMap<String, LinkSpec>, (StatusCode, String)> {
+    let settings = state.settings();
+    let mut wanted = BTreeMap::new();
+    for mut spec in manifest.links {
+        let code = spec.code.clone();
+        let bad = |msg: String| (StatusCode::BAD_REQUEST, format!("{code}: {msg}"));
+        validate_custom_code(&code, &settings, Some(&Caller::Admin)).map_err(bad)?;
+        if wanted.contains_key(&code) {
+            return Err(bad("duplicate code".to_string()));
+        }
+        spec.target_url = validate_target(state, &spec.target_url).map_err(|(_, msg)| bad(msg))?;
+        probe::require_public_host(state, &spec.target_url).await.map_err(|(_, msg)| bad(msg))?;
+        if let Some(exp) = &spec.expires_at {
+            validate_expires_at(exp).map_err(|(_, msg)| bad(msg))?;
+        }
+        if let Some(start) = &spec.not_before {
+            validate_not_before(start, spec.expires_at.as_deref()).map_err(|(_, msg)| bad(msg))?;
+        }
+        if spec.max_clicks.is_some_and(|max| max < 1) {
+            return Err(bad("max_clicks must be at least 1".to_string()));
+        }
+        spec.title = validate_label("title", spec.title.take(), MAX_TITLE_LEN).map_err(|(_, msg)| bad(msg))?;
+        spec.notes = validate_label("notes", spec.notes.take(), MAX_NOTES_LEN).map_err(|(_, msg)| bad(msg))?;
+        spec.device_targets = std::mem::take(&mut spec.device_targets)
+            .validated(state)
+            .map_err(|(_, msg)| bad(msg))?;
+        let devices = &spec.device_targets;
+        for url in [&devices.ios, &devices.android, &devices.desktop].into_iter().flatten() {
+            probe::require_public_host(state, url).await.map_err(|(_, msg)| bad(msg))?;
+        }
+        spec.tags = tags::validate(Some(spec.tags)).map_err(|(_, msg)| bad(msg))?;
+        spec.tags.sort();
+
+        if spec.schedules.len() > schedule::MAX_WINDOWS {
+            return Err(bad(format!("at most {} schedule windows per link", schedule::MAX_WINDOWS)));
+        }
+        let mut windows = Vec::with_capacity(spec.schedules.len());
+        for (i, window) in spec.schedules.into_iter().enumerate() {
+            let bad = |msg: String| bad(format!("schedules[{i}]: {msg}"));
+            let target_url = validate_target(state, &window.target_url).map_err(|(_, msg)| bad(msg))?;
+            probe::require_public_host(state, &target_url).await.map_err(|(_, msg)| bad(msg))?;
+            let starts_at = schedule::parse_time(&window.starts_at)
+                .ok_or_else(|| bad("starts_at must be RFC3339".to_string()))?;
+            let ends_at = match &window.ends_at {
+                Some(end) => Some(schedule::parse_time(end).ok_or_else(|| bad("ends_at must be RFC3339".to_string()))?),
+                None => None,
+            };
+            if ends_at.is_some_and(|end| end <= starts_at) {
+                return Err(bad("ends_at must be after starts_at".to_string()));
+            }
+            windows.push((starts_at, target_url, ends_at));
+        }
+        // stable, so windows starting together keep their order, as they do in the database
+        windows.sort_by_key(|(starts_at, ..)| *starts_at);
+        spec.schedules = windows
+            .into_iter()
+            .map(|(starts_at, target_url, ends_at)| WindowSpec {
+                target_url,
+                starts_at: unix_to_rfc3339(starts_at),
+                ends_at: ends_at.map(unix_to_rfc3339),
+            })
+            .collect();
+
+        let mut aliases = BTreeSet::new();
+        for alias in &spec.aliases {
+            let alias = alias.trim();
+            validate_custom_code(alias, &settings, Some(&Caller::Admin))
+                .map_err(|e| bad(e.replace("custom_code", "alias")))?;
+            aliases.insert(state.normalize_code(alias));
+        }
+        spec.aliases = aliases.into_iter().collect();
+        wanted.insert(code, spec);
+    }
+
+    let mut owners: BTreeMap<&str, &str> = wanted.keys().map(|code| (code.as_str(), code.as_str())).collect();
+    for spec in wanted.values() {
+        for alias in &spec.aliases {
+            if let Some(owner) = owners.insert(alias, &spec.code) {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    format!("{}: alias {alias} is already used by {owner}", spec.code),
+                ));
+            }
+        }
+    }
+    Ok(wanted)
+}
+
+/// Without `prune`, links missing from the document stay. Their codes and
+/// aliases must not clash with the document's.
+fn check_kept_links(
+    wanted: &BTreeMap<String, LinkSpec>,
+    existing: &BTreeMap<String, LinkSpec>,
+) -> Result<(), (StatusCode, String)> {
+    let mut kept: BTreeMap<&str, &str> = BTreeMap::new();
+    for link in existing.values().filter(|link| !wanted.contains_key(&link.code)) {
+        kept.insert(&link.code, &link.code);
+        for alias in &link.aliases {
+            kept.insert(alias, &link.code);
+        }
+    }
+    for spec in wanted.values() {
+        if let Some(owner) = kept.get(spec.code.as_str()) {
+            return Err((StatusCode::CONFLICT, format!("{}: code is an alias of {owner}", spec.code)));
+        }
+        for alias in &spec.aliases {
+            if let Some(owner) = kept.get(alias.as_str()) {
+                return Err((StatusCode::CONFLICT, format!("{}: alias {alias} is already used by {owner}", spec.code)));
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn insert(conn: &mut sqlx::SqliteConnection, spec: &LinkSpec) -> Result<(), (StatusCode, String)> {
+    let new_url = NewUrl {
+        target_url: &spec.target_url,
+        expires_at: spec.expires_at.as_deref(),
+        created_ip: None,
+        created_user_agent: None,
+        listed: spec.listed,
+        utm: Some(&spec.utm),
+        redirect_mode: spec.redirect_mode,
+        redirect_type: spec.redirect_type,
+        forward_query: spec.forward_query,
+        path_forwarding: spec.path_forwarding,
+        qr_landing: spec.qr_landing,
+        device_targets: Some(&spec.device_targets),
+        signed_only: spec.signed_only,
+        created_by: Some(Caller::Admin.name()),
+        pending_review: false,
+        original_url: None,
+        chained_via: None,
+        title: spec.title.as_deref(),
+        notes: spec.notes.as_deref(),
+        max_clicks: spec.max_clicks,
+        not_before: spec.not_before.as_deref(),
+    };
+    insert_url_with(conn, &spec.code, &new_url).await.map_err(|e| match e {
+        InsertUrlError::CodeTaken => (StatusCode::CONFLICT, format!("{}: code already exists", spec.code)),
+        InsertUrlError::Other(e) => internal(e),
+    })
+}
+
+async fn update(conn: &mut sqlx::SqliteConnection, spec: &LinkSpec) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE urls SET target_url = ?, expires_at = ?, listed = ?, \
+                utm_source = ?, utm_medium = ?, utm_campaign = ?, utm_term = ?, utm_content = ?, \
+                redirect_mode = ?, redirect_type = ?, forward_query = ?, path_forwarding = ?, qr_landing = ?, \
+                target_ios = ?, target_android = ?, target_desktop = ?, signed_only = ?, title = ?, notes = ?, \
+                max_clicks = ?, not_before = ?, \
+                expiry_warned_at = CASE WHEN expires_at IS ? THEN expiry_warned_at END \
+         WHERE code = ?",
+    )
+    .bind(&spec.target_url)
+    .bind(&spec.expires_at)
+    .bind(spec.listed)
+    .bind(&spec.utm.source)
+    .bind(&spec.utm.medium)
+    .bind(&spec.utm.campaign)
+    .bind(&spec.utm.term)
+    .bind(&spec.utm.content)
+    .bind(spec.redirect_mode)
+    .bind(spec.redirect_type)
+    .bind(spec.forward_query)
+    .bind(spec.path_forwarding)
+    .bind(spec.qr_landing)
+    .bind(&spec.device_targets.ios)
+    .bind(&spec.device_targets.android)
+    .bind(&spec.device_targets.desktop)
+    .bind(spec.signed_only)
+    .bind(&spec.title)
+    .bind(&spec.notes)
+    .bind(spec.max_clicks)
+    .bind(&spec.not_before)
+    .bind(&spec.expires_at)
+    .bind(&spec.code)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Drops the tags, schedule and aliases of `code` so [`write_extras`] can
+/// write the document's.
+async fn clear_extras(conn: &mut sqlx::SqliteConnection, code: &str) -> Result<(), sqlx::Error> {
+    for table in ["link_tags", "link_schedules", "link_aliases"] {
+        sqlx::query(&format!("DELETE FROM {table} WHERE code = ?"))
+            .bind(code)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+async fn write_extras(conn: &mut sqlx::SqliteConnection, spec: &LinkSpec) -> Result<(), sqlx::Error> {
+    tags::attach(conn, &spec.code, &spec.tags).await?;
+    for window in &spec.schedules {
+        sqlx::query("INSERT INTO link_schedules (code, target_url, starts_at, ends_at) VALUES (?, ?, ?, ?)")
+            .bind(&spec.code)
+            .bind(&window.target_url)
+            .bind(schedule::parse_time(&window.starts_at))
+            .bind(window.ends_at.as_deref().and_then(schedule::parse_time))
+            .execute(&mut *conn)
+            .await?;
+    }
+    let now = OffsetDateTime::now_utc()
+        .format(&time::format_description::well_known::Rfc3339)
+        .unwrap();
+    for alias in &spec.aliases {
+        sqlx::query("INSERT INTO link_aliases (alias, code, created_at) VALUES (?, ?, ?)")
+            .bind(alias)
+            .bind(&spec.code)
+            .bind(&now)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
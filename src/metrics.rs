@@ -0,0 +1,110 @@
+//! Prometheus metrics at `GET /metrics`, so dashboards can break traffic
+//! down without querying the database. Redirects are counted by the link's
+//! UTM campaign, its target domain and the response's status class. Labels
+//! are kept low-cardinality: once [`MAX_SERIES`] label sets exist, new ones
+//! are counted under `other`.
+
+use std::{collections::BTreeMap, fmt::Write, sync::Mutex};
+
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+
+use crate::{channels::host_domain, require_admin, target_domain, AppState, LinkRow};
+
+/// Most label sets kept for the redirect counter.
+const MAX_SERIES: usize = 1000;
+
+/// Label value that collects label sets beyond [`MAX_SERIES`].
+const OTHER: &str = "other";
+
+/// What a redirect is counted under. Empty values stand for links without a
+/// campaign or a parsable target, and for unknown codes.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct RedirectLabels {
+    campaign: String,
+    domain: String,
+}
+
+impl RedirectLabels {
+    pub(crate) fn of(link: &LinkRow) -> Self {
+        Self {
+            campaign: link.utm_campaign.clone().unwrap_or_default(),
+            domain: target_domain(&link.target_url)
+                .and_then(|host| host_domain(&host))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// In-process counters, reset on restart like any Prometheus counter.
+#[derive(Debug, Default)]
+pub(crate) struct Metrics {
+    /// Redirect responses by labels and status class (`3xx`, ...).
+    redirects: Mutex<BTreeMap<(RedirectLabels, &'static str), u64>>,
+}
+
+fn status_class(status: StatusCode) -> &'static str {
+    match status.as_u16() {
+        100..=199 => "1xx",
+        200..=299 => "2xx",
+        300..=399 => "3xx",
+        400..=499 => "4xx",
+        _ => "5xx",
+    }
+}
+
+impl Metrics {
+    /// Counts one response to a visit of a short link. `labels` is `None`
+    /// when the code matched no link.
+    pub(crate) fn record_redirect(&self, labels: Option<RedirectLabels>, status: StatusCode) {
+        let mut key = (labels.unwrap_or_default(), status_class(status));
+        let mut redirects = self.redirects.lock().unwrap();
+        if redirects.len() >= MAX_SERIES && !redirects.contains_key(&key) {
+            key.0 = RedirectLabels {
+                campaign: OTHER.to_string(),
+                domain: OTHER.to_string(),
+            };
+        }
+        *redirects.entry(key).or_default() += 1;
+    }
+
+    /// The metrics in the Prometheus text format.
+    fn render(&self) -> String {
+        let mut out = String::from(
+            "# HELP shortener_redirects_total Visits of short links by campaign, target domain and status class.\n\
+             # TYPE shortener_redirects_total counter\n",
+        );
+        for ((labels, status), count) in self.redirects.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "shortener_redirects_total{{campaign=\"{}\",domain=\"{}\",status=\"{}\"}} {}",
+                escape(&labels.campaign),
+                escape(&labels.domain),
+                status,
+                count
+            );
+        }
+        out
+    }
+}
+
+/// `value` escaped for a Prometheus label.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// `GET /metrics`: the counters for a Prometheus scrape. Admin only, since
+/// labels name campaigns and target domains.
+pub(crate) async fn metrics(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        state.metrics.render(),
+    ))
+}
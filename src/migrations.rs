@@ -0,0 +1,96 @@
+//! Schema migrations, compiled in from `migrations/`. The server applies
+//! pending ones at startup unless `AUTO_MIGRATE=false`; the `migrate`
+//! subcommand runs or inspects them on their own, and
+//! `GET /api/admin/migrations` reports what a running instance sees.
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::Serialize;
+use sqlx::{migrate::Migrator, Pool, Sqlite};
+
+use crate::{internal, require_admin, AppState};
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// One known migration and whether the database has it.
+#[derive(Serialize, Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    /// When it was applied, as SQLite recorded it; `None` while pending.
+    pub applied_at: Option<String>,
+    /// Applied, but the file changed since; sqlx refuses to run further
+    /// migrations until that is resolved.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub checksum_mismatch: bool,
+}
+
+impl MigrationStatus {
+    pub fn is_applied(&self) -> bool {
+        self.applied_at.is_some()
+    }
+}
+
+/// Applies every pending migration and returns how many ran.
+pub async fn run_migrations(pool: &Pool<Sqlite>) -> Result<usize, sqlx::migrate::MigrateError> {
+    let pending = migration_status(pool)
+        .await?
+        .iter()
+        .filter(|m| !m.is_applied())
+        .count();
+    MIGRATOR.run(pool).await?;
+    Ok(pending)
+}
+
+/// Every migration this build knows, oldest first, with its state in `pool`.
+pub async fn migration_status(pool: &Pool<Sqlite>) -> Result<Vec<MigrationStatus>, sqlx::Error> {
+    let (tracked,): (bool,) = sqlx::query_as(
+        "SELECT count(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations'",
+    )
+    .fetch_one(pool)
+    .await?;
+    let applied: Vec<(i64, String, Vec<u8>)> = if tracked {
+        sqlx::query_as("SELECT version, installed_on, checksum FROM _sqlx_migrations WHERE success = 1")
+            .fetch_all(pool)
+            .await?
+    } else {
+        Vec::new()
+    };
+    Ok(MIGRATOR
+        .iter()
+        .map(|m| {
+            let row = applied.iter().find(|(version, _, _)| *version == m.version);
+            MigrationStatus {
+                version: m.version,
+                description: m.description.to_string(),
+                applied_at: row.map(|(_, at, _)| at.clone()),
+                checksum_mismatch: row.is_some_and(|(_, _, checksum)| **checksum != *m.checksum),
+            }
+        })
+        .collect())
+}
+
+#[derive(Serialize)]
+pub(crate) struct MigrationsResp {
+    applied: usize,
+    pending: usize,
+    migrations: Vec<MigrationStatus>,
+}
+
+/// `GET /api/admin/migrations`.
+pub(crate) async fn list_migrations(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<MigrationsResp>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let migrations = migration_status(&state.pool).await.map_err(internal)?;
+    let applied = migrations.iter().filter(|m| m.is_applied()).count();
+    Ok(Json(MigrationsResp {
+        applied,
+        pending: migrations.len() - applied,
+        migrations,
+    }))
+}
@@ -0,0 +1,110 @@
+//! Policy for links created without an API key, and the admin review queue
+//! for the ones held by `anonymous_moderation`. Rejecting a held link is a
+//! plain `DELETE /api/links/:code`.
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::Serialize;
+use time::OffsetDateTime;
+
+use crate::{internal, notify, require_admin, AppState};
+
+/// Rejects anonymous creation when it is disabled or the client IP is over
+/// its daily quota. Expiry caps and moderation are applied by the caller.
+pub(crate) async fn check_anonymous(
+    state: &AppState,
+    ip: Option<&str>,
+) -> Result<(), (StatusCode, String)> {
+    let settings = state.settings();
+    if !settings.anonymous_shorten {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            "an API key is required to create links".to_string(),
+        ));
+    }
+    if let Some(limit) = settings.anonymous_daily_limit {
+        let since = (OffsetDateTime::now_utc() - time::Duration::days(1))
+            .format(&time::format_description::well_known::Rfc3339)
+            .unwrap();
+        let (created,): (i64,) = sqlx::query_as(
+            "SELECT count(*) FROM urls WHERE created_by IS NULL AND created_ip IS ? AND created_at >= ?",
+        )
+        .bind(ip)
+        .bind(since)
+        .fetch_one(&state.pool)
+        .await
+        .map_err(internal)?;
+        if created >= i64::from(limit) {
+            return Err((
+                StatusCode::TOO_MANY_REQUESTS,
+                format!("anonymous clients may create {limit} links per day; use an API key for more"),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Tells the notifiers that `code` is waiting for review.
+pub(crate) fn notify_held(state: &AppState, code: &str, target_url: &str) {
+    notify::spawn_deliver(
+        state,
+        notify::Event::LinkHeld {
+            code: code.to_string(),
+            target_url: target_url.to_string(),
+        },
+    );
+}
+
+#[derive(Serialize)]
+pub(crate) struct PendingLink {
+    code: String,
+    target_url: String,
+    created_at: String,
+    created_ip: Option<String>,
+}
+
+/// `GET /api/admin/moderation`: links awaiting review, oldest first.
+pub(crate) async fn list_pending(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<PendingLink>>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let rows: Vec<(String, String, String, Option<String>)> = sqlx::query_as(
+        "SELECT code, target_url, created_at, created_ip FROM urls \
+         WHERE pending_review = 1 ORDER BY created_at",
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(internal)?;
+    Ok(Json(
+        rows.into_iter()
+            .map(|(code, target_url, created_at, created_ip)| PendingLink {
+                code,
+                target_url,
+                created_at,
+                created_ip,
+            })
+            .collect(),
+    ))
+}
+
+/// `POST /api/admin/moderation/:code/approve`
+pub(crate) async fn approve(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let done = sqlx::query("UPDATE urls SET pending_review = 0 WHERE code = ? AND pending_review = 1")
+        .bind(&code)
+        .execute(&state.pool)
+        .await
+        .map_err(internal)?;
+    if done.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "no pending link with this code".to_string()));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
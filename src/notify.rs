@@ -0,0 +1,308 @@
+//! Outbound notifications. Alerting code describes what happened as an
+//! [`Event`] and hands it to [`deliver`], which sends it through every
+//! [`Notifier`] configured for the deployment (`AppState::notifiers`), plus
+//! an optional per-event destination such as a click alert's own webhook.
+//! A new channel (email, chat, pager) is one more `Notifier`, not a change
+//! at every call site. Failed webhooks are queued for retry; see
+//! [`crate::retry_webhooks`].
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::{anomaly::Anomaly, deliveries, webhook, AppState};
+
+#[derive(Serialize, Clone)]
+#[serde(tag = "event", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum Event {
+    /// A click alert passed its threshold, for one link or a UTM campaign.
+    ClickThreshold {
+        alert_id: i64,
+        code: Option<String>,
+        campaign: Option<String>,
+        threshold: i64,
+        clicks: i64,
+    },
+    /// Anomaly detection flagged unusual traffic.
+    Anomaly { anomaly: Anomaly },
+    /// A new link is held for moderation.
+    LinkHeld { code: String, target_url: String },
+    /// A link expires within `expiry_warning_hours`.
+    LinkExpiring {
+        code: String,
+        target_url: String,
+        expires_at: String,
+    },
+    /// A link was created. This and the lifecycle events below are only
+    /// sent with the `link_events` setting on.
+    LinkCreated {
+        code: String,
+        target_url: String,
+        short_url: String,
+    },
+    /// A link was edited; `changed` names the fields that changed.
+    LinkEdited {
+        code: String,
+        target_url: String,
+        changed: Vec<&'static str>,
+    },
+    /// A link passed its `expires_at`.
+    LinkExpired {
+        code: String,
+        target_url: String,
+        expires_at: String,
+    },
+    /// A redirect used up the link's `max_clicks`.
+    LinkClickLimitReached {
+        code: String,
+        target_url: String,
+        max_clicks: i64,
+    },
+}
+
+impl Event {
+    /// One-line summary for chat messages and logs.
+    pub fn text(&self) -> String {
+        match self {
+            Event::ClickThreshold {
+                code: Some(code),
+                threshold,
+                clicks,
+                ..
+            } => format!("Link {code} passed {threshold} clicks ({clicks} so far)"),
+            Event::ClickThreshold {
+                campaign,
+                threshold,
+                clicks,
+                ..
+            } => format!(
+                "Campaign {} passed {threshold} clicks ({clicks} so far)",
+                campaign.as_deref().unwrap_or_default()
+            ),
+            Event::Anomaly { anomaly } => anomaly.text(),
+            Event::LinkHeld { code, target_url } => {
+                format!("Link {code} to {target_url} is waiting for review")
+            }
+            Event::LinkExpiring { code, expires_at, .. } => format!("Link {code} expires at {expires_at}"),
+            Event::LinkCreated { short_url, target_url, .. } => {
+                format!("Link {short_url} to {target_url} was created")
+            }
+            Event::LinkEdited { code, changed, .. } => {
+                format!("Link {code} was edited ({})", changed.join(", "))
+            }
+            Event::LinkExpired { code, .. } => format!("Link {code} expired"),
+            Event::LinkClickLimitReached { code, max_clicks, .. } => {
+                format!("Link {code} reached its limit of {max_clicks} clicks")
+            }
+        }
+    }
+
+    /// A [`Event::LinkCreated`] for `code`.
+    pub(crate) fn link_created(state: &AppState, code: &str, target_url: &str) -> Self {
+        Event::LinkCreated {
+            code: code.to_string(),
+            target_url: target_url.to_string(),
+            short_url: state.short_url(code),
+        }
+    }
+
+    /// The `event` tag, e.g. `click_threshold`.
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Event::ClickThreshold { .. } => "click_threshold",
+            Event::Anomaly { .. } => "anomaly",
+            Event::LinkHeld { .. } => "link_held",
+            Event::LinkExpiring { .. } => "link_expiring",
+            Event::LinkCreated { .. } => "link_created",
+            Event::LinkEdited { .. } => "link_edited",
+            Event::LinkExpired { .. } => "link_expired",
+            Event::LinkClickLimitReached { .. } => "link_click_limit_reached",
+        }
+    }
+}
+
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    async fn send(&self, event: &Event) -> Result<(), String>;
+
+    /// URL and JSON body to queue for retry when `send` fails. `None`, the
+    /// default, means failures are only logged.
+    fn retry_request(&self, _event: &Event) -> Option<(String, serde_json::Value)> {
+        None
+    }
+
+    /// URL the notifier sends to, for the startup self-check. `None`, the
+    /// default, skips the check.
+    fn endpoint(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// Body of the `POST` a [`WebhookNotifier`] sends.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum WebhookFormat {
+    /// The event's fields, its `event` name and `text`.
+    #[default]
+    Json,
+    /// `{text}`, as Slack incoming webhooks expect.
+    Slack,
+}
+
+/// POSTs events to a URL (behind the `webhooks` feature). For email, point
+/// it at a mail relay or implement [`Notifier`] directly.
+pub struct WebhookNotifier {
+    url: String,
+    format: WebhookFormat,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: impl Into<String>, format: WebhookFormat) -> Self {
+        Self {
+            url: url.into(),
+            format,
+        }
+    }
+
+    fn payload(&self, event: &Event) -> Result<serde_json::Value, String> {
+        Ok(match self.format {
+            WebhookFormat::Json => {
+                let mut payload = serde_json::to_value(event).map_err(|e| e.to_string())?;
+                payload["text"] = event.text().into();
+                payload
+            }
+            WebhookFormat::Slack => serde_json::json!({ "text": event.text() }),
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    async fn send(&self, event: &Event) -> Result<(), String> {
+        webhook::post(&self.url, &self.payload(event)?).await
+    }
+
+    fn retry_request(&self, event: &Event) -> Option<(String, serde_json::Value)> {
+        Some((self.url.clone(), self.payload(event).ok()?))
+    }
+
+    fn endpoint(&self) -> Option<&str> {
+        Some(&self.url)
+    }
+}
+
+/// Sends `event` through `extra`, if any, and every deployment notifier.
+/// Failures are logged and, for webhooks, queued for retry, not returned:
+/// one broken channel must not stop the others or the work that raised the
+/// event.
+pub(crate) async fn deliver(state: &AppState, event: &Event, extra: Option<&dyn Notifier>) {
+    for notifier in extra.into_iter().chain(state.notifiers.iter().map(|n| n.as_ref())) {
+        let Err(e) = notifier.send(event).await else {
+            continue;
+        };
+        tracing::warn!("notification failed ({}): {e}", event.text());
+        if let Some((url, payload)) = notifier.retry_request(event) {
+            if let Err(e) = deliveries::enqueue(state, event.name(), &url, &payload, &e, None).await {
+                tracing::warn!("queueing webhook retry failed: {e}");
+            }
+        }
+    }
+}
+
+/// [`deliver`] in the background, for request handlers.
+pub(crate) fn spawn_deliver(state: &AppState, event: Event) {
+    let state = state.clone();
+    tokio::spawn(async move { deliver(&state, &event, None).await });
+}
+
+/// [`spawn_deliver`] for a link lifecycle event, if the `link_events`
+/// setting is on.
+pub(crate) fn spawn_link_event(state: &AppState, event: Event) {
+    if state.settings().link_events {
+        spawn_deliver(state, event);
+    }
+}
+
+/// Sends a [`Event::LinkExpiring`] for each live link expiring within
+/// `expiry_warning_hours`, once per link. Does nothing when the setting is
+/// unset or the service is read-only; returns how many warnings went out.
+pub async fn warn_expiring_links(state: &AppState) -> Result<usize, sqlx::Error> {
+    let Some(hours) = state.settings().expiry_warning_hours else {
+        return Ok(0);
+    };
+    if state.is_read_only() {
+        return Ok(0);
+    }
+    let rfc3339 = &time::format_description::well_known::Rfc3339;
+    let now = OffsetDateTime::now_utc();
+    let horizon = now + time::Duration::hours(hours.into());
+    let candidates: Vec<(String, String, String)> = sqlx::query_as(
+        "SELECT code, target_url, expires_at FROM urls \
+         WHERE expires_at IS NOT NULL AND expiry_warned_at IS NULL AND archived_at IS NULL",
+    )
+    .fetch_all(&state.pool)
+    .await?;
+
+    let mut warned = 0;
+    for (code, target_url, expires_at) in candidates {
+        let Ok(expiry) = OffsetDateTime::parse(&expires_at, rfc3339) else {
+            continue;
+        };
+        if expiry <= now || expiry > horizon {
+            continue;
+        }
+        let claimed = sqlx::query("UPDATE urls SET expiry_warned_at = ? WHERE code = ? AND expiry_warned_at IS NULL")
+            .bind(now.format(rfc3339).unwrap())
+            .bind(&code)
+            .execute(&state.pool)
+            .await?;
+        if claimed.rows_affected() == 1 {
+            let event = Event::LinkExpiring {
+                code,
+                target_url,
+                expires_at,
+            };
+            deliver(state, &event, None).await;
+            warned += 1;
+        }
+    }
+    Ok(warned)
+}
+
+/// Sends a [`Event::LinkExpired`] for each link that passed its expiry since
+/// the last run, once per link. Links are marked even while the
+/// `link_events` setting is off, so turning it on doesn't announce old
+/// expiries. Does nothing when the service is read-only; returns how many
+/// notifications went out.
+pub async fn notify_expired_links(state: &AppState) -> Result<usize, sqlx::Error> {
+    if state.is_read_only() {
+        return Ok(0);
+    }
+    let now = OffsetDateTime::now_utc()
+        .format(&time::format_description::well_known::Rfc3339)
+        .unwrap();
+    // one statement claims them, so concurrent instances can't both notify
+    let expired: Vec<(String, String, String)> = sqlx::query_as(
+        "UPDATE urls SET expired_notified_at = ? \
+         WHERE expires_at IS NOT NULL AND expired_notified_at IS NULL AND archived_at IS NULL \
+           AND julianday(expires_at) <= julianday('now') \
+         RETURNING code, target_url, expires_at",
+    )
+    .bind(now)
+    .fetch_all(&state.pool)
+    .await?;
+    if !state.settings().link_events {
+        return Ok(0);
+    }
+    for (code, target_url, expires_at) in &expired {
+        let event = Event::LinkExpired {
+            code: code.clone(),
+            target_url: target_url.clone(),
+            expires_at: expires_at.clone(),
+        };
+        deliver(state, &event, None).await;
+    }
+    Ok(expired.len())
+}
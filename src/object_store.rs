@@ -0,0 +1,291 @@
+//! Uploads to S3-compatible object storage (AWS S3, MinIO, R2, ...), behind
+//! the `object-store` feature. The backup job pushes each snapshot here so
+//! copies survive the loss of the server's disk, and keeps the newest
+//! `BACKUP_KEEP` remotely as it does locally. Requests use path-style URLs
+//! (`<endpoint>/<bucket>/<key>`) and AWS Signature Version 4.
+
+use std::path::Path;
+
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use time::OffsetDateTime;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// One bucket, with every key under an optional prefix.
+pub struct ObjectStore {
+    client: reqwest::Client,
+    endpoint: url::Url,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    prefix: String,
+}
+
+impl ObjectStore {
+    /// Client for `bucket` at `endpoint`, e.g. `https://s3.eu-west-1.amazonaws.com`
+    /// or `http://localhost:9000` for MinIO.
+    pub fn new(
+        endpoint: &str,
+        bucket: &str,
+        region: &str,
+        access_key: &str,
+        secret_key: &str,
+    ) -> anyhow::Result<Self> {
+        let endpoint = url::Url::parse(endpoint.trim_end_matches('/'))?;
+        if !matches!(endpoint.scheme(), "http" | "https") || endpoint.host_str().is_none() {
+            anyhow::bail!("object store endpoint must be an http(s) URL: {endpoint}");
+        }
+        if bucket.is_empty() {
+            anyhow::bail!("object store bucket is empty");
+        }
+        Ok(Self {
+            client: reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(300))
+                .user_agent("url-shortener/1.0")
+                .build()
+                .expect("static client config"),
+            endpoint,
+            bucket: bucket.to_string(),
+            region: region.to_string(),
+            access_key: access_key.to_string(),
+            secret_key: secret_key.to_string(),
+            prefix: String::new(),
+        })
+    }
+
+    /// Keeps every key under `prefix`, e.g. `backups/` (a trailing `/` is added).
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        let prefix = prefix.trim_matches('/');
+        self.prefix = if prefix.is_empty() { String::new() } else { format!("{prefix}/") };
+        self
+    }
+
+    /// Stores `body` at `name` under the prefix.
+    pub async fn put(&self, name: &str, body: Vec<u8>) -> anyhow::Result<()> {
+        let key = format!("{}{name}", self.prefix);
+        self.send(reqwest::Method::PUT, &key, &[], body).await?;
+        Ok(())
+    }
+
+    /// Names (without the prefix) of every object under the prefix, sorted.
+    pub async fn list(&self) -> anyhow::Result<Vec<String>> {
+        let mut names = Vec::new();
+        let mut token: Option<String> = None;
+        loop {
+            let mut query = vec![("list-type", "2".to_string()), ("prefix", self.prefix.clone())];
+            if let Some(token) = &token {
+                query.push(("continuation-token", token.clone()));
+            }
+            let xml = self.send(reqwest::Method::GET, "", &query, Vec::new()).await?;
+            names.extend(
+                xml_values(&xml, "Key")
+                    .into_iter()
+                    .filter_map(|key| key.strip_prefix(&self.prefix).map(str::to_string)),
+            );
+            token = xml_values(&xml, "NextContinuationToken").into_iter().next();
+            if token.is_none() {
+                break;
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Removes `name` under the prefix; missing objects are not an error.
+    pub async fn delete(&self, name: &str) -> anyhow::Result<()> {
+        let key = format!("{}{name}", self.prefix);
+        self.send(reqwest::Method::DELETE, &key, &[], Vec::new()).await?;
+        Ok(())
+    }
+
+    /// Uploads the file at `path` under its file name and returns that name.
+    pub async fn upload_file(&self, path: &Path) -> anyhow::Result<String> {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| anyhow::anyhow!("no file name in {}", path.display()))?
+            .to_string();
+        self.put(&name, tokio::fs::read(path).await?).await?;
+        Ok(name)
+    }
+
+    /// Deletes all but the newest `keep` objects whose names start with
+    /// `name_prefix` (names sort by time, as snapshots do); returns how many
+    /// went.
+    pub async fn prune(&self, name_prefix: &str, keep: usize) -> anyhow::Result<usize> {
+        let names: Vec<String> = self
+            .list()
+            .await?
+            .into_iter()
+            .filter(|name| name.starts_with(name_prefix))
+            .collect();
+        let excess = names.len().saturating_sub(keep);
+        for name in &names[..excess] {
+            self.delete(name).await?;
+        }
+        Ok(excess)
+    }
+
+    /// Sends a signed request for `key` in the bucket and returns the body.
+    async fn send(
+        &self,
+        method: reqwest::Method,
+        key: &str,
+        query: &[(&str, String)],
+        body: Vec<u8>,
+    ) -> anyhow::Result<String> {
+        let mut path = format!("{}/{}", self.endpoint.path().trim_end_matches('/'), uri_encode(&self.bucket, false));
+        if !key.is_empty() {
+            path.push('/');
+            path.push_str(&uri_encode(key, true));
+        }
+        let mut query: Vec<(String, String)> = query
+            .iter()
+            .map(|(k, v)| (uri_encode(k, false), uri_encode(v, false)))
+            .collect();
+        query.sort();
+        let query = query
+            .iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<_>>()
+            .join("&");
+        let host = match self.endpoint.port() {
+            Some(port) => format!("{}:{port}", self.endpoint.host_str().unwrap_or_default()),
+            None => self.endpoint.host_str().unwrap_or_default().to_string(),
+        };
+        let payload_hash = hex(&Sha256::digest(&body));
+        let now = OffsetDateTime::now_utc();
+        let authorization = sign(&Signing {
+            access_key: &self.access_key,
+            secret_key: &self.secret_key,
+            region: &self.region,
+            method: method.as_str(),
+            host: &host,
+            path: &path,
+            query: &query,
+            payload_hash: &payload_hash,
+            now,
+        });
+
+        let mut url = format!("{}://{host}{path}", self.endpoint.scheme());
+        if !query.is_empty() {
+            url.push('?');
+            url.push_str(&query);
+        }
+        let resp = self
+            .client
+            .request(method, url)
+            .header("x-amz-date", amz_date(now))
+            .header("x-amz-content-sha256", payload_hash)
+            .header(reqwest::header::AUTHORIZATION, authorization)
+            .body(body)
+            .send()
+            .await?;
+        let status = resp.status();
+        let text = resp.text().await?;
+        if !status.is_success() {
+            let code = xml_values(&text, "Code").into_iter().next().unwrap_or_default();
+            anyhow::bail!("object store answered {status} {code}");
+        }
+        Ok(text)
+    }
+}
+
+/// Inputs to a SigV4 signature over the `host`, `x-amz-content-sha256` and
+/// `x-amz-date` headers. `path` and `query` are already canonical.
+struct Signing<'a> {
+    access_key: &'a str,
+    secret_key: &'a str,
+    region: &'a str,
+    method: &'a str,
+    host: &'a str,
+    path: &'a str,
+    query: &'a str,
+    payload_hash: &'a str,
+    now: OffsetDateTime,
+}
+
+/// The `Authorization` header value for `s`.
+fn sign(s: &Signing) -> String {
+    let amz_date = amz_date(s.now);
+    let date = &amz_date[..8];
+    let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+    let canonical_request = format!(
+        "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{signed_headers}\n{}",
+        s.method, s.path, s.query, s.host, s.payload_hash, amz_date, s.payload_hash
+    );
+    let scope = format!("{date}/{}/s3/aws4_request", s.region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+    let mut key = format!("AWS4{}", s.secret_key).into_bytes();
+    for part in [date, s.region, "s3", "aws4_request", string_to_sign.as_str()] {
+        key = hmac(&key, part);
+    }
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={}",
+        s.access_key,
+        hex(&key)
+    )
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC takes any key length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// `20261016T120000Z`.
+fn amz_date(now: OffsetDateTime) -> String {
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        now.year(),
+        u8::from(now.month()),
+        now.day(),
+        now.hour(),
+        now.minute(),
+        now.second()
+    )
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Percent-encodes all but unreserved characters, and `/` when `keep_slash`.
+fn uri_encode(s: &str, keep_slash: bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b'~') || (keep_slash && b == b'/') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+    }
+    out
+}
+
+/// Text of every `<tag>...</tag>` in `xml`, unescaped. S3's list answers are
+/// flat enough that this beats pulling in an XML parser.
+fn xml_values(xml: &str, tag: &str) -> Vec<String> {
+    let (open, close) = (format!("<{tag}>"), format!("</{tag}>"));
+    let mut values = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        let Some(end) = rest.find(&close) else { break };
+        values.push(
+            rest[..end]
+                .replace("&lt;", "<")
+                .replace("&gt;", ">")
+                .replace("&quot;", "\"")
+                .replace("&apos;", "'")
+                .replace("&amp;", "&"),
+        );
+        rest = &rest[end + close.len()..];
+    }
+    values
+}
@@ -0,0 +1,134 @@
+//! Operator overrides for the HTML pages, loaded once at startup from a
+//! directory (`TEMPLATE_DIR`) so deployments can brand them without forking.
+//! Each page is one file, e.g. `not_found.html`; pages without a file keep
+//! the built-in version. Templates are plain HTML with `{{name}}`
+//! placeholders, the same ones the built-in pages use; values are
+//! HTML-escaped unless documented as markup.
+
+use std::{collections::HashMap, path::Path};
+
+/// The pages that can be overridden.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Page {
+    /// Frame of every dashboard page: `title`, `prefix`, and the markup
+    /// `banner`, `body` and `palette`.
+    Layout,
+    /// Error pages: `code`, `message`, `status`.
+    NotFound,
+    Gone,
+    Disabled,
+    Forbidden,
+    /// `domain`, `continue_url`, `continue_url_js` (a JSON string),
+    /// `seconds`, `refresh`, `disabled`, and the markup `snippet`.
+    Interstitial,
+    /// `short_url`, `title`, `domain`, `target`, `created`, `continue_url`.
+    Preview,
+    /// QR landing: `short_url`, `title`, `domain`, `target`, `continue_url`.
+    QrLanding,
+    /// Dead target page: `name`, `target`, and the markup `archive`.
+    Unavailable,
+    /// Admin token prompt: `action`, and the markup `error`.
+    Login,
+}
+
+impl Page {
+    const ALL: [Page; 10] = [
+        Page::Layout,
+        Page::NotFound,
+        Page::Gone,
+        Page::Disabled,
+        Page::Forbidden,
+        Page::Interstitial,
+        Page::Preview,
+        Page::QrLanding,
+        Page::Unavailable,
+        Page::Login,
+    ];
+
+    fn file_name(self) -> &'static str {
+        match self {
+            Page::Layout => "layout.html",
+            Page::NotFound => "not_found.html",
+            Page::Gone => "gone.html",
+            Page::Disabled => "disabled.html",
+            Page::Forbidden => "forbidden.html",
+            Page::Interstitial => "interstitial.html",
+            Page::Preview => "preview.html",
+            Page::QrLanding => "qr_landing.html",
+            Page::Unavailable => "unavailable.html",
+            Page::Login => "login.html",
+        }
+    }
+}
+
+/// Overridden pages, by page. Empty by default.
+#[derive(Clone, Debug, Default)]
+pub struct PageTemplates {
+    pages: HashMap<Page, String>,
+}
+
+impl PageTemplates {
+    /// Reads every `.html` file in `dir`. Unknown names are an error, so a
+    /// typo doesn't silently leave the built-in page in place; other files
+    /// (assets, notes) are ignored.
+    pub fn load(dir: &Path) -> anyhow::Result<Self> {
+        let mut pages = HashMap::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("html") {
+                continue;
+            }
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            let Some(page) = Page::ALL.into_iter().find(|p| p.file_name() == name) else {
+                let known: Vec<_> = Page::ALL.iter().map(|p| p.file_name()).collect();
+                anyhow::bail!("unknown template {}; expected one of {}", path.display(), known.join(", "));
+            };
+            pages.insert(page, std::fs::read_to_string(&path)?);
+        }
+        Ok(Self { pages })
+    }
+
+    /// File names of the overridden pages, sorted.
+    pub fn overridden(&self) -> Vec<&'static str> {
+        let mut names: Vec<_> = self.pages.keys().map(|p| p.file_name()).collect();
+        names.sort();
+        names
+    }
+
+    /// The override for `page`, if any.
+    pub(crate) fn get(&self, page: Page) -> Option<&str> {
+        self.pages.get(&page).map(String::as_str)
+    }
+
+    /// The override for `page`, else `default`.
+    pub(crate) fn template<'a>(&'a self, page: Page, default: &'a str) -> &'a str {
+        self.get(page).unwrap_or(default)
+    }
+}
+
+/// Fills the `{{name}}` placeholders of `template` from `vars` in one pass,
+/// so values are never scanned for placeholders themselves. Unknown
+/// placeholders are left as they are.
+pub(crate) fn render(template: &str, vars: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let value = after
+            .find("}}")
+            .and_then(|end| vars.iter().find(|(name, _)| *name == after[..end].trim()).map(|(_, v)| (end, v)));
+        match value {
+            Some((end, value)) => {
+                out.push_str(value);
+                rest = &after[end + 2..];
+            }
+            None => {
+                out.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
@@ -0,0 +1,83 @@
+//! Link previews: `/<code>+` or `/<code>?preview` shows where a link goes
+//! instead of redirecting, so recipients can inspect a suspicious link first.
+//! Previews don't count as clicks; the continue button goes through the
+//! normal redirect.
+
+use axum::{
+    http::header,
+    response::{Html, IntoResponse},
+};
+
+use crate::{
+    html_escape,
+    page_templates::{render, Page},
+    target_domain, AppState, LinkRow,
+};
+
+/// Whether `query` asks for a preview with a bare `preview` parameter.
+pub(crate) fn requested(query: Option<&str>) -> bool {
+    query.is_some_and(|q| url::form_urlencoded::parse(q.as_bytes()).any(|(key, _)| key == "preview"))
+}
+
+/// `query` without the `preview` parameter, for the continue link.
+pub(crate) fn strip(query: Option<&str>) -> Option<String> {
+    let rest: Vec<_> = url::form_urlencoded::parse(query?.as_bytes())
+        .filter(|(key, _)| key != "preview")
+        .collect();
+    if rest.is_empty() {
+        return None;
+    }
+    Some(url::form_urlencoded::Serializer::new(String::new()).extend_pairs(rest).finish())
+}
+
+/// Built-in page; see [`Page::Preview`] for the placeholders.
+const DEFAULT_PAGE: &str = r#"<!doctype html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8" />
+    <meta name="robots" content="noindex" />
+    <meta name="viewport" content="width=device-width, initial-scale=1" />
+    <title>Preview of {{short_url}}</title>
+    <style>
+      body { font-family: system-ui, sans-serif; max-width: 640px; margin: 40px auto; padding: 0 16px; }
+      .target { font-family: ui-monospace, monospace; word-break: break-all; padding: 12px; background: #f5f5f5; border-radius: 8px; }
+      .continue { display: inline-block; margin-top: 16px; padding: 10px 14px; border-radius: 10px; background: #0b62d6; color: white; text-decoration: none; }
+    </style>
+  </head>
+  <body>
+    <h1>{{short_url}}</h1>
+    {{title}}
+    <p>This link goes to <strong>{{domain}}</strong>:</p>
+    <p class="target">{{target}}</p>
+    <p>Created {{created}}</p>
+    <a class="continue" href="{{continue_url}}" rel="nofollow">Continue to {{domain}}</a>
+  </body>
+</html>"#;
+
+/// The preview page for `link`, which would send this visitor to `target`.
+/// `query` is kept on the continue link so signed URLs still verify.
+pub(crate) fn page(state: &AppState, link: &LinkRow, target: &str, query: Option<&str>) -> impl IntoResponse {
+    let mut continue_url = state.short_url(&link.code);
+    if let Some(query) = query {
+        continue_url.push('?');
+        continue_url.push_str(query);
+    }
+    let domain = target_domain(target).unwrap_or_default();
+    let title = match &link.title {
+        Some(title) => format!("<p><strong>{}</strong></p>", html_escape(title)),
+        None => String::new(),
+    };
+    let created = link.created_at.split('T').next().unwrap_or(&link.created_at);
+    let page = render(
+        state.page_templates.template(Page::Preview, DEFAULT_PAGE),
+        &[
+            ("short_url", &html_escape(&state.short_url(&link.code))),
+            ("title", &title),
+            ("domain", &html_escape(&domain)),
+            ("target", &html_escape(target)),
+            ("created", &html_escape(created)),
+            ("continue_url", &html_escape(&continue_url)),
+        ],
+    );
+    ([(header::CACHE_CONTROL, "no-store")], Html(page))
+}
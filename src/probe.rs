@@ -0,0 +1,196 @@
+//! Outbound checks of link targets. The default [`HttpProbe`] (behind the
+//! `probe` feature) sends a `HEAD` request; embedders and tests can plug in
+//! their own [`TargetProbe`].
+
+use async_trait::async_trait;
+use axum::http::StatusCode;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::{target_domain, AppState};
+
+#[async_trait]
+pub trait TargetProbe: Send + Sync {
+    /// Whether `url` answers at all. Any HTTP response below 500 counts, so
+    /// servers that reject `HEAD` or require a login are still reachable.
+    async fn reachable(&self, url: &str) -> bool;
+
+    /// Whether visitors sent to `url` would find a page: used by the health
+    /// checker. Defaults to [`TargetProbe::reachable`].
+    async fn alive(&self, url: &str) -> bool {
+        self.reachable(url).await
+    }
+
+    /// Where `url` redirects to (absolute), without following further.
+    /// `None` when it does not redirect or the probe can't tell.
+    async fn redirect_location(&self, _url: &str) -> Option<String> {
+        None
+    }
+}
+
+/// Probes with a short-timeout `HEAD` request that does not follow redirects.
+#[cfg(feature = "probe")]
+pub struct HttpProbe {
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "probe")]
+impl Default for HttpProbe {
+    fn default() -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(3))
+                .redirect(reqwest::redirect::Policy::none())
+                .user_agent("url-shortener/1.0")
+                .build()
+                .expect("static client config"),
+        }
+    }
+}
+
+#[cfg(feature = "probe")]
+#[async_trait]
+impl TargetProbe for HttpProbe {
+    async fn reachable(&self, url: &str) -> bool {
+        self.client
+            .head(url)
+            .send()
+            .await
+            .is_ok_and(|resp| !resp.status().is_server_error())
+    }
+
+    /// Like `reachable`, but 404 and 410 count as dead too.
+    async fn alive(&self, url: &str) -> bool {
+        self.client.head(url).send().await.is_ok_and(|resp| {
+            let status = resp.status();
+            !status.is_server_error()
+                && status != reqwest::StatusCode::NOT_FOUND
+                && status != reqwest::StatusCode::GONE
+        })
+    }
+
+    async fn redirect_location(&self, url: &str) -> Option<String> {
+        let resp = self.client.head(url).send().await.ok()?;
+        if !resp.status().is_redirection() {
+            return None;
+        }
+        let location = resp.headers().get(reqwest::header::LOCATION)?.to_str().ok()?;
+        url::Url::parse(url).ok()?.join(location).ok().map(String::from)
+    }
+}
+
+/// The probe used unless the builder sets one; `None` without the feature.
+pub(crate) fn default_probe() -> Option<std::sync::Arc<dyn TargetProbe>> {
+    #[cfg(feature = "probe")]
+    return Some(std::sync::Arc::new(HttpProbe::default()));
+    #[cfg(not(feature = "probe"))]
+    None
+}
+
+/// The `https://` form of an `http://` target, if the probe reaches it.
+pub(crate) async fn https_upgrade(state: &AppState, target: &str) -> Option<String> {
+    let probe = state.target_probe.as_ref()?;
+    let mut url = url::Url::parse(target).ok()?;
+    if url.scheme() != "http" {
+        return None;
+    }
+    // an explicit port served plain HTTP; its TLS twin is anyone's guess
+    if url.port().is_some() {
+        return None;
+    }
+    url.set_scheme("https").ok()?;
+    let upgraded = url.to_string();
+    probe.reachable(&upgraded).await.then_some(upgraded)
+}
+
+/// Most redirects followed through chained shorteners.
+const MAX_HOPS: usize = 5;
+
+/// Follows `target` through known shorteners to the first URL outside them.
+pub(crate) async fn unshorten(state: &AppState, target: &str) -> Option<String> {
+    let probe = state.target_probe.as_ref()?;
+    let settings = state.settings();
+    let mut url = target.to_string();
+    for _ in 0..MAX_HOPS {
+        let host = target_domain(&url)?;
+        if settings.shortener(&host).is_none() {
+            return Some(url);
+        }
+        url = probe.redirect_location(&url).await?;
+    }
+    None
+}
+
+/// With `public_targets_only`, rejects `url` unless its host resolves, and
+/// only to public addresses: no localhost, private, link-local or otherwise
+/// reserved ones. Keeps the server's own outbound fetches (HTTPS upgrade,
+/// health checks, previews) from reaching into the internal network.
+pub(crate) async fn require_public_host(state: &AppState, url: &str) -> Result<(), (StatusCode, String)> {
+    if !state.settings().public_targets_only {
+        return Ok(());
+    }
+    let rejected = |why: &str| Err((StatusCode::BAD_REQUEST, format!("target host {why}")));
+    let Some(parsed) = url::Url::parse(url).ok() else {
+        return rejected("is missing");
+    };
+    let addrs: Vec<IpAddr> = match parsed.host() {
+        Some(url::Host::Ipv4(ip)) => vec![ip.into()],
+        Some(url::Host::Ipv6(ip)) => vec![ip.into()],
+        Some(url::Host::Domain(host)) => {
+            let host = host.trim_end_matches('.').to_ascii_lowercase();
+            if host == "localhost" || host.ends_with(".localhost") {
+                return rejected("is not public");
+            }
+            let port = parsed.port_or_known_default().unwrap_or(443);
+            let resolved = tokio::net::lookup_host((host.as_str(), port)).await;
+            match resolved {
+                Ok(addrs) => addrs.map(|addr| addr.ip()).collect(),
+                Err(_) => return rejected("does not resolve"),
+            }
+        }
+        None => return rejected("is missing"),
+    };
+    if addrs.is_empty() {
+        return rejected("does not resolve");
+    }
+    // every address counts: DNS may hand the fetcher any one of them
+    if !addrs.into_iter().all(is_public) {
+        return rejected("is not public");
+    }
+    Ok(())
+}
+
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(v4) => is_public_v4(v4),
+            None => is_public_v6(ip),
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || a == 0
+        || (a == 100 && (64..128).contains(&b)) // carrier-grade NAT
+        || (a == 192 && b == 0 && c == 0) // IETF protocol assignments
+        || (a == 198 && (b == 18 || b == 19)) // benchmarking
+        || a >= 240) // reserved
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        || (first & 0xfe00) == 0xfc00 // unique local
+        || (first & 0xffc0) == 0xfe80 // link-local
+        || (first == 0x2001 && ip.segments()[1] == 0x0db8)) // documentation
+}
@@ -0,0 +1,76 @@
+//! QR landing pages: links with `qr_landing` get QR codes that encode
+//! `/q/<code>` instead of the short URL. Scanning shows a small mobile page
+//! with the link's title and destination, so people know where a printed code
+//! leads before they go there. Scans are counted in `qr_scans`, apart from
+//! clicks; the continue button goes through the normal redirect, which counts
+//! the click.
+
+use axum::{
+    http::header,
+    response::{Html, IntoResponse},
+};
+use time::OffsetDateTime;
+
+use crate::{
+    html_escape,
+    page_templates::{render, Page},
+    target_domain, AppState, LinkRow,
+};
+
+/// Counts one scan of `code`. Failures are swallowed like click recording.
+pub(crate) async fn record_scan(state: &AppState, code: &str) {
+    let _ = sqlx::query("INSERT INTO qr_scans (code, at) VALUES (?, ?)")
+        .bind(code)
+        .bind(OffsetDateTime::now_utc().unix_timestamp())
+        .execute(&state.pool)
+        .await;
+}
+
+/// Built-in page; see [`Page::QrLanding`] for the placeholders.
+const DEFAULT_PAGE: &str = r#"<!doctype html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8" />
+    <meta name="robots" content="noindex" />
+    <meta name="viewport" content="width=device-width, initial-scale=1" />
+    <title>{{domain}} via {{short_url}}</title>
+    <style>
+      body { font-family: system-ui, sans-serif; max-width: 480px; margin: 0 auto; padding: 32px 20px; font-size: 18px; }
+      h1 { font-size: 24px; margin: 0 0 12px; }
+      .target { color: #555; font-size: 15px; word-break: break-all; }
+      .continue { display: block; margin-top: 24px; padding: 16px; border-radius: 12px; background: #0b62d6; color: white; text-align: center; text-decoration: none; font-weight: 600; }
+    </style>
+  </head>
+  <body>
+    {{title}}
+    <p>This code goes to <strong>{{domain}}</strong></p>
+    <p class="target">{{target}}</p>
+    <a class="continue" href="{{continue_url}}" rel="nofollow">Continue</a>
+  </body>
+</html>"#;
+
+/// The landing page for `link`, which would send this visitor to `target`.
+/// `query` is kept on the continue link so signed URLs still verify.
+pub(crate) fn page(state: &AppState, link: &LinkRow, target: &str, query: Option<&str>) -> impl IntoResponse {
+    let mut continue_url = state.short_url(&link.code);
+    if let Some(query) = query {
+        continue_url.push('?');
+        continue_url.push_str(query);
+    }
+    let domain = target_domain(target).unwrap_or_default();
+    let title = match &link.title {
+        Some(title) => format!("<h1>{}</h1>", html_escape(title)),
+        None => String::new(),
+    };
+    let page = render(
+        state.page_templates.template(Page::QrLanding, DEFAULT_PAGE),
+        &[
+            ("short_url", &html_escape(&state.short_url(&link.code))),
+            ("title", &title),
+            ("domain", &html_escape(&domain)),
+            ("target", &html_escape(target)),
+            ("continue_url", &html_escape(&continue_url)),
+        ],
+    );
+    ([(header::CACHE_CONTROL, "no-store")], Html(page))
+}
@@ -0,0 +1,102 @@
+//! `POST /api/admin/quarantine?domain=..`: the response to a phishing wave
+//! found after links exist. Disables every link into the domain (and its
+//! subdomains), whether through its target, a device target or a schedule
+//! window, and adds it to `blocked_domains`, in one transaction with an audit
+//! entry per link.
+
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use time::OffsetDateTime;
+
+use crate::{
+    audit, internal, require_admin,
+    settings::{self, in_domain, normalize_domain},
+    target_domain, AppState,
+};
+
+/// Start of `disabled_reason` for links disabled here.
+pub(crate) const QUARANTINE_REASON_PREFIX: &str = "quarantined domain ";
+
+#[derive(Deserialize)]
+pub(crate) struct QuarantineParams {
+    domain: String,
+}
+
+#[derive(Serialize)]
+pub(crate) struct QuarantineReport {
+    domain: String,
+    /// Codes disabled by this call; links disabled earlier are not repeated.
+    disabled: Vec<String>,
+}
+
+pub(crate) async fn quarantine(
+    State(state): State<AppState>,
+    Query(params): Query<QuarantineParams>,
+    headers: HeaderMap,
+) -> Result<Json<QuarantineReport>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let domain = normalize_domain(&params.domain);
+    if domain.is_empty() || !domain.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.') {
+        return Err((StatusCode::BAD_REQUEST, "domain must be a host name".to_string()));
+    }
+
+    let mut updated = state.settings();
+    updated.blocked_domains.push(domain.clone());
+    let updated = updated
+        .normalized()
+        .map_err(|msg| (StatusCode::BAD_REQUEST, msg))?;
+    let now = OffsetDateTime::now_utc()
+        .format(&time::format_description::well_known::Rfc3339)
+        .unwrap();
+    let reason = format!("{QUARANTINE_REASON_PREFIX}{domain}");
+
+    let mut tx = state.pool.begin().await.map_err(internal)?;
+    let targets: Vec<(String, String)> = sqlx::query_as(
+        "SELECT code, target FROM ( \
+             SELECT code, target_url AS target FROM urls WHERE disabled_at IS NULL \
+             UNION ALL SELECT code, target_ios FROM urls WHERE disabled_at IS NULL AND target_ios IS NOT NULL \
+             UNION ALL SELECT code, target_android FROM urls WHERE disabled_at IS NULL AND target_android IS NOT NULL \
+             UNION ALL SELECT code, target_desktop FROM urls WHERE disabled_at IS NULL AND target_desktop IS NOT NULL \
+             UNION ALL SELECT s.code, s.target_url FROM link_schedules s JOIN urls u ON u.code = s.code \
+                       WHERE u.disabled_at IS NULL) \
+         ORDER BY code",
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(internal)?;
+    let codes: BTreeSet<String> = targets
+        .into_iter()
+        .filter(|(_, target)| target_domain(target).is_some_and(|host| in_domain(&host, &domain)))
+        .map(|(code, _)| code)
+        .collect();
+    let mut disabled = Vec::new();
+    for code in codes {
+        sqlx::query("UPDATE urls SET disabled_at = ?, disabled_reason = ? WHERE code = ?")
+            .bind(&now)
+            .bind(&reason)
+            .bind(&code)
+            .execute(&mut *tx)
+            .await
+            .map_err(internal)?;
+        audit::record(&mut tx, "admin", "link.disable", &code, Some(&reason))
+            .await
+            .map_err(internal)?;
+        disabled.push(code);
+    }
+    settings::store(&mut tx, &updated, ["blocked_domains"])
+        .await
+        .map_err(internal)?;
+    let detail = format!("{} links disabled", disabled.len());
+    audit::record(&mut tx, "admin", "domain.quarantine", &domain, Some(&detail))
+        .await
+        .map_err(internal)?;
+    tx.commit().await.map_err(internal)?;
+
+    settings::apply(&state, updated);
+    Ok(Json(QuarantineReport { domain, disabled }))
+}
@@ -0,0 +1,88 @@
+//! Second tier of click storage. Past `click_detail_days`, [`compact_clicks`]
+//! folds individual clicks into per day, country and referrer host counts,
+//! dropping IPs, user agents and full referrer URLs. Click totals, the daily
+//! series and top countries read both tiers.
+
+use std::collections::HashMap;
+
+use time::OffsetDateTime;
+
+use crate::{target_domain, AppState};
+
+/// Clicks rolled up per transaction.
+const BATCH: i64 = 5000;
+
+/// id, code, day, country, referer, is_bot
+type DetailRow = (i64, String, String, Option<String>, Option<String>, bool);
+
+/// SQL for the total clicks of the link whose code is `code_expr`, across
+/// both tiers. Bot clicks only count with `include_bots`.
+pub(crate) fn total_clicks_sql(code_expr: &str, include_bots: bool) -> String {
+    let (human, rolled) = if include_bots { ("", "r.clicks") } else { (" AND NOT c.is_bot", "r.clicks - r.bots") };
+    format!(
+        "((SELECT count(*) FROM clicks c WHERE c.code = {code_expr}{human}) + \
+          (SELECT coalesce(sum({rolled}), 0) FROM click_rollups r WHERE r.code = {code_expr}))"
+    )
+}
+
+/// Rolls up clicks older than `click_detail_days` and deletes them. Returns
+/// how many clicks were compacted; does nothing when the setting is unset or
+/// the service is read-only.
+pub async fn compact_clicks(state: &AppState) -> Result<u64, sqlx::Error> {
+    let Some(days) = state.settings().click_detail_days else {
+        return Ok(0);
+    };
+    if state.is_read_only() {
+        return Ok(0);
+    }
+    let cutoff = (OffsetDateTime::now_utc() - time::Duration::days(days.into())).unix_timestamp();
+
+    let mut compacted = 0;
+    loop {
+        let mut tx = state.pool.begin().await?;
+        let rows: Vec<DetailRow> = sqlx::query_as(
+            "SELECT id, code, date(at, 'unixepoch'), country, referer, is_bot FROM clicks \
+             WHERE at < ? ORDER BY id LIMIT ?",
+        )
+        .bind(cutoff)
+        .bind(BATCH)
+        .fetch_all(&mut *tx)
+        .await?;
+        let Some(&(last_id, ..)) = rows.last() else {
+            break;
+        };
+
+        // (clicks, of which bots)
+        let mut counts: HashMap<(String, String, String, String), (i64, i64)> = HashMap::new();
+        for (_, code, day, country, referer, is_bot) in &rows {
+            let referrer = referer.as_deref().and_then(target_domain).unwrap_or_default();
+            let key = (code.clone(), day.clone(), country.clone().unwrap_or_default(), referrer);
+            let count = counts.entry(key).or_default();
+            count.0 += 1;
+            count.1 += i64::from(*is_bot);
+        }
+        for ((code, day, country, referrer), (clicks, bots)) in counts {
+            sqlx::query(
+                "INSERT INTO click_rollups (code, day, country, referrer, clicks, bots) VALUES (?, ?, ?, ?, ?, ?) \
+                 ON CONFLICT (code, day, country, referrer) \
+                 DO UPDATE SET clicks = clicks + excluded.clicks, bots = bots + excluded.bots",
+            )
+            .bind(code)
+            .bind(day)
+            .bind(country)
+            .bind(referrer)
+            .bind(clicks)
+            .bind(bots)
+            .execute(&mut *tx)
+            .await?;
+        }
+        sqlx::query("DELETE FROM clicks WHERE at < ? AND id <= ?")
+            .bind(cutoff)
+            .bind(last_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        compacted += rows.len() as u64;
+    }
+    Ok(compacted)
+}
@@ -0,0 +1,160 @@
+//! Rotating destinations: a link can point at other targets during time
+//! windows, e.g. `/sale` at a different page each week. Outside every window
+//! it redirects to its own `target_url`; where windows overlap, the one that
+//! started last wins.
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::{api_keys, fetch_link, internal, probe, unix_to_rfc3339, validate_target, AppState};
+
+/// Most windows one link may have.
+pub(crate) const MAX_WINDOWS: usize = 100;
+
+#[derive(Deserialize)]
+pub(crate) struct PutScheduleReq {
+    windows: Vec<WindowReq>,
+}
+
+#[derive(Deserialize)]
+struct WindowReq {
+    target_url: String,
+    /// RFC3339.
+    starts_at: String,
+    /// RFC3339; open-ended when absent.
+    ends_at: Option<String>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct Window {
+    pub(crate) target_url: String,
+    pub(crate) starts_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) ends_at: Option<String>,
+    /// Whether this window decides the target right now.
+    pub(crate) active: bool,
+}
+
+#[derive(Serialize)]
+pub(crate) struct Schedule {
+    code: String,
+    /// Where the link redirects right now.
+    live_target: String,
+    windows: Vec<Window>,
+}
+
+/// Every window of `code`, earliest first.
+pub(crate) async fn windows(state: &AppState, code: &str) -> Result<Vec<Window>, sqlx::Error> {
+    let rows: Vec<(i64, String, i64, Option<i64>)> = sqlx::query_as(
+        "SELECT id, target_url, starts_at, ends_at FROM link_schedules WHERE code = ? ORDER BY starts_at, id",
+    )
+    .bind(code)
+    .fetch_all(&state.pool)
+    .await?;
+    let now = OffsetDateTime::now_utc().unix_timestamp();
+    let active = rows
+        .iter()
+        .filter(|(_, _, start, end)| *start <= now && end.is_none_or(|end| end > now))
+        .max_by_key(|(id, _, start, _)| (*start, *id))
+        .map(|(id, ..)| *id);
+    Ok(rows
+        .into_iter()
+        .map(|(id, target_url, starts_at, ends_at)| Window {
+            target_url,
+            starts_at: unix_to_rfc3339(starts_at),
+            ends_at: ends_at.map(unix_to_rfc3339),
+            active: Some(id) == active,
+        })
+        .collect())
+}
+
+async fn schedule(state: &AppState, code: String, fallback: String) -> Result<Schedule, sqlx::Error> {
+    let windows = windows(state, &code).await?;
+    let live_target = windows
+        .iter()
+        .find(|w| w.active)
+        .map_or(fallback, |w| w.target_url.clone());
+    Ok(Schedule {
+        code,
+        live_target,
+        windows,
+    })
+}
+
+/// `GET /api/links/:code/schedule`.
+pub(crate) async fn get_schedule(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+) -> Result<Json<Schedule>, (StatusCode, String)> {
+    let Some(link) = fetch_link(&state, &code).await.map_err(internal)? else {
+        return Err((StatusCode::NOT_FOUND, "not found".to_string()));
+    };
+    let schedule = schedule(&state, link.code, link.target_url).await.map_err(internal)?;
+    Ok(Json(schedule))
+}
+
+/// `PUT /api/links/:code/schedule` replaces every window; an empty list
+/// removes the schedule. Targets pass the same checks as new links. Needs
+/// an API key.
+pub(crate) async fn put_schedule(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<PutScheduleReq>,
+) -> Result<Json<Schedule>, (StatusCode, String)> {
+    api_keys::require_api_key(&state, &headers).await?;
+    let Some(link) = fetch_link(&state, &code).await.map_err(internal)? else {
+        return Err((StatusCode::NOT_FOUND, "not found".to_string()));
+    };
+    if req.windows.len() > MAX_WINDOWS {
+        return Err((StatusCode::BAD_REQUEST, format!("at most {MAX_WINDOWS} windows per link")));
+    }
+
+    let mut windows = Vec::with_capacity(req.windows.len());
+    for (i, window) in req.windows.into_iter().enumerate() {
+        let bad = |msg: String| (StatusCode::BAD_REQUEST, format!("windows[{i}]: {msg}"));
+        let target = validate_target(&state, &window.target_url).map_err(|(_, e)| bad(e))?;
+        probe::require_public_host(&state, &target).await.map_err(|(_, e)| bad(e))?;
+        let starts_at = parse_time(&window.starts_at).ok_or_else(|| bad("starts_at must be RFC3339".to_string()))?;
+        let ends_at = match &window.ends_at {
+            Some(end) => Some(parse_time(end).ok_or_else(|| bad("ends_at must be RFC3339".to_string()))?),
+            None => None,
+        };
+        if ends_at.is_some_and(|end| end <= starts_at) {
+            return Err(bad("ends_at must be after starts_at".to_string()));
+        }
+        windows.push((target, starts_at, ends_at));
+    }
+
+    let mut tx = state.pool.begin().await.map_err(internal)?;
+    sqlx::query("DELETE FROM link_schedules WHERE code = ?")
+        .bind(&link.code)
+        .execute(&mut *tx)
+        .await
+        .map_err(internal)?;
+    for (target, starts_at, ends_at) in windows {
+        sqlx::query("INSERT INTO link_schedules (code, target_url, starts_at, ends_at) VALUES (?, ?, ?, ?)")
+            .bind(&link.code)
+            .bind(target)
+            .bind(starts_at)
+            .bind(ends_at)
+            .execute(&mut *tx)
+            .await
+            .map_err(internal)?;
+    }
+    tx.commit().await.map_err(internal)?;
+
+    let schedule = schedule(&state, link.code, link.target_url).await.map_err(internal)?;
+    Ok(Json(schedule))
+}
+
+pub(crate) fn parse_time(value: &str) -> Option<i64> {
+    OffsetDateTime::parse(value, &time::format_description::well_known::Rfc3339)
+        .ok()
+        .map(OffsetDateTime::unix_timestamp)
+}
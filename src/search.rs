@@ -0,0 +1,166 @@
+//! Keyword search over links, used by `/api/search`, the dashboard search box
+//! and the go-links "did you mean" page.
+
+use axum::{
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{html_escape, internal, AppState};
+
+const MAX_RESULTS: i64 = 20;
+
+/// Most codes [`closest_codes`] scores, so an unknown code can't make a 404
+/// walk the whole table.
+const MAX_CANDIDATES: i64 = 1000;
+
+#[derive(Deserialize)]
+pub(crate) struct SearchParams {
+    #[serde(default)]
+    q: String,
+}
+
+#[derive(Serialize)]
+pub(crate) struct SearchHit {
+    code: String,
+    short_url: String,
+    target_url: String,
+}
+
+/// Case-insensitive substring match on code, title and target; exact code
+/// matches first, then code prefixes, then everything else.
+pub(crate) async fn search_links(state: &AppState, query: &str) -> Result<Vec<SearchHit>, sqlx::Error> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let pattern = like_pattern(&query);
+    let rows: Vec<(String, String)> = sqlx::query_as(
+        "SELECT code, target_url FROM urls \
+         WHERE lower(code) LIKE ?1 ESCAPE '\\' OR lower(title) LIKE ?1 ESCAPE '\\' \
+            OR lower(target_url) LIKE ?1 ESCAPE '\\' \
+         ORDER BY lower(code) = ?2 DESC, lower(code) LIKE ?2 || '%' DESC, length(code), code \
+         LIMIT ?3",
+    )
+    .bind(&pattern)
+    .bind(&query)
+    .bind(MAX_RESULTS)
+    .fetch_all(&state.pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(code, target_url)| SearchHit {
+            short_url: state.short_url(&code),
+            code,
+            target_url,
+        })
+        .collect())
+}
+
+/// `LIKE` pattern (with `ESCAPE '\'`) matching values that contain the
+/// lowercased `query`.
+pub(crate) fn like_pattern(query: &str) -> String {
+    format!(
+        "%{}%",
+        query
+            .to_lowercase()
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    )
+}
+
+pub(crate) async fn search(
+    State(state): State<AppState>,
+    Query(params): Query<SearchParams>,
+) -> Result<Json<Vec<SearchHit>>, (StatusCode, String)> {
+    Ok(Json(search_links(&state, &params.q).await.map_err(internal)?))
+}
+
+/// Codes of live links closest to `code` by edit distance, best first. Only
+/// codes that could be close enough are scored: those of a similar length,
+/// and those containing `code` or contained in it.
+pub(crate) async fn closest_codes(state: &AppState, code: &str) -> Result<Vec<String>, sqlx::Error> {
+    let wanted = code.to_lowercase();
+    let max_distance = (wanted.len() / 3).max(2);
+    let codes: Vec<(String,)> = sqlx::query_as(
+        "SELECT code FROM urls WHERE archived_at IS NULL \
+         AND (length(code) BETWEEN ?1 AND ?2 OR instr(lower(code), ?3) > 0 OR instr(?3, lower(code)) > 0) \
+         LIMIT ?4",
+    )
+    .bind(wanted.chars().count().saturating_sub(max_distance) as i64)
+    .bind((wanted.chars().count() + max_distance) as i64)
+    .bind(&wanted)
+    .bind(MAX_CANDIDATES)
+    .fetch_all(&state.pool)
+    .await?;
+    let mut scored: Vec<(usize, String)> = codes
+        .into_iter()
+        .filter_map(|(candidate,)| {
+            let lower = candidate.to_lowercase();
+            let distance = if lower.contains(&wanted) || wanted.contains(&lower) {
+                0
+            } else {
+                levenshtein(&wanted, &lower)
+            };
+            (distance <= max_distance).then_some((distance, candidate))
+        })
+        .collect();
+    scored.sort();
+    Ok(scored.into_iter().take(5).map(|(_, code)| code).collect())
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut row = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            row[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(row[j] + 1);
+        }
+        prev = row;
+    }
+    prev[b.len()]
+}
+
+/// 404 page for go-links mode listing the closest keywords.
+pub(crate) fn suggestions_page(state: &AppState, code: &str, suggestions: &[String]) -> impl IntoResponse {
+    let items = if suggestions.is_empty() {
+        "<p>No similar links.</p>".to_string()
+    } else {
+        let links: String = suggestions
+            .iter()
+            .map(|s| {
+                format!(
+                    "<li><a href=\"{}\">{}</a></li>",
+                    html_escape(&state.short_url(s)),
+                    html_escape(s)
+                )
+            })
+            .collect();
+        format!("<p>Did you mean:</p><ul>{links}</ul>")
+    };
+    let page = format!(
+        r#"<!doctype html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8" />
+    <meta name="robots" content="noindex" />
+    <title>No link for {code}</title>
+  </head>
+  <body>
+    <h1>No link for {code}</h1>
+    {items}
+  </body>
+</html>"#,
+        code = html_escape(code),
+    );
+    (
+        [(header::CACHE_CONTROL, "no-store")],
+        axum::response::Html(page),
+    )
+}
@@ -0,0 +1,270 @@
+//! Sample links and synthetic click history, for demo mode and local testing.
+
+use rand::{seq::SliceRandom, Rng};
+use time::{Duration as TimeDuration, OffsetDateTime};
+
+use crate::{insert_url, AppState, InsertUrlError, NewUrl, RedirectMode, RedirectType};
+
+const DEMO_LINKS: &[(&str, &str, bool)] = &[
+    ("rustbook", "https://doc.rust-lang.org/book/", true),
+    ("axum", "https://github.com/tokio-rs/axum", true),
+    ("sqlx", "https://github.com/launchbadge/sqlx", true),
+    ("tokio", "https://tokio.rs/tokio/tutorial", false),
+    ("crates", "https://crates.io/", false),
+];
+
+const COUNTRIES: &[&str] = &[
+    "US", "US", "US", "RO", "RO", "DE", "GB", "FR", "IN", "BR", "CA", "NL", "JP", "ES",
+];
+
+const LANGUAGES: &[&str] = &["en", "en", "en", "ro", "de", "fr", "es", "pt", "ja"];
+
+const USER_AGENTS: &[&str] = &[
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0 Safari/537.36",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 14_4) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.4 Safari/605.1.15",
+    "Mozilla/5.0 (iPhone; CPU iPhone OS 17_4 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.4 Mobile/15E148 Safari/604.1",
+    "Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0 Mobile Safari/537.36",
+    "Mozilla/5.0 (X11; Linux x86_64; rv:125.0) Gecko/20100101 Firefox/125.0",
+];
+
+const REFERERS: &[Option<&str>] = &[
+    None,
+    None,
+    Some("https://www.google.com/"),
+    Some("https://t.co/"),
+    Some("https://www.facebook.com/"),
+    Some("https://www.linkedin.com/"),
+    Some("https://mail.google.com/"),
+];
+
+const DOMAINS: &[&str] = &[
+    "example.com",
+    "shop.example.com",
+    "blog.example.org",
+    "docs.example.net",
+    "news.example.com",
+];
+
+const PATHS: &[&str] = &["spring-sale", "launch", "pricing", "docs/start", "careers", "webinar", "blog/post"];
+
+/// Options for [`seed_synthetic`].
+#[derive(Clone, Debug)]
+pub struct SeedOptions {
+    /// Number of links to create.
+    pub links: usize,
+    /// Clicks are spread over this many past days.
+    pub days: i64,
+    /// Upper bound for clicks on a single link. Counts are skewed so that a
+    /// few links get most of the traffic.
+    pub max_clicks_per_link: usize,
+}
+
+impl Default for SeedOptions {
+    fn default() -> Self {
+        Self {
+            links: 100,
+            days: 30,
+            max_clicks_per_link: 500,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SeedReport {
+    pub links: usize,
+    pub clicks: usize,
+}
+
+/// Creates `options.links` links with random targets and click histories.
+pub async fn seed_synthetic(state: &AppState, options: &SeedOptions) -> anyhow::Result<SeedReport> {
+    let mut report = SeedReport::default();
+    while report.links < options.links {
+        let (target, clicks) = {
+            let mut rng = rand::thread_rng();
+            let target = format!(
+                "https://{}/{}?id={}",
+                DOMAINS.choose(&mut rng).unwrap(),
+                PATHS.choose(&mut rng).unwrap(),
+                rng.gen_range(1..100_000)
+            );
+            let skew: f64 = rng.gen::<f64>().powi(3);
+            (target, (skew * options.max_clicks_per_link as f64) as usize)
+        };
+        let code = {
+            let mut conn = state.pool.acquire().await?;
+            state.code_generator.generate(&mut conn).await?
+        };
+        let new_url = NewUrl {
+            target_url: &target,
+            expires_at: None,
+            created_ip: None,
+            created_user_agent: Some("synthetic-seed"),
+            listed: false,
+            utm: None,
+            redirect_mode: RedirectMode::default(),
+            redirect_type: RedirectType::default(),
+            forward_query: false,
+            path_forwarding: false,
+            qr_landing: false,
+            device_targets: None,
+            signed_only: false,
+            created_by: None,
+            pending_review: false,
+            original_url: None,
+            chained_via: None,
+            title: None,
+            notes: None,
+            max_clicks: None,
+            not_before: None,
+        };
+        match insert_url(state, &code, &new_url).await {
+            Ok(()) => {}
+            Err(InsertUrlError::CodeTaken) => continue,
+            Err(InsertUrlError::Other(e)) => return Err(e),
+        }
+        if clicks > 0 {
+            insert_synthetic_clicks(state, &code, clicks, options.days).await?;
+        }
+        report.links += 1;
+        report.clicks += clicks;
+    }
+    Ok(report)
+}
+
+/// Busier during the day than at night, like real traffic.
+const HOURS: &[i64] = &[
+    0, 6, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13, 14, 14, 15, 15, 16, 17, 18, 19, 20, 21, 22,
+];
+
+/// Creates the demo links (skipping any that already exist) with a month of
+/// synthetic clicks each.
+pub async fn seed_demo(state: &AppState) -> anyhow::Result<()> {
+    for (code, target, listed) in DEMO_LINKS {
+        let new_url = NewUrl {
+            target_url: target,
+            expires_at: None,
+            created_ip: None,
+            created_user_agent: Some("demo-seed"),
+            listed: *listed,
+            utm: None,
+            redirect_mode: RedirectMode::default(),
+            redirect_type: RedirectType::default(),
+            forward_query: false,
+            path_forwarding: false,
+            qr_landing: false,
+            device_targets: None,
+            signed_only: false,
+            created_by: None,
+            pending_review: false,
+            original_url: None,
+            chained_via: None,
+            title: None,
+            notes: None,
+            max_clicks: None,
+            not_before: None,
+        };
+        match insert_url(state, code, &new_url).await {
+            Ok(()) => {}
+            Err(InsertUrlError::CodeTaken) => continue,
+            Err(InsertUrlError::Other(e)) => return Err(e),
+        }
+        let clicks = rand::thread_rng().gen_range(40..400);
+        insert_synthetic_clicks(state, code, clicks, 30).await?;
+    }
+    Ok(())
+}
+
+/// Inserts `count` clicks for `code` spread over the last `days` days, drawn
+/// from a visitor pool about a third the size so unique counts look realistic.
+pub(crate) async fn insert_synthetic_clicks(
+    state: &AppState,
+    code: &str,
+    count: usize,
+    days: i64,
+) -> anyhow::Result<()> {
+    let clicks: Vec<SyntheticClick> = {
+        let mut rng = rand::thread_rng();
+        let visitors: Vec<Visitor> = (0..(count / 3).max(1))
+            .map(|_| Visitor::random(&mut rng))
+            .collect();
+        let now = OffsetDateTime::now_utc();
+        (0..count)
+            .map(|_| {
+                let visitor = visitors.choose(&mut rng).unwrap().clone();
+                let at = now
+                    - TimeDuration::days(rng.gen_range(0..days.max(1)))
+                    - TimeDuration::hours(*HOURS.choose(&mut rng).unwrap())
+                    - TimeDuration::minutes(rng.gen_range(0..60));
+                SyntheticClick {
+                    at: at.min(now),
+                    referer: *REFERERS.choose(&mut rng).unwrap(),
+                    visitor,
+                }
+            })
+            .collect()
+    };
+
+    let mut tx = state.pool.begin().await?;
+    for click in clicks {
+        let visitor_id = state.fingerprint.visitor_id(
+            &click.visitor.ip,
+            Some(click.visitor.user_agent),
+            Some(click.visitor.language),
+            click.at,
+        );
+        let parsed = crate::user_agents::parse(click.visitor.user_agent);
+        sqlx::query(
+            "INSERT INTO clicks (code, at, ip, user_agent, referer, country, city, visitor_id, language, \
+                                 channel, browser, os, device_type, is_bot) \
+             VALUES (?, ?, ?, ?, ?, ?, NULL, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(code)
+        .bind(click.at.unix_timestamp())
+        .bind(&click.visitor.ip)
+        .bind(click.visitor.user_agent)
+        .bind(click.referer)
+        .bind(click.visitor.country)
+        .bind(visitor_id)
+        .bind(click.visitor.language)
+        .bind(crate::channels::classify(click.referer, &[]).as_str())
+        .bind(parsed.browser)
+        .bind(parsed.os)
+        .bind(parsed.device_type)
+        .bind(parsed.is_bot())
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    crate::hll::rebuild(state, code).await?;
+    Ok(())
+}
+
+#[derive(Clone)]
+struct Visitor {
+    ip: String,
+    country: &'static str,
+    language: &'static str,
+    user_agent: &'static str,
+}
+
+impl Visitor {
+    fn random(rng: &mut impl Rng) -> Self {
+        Self {
+            // TEST-NET ranges, so seeded IPs never belong to real hosts
+            ip: format!(
+                "{}.{}",
+                ["192.0.2", "198.51.100", "203.0.113"].choose(rng).unwrap(),
+                rng.gen_range(1..255)
+            ),
+            country: COUNTRIES.choose(rng).unwrap(),
+            language: LANGUAGES.choose(rng).unwrap(),
+            user_agent: USER_AGENTS.choose(rng).unwrap(),
+        }
+    }
+}
+
+struct SyntheticClick {
+    at: OffsetDateTime,
+    referer: Option<&'static str>,
+    visitor: Visitor,
+}
@@ -0,0 +1,201 @@
+//! Configuration self-check. At startup the server checks what it can
+//! without the network (base URL format, database writes, geo lookup,
+//! secrets) and
+//! logs one line per check, or refuses to start with `SELF_CHECK=strict`.
+//! Once it is listening it also checks that the base URL and the webhook
+//! endpoints answer. `GET /api/admin/selfcheck` runs every check on demand.
+//!
+//! A misconfigured `BASE_URL` otherwise only shows up later, as short links
+//! and QR codes that go nowhere.
+
+use axum::{extract::State, http::HeaderMap, http::StatusCode, Json};
+use serde::Serialize;
+
+use crate::{require_admin, target_domain, AppState};
+
+/// Host geo lookups go to; see `geo_country_lookup`.
+const GEO_SERVICE: &str = "https://ipapi.co/";
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    /// Works, but probably not as intended.
+    Warn,
+    /// Broken; `SELF_CHECK=strict` refuses to start.
+    Fail,
+    /// Not checked, e.g. network checks before the server listens.
+    Skipped,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SelfCheckReport {
+    /// No check failed.
+    pub ok: bool,
+    pub checks: Vec<Check>,
+}
+
+impl Check {
+    /// Logs the check at a level matching its status.
+    pub fn log(&self) {
+        match self.status {
+            CheckStatus::Ok | CheckStatus::Skipped => {
+                tracing::info!(check = self.name, status = ?self.status, "{}", self.detail)
+            }
+            CheckStatus::Warn => tracing::warn!(check = self.name, status = ?self.status, "{}", self.detail),
+            CheckStatus::Fail => tracing::error!(check = self.name, status = ?self.status, "{}", self.detail),
+        }
+    }
+}
+
+fn check(name: &'static str, status: CheckStatus, detail: impl Into<String>) -> Check {
+    Check {
+        name,
+        status,
+        detail: detail.into(),
+    }
+}
+
+/// Shape of `base_url`: short links are built from it, so it must be an
+/// absolute http(s) URL without query or fragment.
+fn base_url_format(state: &AppState) -> Check {
+    let url = match url::Url::parse(&state.base_url) {
+        Ok(url) => url,
+        Err(e) => return check("base_url", CheckStatus::Fail, format!("{} is not a URL: {e}", state.base_url)),
+    };
+    if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+        return check("base_url", CheckStatus::Fail, format!("{} is not an http(s) URL with a host", state.base_url));
+    }
+    if url.query().is_some() || url.fragment().is_some() {
+        return check("base_url", CheckStatus::Fail, format!("{} has a query or fragment", state.base_url));
+    }
+    let local = matches!(url.host_str(), Some("localhost" | "127.0.0.1" | "[::1]"));
+    if local && !state.demo {
+        return check(
+            "base_url",
+            CheckStatus::Warn,
+            format!("{} only works on this machine; set BASE_URL to the public address", state.base_url),
+        );
+    }
+    check("base_url", CheckStatus::Ok, state.base_url.clone())
+}
+
+/// Whether the database takes writes: creates a table and rolls back.
+async fn database(state: &AppState) -> Check {
+    let result = async {
+        let mut tx = state.pool.begin().await?;
+        sqlx::query("CREATE TABLE selfcheck_probe (x INTEGER)").execute(&mut *tx).await?;
+        tx.rollback().await
+    }
+    .await;
+    match result {
+        Ok(()) => check("database", CheckStatus::Ok, "writable"),
+        Err(e) => check("database", CheckStatus::Fail, format!("not writable: {e}")),
+    }
+}
+
+fn geo(state: &AppState) -> Check {
+    match (state.geo_lookup, cfg!(feature = "geo")) {
+        (false, _) => check("geo", CheckStatus::Ok, "IP lookups off; countries come from CDN headers only"),
+        (true, false) => check(
+            "geo",
+            CheckStatus::Warn,
+            "GEO_LOOKUP is on but the geo feature is not built in; countries come from CDN headers only",
+        ),
+        (true, true) => check("geo", CheckStatus::Ok, format!("IP lookups through {GEO_SERVICE}")),
+    }
+}
+
+/// Secrets that are random per process unless set. Outside demo mode a
+/// random one breaks things on every restart, and between instances.
+fn secrets(state: &AppState) -> [Check; 2] {
+    let secret = |name: &'static str, var: &str, generated: bool, effect: &str| {
+        if !generated {
+            check(name, CheckStatus::Ok, format!("{var} is set"))
+        } else if state.demo {
+            check(name, CheckStatus::Ok, format!("{var} is not set (demo mode)"))
+        } else {
+            check(name, CheckStatus::Warn, format!("{var} is not set: {effect}"))
+        }
+    };
+    [
+        secret(
+            "signing",
+            "SIGNING_SECRET",
+            state.signing_secret_generated,
+            "signed links stop working on every restart, and instances reject each other's",
+        ),
+        secret(
+            "visitor_ids",
+            "VISITOR_SECRET",
+            state.fingerprint.has_generated_secret(),
+            "visitor ids change on every restart and differ between instances",
+        ),
+    ]
+}
+
+/// Runs the checks. With `online`, also sends requests through the target
+/// probe: to the base URL's `/health`, the geo service and every webhook
+/// notifier; without, those are skipped.
+pub async fn self_check(state: &AppState, online: bool) -> SelfCheckReport {
+    let mut checks = vec![base_url_format(state), database(state).await, geo(state)];
+    checks.extend(secrets(state));
+
+    let probe = state.target_probe.as_ref().filter(|_| online);
+    let health = state.public_url("/health");
+    checks.push(match probe {
+        None => check("base_url_reachable", CheckStatus::Skipped, health),
+        Some(_) if checks[0].status == CheckStatus::Fail => {
+            check("base_url_reachable", CheckStatus::Skipped, "base_url is invalid")
+        }
+        Some(probe) if probe.reachable(&health).await => check("base_url_reachable", CheckStatus::Ok, health),
+        Some(_) => check(
+            "base_url_reachable",
+            CheckStatus::Warn,
+            format!("{health} did not answer; short links may not reach this server"),
+        ),
+    });
+    if state.geo_lookup && cfg!(feature = "geo") {
+        checks.push(match probe {
+            None => check("geo_reachable", CheckStatus::Skipped, GEO_SERVICE),
+            Some(probe) if probe.reachable(GEO_SERVICE).await => check("geo_reachable", CheckStatus::Ok, GEO_SERVICE),
+            Some(_) => check("geo_reachable", CheckStatus::Warn, format!("{GEO_SERVICE} did not answer")),
+        });
+    }
+
+    for url in state.notifiers.iter().filter_map(|n| n.endpoint()) {
+        let host = target_domain(url).unwrap_or_else(|| "invalid URL".to_string());
+        checks.push(if !cfg!(feature = "webhooks") {
+            check("webhook", CheckStatus::Fail, format!("{host}: the webhooks feature is not built in"))
+        } else if target_domain(url).is_none() {
+            check("webhook", CheckStatus::Fail, "a webhook URL is not a valid URL")
+        } else {
+            match probe {
+                None => check("webhook", CheckStatus::Skipped, host),
+                Some(probe) if probe.reachable(url).await => check("webhook", CheckStatus::Ok, host),
+                Some(_) => check("webhook", CheckStatus::Warn, format!("{host} did not answer; deliveries will be retried")),
+            }
+        });
+    }
+
+    SelfCheckReport {
+        ok: checks.iter().all(|c| c.status != CheckStatus::Fail),
+        checks,
+    }
+}
+
+/// `GET /api/admin/selfcheck`: every check, network ones included. Admin only.
+pub(crate) async fn selfcheck(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<SelfCheckReport>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    Ok(Json(self_check(&state, true).await))
+}
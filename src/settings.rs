@@ -0,0 +1,390 @@
+//! Runtime-tunable policy, stored in the `settings` table and editable through
+//! `GET/PUT /api/admin/settings` without restarting the service.
+
+use axum::{extract::State, http::HeaderMap, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, time::Duration};
+use time::OffsetDateTime;
+
+use crate::{internal, require_admin, AppState};
+
+/// Longest `banner` accepted.
+const MAX_BANNER_LEN: usize = 500;
+
+/// Operator policy. Each field is stored as its own row (`key` = field name,
+/// `value` = JSON), so new fields fall back to their defaults.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+#[non_exhaustive]
+pub struct Settings {
+    /// Requests per window allowed per client IP on write endpoints.
+    pub rate_limit_requests: usize,
+    pub rate_limit_window_secs: u64,
+    /// Expiry applied to new links created without `expires_at`.
+    pub default_expiry_days: Option<u32>,
+    /// New links to these domains, or their subdomains, are rejected.
+    pub blocked_domains: Vec<String>,
+    /// Clicks older than this are deleted by [`purge_old_clicks`]. Visitor
+    /// sketches are kept, so unique-visitor estimates survive the purge.
+    pub click_retention_days: Option<u32>,
+    /// Clicks older than this are rolled up into daily per country and
+    /// referrer counts by [`crate::compact_clicks`].
+    pub click_detail_days: Option<u32>,
+    /// Code prefix (e.g. `mkt-`) -> name of the API key allowed to create
+    /// custom codes under it. Generated codes never start with a reserved
+    /// prefix; admins may use any.
+    pub reserved_prefixes: BTreeMap<String, String>,
+    /// Codes nobody may take, matched ignoring case, such as the app's own
+    /// routes. Generated codes skip them too.
+    pub reserved_codes: Vec<String>,
+    /// Shortening a URL the caller already has a plain link for returns that
+    /// link. Requests can override it with `reuse_existing`.
+    pub reuse_existing_links: bool,
+    /// Most links accepted by one `POST /api/shorten/batch`.
+    pub batch_shorten_limit: usize,
+    /// Whether `/api/shorten` and clone accept requests without an API key.
+    /// The `anonymous_*` limits below apply only to such requests.
+    pub anonymous_shorten: bool,
+    /// Anonymous links expire within this many days, whatever they ask for.
+    pub anonymous_expiry_days: Option<u32>,
+    /// Links an anonymous client IP may create per rolling 24 hours.
+    pub anonymous_daily_limit: Option<u32>,
+    /// Hold anonymous links for admin approval before they redirect.
+    pub anonymous_moderation: bool,
+    /// Store `http://` targets as `https://` when the HTTPS version answers.
+    pub https_upgrade: bool,
+    /// Hosts treated as URL shorteners. Links into them are flagged, since
+    /// chained shorteners hide the destination and add a hop.
+    pub known_shorteners: Vec<String>,
+    /// Follow links into known shorteners and store the final destination.
+    pub resolve_shortener_chains: bool,
+    /// Reject new targets unless their host resolves to public addresses
+    /// only (no localhost, private or reserved ranges). Costs a DNS lookup
+    /// per target.
+    pub public_targets_only: bool,
+    /// How often each link's target is checked by [`crate::check_targets`];
+    /// `None` disables health checks.
+    pub health_check_interval_hours: Option<u32>,
+    /// Serve a "destination unavailable" page instead of redirecting to
+    /// targets the health check found dead.
+    pub dead_target_page: bool,
+    /// Run [`crate::detect_anomalies`] on click traffic.
+    pub anomaly_detection: bool,
+    /// Receives a JSON `POST` for each anomaly found.
+    pub anomaly_webhook_url: Option<String>,
+    /// Notify once when a link is this close to expiring; see
+    /// [`crate::warn_expiring_links`]. `None` disables the warnings.
+    pub expiry_warning_hours: Option<u32>,
+    /// Attempts per webhook notification, the first included, before it is
+    /// dead-lettered; see [`crate::retry_webhooks`].
+    pub webhook_max_attempts: u32,
+    /// Send link lifecycle events (created, edited, expired, click limit
+    /// reached) to the deployment notifiers.
+    pub link_events: bool,
+    /// Visitors of unknown, expired, used-up or disabled links are redirected
+    /// here instead of getting an error.
+    pub fallback_url: Option<String>,
+    /// HTML shown to browsers for unknown links, instead of the built-in page.
+    /// `{{code}}`, `{{message}}` and `{{status}}` are replaced, escaped.
+    pub not_found_page: Option<String>,
+    /// Like `not_found_page`, for expired, archived and used-up links.
+    pub gone_page: Option<String>,
+    /// Like `not_found_page`, for links disabled by an admin.
+    pub disabled_page: Option<String>,
+    /// Refuse writes with 503, e.g. during a restore; see
+    /// [`AppState::is_read_only`].
+    pub read_only: bool,
+    /// Plain-text notice shown at the top of every dashboard page, such as
+    /// "Maintenance at 22:00 UTC".
+    pub banner: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            rate_limit_requests: 10,
+            rate_limit_window_secs: 60,
+            default_expiry_days: None,
+            blocked_domains: Vec::new(),
+            click_retention_days: None,
+            click_detail_days: None,
+            reserved_prefixes: BTreeMap::new(),
+            reserved_codes: [
+                "admin", "api", "assets", "dashboard", "favicon", "health", "links", "login",
+                "logout", "metrics", "q", "robots", "static",
+            ]
+            .map(String::from)
+            .to_vec(),
+            reuse_existing_links: false,
+            batch_shorten_limit: 1000,
+            anonymous_shorten: true,
+            anonymous_expiry_days: None,
+            anonymous_daily_limit: None,
+            anonymous_moderation: false,
+            https_upgrade: false,
+            known_shorteners: [
+                "bit.ly", "buff.ly", "cutt.ly", "goo.gl", "is.gd", "ow.ly", "rb.gy",
+                "rebrand.ly", "shorturl.at", "t.co", "tiny.cc", "tinyurl.com",
+            ]
+            .map(String::from)
+            .to_vec(),
+            resolve_shortener_chains: false,
+            public_targets_only: false,
+            health_check_interval_hours: None,
+            dead_target_page: false,
+            anomaly_detection: false,
+            anomaly_webhook_url: None,
+            expiry_warning_hours: None,
+            webhook_max_attempts: 8,
+            link_events: false,
+            fallback_url: None,
+            not_found_page: None,
+            gone_page: None,
+            disabled_page: None,
+            read_only: false,
+            banner: None,
+        }
+    }
+}
+
+impl Settings {
+    /// Whether `host` is a blocked domain or a subdomain of one.
+    pub fn is_blocked(&self, host: &str) -> bool {
+        self.blocked_domains.iter().any(|domain| in_domain(host, domain))
+    }
+
+    /// `expires_at` for a link created now under `default_expiry_days`.
+    pub(crate) fn default_expires_at(&self) -> Option<String> {
+        let days = self.default_expiry_days?;
+        (OffsetDateTime::now_utc() + time::Duration::days(days.into()))
+            .format(&time::format_description::well_known::Rfc3339)
+            .ok()
+    }
+
+    /// The known shortener `host` belongs to, if any.
+    pub fn shortener(&self, host: &str) -> Option<&str> {
+        self.known_shorteners
+            .iter()
+            .find(|domain| in_domain(host, domain))
+            .map(String::as_str)
+    }
+
+    /// `requested` capped at `anonymous_expiry_days` from now.
+    pub(crate) fn anonymous_expires_at(&self, requested: Option<String>) -> Option<String> {
+        let Some(days) = self.anonymous_expiry_days else {
+            return requested;
+        };
+        let rfc3339 = &time::format_description::well_known::Rfc3339;
+        let cap = OffsetDateTime::now_utc() + time::Duration::days(days.into());
+        match requested {
+            Some(exp) if OffsetDateTime::parse(&exp, rfc3339).is_ok_and(|exp| exp <= cap) => Some(exp),
+            _ => cap.format(rfc3339).ok(),
+        }
+    }
+
+    /// The reserved prefix `code` falls under, with its owner. Matching
+    /// ignores case; the longest prefix wins.
+    pub fn prefix_owner(&self, code: &str) -> Option<(&str, &str)> {
+        let code = code.to_ascii_lowercase();
+        self.reserved_prefixes
+            .iter()
+            .filter(|(prefix, _)| code.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(prefix, owner)| (prefix.as_str(), owner.as_str()))
+    }
+
+    /// Whether `code` is one of the `reserved_codes`.
+    pub fn is_reserved_code(&self, code: &str) -> bool {
+        self.reserved_codes.iter().any(|reserved| reserved.eq_ignore_ascii_case(code))
+    }
+
+    pub(crate) fn normalized(mut self) -> Result<Self, String> {
+        if self.rate_limit_requests == 0 || self.rate_limit_window_secs == 0 {
+            return Err("rate limit requests and window must be positive".to_string());
+        }
+        if self.click_detail_days == Some(0) {
+            return Err("click_detail_days must be positive".to_string());
+        }
+        if self.health_check_interval_hours == Some(0) {
+            return Err("health_check_interval_hours must be positive".to_string());
+        }
+        if self.expiry_warning_hours == Some(0) {
+            return Err("expiry_warning_hours must be positive".to_string());
+        }
+        if self.webhook_max_attempts == 0 {
+            return Err("webhook_max_attempts must be positive".to_string());
+        }
+        if self
+            .anomaly_webhook_url
+            .as_deref()
+            .is_some_and(|url| !url.starts_with("http://") && !url.starts_with("https://"))
+        {
+            return Err("anomaly_webhook_url must start with http:// or https://".to_string());
+        }
+        if self
+            .fallback_url
+            .as_deref()
+            .is_some_and(|url| !url.starts_with("http://") && !url.starts_with("https://"))
+        {
+            return Err("fallback_url must start with http:// or https://".to_string());
+        }
+        for page in [&mut self.not_found_page, &mut self.gone_page, &mut self.disabled_page] {
+            if page.as_deref().is_some_and(|html| html.trim().is_empty()) {
+                *page = None;
+            }
+        }
+        self.banner = self.banner.take().map(|b| b.trim().to_string()).filter(|b| !b.is_empty());
+        if self.banner.as_deref().is_some_and(|b| b.chars().count() > MAX_BANNER_LEN) {
+            return Err(format!("banner must be at most {MAX_BANNER_LEN} characters"));
+        }
+        if self.batch_shorten_limit == 0 {
+            return Err("batch_shorten_limit must be positive".to_string());
+        }
+        for domain in &mut self.blocked_domains {
+            *domain = normalize_domain(domain);
+        }
+        self.blocked_domains.retain(|d| !d.is_empty());
+        self.blocked_domains.sort();
+        self.blocked_domains.dedup();
+        for domain in &mut self.known_shorteners {
+            *domain = normalize_domain(domain);
+        }
+        self.known_shorteners.retain(|d| !d.is_empty());
+        self.known_shorteners.sort();
+        self.known_shorteners.dedup();
+        let mut reserved = BTreeMap::new();
+        for (prefix, owner) in std::mem::take(&mut self.reserved_prefixes) {
+            let prefix = prefix.trim().trim_end_matches('*').to_ascii_lowercase();
+            let owner = owner.trim().to_string();
+            if prefix.is_empty() || !prefix.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+                return Err(format!("invalid reserved prefix: {prefix:?}"));
+            }
+            if owner.is_empty() {
+                return Err(format!("reserved prefix {prefix:?} needs an owner"));
+            }
+            reserved.insert(prefix, owner);
+        }
+        self.reserved_prefixes = reserved;
+        for code in &mut self.reserved_codes {
+            *code = code.trim().to_ascii_lowercase();
+        }
+        self.reserved_codes.retain(|c| !c.is_empty());
+        self.reserved_codes.sort();
+        self.reserved_codes.dedup();
+        Ok(self)
+    }
+}
+
+/// Whether `host` is `domain` (normalized, lowercase) or a subdomain of it.
+pub(crate) fn in_domain(host: &str, domain: &str) -> bool {
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    host == domain || host.strip_suffix(domain).is_some_and(|rest| rest.ends_with('.'))
+}
+
+/// Lowercases a domain and strips `*.` and surrounding dots.
+pub(crate) fn normalize_domain(domain: &str) -> String {
+    domain
+        .trim()
+        .trim_start_matches("*.")
+        .trim_matches('.')
+        .to_ascii_lowercase()
+}
+
+/// Loads stored settings over the current ones and applies them. Call once at
+/// startup, after migrations.
+pub async fn load_settings(state: &AppState) -> anyhow::Result<Settings> {
+    let rows: Vec<(String, String)> = sqlx::query_as("SELECT key, value FROM settings")
+        .fetch_all(&state.pool)
+        .await?;
+    let mut merged = serde_json::to_value(state.settings())?;
+    for (key, value) in rows {
+        let value: serde_json::Value = serde_json::from_str(&value)?;
+        merged[key] = value;
+    }
+    let settings = serde_json::from_value::<Settings>(merged)?
+        .normalized()
+        .map_err(anyhow::Error::msg)?;
+    apply(state, settings.clone());
+    Ok(settings)
+}
+
+/// Deletes clicks older than `click_retention_days`. Returns how many were
+/// removed; does nothing when retention is unset or the service is read-only.
+pub async fn purge_old_clicks(state: &AppState) -> Result<u64, sqlx::Error> {
+    let Some(days) = state.settings().click_retention_days else {
+        return Ok(0);
+    };
+    if state.is_read_only() {
+        return Ok(0);
+    }
+    let cutoff = (OffsetDateTime::now_utc() - time::Duration::days(days.into())).unix_timestamp();
+    let result = sqlx::query("DELETE FROM clicks WHERE at < ?")
+        .bind(cutoff)
+        .execute(&state.pool)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+/// Makes `settings` current. Call after [`store`] commits.
+pub(crate) fn apply(state: &AppState, settings: Settings) {
+    state.rate_limiter.reconfigure(
+        settings.rate_limit_requests,
+        Duration::from_secs(settings.rate_limit_window_secs),
+    );
+    *state.settings.write().unwrap() = settings;
+}
+
+pub(crate) async fn get_settings(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Settings>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    Ok(Json(state.settings()))
+}
+
+/// Accepts a partial object; omitted fields keep their current values.
+pub(crate) async fn put_settings(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(changes): Json<serde_json::Map<String, serde_json::Value>>,
+) -> Result<Json<Settings>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+
+    let mut merged = serde_json::to_value(state.settings()).map_err(internal)?;
+    for (key, value) in &changes {
+        merged[key] = value.clone();
+    }
+    let settings = serde_json::from_value::<Settings>(merged)
+        .map_err(|e| e.to_string())
+        .and_then(Settings::normalized)
+        .map_err(|msg| (StatusCode::BAD_REQUEST, format!("invalid settings: {msg}")))?;
+
+    let mut tx = state.pool.begin().await.map_err(internal)?;
+    store(&mut tx, &settings, changes.keys().map(String::as_str))
+        .await
+        .map_err(internal)?;
+    tx.commit().await.map_err(internal)?;
+
+    apply(&state, settings.clone());
+    Ok(Json(settings))
+}
+
+/// Persists the given fields of `settings`, e.g. inside a larger transaction.
+pub(crate) async fn store<'a>(
+    conn: &mut sqlx::SqliteConnection,
+    settings: &Settings,
+    keys: impl IntoIterator<Item = &'a str>,
+) -> anyhow::Result<()> {
+    let stored = serde_json::to_value(settings)?;
+    for key in keys {
+        sqlx::query(
+            "INSERT INTO settings (key, value) VALUES (?, ?) \
+             ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+        )
+        .bind(key)
+        .bind(stored[key].to_string())
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,184 @@
+//! Per-recipient signed variants of a link: `/:code?rcpt=..&exp=..&sig=..`.
+//! The signature is an HMAC over code, recipient and expiry, so recipients
+//! can't forge each other's links or extend their lifetime. Valid clicks are
+//! attributed to the recipient.
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use time::OffsetDateTime;
+
+use crate::{api_keys::require_api_key, fetch_link, internal, AppState};
+
+const DEFAULT_TTL_DAYS: i64 = 7;
+const MAX_RECIPIENTS: usize = 1000;
+
+/// Longest `rid` recorded; longer values are ignored.
+const MAX_RID_LEN: usize = 128;
+
+/// Names of the [`SignedParams`]; never forwarded to targets.
+pub(crate) const PARAM_NAMES: &[&str] = &["rcpt", "exp", "sig", "rid"];
+
+/// Query parameters of a signed link. Unsigned links have none of them.
+#[derive(Deserialize, Default)]
+pub(crate) struct SignedParams {
+    rcpt: Option<String>,
+    exp: Option<String>,
+    sig: Option<String>,
+    /// Opaque, unverified recipient id appended by email tools (`?rid=`).
+    rid: Option<String>,
+}
+
+impl SignedParams {
+    /// The `rid` parameter, if it is short printable text.
+    pub(crate) fn rid(&self) -> Option<&str> {
+        self.rid
+            .as_deref()
+            .map(str::trim)
+            .filter(|r| !r.is_empty() && r.len() <= MAX_RID_LEN)
+            .filter(|r| r.chars().all(|c| !c.is_control()))
+    }
+}
+
+pub(crate) enum SignatureCheck {
+    Unsigned,
+    Valid { recipient: String },
+    Invalid,
+    Expired,
+}
+
+/// Signatures are the first 16 bytes of the HMAC; plenty for a URL parameter.
+const SIG_BYTES: usize = 16;
+
+fn mac(secret: &str, code: &str, recipient: &str, exp: i64) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("any key length");
+    mac.update(format!("{code}\n{recipient}\n{exp}").as_bytes());
+    mac
+}
+
+fn signature(secret: &str, code: &str, recipient: &str, exp: i64) -> String {
+    mac(secret, code, recipient, exp).finalize().into_bytes()[..SIG_BYTES]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+pub(crate) fn check(state: &AppState, code: &str, params: &SignedParams) -> SignatureCheck {
+    let (Some(recipient), Some(exp), Some(sig)) = (&params.rcpt, &params.exp, &params.sig) else {
+        return if params.sig.is_some() {
+            SignatureCheck::Invalid
+        } else {
+            SignatureCheck::Unsigned
+        };
+    };
+    let Ok(exp) = exp.parse::<i64>() else {
+        return SignatureCheck::Invalid;
+    };
+    let Some(given) = decode_hex(sig).filter(|g| g.len() == SIG_BYTES) else {
+        return SignatureCheck::Invalid;
+    };
+    // constant-time comparison of the truncated tag
+    if mac(&state.signing_secret, code, recipient, exp)
+        .verify_truncated_left(&given)
+        .is_err()
+    {
+        return SignatureCheck::Invalid;
+    }
+    if OffsetDateTime::now_utc().unix_timestamp() >= exp {
+        return SignatureCheck::Expired;
+    }
+    SignatureCheck::Valid {
+        recipient: recipient.clone(),
+    }
+}
+
+pub(crate) fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[derive(Deserialize)]
+pub(crate) struct SignReq {
+    recipients: Vec<String>,
+    /// RFC3339; defaults to seven days from now.
+    expires_at: Option<String>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct SignResp {
+    expires_at: String,
+    links: Vec<SignedLink>,
+}
+
+#[derive(Serialize)]
+struct SignedLink {
+    recipient: String,
+    url: String,
+}
+
+/// `POST /api/links/:code/sign`, for admins and API keys.
+pub(crate) async fn sign_link(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<SignReq>,
+) -> Result<Json<SignResp>, (StatusCode, String)> {
+    require_api_key(&state, &headers).await?;
+    let Some(link) = fetch_link(&state, &code).await.map_err(internal)? else {
+        return Err((StatusCode::NOT_FOUND, "not found".to_string()));
+    };
+    if payload.recipients.is_empty() || payload.recipients.len() > MAX_RECIPIENTS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("recipients must list 1-{MAX_RECIPIENTS} entries"),
+        ));
+    }
+    if payload.recipients.iter().any(|r| r.trim().is_empty()) {
+        return Err((StatusCode::BAD_REQUEST, "recipients must not be empty".to_string()));
+    }
+    let expires_at = match &payload.expires_at {
+        Some(exp) => OffsetDateTime::parse(exp, &time::format_description::well_known::Rfc3339)
+            .map_err(|_| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    "expires_at must be RFC3339 (e.g. 2026-01-31T00:00:00Z)".to_string(),
+                )
+            })?,
+        None => OffsetDateTime::now_utc() + time::Duration::days(DEFAULT_TTL_DAYS),
+    };
+    let exp = expires_at.unix_timestamp();
+
+    let short_url = state.short_url(&link.code);
+    let links = payload
+        .recipients
+        .into_iter()
+        .map(|recipient| {
+            let sig = signature(&state.signing_secret, &link.code, &recipient, exp);
+            let query = url::form_urlencoded::Serializer::new(String::new())
+                .append_pair("rcpt", &recipient)
+                .append_pair("exp", &exp.to_string())
+                .append_pair("sig", &sig)
+                .finish();
+            SignedLink {
+                url: format!("{short_url}?{query}"),
+                recipient,
+            }
+        })
+        .collect();
+    Ok(Json(SignResp {
+        expires_at: expires_at
+            .format(&time::format_description::well_known::Rfc3339)
+            .map_err(internal)?,
+        links,
+    }))
+}
@@ -0,0 +1,99 @@
+//! `POST /api/stats/batch`: compact stats for many links in one request and
+//! one query, for reports embedding dozens of short links.
+
+use axum::{extract::State, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+
+use crate::{hll, internal, rollup, AppState};
+
+/// Most codes accepted by one request.
+const MAX_CODES: usize = 200;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct BatchStatsReq {
+    codes: Vec<String>,
+    /// Exact unique visitor counts instead of sketch estimates.
+    #[serde(default)]
+    exact: bool,
+}
+
+#[derive(Serialize)]
+pub(crate) struct LinkStats {
+    /// As requested; may be an alias.
+    code: String,
+    total_clicks: i64,
+    unique_visitors: i64,
+    /// Time of the newest click, or its day once clicks are rolled up.
+    last_click_at: Option<String>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct BatchStatsResp {
+    /// In request order, once per distinct code.
+    stats: Vec<LinkStats>,
+    not_found: Vec<String>,
+}
+
+type BatchStatsRow = (String, i64, Option<i64>, Option<Vec<u8>>, Option<String>);
+
+pub(crate) async fn batch_stats(
+    State(state): State<AppState>,
+    Json(req): Json<BatchStatsReq>,
+) -> Result<Json<BatchStatsResp>, (StatusCode, String)> {
+    let mut codes = req.codes;
+    let mut seen = std::collections::HashSet::new();
+    codes.retain(|code| seen.insert(code.clone()));
+    if codes.is_empty() || codes.len() > MAX_CODES {
+        return Err((StatusCode::BAD_REQUEST, format!("send 1 to {MAX_CODES} codes")));
+    }
+    let exact = req.exact || state.exact_unique_counts;
+
+    let collate = if state.go_links { " COLLATE NOCASE" } else { "" };
+    let (unique, registers) = if exact {
+        ("(SELECT count(DISTINCT c.visitor_id) FROM clicks c WHERE c.code = u.code AND NOT c.is_bot)", "NULL")
+    } else {
+        ("NULL", "s.registers")
+    };
+    let values = vec!["(?)"; codes.len()].join(", ");
+    let sql = format!(
+        "WITH wanted(requested) AS (VALUES {values}) \
+         SELECT w.requested, {total}, {unique}, {registers}, \
+                coalesce((SELECT strftime('%Y-%m-%dT%H:%M:%SZ', max(c.at), 'unixepoch') FROM clicks c WHERE c.code = u.code), \
+                         (SELECT max(r.day) FROM click_rollups r WHERE r.code = u.code)) \
+         FROM wanted w \
+         JOIN urls u ON u.code{collate} = coalesce( \
+             (SELECT a.code FROM link_aliases a WHERE a.alias{collate} = w.requested), w.requested) \
+         LEFT JOIN visitor_sketches s ON s.code = u.code AND s.day = '*'",
+        total = rollup::total_clicks_sql("u.code", false),
+    );
+    let mut query = sqlx::query_as::<_, BatchStatsRow>(&sql);
+    for code in &codes {
+        query = query.bind(code);
+    }
+    let rows = query.fetch_all(&state.pool).await.map_err(internal)?;
+
+    let mut found: std::collections::HashMap<String, LinkStats> = rows
+        .into_iter()
+        .map(|(code, total_clicks, exact_unique, registers, last_click_at)| {
+            let stats = LinkStats {
+                code: code.clone(),
+                total_clicks,
+                unique_visitors: exact_unique.unwrap_or_else(|| hll::estimate_blob(registers.as_deref())),
+                last_click_at,
+            };
+            (code, stats)
+        })
+        .collect();
+    let mut resp = BatchStatsResp {
+        stats: Vec::new(),
+        not_found: Vec::new(),
+    };
+    for code in codes {
+        match found.remove(&code) {
+            Some(stats) => resp.stats.push(stats),
+            None => resp.not_found.push(code),
+        }
+    }
+    Ok(Json(resp))
+}
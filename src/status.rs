@@ -0,0 +1,139 @@
+//! `GET /api/links/:code/status`: the one state a link is in, in the order
+//! `redirect` checks them, so clients don't have to piece it together from
+//! `resolve` fields.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::Serialize;
+
+use crate::{fetch_link, internal, is_expired, is_scheduled, quarantine, unix_to_rfc3339, AppState, RedirectMode};
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum LinkState {
+    /// Past `expires_at`.
+    Expired,
+    /// Before `not_before`.
+    Scheduled,
+    /// Disabled when an admin quarantined the target's domain.
+    Quarantined,
+    /// Disabled by an admin for another reason.
+    Disabled,
+    Archived,
+    /// Anonymous link waiting for moderation.
+    PendingReview,
+    /// Used up its `max_clicks`.
+    Consumed,
+    /// Redirects, but the health check found the target dead.
+    DeadTarget,
+    Active,
+}
+
+#[derive(Serialize)]
+pub(crate) struct LinkStatus {
+    code: String,
+    state: LinkState,
+    /// Whether visiting the short URL sends people to the target. Signed-only
+    /// links count as not redirecting, since unsigned visits get 403.
+    redirects: bool,
+    /// What `redirect` answers to an unsigned visit; 200 for HTML redirects
+    /// and the dead-target page.
+    http_status: u16,
+    reason: String,
+    /// When the link entered this state, if known.
+    since: Option<String>,
+    /// When it leaves it on its own: activation for scheduled links, expiry
+    /// otherwise.
+    until: Option<String>,
+}
+
+pub(crate) async fn link_status(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+) -> Result<Json<LinkStatus>, (StatusCode, String)> {
+    let Some(link) = fetch_link(&state, &code).await.map_err(internal)? else {
+        return Err((StatusCode::NOT_FOUND, "not found".to_string()));
+    };
+
+    let (link_state, reason, since, until) = if is_expired(link.expires_at.as_deref()) {
+        (LinkState::Expired, "the link has expired".to_string(), link.expires_at.clone(), None)
+    } else if is_scheduled(link.not_before.as_deref()) {
+        (
+            LinkState::Scheduled,
+            "the link is not active yet".to_string(),
+            Some(link.created_at.clone()),
+            link.not_before.clone(),
+        )
+    } else if let Some(why) = &link.disabled_reason {
+        let quarantined = why.starts_with(quarantine::QUARANTINE_REASON_PREFIX);
+        (
+            if quarantined { LinkState::Quarantined } else { LinkState::Disabled },
+            why.clone(),
+            link.disabled_at.clone(),
+            None,
+        )
+    } else if link.archived_at.is_some() {
+        (LinkState::Archived, "the link has been archived".to_string(), link.archived_at.clone(), None)
+    } else if link.pending_review {
+        (
+            LinkState::PendingReview,
+            "the link is awaiting review".to_string(),
+            Some(link.created_at.clone()),
+            link.expires_at.clone(),
+        )
+    } else if link.max_clicks.is_some_and(|max| link.click_count >= max) {
+        let (last_click,): (Option<i64>,) = sqlx::query_as("SELECT max(at) FROM clicks WHERE code = ?")
+            .bind(&link.code)
+            .fetch_one(&state.pool)
+            .await
+            .map_err(internal)?;
+        (
+            LinkState::Consumed,
+            format!("the link has used all {} of its clicks", link.max_clicks.unwrap_or_default()),
+            last_click.map(unix_to_rfc3339),
+            None,
+        )
+    } else if link.target_dead_since.is_some() {
+        (
+            LinkState::DeadTarget,
+            "the target did not answer the last health check".to_string(),
+            link.target_dead_since.clone(),
+            link.expires_at.clone(),
+        )
+    } else {
+        (
+            LinkState::Active,
+            "the link redirects to its target".to_string(),
+            Some(link.not_before.clone().unwrap_or_else(|| link.created_at.clone())),
+            link.expires_at.clone(),
+        )
+    };
+
+    let dead_target_page = link_state == LinkState::DeadTarget && state.settings().dead_target_page;
+    let redirects = matches!(link_state, LinkState::Active | LinkState::DeadTarget)
+        && !dead_target_page
+        && !link.signed_only;
+    let http_status = match link_state {
+        LinkState::Scheduled => StatusCode::NOT_FOUND,
+        LinkState::PendingReview => StatusCode::FORBIDDEN,
+        LinkState::Active | LinkState::DeadTarget if link.signed_only => StatusCode::FORBIDDEN,
+        LinkState::Active | LinkState::DeadTarget if dead_target_page => StatusCode::OK,
+        LinkState::Active | LinkState::DeadTarget => match link.redirect_mode {
+            RedirectMode::Http => link.redirect_type.status(),
+            RedirectMode::Html | RedirectMode::Interstitial => StatusCode::OK,
+        },
+        _ => StatusCode::GONE,
+    };
+    Ok(Json(LinkStatus {
+        code: link.code,
+        state: link_state,
+        redirects,
+        http_status: http_status.as_u16(),
+        reason,
+        since,
+        until,
+    }))
+}
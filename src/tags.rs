@@ -0,0 +1,103 @@
+//! Free-form labels on links, e.g. one per marketing campaign. Tags are
+//! lowercased on the way in, set when a link is created and filter
+//! `GET /api/links?tag=`.
+
+use std::collections::HashMap;
+
+use axum::{extract::State, http::StatusCode, Json};
+use serde::Serialize;
+
+use crate::{internal, AppState};
+
+const MAX_TAGS: usize = 20;
+const MAX_TAG_LEN: usize = 50;
+
+/// Trims, lowercases and dedupes `tags`, keeping the first occurrence's
+/// order. Tags are letters, digits, `-`, `_` and `.`.
+pub(crate) fn validate(tags: Option<Vec<String>>) -> Result<Vec<String>, (StatusCode, String)> {
+    let mut out: Vec<String> = Vec::new();
+    for tag in tags.unwrap_or_default() {
+        let tag = tag.trim().to_lowercase();
+        if tag.is_empty() {
+            continue;
+        }
+        if tag.chars().count() > MAX_TAG_LEN
+            || !tag.chars().all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("invalid tag {tag:?}: use at most {MAX_TAG_LEN} letters, digits, '-', '_' or '.'"),
+            ));
+        }
+        if !out.contains(&tag) {
+            out.push(tag);
+        }
+    }
+    if out.len() > MAX_TAGS {
+        return Err((StatusCode::BAD_REQUEST, format!("at most {MAX_TAGS} tags per link")));
+    }
+    Ok(out)
+}
+
+/// Adds `tags` to the link `code`, creating tags that don't exist yet.
+pub(crate) async fn attach(
+    conn: &mut sqlx::SqliteConnection,
+    code: &str,
+    tags: &[String],
+) -> Result<(), sqlx::Error> {
+    for tag in tags {
+        sqlx::query("INSERT OR IGNORE INTO tags (name) VALUES (?)")
+            .bind(tag)
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query("INSERT OR IGNORE INTO link_tags (code, tag_id) SELECT ?, id FROM tags WHERE name = ?")
+            .bind(code)
+            .bind(tag)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Tags of each of `codes`, alphabetically. Codes without tags are absent.
+pub(crate) async fn for_codes(
+    executor: impl sqlx::SqliteExecutor<'_>,
+    codes: &[String],
+) -> Result<HashMap<String, Vec<String>>, sqlx::Error> {
+    let rows: Vec<(String, String)> = sqlx::query_as(
+        "SELECT lt.code, t.name FROM link_tags lt JOIN tags t ON t.id = lt.tag_id \
+         WHERE lt.code IN (SELECT value FROM json_each(?)) ORDER BY t.name",
+    )
+    .bind(serde_json::to_string(codes).unwrap_or_default())
+    .fetch_all(executor)
+    .await?;
+    let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+    for (code, name) in rows {
+        tags.entry(code).or_default().push(name);
+    }
+    Ok(tags)
+}
+
+/// SQL condition on `urls.code` matching links tagged `?5`, or all when unset.
+pub(crate) const FILTER_SQL: &str =
+    "(?5 IS NULL OR code IN (SELECT lt.code FROM link_tags lt JOIN tags t ON t.id = lt.tag_id WHERE t.name = ?5))";
+
+#[derive(Serialize, sqlx::FromRow)]
+pub(crate) struct TagCount {
+    name: String,
+    links: i64,
+}
+
+/// `GET /api/tags`: every tag in use with its number of links, most used first.
+pub(crate) async fn list_tags(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<TagCount>>, (StatusCode, String)> {
+    let tags = sqlx::query_as(
+        "SELECT t.name, count(*) AS links FROM tags t JOIN link_tags lt ON lt.tag_id = t.id \
+         GROUP BY t.id ORDER BY links DESC, t.name",
+    )
+    .fetch_all(&state.pool)
+    .await
+    .map_err(internal)?;
+    Ok(Json(tags))
+}
@@ -0,0 +1,143 @@
+//! Reusable link templates: named defaults applied by `/api/shorten` when the
+//! request sets `template`. Fields given in the request always win.
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::{internal, require_admin, AppState, RedirectMode, RedirectType, ShortenReq, UtmParams};
+
+/// Stored as JSON in `link_templates.config`, so new fields don't need a
+/// migration.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct LinkTemplate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expiry_days: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    utm: Option<UtmParams>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    redirect_mode: Option<RedirectMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    redirect_type: Option<RedirectType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    listed: Option<bool>,
+}
+
+impl LinkTemplate {
+    /// Fills everything `req` leaves unset from the template.
+    pub(crate) fn apply(&self, req: &mut ShortenReq) {
+        if req.expires_at.is_none() {
+            req.expires_at = self.expiry_days.and_then(|days| {
+                (OffsetDateTime::now_utc() + time::Duration::days(days.into()))
+                    .format(&time::format_description::well_known::Rfc3339)
+                    .ok()
+            });
+        }
+        if let Some(defaults) = &self.utm {
+            let utm = req.utm.get_or_insert_with(UtmParams::default);
+            for (field, default) in [
+                (&mut utm.source, &defaults.source),
+                (&mut utm.medium, &defaults.medium),
+                (&mut utm.campaign, &defaults.campaign),
+                (&mut utm.term, &defaults.term),
+                (&mut utm.content, &defaults.content),
+            ] {
+                if field.is_none() {
+                    field.clone_from(default);
+                }
+            }
+        }
+        req.redirect_mode = req.redirect_mode.or(self.redirect_mode);
+        req.redirect_type = req.redirect_type.or(self.redirect_type);
+        req.listed = req.listed.or(self.listed);
+    }
+}
+
+pub(crate) async fn load(state: &AppState, id: &str) -> Result<Option<LinkTemplate>, sqlx::Error> {
+    let row: Option<(String,)> = sqlx::query_as("SELECT config FROM link_templates WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&state.pool)
+        .await?;
+    // rows are only written by `put_template`, so the JSON is known-good
+    Ok(row.and_then(|(config,)| serde_json::from_str(&config).ok()))
+}
+
+#[derive(Serialize)]
+pub(crate) struct TemplateEntry {
+    id: String,
+    #[serde(flatten)]
+    template: LinkTemplate,
+}
+
+pub(crate) async fn list_templates(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<TemplateEntry>>, (StatusCode, String)> {
+    let rows: Vec<(String, String)> =
+        sqlx::query_as("SELECT id, config FROM link_templates ORDER BY id")
+            .fetch_all(&state.pool)
+            .await
+            .map_err(internal)?;
+    Ok(Json(
+        rows.into_iter()
+            .filter_map(|(id, config)| {
+                let template = serde_json::from_str(&config).ok()?;
+                Some(TemplateEntry { id, template })
+            })
+            .collect(),
+    ))
+}
+
+/// Creates or replaces a template. Admin only.
+pub(crate) async fn put_template(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Json(template): Json<LinkTemplate>,
+) -> Result<Json<TemplateEntry>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    if id.is_empty()
+        || id.len() > 64
+        || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "template id must be 1-64 letters, digits, '-' or '_'".to_string(),
+        ));
+    }
+    let now = OffsetDateTime::now_utc()
+        .format(&time::format_description::well_known::Rfc3339)
+        .unwrap();
+    sqlx::query(
+        "INSERT INTO link_templates (id, config, created_at) VALUES (?, ?, ?) \
+         ON CONFLICT (id) DO UPDATE SET config = excluded.config",
+    )
+    .bind(&id)
+    .bind(serde_json::to_string(&template).map_err(internal)?)
+    .bind(now)
+    .execute(&state.pool)
+    .await
+    .map_err(internal)?;
+    Ok(Json(TemplateEntry { id, template }))
+}
+
+pub(crate) async fn delete_template(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let done = sqlx::query("DELETE FROM link_templates WHERE id = ?")
+        .bind(&id)
+        .execute(&state.pool)
+        .await
+        .map_err(internal)?;
+    if done.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "not found".to_string()));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
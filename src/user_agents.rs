@@ -0,0 +1,139 @@
+//! Browser, OS and device type of a click, parsed from its `User-Agent` when
+//! the click is recorded and stored next to the raw string, so stats can
+//! group by them. Rules are ordered token checks covering the mainstream
+//! browsers and crawlers; anything unrecognised is `Other`.
+
+use sqlx::{Pool, Sqlite};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ParsedUa {
+    pub(crate) browser: &'static str,
+    pub(crate) os: &'static str,
+    /// `desktop`, `mobile`, `tablet` or `bot`.
+    pub(crate) device_type: &'static str,
+}
+
+/// Known crawlers and link unfurlers, checked before browsers because most
+/// of them pretend to be one.
+const BOTS: &[(&str, &str)] = &[
+    ("Googlebot", "Googlebot"),
+    ("bingbot", "Bingbot"),
+    ("Slackbot", "Slackbot"),
+    ("Twitterbot", "Twitterbot"),
+    ("facebookexternalhit", "Facebook"),
+    ("LinkedInBot", "LinkedInBot"),
+    ("Discordbot", "Discordbot"),
+    ("TelegramBot", "TelegramBot"),
+    ("WhatsApp", "WhatsApp"),
+    ("DuckDuckBot", "DuckDuckBot"),
+    ("YandexBot", "YandexBot"),
+    ("Baiduspider", "Baiduspider"),
+    ("curl/", "curl"),
+    ("Wget/", "Wget"),
+    ("python-requests", "python-requests"),
+];
+
+/// First match wins: Chromium-based browsers are listed before Chrome, and
+/// Chrome before Safari, since each includes the later tokens.
+const BROWSERS: &[(&str, &str)] = &[
+    ("Edg/", "Edge"),
+    ("EdgA/", "Edge"),
+    ("EdgiOS/", "Edge"),
+    ("OPR/", "Opera"),
+    ("SamsungBrowser/", "Samsung Internet"),
+    ("YaBrowser/", "Yandex Browser"),
+    ("Vivaldi/", "Vivaldi"),
+    ("Firefox/", "Firefox"),
+    ("FxiOS/", "Firefox"),
+    ("CriOS/", "Chrome"),
+    ("Chrome/", "Chrome"),
+    ("Version/", "Safari"),
+    ("MSIE ", "Internet Explorer"),
+    ("Trident/", "Internet Explorer"),
+];
+
+const OSES: &[(&str, &str)] = &[
+    ("Windows Phone", "Windows Phone"),
+    ("Windows", "Windows"),
+    ("iPhone", "iOS"),
+    ("iPad", "iOS"),
+    ("iPod", "iOS"),
+    ("Android", "Android"),
+    ("CrOS", "ChromeOS"),
+    ("Mac OS X", "macOS"),
+    ("Macintosh", "macOS"),
+    ("Linux", "Linux"),
+];
+
+pub(crate) fn parse(ua: &str) -> ParsedUa {
+    let find = |table: &[(&str, &'static str)]| {
+        table
+            .iter()
+            .find(|(token, _)| ua.contains(token))
+            .map(|(_, name)| *name)
+    };
+    let os = find(OSES).unwrap_or("Other");
+    if let Some(bot) = find(BOTS) {
+        return ParsedUa { browser: bot, os, device_type: "bot" };
+    }
+    let lower = ua.to_ascii_lowercase();
+    if ["bot", "crawler", "spider"].iter().any(|t| lower.contains(t)) {
+        return ParsedUa { browser: "Other", os, device_type: "bot" };
+    }
+    let device_type = if ua.contains("iPad") || ua.contains("Tablet") || (ua.contains("Android") && !ua.contains("Mobile")) {
+        "tablet"
+    } else if ["Mobile", "iPhone", "iPod", "Windows Phone"].iter().any(|t| ua.contains(t)) {
+        "mobile"
+    } else {
+        "desktop"
+    };
+    // `Version/` only marks Safari when it's the WebKit engine
+    let browser = find(BROWSERS)
+        .filter(|b| *b != "Safari" || ua.contains("Safari/"))
+        .unwrap_or("Other");
+    ParsedUa { browser, os, device_type }
+}
+
+impl ParsedUa {
+    pub(crate) fn is_bot(&self) -> bool {
+        self.device_type == "bot"
+    }
+}
+
+/// Fills `browser`, `os`, `device_type` and `is_bot` of clicks recorded
+/// before they were parsed. Links that turn out to have bot clicks lose their
+/// visitor sketches, which [`crate::backfill_sketches`] then rebuilds without
+/// them; run this first. Returns how many clicks were updated.
+pub async fn backfill_user_agents(pool: &Pool<Sqlite>) -> Result<u64, sqlx::Error> {
+    let mut updated = 0;
+    loop {
+        let rows: Vec<(i64, String, String)> = sqlx::query_as(
+            "SELECT id, code, user_agent FROM clicks WHERE browser IS NULL AND user_agent IS NOT NULL LIMIT 1000",
+        )
+        .fetch_all(pool)
+        .await?;
+        if rows.is_empty() {
+            return Ok(updated);
+        }
+        let mut tx = pool.begin().await?;
+        for (id, code, ua) in &rows {
+            let parsed = parse(ua);
+            sqlx::query("UPDATE clicks SET browser = ?, os = ?, device_type = ?, is_bot = ? WHERE id = ?")
+                .bind(parsed.browser)
+                .bind(parsed.os)
+                .bind(parsed.device_type)
+                .bind(parsed.is_bot())
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            if parsed.is_bot() {
+                sqlx::query("DELETE FROM visitor_sketches WHERE code = ?")
+                    .bind(code)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+        tx.commit().await?;
+        updated += rows.len() as u64;
+    }
+}
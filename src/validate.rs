@@ -0,0 +1,113 @@
+//! `POST /api/shorten/validate`: runs the checks of `/api/shorten` on a
+//! request without creating anything, so forms can show problems before
+//! submitting. Field checks run independently, so one response lists every
+//! invalid field.
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::Serialize;
+
+use crate::{
+    api_keys, fetch_link, internal, prepare_link, probe, templates, validate_custom_code, validate_expires_at,
+    validate_label, validate_not_before, validate_target, AppState, ShortenReq, MAX_NOTES_LEN, MAX_TITLE_LEN,
+};
+
+#[derive(Serialize)]
+pub(crate) struct FieldError {
+    /// Request field at fault; `request` for checks spanning several fields
+    /// or the caller, such as anonymous limits.
+    field: &'static str,
+    message: String,
+}
+
+#[derive(Serialize)]
+pub(crate) struct ValidationReport {
+    valid: bool,
+    /// The target as it would be stored, after normalization, unshortening
+    /// and HTTPS upgrades.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target_url: Option<String>,
+    /// Existing link `reuse_existing` would return instead of a new one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    existing_code: Option<String>,
+    errors: Vec<FieldError>,
+    warnings: Vec<String>,
+}
+
+pub(crate) async fn validate_shorten(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<ShortenReq>,
+) -> Result<Json<ValidationReport>, (StatusCode, String)> {
+    let caller = api_keys::caller(&state, &headers).await?;
+    let mut errors = Vec::new();
+    let mut check = |field, result: Result<(), (StatusCode, String)>| {
+        if let Err((_, message)) = result {
+            errors.push(FieldError { field, message });
+        }
+    };
+
+    let mut filled = payload.clone();
+    if let Some(id) = &payload.template {
+        match templates::load(&state, id).await.map_err(internal)? {
+            Some(template) => template.apply(&mut filled),
+            None => check("template", Err((StatusCode::BAD_REQUEST, format!("unknown template: {id}")))),
+        }
+    }
+    let url = match validate_target(&state, &filled.url) {
+        Ok(target) => probe::require_public_host(&state, &target).await,
+        Err(e) => Err(e),
+    };
+    check("url", url);
+    check("title", validate_label("title", filled.title.clone(), MAX_TITLE_LEN).map(|_| ()));
+    check("notes", validate_label("notes", filled.notes.clone(), MAX_NOTES_LEN).map(|_| ()));
+    if let Some(exp) = &filled.expires_at {
+        check("expires_at", validate_expires_at(exp));
+    }
+    if let Some(start) = &filled.not_before {
+        check("not_before", validate_not_before(start, filled.expires_at.as_deref()));
+    }
+    if filled.max_clicks.is_some_and(|max| max < 1) {
+        check("max_clicks", Err((StatusCode::BAD_REQUEST, "max_clicks must be at least 1".to_string())));
+    }
+    if filled.single_use == Some(true) && filled.max_clicks.is_some_and(|max| max != 1) {
+        check("single_use", Err((StatusCode::BAD_REQUEST, "single_use conflicts with max_clicks".to_string())));
+    }
+    if let Some(code) = &filled.custom_code {
+        let code = state.normalize_code(code);
+        match validate_custom_code(&code, &state.settings(), caller.as_ref()) {
+            Err(message) => check("custom_code", Err((StatusCode::BAD_REQUEST, message))),
+            Ok(()) => {
+                if fetch_link(&state, &code).await.map_err(internal)?.is_some() {
+                    check("custom_code", Err((StatusCode::CONFLICT, "code already exists".to_string())));
+                }
+            }
+        }
+    }
+
+    let mut report = ValidationReport {
+        valid: false,
+        target_url: None,
+        existing_code: None,
+        errors,
+        warnings: Vec::new(),
+    };
+    if report.errors.is_empty() {
+        match prepare_link(&state, &headers, caller.as_ref(), payload).await {
+            Ok(link) => {
+                report.existing_code = link.find_existing(&state.pool).await.map_err(internal)?.map(|l| l.code);
+                report.target_url = Some(link.target);
+                report.warnings = link.warnings;
+            }
+            Err((status, message)) if status != StatusCode::INTERNAL_SERVER_ERROR => {
+                report.errors.push(FieldError { field: "request", message });
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    report.valid = report.errors.is_empty();
+    Ok(Json(report))
+}
@@ -0,0 +1,88 @@
+//! Outbound JSON webhooks (behind the `webhooks` feature).
+
+/// Header carrying a signed payload's [`signature`].
+#[cfg(feature = "webhooks")]
+pub(crate) const SIGNATURE_HEADER: &str = "x-shortener-signature";
+
+/// `sha256=` and the hex HMAC-SHA256 of `body` under `secret`.
+#[cfg(feature = "webhooks")]
+pub(crate) fn signature(secret: &str, body: &[u8]) -> String {
+    use hmac::{Hmac, Mac};
+    let mut mac = Hmac::<sha2::Sha256>::new_from_slice(secret.as_bytes()).expect("any key length");
+    mac.update(body);
+    let hex: String = mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256={hex}")
+}
+
+/// POSTs `payload` to `url`, failing on errors and non-2xx answers.
+pub(crate) async fn post(url: &str, payload: &serde_json::Value) -> Result<(), String> {
+    post_signed(url, payload, None).await
+}
+
+/// [`post`], signing the body with `secret` in [`SIGNATURE_HEADER`] if given.
+pub(crate) async fn post_signed(url: &str, payload: &serde_json::Value, secret: Option<&str>) -> Result<(), String> {
+    Client::default().post_signed(url, payload, secret).await
+}
+
+/// Sends webhooks. Clones share one connection pool, so code sending many
+/// webhooks should keep one around.
+#[derive(Clone)]
+pub(crate) struct Client {
+    #[cfg(feature = "webhooks")]
+    inner: reqwest::Client,
+}
+
+impl Default for Client {
+    #[cfg(feature = "webhooks")]
+    fn default() -> Self {
+        Self {
+            inner: reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(10))
+                .user_agent("url-shortener/1.0")
+                .build()
+                .expect("static client config"),
+        }
+    }
+
+    #[cfg(not(feature = "webhooks"))]
+    fn default() -> Self {
+        Self {}
+    }
+}
+
+impl Client {
+    /// See [`post_signed`].
+    #[cfg(feature = "webhooks")]
+    pub(crate) async fn post_signed(
+        &self,
+        url: &str,
+        payload: &serde_json::Value,
+        secret: Option<&str>,
+    ) -> Result<(), String> {
+        let body = payload.to_string();
+        let mut request = self
+            .inner
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json");
+        if let Some(secret) = secret {
+            request = request.header(SIGNATURE_HEADER, signature(secret, body.as_bytes()));
+        }
+        request
+            .body(body)
+            .send()
+            .await
+            .and_then(|resp| resp.error_for_status())
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    #[cfg(not(feature = "webhooks"))]
+    pub(crate) async fn post_signed(
+        &self,
+        _url: &str,
+        _payload: &serde_json::Value,
+        _secret: Option<&str>,
+    ) -> Result<(), String> {
+        Err("the webhooks feature is disabled".to_string())
+    }
+}